@@ -0,0 +1,79 @@
+//! Benchmarks of representative workloads, run with each heap management strategy, so that a change to the evaluator or to a collector which slows it down shows up as a regression.
+//!
+//! Each workload in `benches/workloads` is measured once per strategy, on a fresh interpreter each iteration, and the workloads which allocate are measured again with escape analysis, so that evaluator changes are compared as well as heaps. Criterion compares each run with the previous one; `cargo bench -- --save-baseline <name>` and `--baseline <name>` compare against a saved run instead.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use slang_core::{Interpreter, heap::Strategy};
+
+/// The strategies each workload is run with.
+const STRATEGIES: [Strategy; 7] = [
+    Strategy::GarbageCollected,
+    Strategy::Generational,
+    Strategy::Compacting,
+    Strategy::Copying,
+    Strategy::ReferenceCounted,
+    Strategy::Region,
+    Strategy::Naive,
+];
+
+/// The workloads, each named by its file.
+const WORKLOADS: [(&str, &str); 3] = [
+    (
+        "deep_recursion",
+        include_str!("workloads/deep_recursion.slang"),
+    ),
+    ("object_churn", include_str!("workloads/object_churn.slang")),
+    (
+        "long_lived_graph",
+        include_str!("workloads/long_lived_graph.slang"),
+    ),
+];
+
+/// The workloads which allocate objects, and so are affected by escape analysis.
+const ALLOCATING: [&str; 2] = ["object_churn", "long_lived_graph"];
+
+/// Runs a workload to completion on a fresh interpreter.
+fn run(source: &str, strategy: Strategy, escape_analysis: bool) {
+    let mut interpreter = Interpreter::new(strategy);
+    interpreter.set_escape_analysis(escape_analysis);
+
+    interpreter.run(source).expect("the workload runs");
+}
+
+fn heaps(c: &mut Criterion) {
+    for (name, source) in WORKLOADS {
+        let mut group = c.benchmark_group(name);
+
+        for strategy in STRATEGIES {
+            group.bench_with_input(
+                BenchmarkId::from_parameter(strategy.code()),
+                &strategy,
+                |b, strategy| b.iter(|| run(source, *strategy, false)),
+            );
+        }
+
+        group.finish();
+    }
+}
+
+fn escape_analysis(c: &mut Criterion) {
+    for (name, source) in WORKLOADS
+        .into_iter()
+        .filter(|(name, _)| ALLOCATING.contains(name))
+    {
+        let mut group = c.benchmark_group(format!("{}_escape_analysis", name));
+
+        for strategy in STRATEGIES {
+            group.bench_with_input(
+                BenchmarkId::from_parameter(strategy.code()),
+                &strategy,
+                |b, strategy| b.iter(|| run(source, *strategy, true)),
+            );
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, heaps, escape_analysis);
+criterion_main!(benches);
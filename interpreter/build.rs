@@ -0,0 +1,17 @@
+//! Records the version of the compiler this crate is built with, which plugins are checked against when they are loaded.
+
+use std::{env, process::Command};
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let output = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .expect("the compiler's version could be read");
+
+    println!(
+        "cargo:rustc-env=SLANG_RUSTC_VERSION={}",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,16 @@
+//! A plugin which defines `square(x)`, loaded with `--load` once built with `cargo build --example slang_math`.
+
+use slang_core::{Value, plugin::Registry};
+
+fn square(arguments: &[Value]) -> Result<Option<Value>, String> {
+    match arguments {
+        [Value::Integer(value)] => Ok(Some(Value::Integer(value * value))),
+        _ => Err(String::from("expected one Integer")),
+    }
+}
+
+fn register(registry: &mut Registry) {
+    registry.register("square", square);
+}
+
+slang_core::export_plugin!(register);
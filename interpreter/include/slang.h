@@ -0,0 +1,47 @@
+#ifndef SLANG_H
+#define SLANG_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+// Returned by [slang_eval] when the source code ran successfully.
+#define SLANG_OK 0
+
+// Returned by [slang_eval] when the source code could not be lexed, parsed or executed.
+#define SLANG_ERROR 1
+
+// Returned by [slang_eval] when the interpreter or source code was null, or the source code was not valid UTF-8.
+#define SLANG_INVALID_ARGUMENT 2
+
+// An interpreter created by [slang_new], along with the output it has captured and the result of its last run.
+typedef struct SlangInterpreter SlangInterpreter;
+
+// Creates an interpreter whose heap is managed with a strategy, given by its code (`gc`, `gen`, `mc`, `cp`, `rc`, `rg` or `na`), or with the garbage collected heap if the code is null, and defines the functions of the prelude. Returns null if the code is not a known strategy.
+//
+// # Safety
+//
+// `heap` must be null or a valid nul-terminated string.
+struct SlangInterpreter *slang_new(const char *heap);
+
+// Lexes, parses and executes some source code, against the variables and functions defined by earlier runs. Returns [SLANG_OK] if it succeeded, after which [slang_result] gives what it printed, or [SLANG_ERROR] if it failed, after which [slang_result] gives its errors, one per line.
+//
+// # Safety
+//
+// `interpreter` must be null or a pointer returned by [slang_new] which has not been freed, and `source` must be null or a valid nul-terminated string.
+int slang_eval(struct SlangInterpreter *interpreter,
+               const char *source);
+
+// Returns what the last run printed if it succeeded, or its errors if it failed, as a nul-terminated string. The string is owned by the interpreter, and is valid until its next run or until it is freed. Returns null if the interpreter is null.
+//
+// # Safety
+//
+// `interpreter` must be null or a pointer returned by [slang_new] which has not been freed.
+const char *slang_result(const struct SlangInterpreter *interpreter);
+
+// Frees an interpreter, along with its heap and its last result. Does nothing if the interpreter is null.
+//
+// # Safety
+//
+// `interpreter` must be null or a pointer returned by [slang_new] which has not already been freed.
+void slang_free(struct SlangInterpreter *interpreter);
+
+#endif  /* SLANG_H */
@@ -0,0 +1,114 @@
+//! Benchmarking, by running a program repeatedly and summarising how long each run took.
+
+use std::time::Duration;
+
+use slang_core::{
+    heap::{Allocations, Strategy},
+    stats::{self, Field, Format, Record},
+};
+
+/// A single measured run of a program.
+pub struct Iteration {
+    /// The wall time taken to lex, parse and execute the program.
+    pub elapsed: Duration,
+    /// The allocations made by the heap during the run.
+    pub allocations: Allocations,
+}
+
+impl Record for Iteration {
+    const NAMES: &'static [&'static str] = &["elapsed", "allocations", "bytes_allocated"];
+
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            Field::Float(self.elapsed.as_secs_f64()),
+            Field::Integer(self.allocations.count),
+            Field::Integer(self.allocations.bytes),
+        ]
+    }
+}
+
+/// The results of benchmarking a program.
+pub struct Benchmark {
+    pub strategy: Strategy,
+    /// The number of unmeasured runs made before the measured runs.
+    pub warmup: usize,
+    pub iterations: Vec<Iteration>,
+}
+
+impl Benchmark {
+    /// Returns the shortest, mean and longest wall time of the measured runs.
+    pub fn times(&self) -> (Duration, Duration, Duration) {
+        let times = self.iterations.iter().map(|iteration| iteration.elapsed);
+
+        let min = times.clone().min().unwrap_or_default();
+        let max = times.clone().max().unwrap_or_default();
+        let mean = match self.iterations.len() {
+            0 => Duration::ZERO,
+            count => times.sum::<Duration>() / count as u32,
+        };
+
+        (min, mean, max)
+    }
+
+    /// Returns the total allocations made across all of the measured runs.
+    pub fn allocations(&self) -> Allocations {
+        let mut total = Allocations::default();
+
+        for iteration in &self.iterations {
+            total.count += iteration.allocations.count;
+            total.bytes += iteration.allocations.bytes;
+        }
+
+        total
+    }
+
+    /// Renders a human-readable summary.
+    pub fn report(&self) -> String {
+        let (min, mean, max) = self.times();
+        let allocations = self.allocations();
+
+        format!(
+            "{} iterations ({} warmup) with the {} heap\n\
+             time:        min {:.3} ms, mean {:.3} ms, max {:.3} ms\n\
+             allocations: {} objects, {} bytes in total",
+            self.iterations.len(),
+            self.warmup,
+            self.strategy.code(),
+            min.as_secs_f64() * 1000.0,
+            mean.as_secs_f64() * 1000.0,
+            max.as_secs_f64() * 1000.0,
+            allocations.count,
+            allocations.bytes
+        )
+    }
+
+    /// Renders the results in a machine-readable format.
+    ///
+    /// CSV and JSON lines contain one record per measured run, and JSON additionally contains the summary.
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Csv => stats::csv(&self.iterations),
+            Format::Json => {
+                let (min, mean, max) = self.times();
+                let allocations = self.allocations();
+
+                format!(
+                    "{{\n  \"heap\": \"{}\",\n  \"warmup\": {},\n  \"min\": {},\n  \"mean\": {},\n  \"max\": {},\n  \"allocations\": {},\n  \"bytes_allocated\": {},\n  \"iterations\": {}\n}}\n",
+                    self.strategy.code(),
+                    self.warmup,
+                    min.as_secs_f64(),
+                    mean.as_secs_f64(),
+                    max.as_secs_f64(),
+                    allocations.count,
+                    allocations.bytes,
+                    stats::json_array(&self.iterations)
+                )
+            }
+            Format::JsonLines => self
+                .iterations
+                .iter()
+                .map(|iteration| format!("{}\n", stats::json_object(iteration, None)))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Capabilities, which allow programs to use native functions that reach outside of the interpreter, such as to run shell commands or use the filesystem (see [files](crate::files)). Every capability is denied by default, so that running a program cannot affect the system unless it is allowed to.
+
+use std::process::Command;
+
+use crate::{expression::EvaluationError, heap::Object, shared::Shared, value::Value};
+
+/// The capabilities a program has been given.
+#[derive(Clone, Copy, Default)]
+pub struct Capabilities {
+    /// Whether shell commands may be run with `exec`, given by `--allow-exec`.
+    pub exec: bool,
+    /// Whether files and directories may be read, written and removed, given by `--allow-fs`.
+    pub fs: bool,
+}
+
+impl Capabilities {
+    /// Fails unless shell commands may be run.
+    pub fn require_exec(&self) -> Result<(), EvaluationError> {
+        require(self.exec, "exec", "--allow-exec")
+    }
+
+    /// Fails unless the filesystem may be used, by a native function with an identifier.
+    pub fn require_fs(&self, function: &str) -> Result<(), EvaluationError> {
+        require(self.fs, function, "--allow-fs")
+    }
+}
+
+/// Fails unless a capability has been given, which a native function with an identifier needs, and which is given by a command line option.
+fn require(allowed: bool, function: &str, option: &str) -> Result<(), EvaluationError> {
+    if allowed {
+        Ok(())
+    } else {
+        Err(EvaluationError::CapabilityDenied {
+            function: function.to_string(),
+            option: option.to_string(),
+        })
+    }
+}
+
+/// Runs a shell command, waiting for it to finish, and returns an object with what it wrote to `stdout` and `stderr`, and its exit `code` (which is `-1` if it was stopped by a signal).
+pub fn exec(command: &str) -> Result<Value, EvaluationError> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+
+    let output = shell
+        .arg(command)
+        .output()
+        .map_err(|error| EvaluationError::ExecFailed {
+            command: command.to_string(),
+            error: error.to_string(),
+        })?;
+
+    let object: Object = [
+        (
+            "stdout",
+            Value::String(Shared::from(
+                String::from_utf8_lossy(&output.stdout).as_ref(),
+            )),
+        ),
+        (
+            "stderr",
+            Value::String(Shared::from(
+                String::from_utf8_lossy(&output.stderr).as_ref(),
+            )),
+        ),
+        ("code", Value::Integer(output.status.code().unwrap_or(-1))),
+    ]
+    .into_iter()
+    .map(|(field, value)| (Shared::from(field), value))
+    .collect();
+
+    Ok(Value::Object(object))
+}
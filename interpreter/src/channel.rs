@@ -0,0 +1,103 @@
+//! Channels, which pass messages between threads (see [thread](crate::thread)), created by the native function `channel`, with messages sent by `send` and received by `recv`.
+//!
+//! Channels belong to the program which created them and the threads it spawns. Each message is a copy of the value sent (see [Copied]), received in order by only one receiver.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    expression::EvaluationError,
+    heap::ManagedHeap,
+    stack::Stack,
+    thread::{self, Copied},
+    value::Value,
+};
+
+/// The channels which have been created by a program and the threads it spawns, indexed by their handles. Clones share the same channels.
+#[derive(Clone, Default)]
+pub struct Channels(Arc<Mutex<Vec<Arc<Channel>>>>);
+
+/// How long a receiver waits for a message before checking whether its program has been interrupted.
+const INTERRUPT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The messages which have been sent on a channel but not yet received, each as a copy made outside of every heap, along with the condition receivers wait on.
+#[derive(Default)]
+struct Channel {
+    messages: Mutex<VecDeque<Copied>>,
+    sent: Condvar,
+}
+
+/// Creates a channel, returning its handle.
+pub fn create(stack: &Stack) -> Result<Value, EvaluationError> {
+    let mut channels = stack.channels.0.lock().expect("the channels were poisoned");
+    channels.push(Arc::default());
+
+    Value::try_from(channels.len() - 1).map_err(EvaluationError::from)
+}
+
+/// Sends a copy of a value on the channel with a handle.
+pub fn send(
+    handle: i32,
+    value: &Value,
+    stack: &Stack,
+    heap: &ManagedHeap,
+) -> Result<(), EvaluationError> {
+    let channel = channel(handle, stack)?;
+    let message = thread::export(heap, value).map_err(|error| EvaluationError::MessageFailed {
+        message: error.to_string(),
+    })?;
+
+    channel
+        .messages
+        .lock()
+        .expect("the messages were poisoned")
+        .push_back(message);
+    channel.sent.notify_one();
+
+    Ok(())
+}
+
+/// Waits for a message on the channel with a handle, returning it copied into the heap. Stops waiting if the program is interrupted.
+pub fn receive(
+    handle: i32,
+    stack: &mut Stack,
+    heap: &mut ManagedHeap,
+) -> Result<Option<Value>, EvaluationError> {
+    let channel = channel(handle, stack)?;
+    let mut messages = channel.messages.lock().expect("the messages were poisoned");
+
+    let message = loop {
+        if let Some(message) = messages.pop_front() {
+            break message;
+        }
+
+        if stack.interrupt.take() {
+            return Err(EvaluationError::Interrupted);
+        }
+
+        (messages, _) = channel
+            .sent
+            .wait_timeout(messages, INTERRUPT_INTERVAL)
+            .expect("the messages were poisoned");
+    };
+
+    drop(messages);
+
+    thread::import(&message, stack, heap).map_err(|error| EvaluationError::MessageFailed {
+        message: error.to_string(),
+    })
+}
+
+/// Returns the channel with a handle.
+fn channel(handle: i32, stack: &Stack) -> Result<Arc<Channel>, EvaluationError> {
+    let channels = stack.channels.0.lock().expect("the channels were poisoned");
+
+    usize::try_from(handle)
+        .ok()
+        .and_then(|index| channels.get(index))
+        .cloned()
+        .ok_or(EvaluationError::UnknownChannel { handle })
+}
@@ -0,0 +1,660 @@
+//! Parsing of the command line arguments given to the interpreter.
+
+use std::{
+    error::Error,
+    fmt::{Debug, Display},
+    path::PathBuf,
+};
+
+use slang_core::{
+    capabilities::Capabilities,
+    doc,
+    heap::{Limits, Strategy},
+    lint::{Level, Rule},
+    stats::{Format, Sampling},
+};
+
+/// How the interpreter should be invoked.
+pub const USAGE: &str =
+    "Usage: slang [run] [--watch] [--profile] [--stats] [--instrument] [--stats-out <path>] [--stats-format <csv|json|jsonl>] [--stats-interval <N|Tms>]
+                  [--gc-threshold <objects>] [--gc-growth-factor <factor>] [--gc-every-n-statements <N>] [--gc-stress] [--gc-threads <N>] [--rc-deferred]
+                  [--escape-analysis] [--allocation-sites] [--heap-dump-on-exit] [--dump-on-error] [--dump-out <path>] [--max-heap-objects <N>] [--max-heap-bytes <N>] [--max-steps <N>] [--watch-var <name>]... [--load <plugin>]... [--no-prelude] [--restore <path>] [--allow-exec] [--allow-fs]
+                  [gc|gen|mc|cp|rc|rg|na] [filename]
+       slang fmt [--check] <filename>...
+       slang lint [--allow <rule>] [--warn <rule>] [--deny <rule>]... <filename>...
+       slang doc [--format <markdown|html>] [--out <path>] <filename>...
+       slang test [--filter <text>] [gc|gen|mc|cp|rc|rg|na] <filename>...
+       slang bench [--iterations <N>] [--warmup <M>] [--out <path>] [gc|gen|mc|cp|rc|rg|na] <filename>
+       slang compare [--out <path>] [--escape-analysis] [--gc-threads <N>] <filename>
+       slang calc [gc|gen|mc|cp|rc|rg|na] [expression]...
+       slang --fuzz-check <filename>...";
+
+/// All errors which can occur while parsing command line arguments.
+pub enum CliError {
+    /// When an option is not recognised.
+    UnknownOption(String),
+    /// When there are more positional arguments than expected.
+    UnexpectedArgument(String),
+    /// When a command requires at least one filename, but none were given.
+    MissingFilename,
+    /// When an option requires a value, but none was given.
+    MissingValue(String),
+    /// When the value given for an option is not valid.
+    InvalidValue { option: String, value: String },
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownOption(option) => write!(f, "Unknown option `{}`.", option),
+            Self::UnexpectedArgument(argument) => {
+                write!(f, "Unexpected argument `{}`.", argument)
+            }
+            Self::MissingFilename => write!(f, "Expected a filename."),
+            Self::MissingValue(option) => write!(f, "Expected a value for `{}`.", option),
+            Self::InvalidValue { option, value } => {
+                write!(f, "Invalid value `{}` for `{}`.", value, option)
+            }
+        }
+    }
+}
+
+impl Debug for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Error for CliError {}
+
+/// A command given to the interpreter.
+pub enum Command {
+    /// Runs a file, or starts the REPL if no file is given.
+    Run(RunOptions),
+    /// Formats files, or only checks whether they are formatted.
+    Format { check: bool, filenames: Vec<String> },
+    /// Lints files, with the level of each rule given, which override those in the configuration file.
+    Lint {
+        levels: Vec<(Rule, Level)>,
+        filenames: Vec<String>,
+    },
+    /// Writes the documentation of files, to a path if it was given, or to stdout otherwise.
+    Doc {
+        format: doc::Format,
+        out: Option<PathBuf>,
+        filenames: Vec<String>,
+    },
+    /// Runs the tests in files, or only those whose names contain the filter, with the heap management strategy if it was given.
+    Test {
+        filter: Option<String>,
+        strategy: Option<Strategy>,
+        filenames: Vec<String>,
+    },
+    /// Runs a file repeatedly, and reports how long each run took.
+    Bench(BenchOptions),
+    /// Runs a file with each heap management strategy, and writes their stats side by side.
+    Compare {
+        filename: String,
+        out: Option<PathBuf>,
+        escape_analysis: Option<bool>,
+        gc_threads: Option<usize>,
+    },
+    /// Evaluates each expression and prints its value, or starts a REPL which does so for each line if no expressions are given, with the heap management strategy if it was given.
+    Calc {
+        strategy: Option<Strategy>,
+        expressions: Vec<String>,
+    },
+    /// Lexes and parses the bytes of each file as the fuzz targets do, to check that they do not panic.
+    FuzzCheck { filenames: Vec<String> },
+}
+
+/// The options for running a program.
+pub struct RunOptions {
+    /// The technique used to manage the heap, if it was given.
+    pub strategy: Option<Strategy>,
+    /// The file to run. The REPL is started if this is [None].
+    pub filename: Option<String>,
+    /// Whether the file should be re-run each time it changes.
+    pub watch: bool,
+    /// Whether a report of per-function timings should be printed after the run.
+    pub profile: bool,
+    /// Whether the costlier stats, which are measured as every statement runs, should be recorded too.
+    pub instrument: bool,
+    /// Whether stats should be collected, if it was specified.
+    pub stats: Option<bool>,
+    /// Where the stats file should be written, if it was given.
+    pub stats_out: Option<PathBuf>,
+    /// The format of the stats file, if it was given.
+    pub stats_format: Option<Format>,
+    /// How often stats are recorded, if it was given.
+    pub stats_interval: Option<Sampling>,
+    /// The fewest objects the garbage collected heap must contain for a collection to start, if it was given.
+    pub gc_threshold: Option<usize>,
+    /// The multiple of the surviving objects the garbage collected heap must grow to before another collection, if it was given.
+    pub gc_growth_factor: Option<f64>,
+    /// How many statements apart the garbage collected heap starts collections, if it was given.
+    pub gc_every_n_statements: Option<usize>,
+    /// Whether a full collection should be forced before every allocation.
+    pub gc_stress: bool,
+    /// The number of threads which mark the heap once it is large, if it was given.
+    pub gc_threads: Option<usize>,
+    /// Whether changes to reference counts should be buffered and applied at safe points, under reference counting, if it was specified.
+    pub rc_deferred: Option<bool>,
+    /// Whether object literals which never escape their block should be stored inline, rather than on the heap, if it was specified.
+    pub escape_analysis: Option<bool>,
+    /// The most the heap may hold.
+    pub heap_limits: Limits,
+    /// Whether the heap should be dumped to `<filename>.heap.json` after the run.
+    pub heap_dump_on_exit: bool,
+    /// Whether the state of the program should be dumped if an evaluation error stops it.
+    pub dump_on_error: bool,
+    /// Where the dump on error should be written, if it was given, rather than to stderr.
+    pub dump_out: Option<PathBuf>,
+    /// Whether a report of the live objects from each allocation site should be printed after the run.
+    pub allocation_sites: bool,
+    /// The most steps the program may take before it is stopped, if there is a limit.
+    pub max_steps: Option<usize>,
+    /// The variables and fields whose assignments are logged to stderr.
+    pub watch_vars: Vec<String>,
+    /// The plugins to load before running, whose native functions are defined in the global scope.
+    pub plugins: Vec<PathBuf>,
+    /// Whether the prelude should be run before anything else.
+    pub prelude: bool,
+    /// The saved session to restore before running, if one was given.
+    pub restore: Option<PathBuf>,
+    /// The native functions which reach outside of the interpreter that the program may call.
+    pub capabilities: Capabilities,
+}
+
+/// The options for benchmarking a program.
+pub struct BenchOptions {
+    /// The technique used to manage the heap, if it was given.
+    pub strategy: Option<Strategy>,
+    pub filename: String,
+    /// The number of measured runs.
+    pub iterations: usize,
+    /// The number of unmeasured runs made first.
+    pub warmup: usize,
+    /// Where the machine-readable results should be written, if anywhere.
+    pub out: Option<PathBuf>,
+}
+
+/// Parses the command line arguments, excluding the name of the executable.
+pub fn parse(arguments: &[String]) -> Result<Command, CliError> {
+    match arguments {
+        [command, arguments @ ..] if command == "fmt" => parse_format(arguments),
+        [command, arguments @ ..] if command == "lint" => parse_lint(arguments),
+        [command, arguments @ ..] if command == "doc" => parse_doc(arguments),
+        [command, arguments @ ..] if command == "test" => parse_test(arguments),
+        [command, arguments @ ..] if command == "run" => parse_run(arguments),
+        [command, arguments @ ..] if command == "bench" => parse_bench(arguments),
+        [command, arguments @ ..] if command == "compare" => parse_compare(arguments),
+        [command, arguments @ ..] if command == "calc" => parse_calc(arguments),
+        [option, filenames @ ..] if option == "--fuzz-check" => {
+            if filenames.is_empty() {
+                return Err(CliError::MissingFilename);
+            }
+
+            Ok(Command::FuzzCheck {
+                filenames: filenames.to_vec(),
+            })
+        }
+        arguments => parse_run(arguments),
+    }
+}
+
+/// Parses the arguments to the `run` command, which is also used when no command is given.
+fn parse_run(arguments: &[String]) -> Result<Command, CliError> {
+    let mut strategy = None;
+    let mut filename = None;
+    let mut watch = false;
+    let mut profile = false;
+    let mut instrument = false;
+    let mut stats = None;
+    let mut stats_out = None;
+    let mut stats_format = None;
+    let mut stats_interval = None;
+    let mut gc_threshold = None;
+    let mut gc_growth_factor = None;
+    let mut gc_every_n_statements = None;
+    let mut gc_stress = false;
+    let mut gc_threads = None;
+    let mut rc_deferred = None;
+    let mut escape_analysis = None;
+    let mut heap_limits = Limits::default();
+    let mut heap_dump_on_exit = false;
+    let mut dump_on_error = false;
+    let mut dump_out = None;
+    let mut allocation_sites = false;
+    let mut max_steps = None;
+    let mut watch_vars = Vec::new();
+    let mut plugins = Vec::new();
+    let mut prelude = true;
+    let mut restore = None;
+    let mut capabilities = Capabilities::default();
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--watch" => watch = true,
+            "--profile" => profile = true,
+            "--heap-dump-on-exit" => heap_dump_on_exit = true,
+            "--dump-on-error" => dump_on_error = true,
+            // Choosing where the dump is written implies that it should be written.
+            "--dump-out" => {
+                dump_on_error = true;
+                dump_out = Some(PathBuf::from(value(argument, &mut arguments)?));
+            }
+            "--allocation-sites" => allocation_sites = true,
+            "--gc-stress" => gc_stress = true,
+            "--escape-analysis" => escape_analysis = Some(true),
+            "--stats" => stats = Some(true),
+            // Instrumentation is recorded alongside the rest of the stats.
+            "--instrument" => {
+                instrument = true;
+                stats = Some(true);
+            }
+            // Choosing where stats are written implies that they should be collected.
+            "--stats-out" => {
+                stats = Some(true);
+                stats_out = Some(PathBuf::from(value(argument, &mut arguments)?));
+            }
+            "--stats-format" => {
+                let name = value(argument, &mut arguments)?;
+
+                stats_format =
+                    Some(
+                        Format::from_name(name).ok_or_else(|| CliError::InvalidValue {
+                            option: argument.clone(),
+                            value: name.clone(),
+                        })?,
+                    );
+            }
+            "--stats-interval" => {
+                let name = value(argument, &mut arguments)?;
+
+                stats_interval =
+                    Some(
+                        Sampling::from_name(name).ok_or_else(|| CliError::InvalidValue {
+                            option: argument.clone(),
+                            value: name.clone(),
+                        })?,
+                    );
+            }
+            "--gc-threshold" => gc_threshold = Some(count(argument, &mut arguments)?),
+            "--gc-growth-factor" => {
+                let factor = value(argument, &mut arguments)?;
+
+                gc_growth_factor = Some(
+                    factor
+                        .parse()
+                        .ok()
+                        .filter(|factor| *factor >= 1.0)
+                        .ok_or_else(|| CliError::InvalidValue {
+                            option: argument.clone(),
+                            value: factor.clone(),
+                        })?,
+                );
+            }
+            "--gc-every-n-statements" => {
+                let every_n_statements = count(argument, &mut arguments)?;
+
+                if every_n_statements == 0 {
+                    return Err(CliError::InvalidValue {
+                        option: argument.clone(),
+                        value: String::from("0"),
+                    });
+                }
+
+                gc_every_n_statements = Some(every_n_statements);
+            }
+            "--gc-threads" => gc_threads = Some(threads(argument, &mut arguments)?),
+            "--rc-deferred" => rc_deferred = Some(true),
+            "--max-heap-objects" => {
+                heap_limits.max_objects = Some(count(argument, &mut arguments)?)
+            }
+            "--max-heap-bytes" => heap_limits.max_bytes = Some(count(argument, &mut arguments)?),
+            "--max-steps" => max_steps = Some(count(argument, &mut arguments)?),
+            "--watch-var" => watch_vars.push(value(argument, &mut arguments)?.clone()),
+            "--no-prelude" => prelude = false,
+            "--load" => plugins.push(PathBuf::from(value(argument, &mut arguments)?)),
+            "--restore" => restore = Some(PathBuf::from(value(argument, &mut arguments)?)),
+            "--allow-exec" => capabilities.exec = true,
+            "--allow-fs" => capabilities.fs = true,
+            option if option.starts_with("--") => {
+                return Err(CliError::UnknownOption(option.to_string()));
+            }
+            code if strategy.is_none()
+                && filename.is_none()
+                && Strategy::from_code(code).is_some() =>
+            {
+                strategy = Strategy::from_code(code);
+            }
+            _ if filename.is_none() => filename = Some(argument.clone()),
+            _ => return Err(CliError::UnexpectedArgument(argument.clone())),
+        }
+    }
+
+    if watch && filename.is_none() {
+        return Err(CliError::MissingFilename);
+    }
+
+    Ok(Command::Run(RunOptions {
+        strategy,
+        filename,
+        watch,
+        profile,
+        instrument,
+        stats,
+        stats_out,
+        stats_format,
+        stats_interval,
+        gc_threshold,
+        gc_growth_factor,
+        gc_every_n_statements,
+        gc_stress,
+        gc_threads,
+        rc_deferred,
+        escape_analysis,
+        heap_limits,
+        heap_dump_on_exit,
+        dump_on_error,
+        dump_out,
+        allocation_sites,
+        max_steps,
+        watch_vars,
+        plugins,
+        prelude,
+        restore,
+        capabilities,
+    }))
+}
+
+/// Parses the arguments to the `bench` command.
+fn parse_bench(arguments: &[String]) -> Result<Command, CliError> {
+    let mut strategy = None;
+    let mut filename = None;
+    let mut iterations = 10;
+    let mut warmup = 1;
+    let mut out = None;
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--iterations" => {
+                iterations = count(argument, &mut arguments)?;
+
+                if iterations == 0 {
+                    return Err(CliError::InvalidValue {
+                        option: argument.clone(),
+                        value: String::from("0"),
+                    });
+                }
+            }
+            "--warmup" => warmup = count(argument, &mut arguments)?,
+            "--out" => out = Some(PathBuf::from(value(argument, &mut arguments)?)),
+            option if option.starts_with("--") => {
+                return Err(CliError::UnknownOption(option.to_string()));
+            }
+            code if strategy.is_none()
+                && filename.is_none()
+                && Strategy::from_code(code).is_some() =>
+            {
+                strategy = Strategy::from_code(code);
+            }
+            _ if filename.is_none() => filename = Some(argument.clone()),
+            _ => return Err(CliError::UnexpectedArgument(argument.clone())),
+        }
+    }
+
+    let Some(filename) = filename else {
+        return Err(CliError::MissingFilename);
+    };
+
+    Ok(Command::Bench(BenchOptions {
+        strategy,
+        filename,
+        iterations,
+        warmup,
+        out,
+    }))
+}
+
+/// Parses the arguments to the `compare` command.
+fn parse_compare(arguments: &[String]) -> Result<Command, CliError> {
+    let mut filename = None;
+    let mut out = None;
+    let mut escape_analysis = None;
+    let mut gc_threads = None;
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--out" => out = Some(PathBuf::from(value(argument, &mut arguments)?)),
+            "--escape-analysis" => escape_analysis = Some(true),
+            "--gc-threads" => gc_threads = Some(threads(argument, &mut arguments)?),
+            option if option.starts_with("--") => {
+                return Err(CliError::UnknownOption(option.to_string()));
+            }
+            _ if filename.is_none() => filename = Some(argument.clone()),
+            _ => return Err(CliError::UnexpectedArgument(argument.clone())),
+        }
+    }
+
+    let Some(filename) = filename else {
+        return Err(CliError::MissingFilename);
+    };
+
+    Ok(Command::Compare {
+        filename,
+        out,
+        escape_analysis,
+        gc_threads,
+    })
+}
+
+/// Takes the value following an option, which must be a positive number of threads.
+fn threads<'a>(
+    option: &str,
+    arguments: &mut impl Iterator<Item = &'a String>,
+) -> Result<usize, CliError> {
+    let threads = count(option, arguments)?;
+
+    if threads == 0 {
+        return Err(CliError::InvalidValue {
+            option: option.to_string(),
+            value: String::from("0"),
+        });
+    }
+
+    Ok(threads)
+}
+
+/// Takes the value following an option, which must be a non-negative integer.
+fn count<'a>(
+    option: &str,
+    arguments: &mut impl Iterator<Item = &'a String>,
+) -> Result<usize, CliError> {
+    let value = value(option, arguments)?;
+
+    value.parse().map_err(|_| CliError::InvalidValue {
+        option: option.to_string(),
+        value: value.clone(),
+    })
+}
+
+/// Takes the value following an option.
+fn value<'a>(
+    option: &str,
+    arguments: &mut impl Iterator<Item = &'a String>,
+) -> Result<&'a String, CliError> {
+    arguments
+        .next()
+        .ok_or_else(|| CliError::MissingValue(option.to_string()))
+}
+
+/// Parses the arguments to the `fmt` command.
+fn parse_format(arguments: &[String]) -> Result<Command, CliError> {
+    let mut check = false;
+    let mut filenames = Vec::new();
+
+    for argument in arguments {
+        match argument.as_str() {
+            "--check" => check = true,
+            option if option.starts_with("--") => {
+                return Err(CliError::UnknownOption(option.to_string()));
+            }
+            _ => filenames.push(argument.clone()),
+        }
+    }
+
+    if filenames.is_empty() {
+        return Err(CliError::MissingFilename);
+    }
+
+    Ok(Command::Format { check, filenames })
+}
+
+/// Parses the arguments to the `lint` command.
+fn parse_lint(arguments: &[String]) -> Result<Command, CliError> {
+    let mut levels = Vec::new();
+    let mut filenames = Vec::new();
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            option @ ("--allow" | "--warn" | "--deny") => {
+                let name = value(argument, &mut arguments)?;
+
+                let rule = Rule::from_name(name).ok_or_else(|| CliError::InvalidValue {
+                    option: argument.clone(),
+                    value: name.clone(),
+                })?;
+
+                let level = Level::from_name(&option[2..]).expect("each option is a level");
+
+                levels.push((rule, level));
+            }
+            option if option.starts_with("--") => {
+                return Err(CliError::UnknownOption(option.to_string()));
+            }
+            _ => filenames.push(argument.clone()),
+        }
+    }
+
+    if filenames.is_empty() {
+        return Err(CliError::MissingFilename);
+    }
+
+    Ok(Command::Lint { levels, filenames })
+}
+
+/// Parses the arguments to the `doc` command.
+fn parse_doc(arguments: &[String]) -> Result<Command, CliError> {
+    let mut format = None;
+    let mut out = None;
+    let mut filenames = Vec::new();
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--format" => {
+                let name = value(argument, &mut arguments)?;
+
+                format =
+                    Some(
+                        doc::Format::from_name(name).ok_or_else(|| CliError::InvalidValue {
+                            option: argument.clone(),
+                            value: name.clone(),
+                        })?,
+                    );
+            }
+            "--out" => out = Some(PathBuf::from(value(argument, &mut arguments)?)),
+            option if option.starts_with("--") => {
+                return Err(CliError::UnknownOption(option.to_string()));
+            }
+            _ => filenames.push(argument.clone()),
+        }
+    }
+
+    if filenames.is_empty() {
+        return Err(CliError::MissingFilename);
+    }
+
+    // An explicit format takes precedence over one inferred from the output path.
+    let format = format
+        .or(out.as_deref().and_then(doc::Format::from_path))
+        .unwrap_or(doc::Format::Markdown);
+
+    Ok(Command::Doc {
+        format,
+        out,
+        filenames,
+    })
+}
+
+/// Parses the arguments to the `calc` command.
+fn parse_calc(arguments: &[String]) -> Result<Command, CliError> {
+    let mut strategy = None;
+    let mut expressions = Vec::new();
+
+    for argument in arguments {
+        match argument.as_str() {
+            option if option.starts_with("--") => {
+                return Err(CliError::UnknownOption(option.to_string()));
+            }
+            code if strategy.is_none()
+                && expressions.is_empty()
+                && Strategy::from_code(code).is_some() =>
+            {
+                strategy = Strategy::from_code(code);
+            }
+            _ => expressions.push(argument.clone()),
+        }
+    }
+
+    Ok(Command::Calc {
+        strategy,
+        expressions,
+    })
+}
+
+/// Parses the arguments to the `test` command.
+fn parse_test(arguments: &[String]) -> Result<Command, CliError> {
+    let mut filter = None;
+    let mut strategy = None;
+    let mut filenames = Vec::new();
+
+    let mut arguments = arguments.iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--filter" => filter = Some(value(argument, &mut arguments)?.clone()),
+            option if option.starts_with("--") => {
+                return Err(CliError::UnknownOption(option.to_string()));
+            }
+            code if strategy.is_none()
+                && filenames.is_empty()
+                && Strategy::from_code(code).is_some() =>
+            {
+                strategy = Strategy::from_code(code);
+            }
+            _ => filenames.push(argument.clone()),
+        }
+    }
+
+    if filenames.is_empty() {
+        return Err(CliError::MissingFilename);
+    }
+
+    Ok(Command::Test {
+        filter,
+        strategy,
+        filenames,
+    })
+}
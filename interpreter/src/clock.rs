@@ -0,0 +1,52 @@
+//! The clock used to time programs and collections, on each supported platform.
+//!
+//! WebAssembly in a browser has no clock in the standard library, so there one must be injected with `set_clock`. Until it is, no time is measured to pass.
+
+pub use platform::*;
+
+#[cfg(not(target_family = "wasm"))]
+mod platform {
+    pub use std::time::Instant;
+}
+
+#[cfg(target_family = "wasm")]
+mod platform {
+    use std::{cell::Cell, time::Duration};
+
+    thread_local! {
+        /// Returns the time since some fixed point, such as when the page was loaded.
+        static CLOCK: Cell<Option<fn() -> Duration>> = const { Cell::new(None) };
+    }
+
+    /// Sets the function which returns the time since some fixed point, which is used for every [Instant].
+    pub fn set_clock(clock: fn() -> Duration) {
+        CLOCK.with(|cell| cell.set(Some(clock)));
+    }
+
+    /// A measurement of the injected clock, with the same interface as [std::time::Instant].
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    pub struct Instant(Duration);
+
+    impl Instant {
+        pub fn now() -> Self {
+            Self(
+                CLOCK
+                    .with(|cell| cell.get())
+                    .map(|clock| clock())
+                    .unwrap_or_default(),
+            )
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            Self::now().saturating_duration_since(*self)
+        }
+
+        pub fn duration_since(&self, earlier: Instant) -> Duration {
+            self.saturating_duration_since(earlier)
+        }
+
+        pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+            self.0.saturating_sub(earlier.0)
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Comparison of the same program run with each heap management strategy.
+
+use slang_core::{
+    heap::Strategy,
+    stats::{Entry, Field, Record},
+};
+
+/// The entries recorded while running a program with a single strategy.
+pub struct Run {
+    pub strategy: Strategy,
+    pub entries: Vec<Entry>,
+}
+
+/// Renders the runs as a single CSV, with one row per statement and one group of columns per strategy.
+///
+/// Each row aligns the entries recorded before the same statement in every run. If a run recorded fewer entries than the others (for example, because of an error), its cells are left empty.
+pub fn csv(runs: &[Run]) -> String {
+    let mut header = vec![String::from("statement")];
+
+    for run in runs {
+        for name in Entry::NAMES {
+            header.push(format!("{}_{}", run.strategy.code(), name));
+        }
+    }
+
+    let mut contents = header.join(",");
+
+    let rows = runs.iter().map(|run| run.entries.len()).max().unwrap_or(0);
+
+    for row in 0..rows {
+        let mut cells = vec![row.to_string()];
+
+        for run in runs {
+            match run.entries.get(row) {
+                Some(entry) => cells.extend(entry.fields().into_iter().map(|field| match field {
+                    Field::Missing => String::from("unable to calculate"),
+                    field => field.to_string(),
+                })),
+                None => cells.extend(Entry::NAMES.iter().map(|_| String::new())),
+            }
+        }
+
+        contents.push('\n');
+        contents.push_str(&cells.join(","));
+    }
+
+    contents
+}
+
+/// Renders a human-readable summary of each run, using the final entry of each.
+pub fn summary(runs: &[Run]) -> String {
+    let mut lines = vec![format!(
+        "{:<4}  {:>12}  {:>17}  {:>15}  {:>17}  {:>18}  {:>11}  {:>19}  {:>13}  {:>18}",
+        "heap",
+        "time (ms)",
+        "peak heap objects",
+        "peak heap bytes",
+        "peak stack frames",
+        "final heap objects",
+        "collections",
+        "longest pause (ms)",
+        "objects freed",
+        "allocations elided"
+    )];
+
+    for run in runs {
+        let line = match run.entries.last() {
+            Some(last) => format!(
+                "{:<4}  {:>12.3}  {:>17}  {:>15}  {:>17}  {:>18}  {:>11}  {:>19.3}  {:>13}  {:>18}",
+                run.strategy.code(),
+                last.elapsed.as_secs_f64() * 1000.0,
+                last.usage.peak_objects,
+                last.usage.peak_bytes,
+                last.peak_stack_frames_count,
+                last.heap_objects_count,
+                last.totals.collections,
+                last.totals.longest_pause.as_secs_f64() * 1000.0,
+                last.totals.objects_freed,
+                last.allocations.elided
+            ),
+            None => format!("{:<4}  no entries were recorded", run.strategy.code()),
+        };
+
+        lines.push(line);
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,357 @@
+//! Project configuration, read from a `slang.toml` or `.slangrc` file.
+//!
+//! Both files use the same subset of TOML: `[sections]`, `key = value` pairs, and `#` comments, where each value is a string, boolean, integer or float.
+
+use std::{
+    env,
+    error::Error,
+    fmt::{Debug, Display},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use slang_core::{
+    heap::Strategy,
+    lint::{Level, Rule},
+    stats::{Format, Sampling},
+};
+
+/// The names of configuration files, in order of preference.
+pub const FILENAMES: [&str; 2] = ["slang.toml", ".slangrc"];
+
+/// All errors which can occur while reading a configuration file.
+pub enum ConfigError {
+    /// When the configuration file could not be read.
+    Io { path: PathBuf, error: io::Error },
+    /// When a line is not a section header, a key-value pair, a comment, or blank.
+    InvalidSyntax { path: PathBuf, line: usize },
+    /// When a key is not recognised.
+    UnknownKey {
+        path: PathBuf,
+        line: usize,
+        key: String,
+    },
+    /// When the value for a key is not valid.
+    InvalidValue {
+        path: PathBuf,
+        line: usize,
+        key: String,
+        expected: String,
+    },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            Self::InvalidSyntax { path, line } => {
+                write!(f, "{}:{} Invalid syntax.", path.display(), line)
+            }
+            Self::UnknownKey { path, line, key } => {
+                write!(f, "{}:{} Unknown key `{}`.", path.display(), line, key)
+            }
+            Self::InvalidValue {
+                path,
+                line,
+                key,
+                expected,
+            } => write!(
+                f,
+                "{}:{} Invalid value for `{}` (expected {}).",
+                path.display(),
+                line,
+                key,
+                expected
+            ),
+        }
+    }
+}
+
+impl Debug for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Error for ConfigError {}
+
+/// A value in a configuration file.
+enum ConfigValue {
+    String(String),
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+}
+
+/// Defaults read from a configuration file, which are overridden by command line arguments.
+#[derive(Default)]
+pub struct Config {
+    /// The technique used to manage the heap (`heap`).
+    pub strategy: Option<Strategy>,
+    /// Whether stats should be collected (`stats.enabled`).
+    pub stats_enabled: Option<bool>,
+    /// The directory which stats files are written to (`stats.directory`), instead of next to the source file.
+    pub stats_directory: Option<PathBuf>,
+    /// The format stats files are written in (`stats.format`).
+    pub stats_format: Option<Format>,
+    /// How often stats are recorded (`stats.interval`).
+    pub stats_interval: Option<Sampling>,
+    /// The level of each lint rule which is given (`lint.<rule>`), in order.
+    pub lint: Vec<(Rule, Level)>,
+    /// The fewest objects the garbage collected heap must contain for a collection to start (`gc.threshold`).
+    pub gc_threshold: Option<usize>,
+    /// The multiple of the surviving objects the garbage collected heap must grow to before another collection (`gc.growth_factor`).
+    pub gc_growth_factor: Option<f64>,
+    /// How many statements apart the garbage collected heap starts collections (`gc.every_n_statements`).
+    pub gc_every_n_statements: Option<usize>,
+    /// The number of threads which mark the heap once it is large (`gc.threads`).
+    pub gc_threads: Option<usize>,
+    /// Whether object literals which never escape their block are stored inline (`optimizations.escape_analysis`).
+    pub escape_analysis: Option<bool>,
+    /// Whether changes to reference counts are deferred to safe points (`optimizations.rc_deferred`).
+    pub rc_deferred: Option<bool>,
+}
+
+impl Config {
+    /// Finds the nearest configuration file, starting in the working directory and moving up through its parents.
+    ///
+    /// If there is no configuration file, the default configuration is returned.
+    pub fn discover() -> Result<Self, ConfigError> {
+        let Ok(directory) = env::current_dir() else {
+            return Ok(Self::default());
+        };
+
+        for directory in directory.ancestors() {
+            for filename in FILENAMES {
+                let path = directory.join(filename);
+
+                if path.is_file() {
+                    return Self::read(&path);
+                }
+            }
+        }
+
+        Ok(Self::default())
+    }
+
+    /// Reads a specific configuration file.
+    pub fn read(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|error| ConfigError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        let mut config = Self::default();
+        let mut section = String::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = strip_comment(line).trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                section = format!("{}.", name.trim());
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(ConfigError::InvalidSyntax {
+                    path: path.to_path_buf(),
+                    line: line_number,
+                });
+            };
+
+            let key = format!("{}{}", section, key.trim());
+
+            let Some(value) = parse_value(value.trim()) else {
+                return Err(ConfigError::InvalidSyntax {
+                    path: path.to_path_buf(),
+                    line: line_number,
+                });
+            };
+
+            config.set(path, line_number, key, value)?;
+        }
+
+        Ok(config)
+    }
+
+    /// Sets the field corresponding to a key.
+    fn set(
+        &mut self,
+        path: &Path,
+        line: usize,
+        key: String,
+        value: ConfigValue,
+    ) -> Result<(), ConfigError> {
+        let invalid = |key: String, expected: &str| ConfigError::InvalidValue {
+            path: path.to_path_buf(),
+            line,
+            key,
+            expected: expected.to_string(),
+        };
+
+        match key.as_str() {
+            "heap" => match value {
+                ConfigValue::String(code) if Strategy::from_code(&code).is_some() => {
+                    self.strategy = Strategy::from_code(&code);
+                }
+                _ => {
+                    return Err(invalid(
+                        key,
+                        "\"gc\", \"gen\", \"mc\", \"cp\", \"rc\", \"rg\" or \"na\"",
+                    ));
+                }
+            },
+            "stats.enabled" => match value {
+                ConfigValue::Boolean(enabled) => self.stats_enabled = Some(enabled),
+                _ => return Err(invalid(key, "a boolean")),
+            },
+            "stats.directory" => match value {
+                // Relative directories are relative to the configuration file, not the working directory.
+                ConfigValue::String(directory) => {
+                    self.stats_directory = Some(match path.parent() {
+                        Some(parent) => parent.join(directory),
+                        None => PathBuf::from(directory),
+                    })
+                }
+                _ => return Err(invalid(key, "a string")),
+            },
+            "stats.format" => match value {
+                ConfigValue::String(name) if Format::from_name(&name).is_some() => {
+                    self.stats_format = Format::from_name(&name);
+                }
+                _ => return Err(invalid(key, "\"csv\", \"json\" or \"jsonl\"")),
+            },
+            "stats.interval" => match value {
+                ConfigValue::String(name) if Sampling::from_name(&name).is_some() => {
+                    self.stats_interval = Sampling::from_name(&name);
+                }
+                _ => return Err(invalid(key, "a number of statements or milliseconds")),
+            },
+            "gc.threshold" => match value {
+                ConfigValue::Integer(threshold) if threshold >= 0 => {
+                    self.gc_threshold = usize::try_from(threshold).ok();
+                }
+                _ => return Err(invalid(key, "a number of objects")),
+            },
+            "gc.growth_factor" => match value {
+                ConfigValue::Integer(factor) if factor >= 1 => {
+                    self.gc_growth_factor = Some(factor as f64);
+                }
+                ConfigValue::Float(factor) if factor >= 1.0 => {
+                    self.gc_growth_factor = Some(factor);
+                }
+                _ => return Err(invalid(key, "a number which is at least 1")),
+            },
+            "gc.every_n_statements" => match value {
+                ConfigValue::Integer(every_n_statements) if every_n_statements > 0 => {
+                    self.gc_every_n_statements = usize::try_from(every_n_statements).ok();
+                }
+                _ => return Err(invalid(key, "a positive number of statements")),
+            },
+            "gc.threads" => match value {
+                ConfigValue::Integer(threads) if threads > 0 => {
+                    self.gc_threads = usize::try_from(threads).ok();
+                }
+                _ => return Err(invalid(key, "a positive number of threads")),
+            },
+            "optimizations.escape_analysis" => match value {
+                ConfigValue::Boolean(enabled) => self.escape_analysis = Some(enabled),
+                _ => return Err(invalid(key, "a boolean")),
+            },
+            "optimizations.rc_deferred" => match value {
+                ConfigValue::Boolean(enabled) => self.rc_deferred = Some(enabled),
+                _ => return Err(invalid(key, "a boolean")),
+            },
+            key if key.starts_with("lint.") => match (Rule::from_name(&key[5..]), value) {
+                (Some(rule), ConfigValue::String(name)) if Level::from_name(&name).is_some() => {
+                    self.lint
+                        .push((rule, Level::from_name(&name).expect("the level is valid")));
+                }
+                (Some(_), _) => {
+                    return Err(invalid(key.to_string(), "\"allow\", \"warn\" or \"deny\""));
+                }
+                (None, _) => {
+                    return Err(ConfigError::UnknownKey {
+                        path: path.to_path_buf(),
+                        line,
+                        key: key.to_string(),
+                    });
+                }
+            },
+            _ => {
+                return Err(ConfigError::UnknownKey {
+                    path: path.to_path_buf(),
+                    line,
+                    key,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the path of the stats file for a source file.
+    pub fn stats_path(&self, source_code_filename: &str, format: Format) -> PathBuf {
+        let filename = format!("{}.{}", source_code_filename, format.extension());
+
+        match &self.stats_directory {
+            Some(directory) => match Path::new(&filename).file_name() {
+                Some(name) => directory.join(name),
+                None => directory.join(filename),
+            },
+            None => PathBuf::from(filename),
+        }
+    }
+}
+
+/// Removes a `#` comment from the end of a line, ignoring any `#` within a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+/// Parses a string, boolean, integer or float value.
+fn parse_value(value: &str) -> Option<ConfigValue> {
+    if let Some(string) = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        return Some(ConfigValue::String(string.to_string()));
+    }
+
+    match value {
+        "true" => Some(ConfigValue::Boolean(true)),
+        "false" => Some(ConfigValue::Boolean(false)),
+        // Only plain decimal numbers are supported, with digits on both sides of a decimal point.
+        _ if value.contains('.') => {
+            let (whole, fraction) = value.split_once('.')?;
+            let digits = |part: &str| {
+                !part.is_empty() && part.chars().all(|character| character.is_ascii_digit())
+            };
+
+            if !digits(whole.strip_prefix(['-', '+']).unwrap_or(whole)) || !digits(fraction) {
+                return None;
+            }
+
+            value.parse().ok().map(ConfigValue::Float)
+        }
+        _ => value.parse().ok().map(ConfigValue::Integer),
+    }
+}
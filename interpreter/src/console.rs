@@ -0,0 +1,99 @@
+//! The console a program reads from and writes to, which embedders can replace.
+
+use std::io::{self, BufRead, Read, Write};
+
+use crate::shared::{MaybeSend, Mutable, Shared};
+
+/// Where a program's output is written, such as by `print()`, where its diagnostics are written, such as by `eprint()`, and where its input is read from, such as by `input()`.
+pub struct Console {
+    pub output: Box<dyn Output>,
+    pub error: Box<dyn Output>,
+    pub input: Box<dyn Input>,
+}
+
+/// Writers which a program's output can be written to: any writer, unless the `send` feature is enabled, in which case it must also be [Send].
+pub trait Output: Write + MaybeSend {}
+
+impl<T: Write + MaybeSend> Output for T {}
+
+/// Readers which a program's input can be read from: any buffered reader, unless the `send` feature is enabled, in which case it must also be [Send].
+pub trait Input: BufRead + MaybeSend {}
+
+impl<T: BufRead + MaybeSend> Input for T {}
+
+impl Default for Console {
+    /// Writes output to stdout and diagnostics to stderr, and reads from stdin.
+    fn default() -> Self {
+        Self {
+            output: Box::new(io::stdout()),
+            error: Box::new(io::stderr()),
+            input: Box::new(StdinLines::default()),
+        }
+    }
+}
+
+/// Reads from stdin a line at a time, so that nothing after the line being read is taken from stdin, and it can still be read elsewhere (such as by the REPL).
+#[derive(Default)]
+pub struct StdinLines {
+    line: Vec<u8>,
+    /// The number of bytes of the line which have been consumed.
+    position: usize,
+}
+
+impl Read for StdinLines {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let read = available.len().min(buf.len());
+
+        buf[..read].copy_from_slice(&available[..read]);
+        self.consume(read);
+
+        Ok(read)
+    }
+}
+
+impl BufRead for StdinLines {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.position >= self.line.len() {
+            self.line.clear();
+            self.position = 0;
+
+            io::stdin().lock().read_until(b'\n', &mut self.line)?;
+        }
+
+        Ok(&self.line[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position += amount;
+    }
+}
+
+/// An output which collects everything written to it, so that it can be read after a program has run. Clones share the same contents.
+#[derive(Clone, Default)]
+pub struct Capture(Shared<Mutable<Vec<u8>>>);
+
+impl Capture {
+    /// Returns everything written so far, replacing any invalid UTF-8.
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+
+    /// Takes everything written so far, leaving the capture empty.
+    pub fn take(&self) -> String {
+        let contents = self.contents();
+        self.0.borrow_mut().clear();
+
+        contents
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
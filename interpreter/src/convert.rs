@@ -0,0 +1,330 @@
+//! Conversions between Rust types and slang values, for embedding code and native functions.
+//!
+//! Rust values convert into [Value]s with [From] (or [TryFrom], for integers which may not fit), and back with [TryFrom], which fails with a [ConversionError] if the value has a different type. Structs convert to and from objects with [FromObject] and [IntoObject], which can be implemented field by field with the [object_conversions](crate::object_conversions) macro.
+//!
+//! ```
+//! use slang_core::{
+//!     Value,
+//!     convert::{FromObject, IntoObject},
+//!     object_conversions,
+//! };
+//!
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//!     label: String,
+//! }
+//!
+//! object_conversions!(Point { x, y, label });
+//!
+//! let value = Value::from(Point { x: 1, y: 2, label: String::from("origin") }.into_object());
+//! let point = Point::from_value(value).unwrap();
+//!
+//! assert_eq!((point.x, point.y, point.label.as_str()), (1, 2, "origin"));
+//! assert!(i32::try_from(Value::from("one")).is_err());
+//! ```
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Debug, Display},
+};
+
+use crate::{
+    heap::{Object, Pointer},
+    shared::Shared,
+    value::{Function, Type, Value},
+};
+
+/// All errors which can occur while converting between Rust types and slang values.
+pub enum ConversionError {
+    /// When a value does not have the type being converted to.
+    WrongType { expected: Type, found: Type },
+    /// When an integer does not fit within a slang Integer.
+    OutOfRange { value: i64 },
+    /// When an Integer is negative, but is being converted to an unsigned integer.
+    Negative { value: i32 },
+    /// When an object does not have a field which is being converted.
+    MissingField(String),
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WrongType { expected, found } => {
+                write!(f, "Expected a value of type {}, found {}.", expected, found)
+            }
+            Self::OutOfRange { value } => {
+                write!(f, "The value {} is out of range for an Integer.", value)
+            }
+            Self::Negative { value } => {
+                write!(
+                    f,
+                    "Expected an Integer which is not negative, found {}.",
+                    value
+                )
+            }
+            Self::MissingField(field) => write!(f, "The object has no field `{}`.", field),
+        }
+    }
+}
+
+impl Debug for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Error for ConversionError {}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl TryFrom<i64> for Value {
+    type Error = ConversionError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        i32::try_from(value)
+            .map(Self::Integer)
+            .map_err(|_| ConversionError::OutOfRange { value })
+    }
+}
+
+impl TryFrom<usize> for Value {
+    type Error = ConversionError;
+
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        i32::try_from(value)
+            .map(Self::Integer)
+            .map_err(|_| ConversionError::OutOfRange {
+                value: i64::try_from(value).unwrap_or(i64::MAX),
+            })
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl From<Shared<str>> for Value {
+    fn from(value: Shared<str>) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<Function> for Value {
+    fn from(value: Function) -> Self {
+        Self::Function(value)
+    }
+}
+
+impl From<Pointer> for Value {
+    fn from(value: Pointer) -> Self {
+        Self::ObjectReference(value)
+    }
+}
+
+/// Converts a map into an object which has not yet been allocated on the heap.
+impl<K: Into<Shared<str>>, V: Into<Value>> From<HashMap<K, V>> for Value {
+    fn from(value: HashMap<K, V>) -> Self {
+        Self::Object(
+            value
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Returns an error for a value which does not have the type being converted to.
+fn wrong_type(expected: Type, found: &Value) -> ConversionError {
+    ConversionError::WrongType {
+        expected,
+        found: found.slang_type(),
+    }
+}
+
+impl TryFrom<Value> for i32 {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Integer(value) => Ok(value),
+            other => Err(wrong_type(Type::Integer, &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        i32::try_from(value).map(i64::from)
+    }
+}
+
+impl TryFrom<Value> for usize {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let value = i32::try_from(value)?;
+
+        usize::try_from(value).map_err(|_| ConversionError::Negative { value })
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Float(value) => Ok(value),
+            other => Err(wrong_type(Type::Float, &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Boolean(value) => Ok(value),
+            other => Err(wrong_type(Type::Boolean, &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for Shared<str> {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(value) => Ok(value),
+            other => Err(wrong_type(Type::String, &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Shared::<str>::try_from(value).map(|value| value.to_string())
+    }
+}
+
+impl TryFrom<Value> for Function {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Function(function) => Ok(function),
+            other => Err(wrong_type(Type::Function, &other)),
+        }
+    }
+}
+
+/// Converts a reference to an object on the heap. Objects which have not been allocated have no pointer, so they are not converted.
+impl TryFrom<Value> for Pointer {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::ObjectReference(pointer) => Ok(pointer),
+            other => Err(wrong_type(Type::Object, &other)),
+        }
+    }
+}
+
+/// Converts an object which has not been allocated. References to objects on the heap must be read from the heap first, so they are not converted.
+impl TryFrom<Value> for Object {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Object(object) => Ok(object),
+            other => Err(wrong_type(Type::Object, &other)),
+        }
+    }
+}
+
+/// Types which can be built from the fields of an object, such as a struct with a field for each of the object's.
+pub trait FromObject: Sized {
+    fn from_object(object: &Object) -> Result<Self, ConversionError>;
+
+    /// Builds the type from a value, which must be an object that has not been allocated.
+    fn from_value(value: Value) -> Result<Self, ConversionError> {
+        Self::from_object(&Object::try_from(value)?)
+    }
+}
+
+/// Types which can be turned into an object, such as a struct with a field for each of the object's.
+pub trait IntoObject {
+    fn into_object(self) -> Object;
+}
+
+/// Converts a field of an object, failing if the object does not have it.
+pub fn field<T: TryFrom<Value, Error = ConversionError>>(
+    object: &Object,
+    name: &str,
+) -> Result<T, ConversionError> {
+    let value = object
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ConversionError::MissingField(name.to_string()))?;
+
+    T::try_from(value)
+}
+
+/// Implements [FromObject](crate::convert::FromObject) and [IntoObject](crate::convert::IntoObject) for a struct, with an object field of the same name for each of the listed struct fields. Each field's type must convert to a [Value](crate::value::Value) with [From], and back with [TryFrom].
+#[macro_export]
+macro_rules! object_conversions {
+    ($type:ident { $($field:ident),* $(,)? }) => {
+        impl $crate::convert::FromObject for $type {
+            fn from_object(
+                object: &$crate::heap::Object,
+            ) -> Result<Self, $crate::convert::ConversionError> {
+                Ok(Self {
+                    $($field: $crate::convert::field(object, stringify!($field))?,)*
+                })
+            }
+        }
+
+        impl $crate::convert::IntoObject for $type {
+            fn into_object(self) -> $crate::heap::Object {
+                let mut object = $crate::heap::Object::new();
+
+                $(object.insert(
+                    $crate::shared::Shared::from(stringify!($field)),
+                    $crate::value::Value::from(self.$field),
+                );)*
+
+                object
+            }
+        }
+    };
+}
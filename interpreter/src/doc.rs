@@ -0,0 +1,240 @@
+//! The documentation generator, which describes the functions and variables of slang files from their doc comments, for `slang doc`.
+//!
+//! Each file is a module, whose public items are its top-level functions and variables not starting with `_`. An item is documented by the doc comments (see [documentation]) directly before it, and a module by those at the start of its file which are followed by a blank line.
+
+use std::path::Path;
+
+use crate::{
+    interpreter::InterpreterError, lexer::Lexer, parser::Parser, source::Source,
+    statement::Statement, token::documentation, token_stream::TokenStream,
+};
+
+/// The formats documentation can be written in.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    /// Returns the format with a name (`markdown` or `html`) or file extension (`md`, `html` or `htm`), if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "markdown" | "md" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    /// Returns the format matching the extension of a path, if there is one.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_name)
+    }
+}
+
+/// The public items of a file, and its own documentation.
+pub struct Module {
+    pub name: String,
+    pub documentation: Option<String>,
+    pub items: Vec<Item>,
+}
+
+/// A function or variable declared at the top level of a module.
+pub struct Item {
+    pub identifier: String,
+    /// The parameters of a function, or [None] for a variable.
+    pub parameters: Option<Vec<String>>,
+    pub documentation: Option<String>,
+}
+
+impl Item {
+    /// Returns how the item is written in the heading which introduces it, such as `max(a, b)` for a function.
+    pub fn signature(&self) -> String {
+        match &self.parameters {
+            Some(parameters) => format!("{}({})", self.identifier, parameters.join(", ")),
+            None => self.identifier.clone(),
+        }
+    }
+}
+
+/// Lexes and parses a file, keeping its comments, and returns its module.
+pub fn document(name: &str, source: Source) -> Result<Module, InterpreterError> {
+    let (tokens, errors) = Lexer::with_trivia(source).lex();
+
+    if !errors.is_empty() {
+        return Err(InterpreterError::Lexer(errors));
+    }
+
+    let statements = Parser::new(TokenStream::new(tokens))
+        .parse()
+        .map_err(InterpreterError::Parser)?;
+
+    Ok(module(name, &statements))
+}
+
+/// Returns the module of a file which has been parsed with its comments kept.
+pub fn module(name: &str, statements: &[Statement]) -> Module {
+    let mut module = Module {
+        name: name.to_string(),
+        documentation: None,
+        items: Vec::new(),
+    };
+
+    // The lines of the doc comments since the last statement which is not a comment.
+    let mut lines: Vec<String> = Vec::new();
+    let mut start = true;
+
+    for statement in statements {
+        match statement {
+            Statement::Comment {
+                text,
+                trailing: false,
+            } => {
+                if let Some(line) = documentation(text) {
+                    lines.push(line);
+                }
+
+                continue;
+            }
+            Statement::BlankLine if start && !lines.is_empty() => {
+                module.documentation = Some(lines.join("\n"));
+            }
+            Statement::FunctionDefinition {
+                identifier,
+                parameters,
+                ..
+            } if !identifier.starts_with('_') => module.items.push(Item {
+                identifier: identifier.clone(),
+                parameters: Some(parameters.clone()),
+                documentation: join(&lines),
+            }),
+            Statement::VariableDeclaration { identifier, .. } if !identifier.starts_with('_') => {
+                module.items.push(Item {
+                    identifier: identifier.clone(),
+                    parameters: None,
+                    documentation: join(&lines),
+                })
+            }
+            _ => {}
+        }
+
+        lines.clear();
+        start = false;
+    }
+
+    module
+}
+
+/// Returns the documentation written over some lines, or [None] if there are none.
+fn join(lines: &[String]) -> Option<String> {
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Writes the documentation of some modules in a format.
+pub fn render(modules: &[Module], format: Format) -> String {
+    match format {
+        Format::Markdown => markdown(modules),
+        Format::Html => html(modules),
+    }
+}
+
+/// Writes the documentation of some modules as Markdown, with a section for each module, and a subsection for each of its functions and variables.
+pub fn markdown(modules: &[Module]) -> String {
+    let mut output = String::new();
+
+    for module in modules {
+        output.push_str(&format!("# {}\n\n", module.name));
+
+        if let Some(documentation) = &module.documentation {
+            output.push_str(&format!("{}\n\n", documentation));
+        }
+
+        for (heading, functions) in [("Functions", true), ("Variables", false)] {
+            let items = module
+                .items
+                .iter()
+                .filter(|item| item.parameters.is_some() == functions)
+                .collect::<Vec<_>>();
+
+            if items.is_empty() {
+                continue;
+            }
+
+            output.push_str(&format!("## {}\n\n", heading));
+
+            for item in items {
+                output.push_str(&format!("### `{}`\n\n", item.signature()));
+
+                if let Some(documentation) = &item.documentation {
+                    output.push_str(&format!("{}\n\n", documentation));
+                }
+            }
+        }
+    }
+
+    output.truncate(output.trim_end().len());
+    output.push('\n');
+
+    output
+}
+
+/// Writes the documentation of some modules as a standalone HTML page, laid out in the same way as [markdown].
+pub fn html(modules: &[Module]) -> String {
+    let mut output = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Documentation</title>\n</head>\n<body>\n",
+    );
+
+    let paragraphs = |output: &mut String, documentation: &str| {
+        for paragraph in documentation.split("\n\n") {
+            output.push_str(&format!("<p>{}</p>\n", escape(paragraph)));
+        }
+    };
+
+    for module in modules {
+        output.push_str(&format!("<h1>{}</h1>\n", escape(&module.name)));
+
+        if let Some(documentation) = &module.documentation {
+            paragraphs(&mut output, documentation);
+        }
+
+        for (heading, functions) in [("Functions", true), ("Variables", false)] {
+            let items = module
+                .items
+                .iter()
+                .filter(|item| item.parameters.is_some() == functions)
+                .collect::<Vec<_>>();
+
+            if items.is_empty() {
+                continue;
+            }
+
+            output.push_str(&format!("<h2>{}</h2>\n", heading));
+
+            for item in items {
+                output.push_str(&format!(
+                    "<h3 id=\"{}\"><code>{}</code></h3>\n",
+                    escape(&item.identifier),
+                    escape(&item.signature())
+                ));
+
+                if let Some(documentation) = &item.documentation {
+                    paragraphs(&mut output, documentation);
+                }
+            }
+        }
+    }
+
+    output.push_str("</body>\n</html>\n");
+
+    output
+}
+
+/// Escapes the characters which have a meaning in HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1,20 +1,28 @@
 //! Environments and scopes for the slang programming language.
 
-use std::{cell::RefCell, collections::HashMap, mem, rc::Rc};
+use std::{collections::HashMap, mem};
 
 use crate::{
     heap::Pointer,
+    shared::{Mutable, Shared},
+    suggest,
     value::{Function, NativeFunction, Value},
 };
 
 /// All errors which can occur while accessing the environment.
 pub enum EnvironmentError {
-    /// When there is an attempt to assign a value to a target which has not been defined.
-    UndefinedAssignmentTarget { identifier: String },
+    /// When there is an attempt to assign a value to a target which has not been defined, along with a similar target which is defined, if there is one.
+    UndefinedAssignmentTarget {
+        identifier: String,
+        suggestion: Option<String>,
+    },
     /// When there is an attempt to get the value of a target which has not been initialised.
     UninitialisedTarget { identifier: String },
-    /// When there is an attempt to get the value of a target which has not been defined.
-    UndefinedTarget { identifier: String },
+    /// When there is an attempt to get the value of a target which has not been defined, along with a similar target which is defined, if there is one.
+    UndefinedTarget {
+        identifier: String,
+        suggestion: Option<String>,
+    },
 }
 
 /// An [Environment] represents a set of scopes, stacked on top of one another.
@@ -27,30 +35,87 @@ pub struct Environment {
     scope: HashMap<String, Option<Value>>,
     /// Object references returned from functions.
     returned_object_references: Vec<Pointer>,
+    /// The number of scopes around this one, counting those of the calling functions, so the global scope's depth is 0.
+    depth: usize,
 }
 
-pub type MutEnvironment = Rc<RefCell<Environment>>;
+pub type MutEnvironment = Shared<Mutable<Environment>>;
+
+/// The native functions, along with the identifiers they are defined as in the global scope.
+pub const NATIVE_FUNCTIONS: [(&str, NativeFunction); 47] = [
+    ("print", NativeFunction::Print),
+    ("print_with", NativeFunction::PrintWith),
+    ("write", NativeFunction::Write),
+    ("eprint", NativeFunction::ErrorPrint),
+    ("format", NativeFunction::Format),
+    ("input", NativeFunction::Input),
+    ("int", NativeFunction::Int),
+    ("float", NativeFunction::Float),
+    ("gc", NativeFunction::Gc),
+    ("heap_dump", NativeFunction::HeapDump),
+    ("heap_stats", NativeFunction::HeapStats),
+    ("heap_objects", NativeFunction::HeapObjects),
+    ("stack_frames", NativeFunction::StackFrames),
+    ("memory_management", NativeFunction::MemoryManagement),
+    ("gc_config", NativeFunction::GcConfig),
+    ("gc_stats", NativeFunction::GcStats),
+    ("finalize", NativeFunction::Finalize),
+    ("spawn", NativeFunction::Spawn),
+    ("join", NativeFunction::Join),
+    ("channel", NativeFunction::Channel),
+    ("send", NativeFunction::Send),
+    ("recv", NativeFunction::Receive),
+    ("mutex", NativeFunction::Mutex),
+    ("lock", NativeFunction::Lock),
+    ("atomic", NativeFunction::Atomic),
+    ("atomic_add", NativeFunction::AtomicAdd),
+    ("exec", NativeFunction::Exec),
+    ("read_file", NativeFunction::ReadFile),
+    ("write_file", NativeFunction::WriteFile),
+    ("exists", NativeFunction::Exists),
+    ("mkdir", NativeFunction::MakeDirectory),
+    ("remove", NativeFunction::Remove),
+    ("list_dir", NativeFunction::ListDirectory),
+    ("regex_match", NativeFunction::RegexMatch),
+    ("regex_find_all", NativeFunction::RegexFindAll),
+    ("regex_replace", NativeFunction::RegexReplace),
+    ("error", NativeFunction::Error),
+    ("to_fixed", NativeFunction::ToFixed),
+    ("thousands", NativeFunction::Thousands),
+    ("pad_left", NativeFunction::PadLeft),
+    ("pad_right", NativeFunction::PadRight),
+    ("builder", NativeFunction::Builder),
+    ("append", NativeFunction::Append),
+    ("build", NativeFunction::Build),
+    ("inspect", NativeFunction::Inspect),
+    ("assert", NativeFunction::Assert),
+    ("assert_eq", NativeFunction::AssertEqual),
+];
 
 impl Environment {
-    /// Creates a new [Environment].
+    /// Creates a new [Environment], one scope deeper than its parent.
     pub fn new(parent: Option<MutEnvironment>) -> Self {
+        let depth = match &parent {
+            Some(parent) => parent.borrow().depth + 1,
+            None => 0,
+        };
+
+        Self::with_depth(parent, depth)
+    }
+
+    /// Creates a new [Environment] at a depth, such as for the frame of a function call, whose parent is the global scope but which is within the scopes of its caller.
+    pub fn with_depth(parent: Option<MutEnvironment>, depth: usize) -> Self {
         let mut scope = HashMap::new();
 
         if parent.is_none() {
-            [
-                ("print", NativeFunction::Print),
-                ("format", NativeFunction::Format),
-                ("input", NativeFunction::Input),
-                ("int", NativeFunction::Int),
-                ("float", NativeFunction::Float),
-            ]
-            .into_iter()
-            .for_each(|(identifier, function)| {
-                scope.insert(
-                    identifier.to_string(),
-                    Some(Value::Function(Function::Native(function))),
-                );
-            });
+            NATIVE_FUNCTIONS
+                .into_iter()
+                .for_each(|(identifier, function)| {
+                    scope.insert(
+                        identifier.to_string(),
+                        Some(Value::Function(Function::Native(function))),
+                    );
+                });
         }
 
         let returned_object_references = Vec::new();
@@ -59,6 +124,7 @@ impl Environment {
             scope,
             parent,
             returned_object_references,
+            depth,
         }
     }
 
@@ -75,48 +141,92 @@ impl Environment {
         identifier: String,
         value: Option<Value>,
     ) -> Result<Option<Value>, EnvironmentError> {
-        if let Some(target) = self.scope.get_mut(&identifier) {
-            let mut value = value;
-
-            mem::swap(target, &mut value);
+        match self.replace(&identifier, value) {
+            Some(previous) => Ok(previous),
+            None => Err(EnvironmentError::UndefinedAssignmentTarget {
+                suggestion: self.suggest(&identifier),
+                identifier,
+            }),
+        }
+    }
 
-            let previous = value;
+    /// Replaces the value of a target in the innermost scope it is defined in, returning its previous value, or [None] if it is not defined anywhere.
+    fn replace(&mut self, identifier: &str, value: Option<Value>) -> Option<Option<Value>> {
+        if let Some(target) = self.scope.get_mut(identifier) {
+            Some(mem::replace(target, value))
+        } else if let Some(parent) = &self.parent {
+            parent.borrow_mut().replace(identifier, value)
+        } else {
+            None
+        }
+    }
 
-            Ok(previous)
+    /// Returns the depth of the scope a target is defined in, if it is defined.
+    pub fn depth_of(&self, identifier: &str) -> Option<usize> {
+        if self.scope.contains_key(identifier) {
+            Some(self.depth)
         } else if let Some(parent) = &self.parent {
-            parent.borrow_mut().assign(identifier, value)
+            parent.borrow().depth_of(identifier)
         } else {
-            Err(EnvironmentError::UndefinedAssignmentTarget { identifier })
+            None
         }
     }
 
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
     /// Gets the value of a target.
     ///
     /// In order to find the target, the program starts in the innermost scope and works outwards until the target is found (or is not found anywhere).
     pub fn get(&self, identifier: &str) -> Result<Value, EnvironmentError> {
-        match self.scope.get(identifier) {
-            Some(Some(value)) => Ok(value.clone()),
+        match self.lookup(identifier) {
+            Some(Some(value)) => Ok(value),
             Some(None) => Err(EnvironmentError::UninitialisedTarget {
                 identifier: identifier.to_string(),
             }),
-            None => {
-                if let Some(parent) = &self.parent {
-                    parent.borrow().get(identifier)
-                } else {
-                    Err(EnvironmentError::UndefinedTarget {
-                        identifier: identifier.to_string(),
-                    })
-                }
-            }
+            None => Err(EnvironmentError::UndefinedTarget {
+                identifier: identifier.to_string(),
+                suggestion: self.suggest(identifier),
+            }),
+        }
+    }
+
+    /// Returns the value of a target from the innermost scope it is defined in, if it has been initialised, or [None] if it is not defined anywhere. Unlike [Environment::get], nothing is suggested for a target which is not defined, so this is cheaper when a target may well not be.
+    pub fn lookup(&self, identifier: &str) -> Option<Option<Value>> {
+        match self.scope.get(identifier) {
+            Some(value) => Some(value.clone()),
+            None => self.parent.as_ref()?.borrow().lookup(identifier),
         }
     }
 
+    /// Returns the defined target whose identifier is most similar to one which is not defined, as it may have been meant instead.
+    fn suggest(&self, identifier: &str) -> Option<String> {
+        let identifiers = self.identifiers();
+
+        suggest::closest(identifier, identifiers.iter().map(String::as_str)).map(str::to_string)
+    }
+
+    /// Returns the identifier of every target in the current scope and the scopes around it, in order.
+    pub fn identifiers(&self) -> Vec<String> {
+        let mut identifiers = self.scope.keys().cloned().collect::<Vec<_>>();
+
+        if let Some(parent) = &self.parent {
+            identifiers.extend(parent.borrow().identifiers());
+        }
+
+        identifiers.sort();
+        identifiers.dedup();
+
+        identifiers
+    }
+
     /// Gets the outermost scope.
     ///
-    /// Accepts an Rc<RefCell> to itself.
+    /// Accepts a shared pointer to itself.
     pub fn global(&self, self_reference: MutEnvironment) -> MutEnvironment {
         if let Some(parent) = &self.parent {
-            parent.borrow().global(Rc::clone(parent))
+            parent.borrow().global(Shared::clone(parent))
         } else {
             self_reference
         }
@@ -126,8 +236,17 @@ impl Environment {
         let mut roots = Vec::new();
 
         for value in self.scope.values() {
-            if let Some(Value::ObjectReference(pointer)) = value {
-                roots.push(pointer.clone());
+            match value {
+                Some(Value::ObjectReference(pointer)) => roots.push(*pointer),
+                // Objects stored inline are not on the heap, but the objects they reference are reachable.
+                Some(Value::Object(data)) => {
+                    for value in data.values() {
+                        if let Value::ObjectReference(pointer) = value {
+                            roots.push(*pointer);
+                        }
+                    }
+                }
+                _ => {}
             }
         }
 
@@ -148,11 +267,43 @@ impl Environment {
 
     pub fn parent(&self) -> Option<MutEnvironment> {
         match &self.parent {
-            Some(parent) => Some(Rc::clone(&parent)),
+            Some(parent) => Some(Shared::clone(&parent)),
             None => None,
         }
     }
 
+    /// Returns every target in the current scope, in order of identifier, with its value if it has been initialised.
+    pub fn bindings(&self) -> Vec<(String, Option<Value>)> {
+        let mut bindings = self
+            .scope
+            .iter()
+            .map(|(identifier, value)| (identifier.clone(), value.clone()))
+            .collect::<Vec<_>>();
+
+        bindings.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+        bindings
+    }
+
+    /// Returns every target in the current scope and the scopes around it, other than the global scope, in order of identifier, with its value if it has been initialised. Targets shadowed by those in an inner scope are left out.
+    pub fn locals(&self) -> Vec<(String, Option<Value>)> {
+        let Some(parent) = &self.parent else {
+            return Vec::new();
+        };
+
+        let mut locals = self.bindings();
+
+        for (identifier, value) in parent.borrow().locals() {
+            if !locals.iter().any(|(local, _)| *local == identifier) {
+                locals.push((identifier, value));
+            }
+        }
+
+        locals.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+        locals
+    }
+
     pub fn values(&self) -> Vec<Value> {
         self.scope
             .iter()
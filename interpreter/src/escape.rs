@@ -0,0 +1,87 @@
+//! Escape analysis, which finds the object literals that can be stored inline in the environment rather than on the heap.
+
+use crate::{
+    expression::Expression,
+    statement::Statement,
+    visit::{Visitor, VisitorMut, walk_expression, walk_statement_mut},
+};
+
+/// Marks every variable declaration in a block whose object literal never escapes it, so that the object is stored inline.
+///
+/// An object escapes if its variable is used in any way other than getting or setting one of its fields, such as `point.x` or `point.x = 1`. Otherwise, nothing but the variable can ever refer to the object, so it needs no identity on the heap. Only the statements after the declaration in the same block need to be searched, as a function's frame cannot see the blocks it was called from. Top-level declarations are never marked, as they can be used by any function, and by later lines in the REPL.
+pub fn analyse(statements: &mut [Statement]) {
+    for statement in statements {
+        Analysis.visit_statement(statement);
+    }
+}
+
+/// Marks the declarations in every block which do not escape it.
+struct Analysis;
+
+impl VisitorMut for Analysis {
+    fn visit_statement(&mut self, statement: &mut Statement) {
+        let Statement::Block(statements) = statement else {
+            return walk_statement_mut(self, statement);
+        };
+
+        let mut remaining = statements.as_mut_slice();
+
+        while let Some((statement, rest)) = remaining.split_first_mut() {
+            if let Statement::VariableDeclaration {
+                identifier,
+                initialiser: Some(Expression::Object { .. }),
+                inline,
+                ..
+            } = statement
+            {
+                let mut escapes = Escapes {
+                    identifier,
+                    escapes: false,
+                };
+
+                for statement in rest.iter() {
+                    escapes.visit_statement(statement);
+                }
+
+                *inline = !escapes.escapes;
+            }
+
+            self.visit_statement(statement);
+            remaining = rest;
+        }
+    }
+}
+
+/// Finds whether a variable is used in any way other than getting or setting its fields.
+struct Escapes<'a> {
+    identifier: &'a str,
+    escapes: bool,
+}
+
+impl Visitor<'_> for Escapes<'_> {
+    fn visit_expression(&mut self, expression: &Expression) {
+        let is_variable = |expression: &Expression| matches!(expression, Expression::Variable { identifier: used, .. } if used == self.identifier);
+
+        match expression {
+            Expression::GetField { object, .. } if is_variable(object) => {}
+            Expression::SetField { object, value, .. } if is_variable(object) => {
+                self.visit_expression(value)
+            }
+            Expression::Variable {
+                identifier: used, ..
+            } => {
+                self.escapes |= used == self.identifier;
+            }
+            // Reassigning the variable is treated as an escape, so that its object is only ever dropped with its scope.
+            Expression::Assignment {
+                identifier: assigned,
+                value,
+                ..
+            } => {
+                self.escapes |= assigned == self.identifier;
+                self.visit_expression(value);
+            }
+            _ => walk_expression(self, expression),
+        }
+    }
+}
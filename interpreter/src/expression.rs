@@ -4,15 +4,22 @@ use std::{
     collections::HashMap,
     error::Error,
     fmt::{Debug, Display},
-    io::{self, Write},
+    fs,
+    io::Write,
 };
 
 use crate::{
+    capabilities, channel,
+    convert::ConversionError,
     environment::EnvironmentError,
-    heap::{ManagedHeap, Pointer},
+    files,
+    heap::{HeapError, Limit, ManagedHeap, Object, Pointer, dump},
+    inspect, patterns,
+    shared::Shared,
+    source::{FileId, Location, Span},
     stack::Stack,
-    statement::ControlFlow,
-    stats::Logger,
+    statement::{self, ControlFlow, Statement},
+    stats, sync, text, thread, tuning,
     value::{Function, NativeFunction, Type, Value},
 };
 
@@ -35,9 +42,10 @@ pub enum EvaluationError {
     },
     /// When a division by zero occurs.
     DivisionByZero,
-    /// When there is an attempt to get the value of a variable which has not been defined.
+    /// When there is an attempt to get the value of a variable which has not been defined, along with a similar variable which is defined, if there is one.
     UndefinedIdentifier {
         identifier: String,
+        suggestion: Option<String>,
     },
     /// When there is an attempt to get the value of a variable which has not been initialised.
     UninitialisedTarget {
@@ -55,6 +63,16 @@ pub enum EvaluationError {
         expected: usize,
         passed: usize,
     },
+    /// When an argument to a native function does not have the expected type.
+    InvalidArgumentType {
+        expected: Type,
+        passed: Type,
+    },
+    /// When a heap dump could not be written to a file.
+    HeapDumpFailed {
+        path: String,
+        error: String,
+    },
     AttemptToUseNothing,
     AttemptToAccessNonObject {
         attempt: Type,
@@ -64,17 +82,123 @@ pub enum EvaluationError {
         from: Value,
         to: Type,
     },
+    /// When an object is used after it has been freed, which means that the heap freed an object that was still reachable.
+    UseAfterFree {
+        pointer: Pointer,
+    },
+    /// When an object could not be allocated without exceeding one of the heap's limits.
+    OutOfMemory {
+        limit: Limit,
+    },
+    /// When an integer given to or returned by a native function does not fit within an Integer.
+    IntegerOutOfRange {
+        value: i64,
+    },
+    /// When an Integer given to a native function is negative, but must not be, such as a width.
+    NegativeInteger {
+        value: i32,
+    },
+    /// When the program was interrupted before it finished.
+    Interrupted,
+    /// When the program took more steps (statements executed and expressions evaluated) than it was allowed to.
+    ResourceLimitExceeded {
+        steps: usize,
+    },
+    /// When a native function loaded from a plugin failed.
+    PluginFailed {
+        function: String,
+        message: String,
+    },
+    /// When a thread could not be spawned, or stopped with an error.
+    ThreadFailed {
+        message: String,
+    },
+    /// When a thread is joined with a handle which was never returned by `spawn`, or which has already been joined.
+    UnknownThread {
+        handle: i32,
+    },
+    /// When a message could not be copied between threads.
+    MessageFailed {
+        message: String,
+    },
+    /// When a channel is used with a handle which was never returned by `channel`.
+    UnknownChannel {
+        handle: i32,
+    },
+    /// When a mutex is locked with a handle which was never returned by `mutex`.
+    UnknownMutex {
+        handle: i32,
+    },
+    /// When an atomic integer is used with a handle which was never returned by `atomic`.
+    UnknownAtomic {
+        handle: i32,
+    },
+    /// When a builder is used with a handle which was never returned by `builder`, or which has already been built.
+    UnknownBuilder {
+        handle: i32,
+    },
+    /// When a native function is called without the capability it needs, which is given by a command line option.
+    CapabilityDenied {
+        function: String,
+        option: String,
+    },
+    /// When a shell command could not be run.
+    ExecFailed {
+        command: String,
+        error: String,
+    },
+    /// When a file or directory could not be read, written, created or removed.
+    FileSystemFailed {
+        path: String,
+        error: String,
+    },
+    /// When a regular expression is not valid.
+    InvalidPattern {
+        pattern: String,
+        error: String,
+    },
+    /// When the program raised an error itself, by calling `error`.
+    Raised {
+        message: String,
+    },
+    /// When text is padded with something other than a single character.
+    InvalidFill {
+        fill: String,
+    },
+    /// When an assertion made by `assert` or `assert_eq` does not hold.
+    AssertionFailed {
+        message: String,
+    },
+    /// When `gc_config` is given a setting which the garbage collector does not have.
+    UnknownGcSetting {
+        setting: String,
+    },
+    /// When `gc_config` is given a value which a setting cannot take, such as a growth factor below 1.
+    InvalidGcSetting {
+        setting: String,
+        value: Value,
+    },
+    /// An error, along with the source code of the innermost expression or statement which it occurred within. See [EvaluationError::at].
+    Located {
+        error: Box<EvaluationError>,
+        span: Span,
+    },
 }
 
 impl From<EnvironmentError> for EvaluationError {
     fn from(value: EnvironmentError) -> Self {
         match value {
-            EnvironmentError::UndefinedAssignmentTarget { identifier } => {
-                Self::UndefinedIdentifier { identifier }
-            }
-            EnvironmentError::UndefinedTarget { identifier } => {
-                Self::UndefinedIdentifier { identifier }
+            EnvironmentError::UndefinedAssignmentTarget {
+                identifier,
+                suggestion,
             }
+            | EnvironmentError::UndefinedTarget {
+                identifier,
+                suggestion,
+            } => Self::UndefinedIdentifier {
+                identifier,
+                suggestion,
+            },
             EnvironmentError::UninitialisedTarget { identifier } => {
                 Self::UninitialisedTarget { identifier }
             }
@@ -82,8 +206,35 @@ impl From<EnvironmentError> for EvaluationError {
     }
 }
 
+impl From<HeapError> for EvaluationError {
+    fn from(value: HeapError) -> Self {
+        match value {
+            HeapError::UseAfterFree { pointer } => Self::UseAfterFree { pointer },
+            HeapError::OutOfMemory { limit } => Self::OutOfMemory { limit },
+        }
+    }
+}
+
+impl From<ConversionError> for EvaluationError {
+    fn from(value: ConversionError) -> Self {
+        match value {
+            ConversionError::WrongType { expected, found } => Self::InvalidArgumentType {
+                expected,
+                passed: found,
+            },
+            ConversionError::OutOfRange { value } => Self::IntegerOutOfRange { value },
+            ConversionError::Negative { value } => Self::NegativeInteger { value },
+            ConversionError::MissingField(field) => Self::UndefinedField(field),
+        }
+    }
+}
+
 impl Display for EvaluationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Self::Located { error, span } = self {
+            return write!(f, "{} {}", span, error);
+        }
+
         write!(f, "[evaluation error] ")?;
 
         match self {
@@ -115,8 +266,16 @@ impl Display for EvaluationError {
             Self::DivisionByZero => {
                 write!(f, "Division by zero.")
             }
-            Self::UndefinedIdentifier { identifier } => {
-                write!(f, "The identifier `{}` is not defined.", identifier)
+            Self::UndefinedIdentifier {
+                identifier,
+                suggestion,
+            } => {
+                write!(f, "The identifier `{}` is not defined.", identifier)?;
+
+                match suggestion {
+                    Some(suggestion) => write!(f, " Did you mean `{}`?", suggestion),
+                    None => Ok(()),
+                }
             }
             Self::UninitialisedTarget { identifier } => {
                 write!(f, "The target `{}` has not been initialised.", identifier)
@@ -145,6 +304,16 @@ impl Display for EvaluationError {
                     expected, passed
                 )
             }
+            Self::InvalidArgumentType { expected, passed } => {
+                write!(
+                    f,
+                    "Expected an argument of type {}, but received {}.",
+                    expected, passed
+                )
+            }
+            Self::HeapDumpFailed { path, error } => {
+                write!(f, "Unable to write a heap dump to `{}`: {}", path, error)
+            }
             Self::AttemptToUseNothing => write!(
                 f,
                 "Attempted to use the return value from a function, however the function returned nothing."
@@ -164,6 +333,76 @@ impl Display for EvaluationError {
             Self::CastingError { from, to } => {
                 write!(f, "Unable to cast from {:?} to {}.", from, to)
             }
+            Self::UseAfterFree { pointer } => {
+                write!(f, "{}", HeapError::UseAfterFree { pointer: *pointer })
+            }
+            Self::OutOfMemory { limit } => {
+                write!(f, "{}", HeapError::OutOfMemory { limit: *limit })
+            }
+            Self::IntegerOutOfRange { value } => {
+                write!(f, "{}", ConversionError::OutOfRange { value: *value })
+            }
+            Self::NegativeInteger { value } => {
+                write!(f, "{}", ConversionError::Negative { value: *value })
+            }
+            Self::Interrupted => write!(f, "The program was interrupted."),
+            Self::ResourceLimitExceeded { steps } => write!(
+                f,
+                "The program exceeded its limit of {} steps, so it was stopped.",
+                steps
+            ),
+            Self::PluginFailed { function, message } => {
+                write!(f, "The native function `{}` failed: {}", function, message)
+            }
+            Self::ThreadFailed { message } => write!(f, "A thread failed: {}", message),
+            Self::UnknownThread { handle } => {
+                write!(f, "There is no thread to join with the handle {}.", handle)
+            }
+            Self::MessageFailed { message } => {
+                write!(f, "A message could not be passed: {}", message)
+            }
+            Self::UnknownChannel { handle } => {
+                write!(f, "There is no channel with the handle {}.", handle)
+            }
+            Self::UnknownMutex { handle } => {
+                write!(f, "There is no mutex with the handle {}.", handle)
+            }
+            Self::UnknownAtomic { handle } => {
+                write!(f, "There is no atomic integer with the handle {}.", handle)
+            }
+            Self::UnknownBuilder { handle } => {
+                write!(f, "There is no builder with the handle {}.", handle)
+            }
+            Self::CapabilityDenied { function, option } => write!(
+                f,
+                "The native function `{}` is not allowed unless the interpreter is run with `{}`.",
+                function, option
+            ),
+            Self::ExecFailed { command, error } => {
+                write!(f, "Unable to run the command `{}`: {}", command, error)
+            }
+            Self::FileSystemFailed { path, error } => {
+                write!(f, "Unable to use `{}`: {}", path, error)
+            }
+            Self::InvalidPattern { pattern, error } => {
+                write!(f, "The pattern `{}` is not valid: {}", pattern, error)
+            }
+            Self::Raised { message } => write!(f, "{}", message),
+            Self::InvalidFill { fill } => write!(
+                f,
+                "Text can only be padded with a single character, but received \"{}\".",
+                fill
+            ),
+            Self::AssertionFailed { message } => write!(f, "Assertion failed: {}", message),
+            Self::UnknownGcSetting { setting } => {
+                write!(f, "The garbage collector has no setting `{}`.", setting)
+            }
+            Self::InvalidGcSetting { setting, value } => write!(
+                f,
+                "The garbage collector's `{}` cannot be set to {:?}.",
+                setting, value
+            ),
+            Self::Located { .. } => unreachable!(),
         }
     }
 }
@@ -176,7 +415,47 @@ impl Debug for EvaluationError {
 
 impl Error for EvaluationError {}
 
+impl EvaluationError {
+    /// Locates the error at some source code, unless it has already been located. Errors are located as they leave each expression or statement with a span, so an error is located at the innermost one, such as a call within a function rather than the call of the function.
+    ///
+    /// The exception is an error located within the prelude, which is located again where the prelude was called from, as the mistake is more likely to have been made there (such as unwrapping a result which failed).
+    pub fn at(self, span: Option<Span>) -> Self {
+        let in_prelude = |span: &Span| span.start().file() == Some(FileId::PRELUDE);
+
+        match (self, span) {
+            (Self::Located { error, span: inner }, Some(span))
+                if in_prelude(&inner) && !in_prelude(&span) =>
+            {
+                Self::Located { error, span }
+            }
+            (error @ Self::Located { .. }, _) | (error, None) => error,
+            (error, Some(span)) => Self::Located {
+                error: Box::new(error),
+                span,
+            },
+        }
+    }
+
+    /// Returns the source code which the error occurred within, if it is known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Located { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its location.
+    pub fn unlocated(&self) -> &Self {
+        match self {
+            Self::Located { error, .. } => error,
+            error => error,
+        }
+    }
+}
+
 /// Represents all possible expressions within the slang programming language.
+///
+/// Each expression which can fail has a `span`, the source code it was parsed from, where its errors are reported. It is [None] for expressions built by the interpreter itself, such as the call of a finalizer, whose errors are reported where the expression which built them was.
 #[derive(Clone, PartialEq)]
 pub enum Expression {
     /// Ternary expressions, in the form `condition ? if_true : if_false`.
@@ -184,27 +463,32 @@ pub enum Expression {
         condition: Box<Expression>,
         left: Box<Expression>,
         right: Box<Expression>,
+        span: Option<Span>,
     },
     /// Binary expressions, in the form `left operator right`.
     Binary {
         left: Box<Expression>,
         operator: BinaryOperator,
         right: Box<Expression>,
+        span: Option<Span>,
     },
     /// Unary expressions, in the form `operator operand`.
     Unary {
         operator: UnaryOperator,
         operand: Box<Expression>,
+        span: Option<Span>,
     },
     /// A function call.
     Call {
         function: Box<Expression>,
         arguments: Vec<Box<Expression>>,
+        span: Option<Span>,
     },
     /// An assignment expression, which yields the assigned value.
     Assignment {
         identifier: String,
         value: Box<Expression>,
+        span: Option<Span>,
     },
     /// An expression surrounded by parenthesis.
     Grouping {
@@ -217,71 +501,151 @@ pub enum Expression {
     /// A reference to a variable.
     Variable {
         identifier: String,
+        span: Option<Span>,
     },
     GetField {
         object: Box<Expression>,
         field: String,
+        span: Option<Span>,
     },
     SetField {
         object: Box<Expression>,
         field: String,
         value: Box<Expression>,
+        span: Option<Span>,
+    },
+    /// An object literal, with its fields in the order they were written.
+    Object {
+        fields: Vec<(String, Expression)>,
+        /// The location of its opening brace, which is recorded as the allocation site of the objects made from it.
+        location: Location,
+    },
+    /// A block expression, in the form `{ ... }`, which yields the value of its final expression statement.
+    Block {
+        /// A block.
+        block: Box<Statement>,
+    },
+    /// An if-expression, in the form `if condition { ... } else { ... }`, which yields the value of the branch taken.
+    If {
+        condition: Box<Expression>,
+        /// A block.
+        execute_if_true: Box<Statement>,
+        /// A block, or an if-statement for `else if`, which the parser ensures has an `else` branch of its own.
+        execute_if_false: Box<Statement>,
+        span: Option<Span>,
     },
-    Object(HashMap<String, Expression>),
 }
 
 impl Expression {
+    /// Returns the name of the kind of expression this is, as it is counted in stats.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Ternary { .. } => "ternary",
+            Self::Binary { .. } => "binary operation",
+            Self::Unary { .. } => "unary operation",
+            Self::Call { .. } => "call",
+            Self::Assignment { .. } => "assignment",
+            Self::Grouping { .. } => "grouping",
+            Self::Literal { .. } => "literal",
+            Self::Variable { .. } => "variable",
+            Self::GetField { .. } => "field access",
+            Self::SetField { .. } => "field assignment",
+            Self::Object { .. } => "object literal",
+            Self::Block { .. } => "block expression",
+            Self::If { .. } => "if expression",
+        }
+    }
+
     /// Evaluates an expression, returning an error if it is nothing.
     pub fn evaluate_not_nothing(
         self,
         stack: &mut Stack,
         heap: &mut ManagedHeap,
-        logger: &mut Logger,
     ) -> Result<Value, EvaluationError> {
-        self.evaluate(stack, heap, logger)
-            .map(|value| match value {
-                Some(value) => Ok(value),
-                None => Err(EvaluationError::AttemptToUseNothing),
-            })?
+        let span = self.span();
+
+        self.evaluate(stack, heap).map(|value| match value {
+            Some(value) => Ok(value),
+            None => Err(EvaluationError::AttemptToUseNothing.at(span)),
+        })?
     }
 
-    /// Evaluates the expression.
+    /// Returns the source code which the expression was parsed from, if it is one which can fail, and was parsed rather than built by the interpreter.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Ternary { span, .. }
+            | Self::Binary { span, .. }
+            | Self::Unary { span, .. }
+            | Self::Call { span, .. }
+            | Self::Assignment { span, .. }
+            | Self::Variable { span, .. }
+            | Self::GetField { span, .. }
+            | Self::SetField { span, .. }
+            | Self::If { span, .. } => *span,
+            Self::Grouping { .. }
+            | Self::Literal { .. }
+            | Self::Object { .. }
+            | Self::Block { .. } => None,
+        }
+    }
+
+    /// Evaluates the expression, locating any error which occurs within it at its span.
     pub fn evaluate(
         self,
         stack: &mut Stack,
         heap: &mut ManagedHeap,
-        logger: &mut Logger,
     ) -> Result<Option<Value>, EvaluationError> {
+        let span = self.span();
+
+        self.evaluate_unlocated(stack, heap)
+            .map_err(|error| error.at(span))
+    }
+
+    fn evaluate_unlocated(
+        self,
+        stack: &mut Stack,
+        heap: &mut ManagedHeap,
+    ) -> Result<Option<Value>, EvaluationError> {
+        stack.step()?;
+        stats::expression(self.kind());
+
         match self {
             Self::Ternary {
                 condition,
                 left,
                 right,
-            } => Expression::evaluate_ternary(stack, heap, logger, condition, left, right),
+                ..
+            } => Expression::evaluate_ternary(stack, heap, condition, left, right),
 
             Self::Binary {
                 left,
                 operator,
                 right,
-            } => Expression::evaluate_binary(stack, heap, logger, left, operator, right),
+                ..
+            } => Expression::evaluate_binary(stack, heap, left, operator, right),
 
-            Self::Unary { operator, operand } => {
-                Expression::evaluate_unary(stack, heap, logger, operator, operand)
-            }
+            Self::Unary {
+                operator, operand, ..
+            } => Expression::evaluate_unary(stack, heap, operator, operand),
 
             Self::Call {
                 function,
                 arguments,
-            } => Expression::evaluate_call(stack, heap, logger, function, arguments),
+                ..
+            } => Expression::evaluate_call(stack, heap, function, arguments),
 
-            Self::Assignment { identifier, value } => {
-                let next = value.evaluate(stack, heap, logger)?;
+            Self::Assignment {
+                identifier, value, ..
+            } => {
+                let next = value.evaluate(stack, heap)?;
 
                 let next = match next {
-                    Some(Value::Object(data)) => Some(Value::ObjectReference(heap.allocate(data))),
+                    Some(Value::Object(data)) => Some(Value::ObjectReference(
+                        heap.allocate(data, || stack.roots())?,
+                    )),
                     Some(Value::ObjectReference(ref pointer)) => {
                         if let ManagedHeap::ReferenceCounted(heap) = heap {
-                            heap.increment(Pointer::clone(pointer));
+                            heap.increment(*pointer);
                         }
 
                         next
@@ -299,7 +663,21 @@ impl Expression {
 
                 */
 
-                let previous = stack.top().borrow_mut().assign(identifier, next.clone())?;
+                let previous = stack
+                    .top()
+                    .borrow_mut()
+                    .assign(identifier.clone(), next.clone())?;
+
+                stack
+                    .watches
+                    .variable(&identifier, previous.as_ref(), next.as_ref());
+
+                // An object assigned to a variable in an outer scope escapes to that scope's region.
+                if let (ManagedHeap::Region(heap), Some(next)) = (&mut *heap, &next)
+                    && let Some(depth) = stack.top().borrow().depth_of(&identifier)
+                {
+                    heap.promote_value(next, depth);
+                }
 
                 if let (Some(previous), ManagedHeap::ReferenceCounted(heap)) = (previous, heap) {
                     heap.conditionally_decrement(previous);
@@ -308,23 +686,23 @@ impl Expression {
                 Ok(next)
             }
 
-            Self::Grouping { contained } => contained.evaluate(stack, heap, logger),
+            Self::Grouping { contained } => contained.evaluate(stack, heap),
 
             Self::Literal { value } => Ok(Some(value)),
 
-            Self::Variable { identifier } => Ok(Some(stack.top().borrow().get(&identifier)?)),
+            Self::Variable { identifier, .. } => Ok(Some(stack.top().borrow().get(&identifier)?)),
 
-            Self::GetField { object, field } => {
-                match object.evaluate_not_nothing(stack, heap, logger)? {
+            Self::GetField { object, field, .. } => {
+                match object.evaluate_not_nothing(stack, heap)? {
                     Value::ObjectReference(pointer) => {
-                        if let Some(value) = pointer.borrow().data.get(&field).cloned() {
+                        if let Some(value) = heap.get(pointer)?.data.get(field.as_str()).cloned() {
                             Ok(Some(value))
                         } else {
                             Err(EvaluationError::UndefinedField(field))
                         }
                     }
                     Value::Object(fields) => {
-                        if let Some(value) = fields.get(&field).cloned() {
+                        if let Some(value) = fields.get(field.as_str()).cloned() {
                             Ok(Some(value))
                         } else {
                             Err(EvaluationError::UndefinedField(field))
@@ -340,72 +718,191 @@ impl Expression {
                 object,
                 field,
                 value,
-            } => match object.evaluate_not_nothing(stack, heap, logger)? {
-                Value::ObjectReference(pointer) => {
-                    let next = value.evaluate_not_nothing(stack, heap, logger)?;
-
-                    let next = match next {
-                        Value::Object(data) => Value::ObjectReference(heap.allocate(data)),
-                        Value::ObjectReference(ref pointer) => {
-                            if let ManagedHeap::ReferenceCounted(heap) = heap {
-                                heap.increment(Pointer::clone(pointer));
-                            }
+                ..
+            } => {
+                // An object stored inline can only be reached through its variable, so the variable is updated instead.
+                let variable = match object.as_ref() {
+                    Self::Variable { identifier, .. } => Some(identifier.clone()),
+                    _ => None,
+                };
 
-                            next
+                match (object.evaluate_not_nothing(stack, heap)?, variable) {
+                    (Value::ObjectReference(pointer), variable) => {
+                        let temporaries = stack.temporaries_count();
+                        stack.hold_temporary(&Value::ObjectReference(pointer));
+
+                        // The object stays held while the value is allocated, in case a collection is forced.
+                        let next = match value.evaluate_not_nothing(stack, heap) {
+                            Ok(Value::Object(data)) => heap
+                                .allocate(data, || stack.roots())
+                                .map(Value::ObjectReference)
+                                .map_err(EvaluationError::from),
+                            Ok(Value::ObjectReference(pointer)) => {
+                                if let ManagedHeap::ReferenceCounted(heap) = heap {
+                                    heap.increment(pointer);
+                                }
+
+                                Ok(Value::ObjectReference(pointer))
+                            }
+                            next => next,
+                        };
+                        stack.release_temporaries(temporaries);
+                        let next = next?;
+
+                        let previous = heap.set_field(pointer, &field, next.clone())?;
+
+                        stack
+                            .watches
+                            .field(variable.as_deref(), &field, previous.as_ref(), &next);
+
+                        match heap {
+                            ManagedHeap::GarbageCollected(heap) => heap.write_barrier(&next),
+                            ManagedHeap::Generational(heap) => heap.write_barrier(pointer, &next),
+                            // An object stored in a field of an object in an outer region escapes to that region.
+                            ManagedHeap::Region(heap) => {
+                                if let Some(depth) = heap.region_of(pointer) {
+                                    heap.promote_value(&next, depth);
+                                }
+                            }
+                            _ => {}
                         }
-                        _ => next,
-                    };
 
-                    let previous = pointer.borrow_mut().data.insert(field, next.clone());
+                        if let (ManagedHeap::ReferenceCounted(heap), Some(previous)) =
+                            (heap, previous)
+                        {
+                            heap.conditionally_decrement(previous);
+                        }
 
-                    if let (ManagedHeap::ReferenceCounted(heap), Some(previous)) = (heap, previous)
-                    {
-                        heap.conditionally_decrement(previous);
+                        Ok(None)
                     }
+                    (Value::Object(_), Some(identifier)) => {
+                        let next = match value.evaluate_not_nothing(stack, heap)? {
+                            Value::Object(data) => {
+                                Value::ObjectReference(heap.allocate(data, || stack.roots())?)
+                            }
+                            Value::ObjectReference(pointer) => {
+                                if let ManagedHeap::ReferenceCounted(heap) = heap {
+                                    heap.increment(pointer);
+                                }
 
-                    Ok(None)
-                }
-                attempt => Err(EvaluationError::AttemptToAccessNonObject {
-                    attempt: attempt.slang_type(),
-                }),
-            },
+                                Value::ObjectReference(pointer)
+                            }
+                            next => next,
+                        };
+
+                        // The object is read after the value is evaluated, in case that set any of its other fields.
+                        let mut data = match stack.top().borrow().get(&identifier)? {
+                            Value::Object(data) => data,
+                            attempt => {
+                                return Err(EvaluationError::AttemptToAccessNonObject {
+                                    attempt: attempt.slang_type(),
+                                });
+                            }
+                        };
+
+                        let previous = data.insert(field.as_str().into(), next.clone());
+
+                        stack
+                            .watches
+                            .field(Some(&identifier), &field, previous.as_ref(), &next);
+
+                        // The object is stored in its variable's scope, so what it now references escapes to that scope's region.
+                        if let ManagedHeap::Region(heap) = heap
+                            && let Some(depth) = stack.top().borrow().depth_of(&identifier)
+                        {
+                            heap.promote_value(&next, depth);
+                        }
+
+                        stack
+                            .top()
+                            .borrow_mut()
+                            .assign(identifier, Some(Value::Object(data)))?;
+
+                        if let ManagedHeap::GarbageCollected(heap) = heap {
+                            heap.write_barrier(&next);
+                        }
 
-            Self::Object(unevaluated_fields) => {
-                let mut fields = HashMap::new();
+                        if let (ManagedHeap::ReferenceCounted(heap), Some(previous)) =
+                            (heap, previous)
+                        {
+                            heap.conditionally_decrement(previous);
+                        }
 
-                for (identifier, expression) in unevaluated_fields.into_iter() {
-                    /* We evaluate the expression, and if it is an Object, then the Object itself will be inserted into fields,
-                    but if it is an ObjectReference then the pointer will be inserted into fields. Note that that the reference count
-                    is not incremented, but this is correct, as the Object being evaluated has not yet been assigned to anything, so its children
-                    should not have their reference counts incremented.
-                    */
-                    fields.insert(
-                        identifier,
-                        expression.evaluate_not_nothing(stack, heap, logger)?,
-                    );
+                        Ok(None)
+                    }
+                    (attempt, _) => Err(EvaluationError::AttemptToAccessNonObject {
+                        attempt: attempt.slang_type(),
+                    }),
                 }
+            }
+
+            Self::Object { fields, location } => {
+                let temporaries = stack.temporaries_count();
+                let fields = Self::evaluate_fields(fields, stack, heap);
+                stack.release_temporaries(temporaries);
+
+                // Any object literals nested within this one have already been evaluated, so they are attributed to this site too.
+                heap.set_site(location);
 
-                Ok(Some(Value::Object(fields)))
+                Ok(Some(Value::Object(fields?)))
             }
+
+            Self::Block { block } => statement::evaluate_block(*block, stack, heap),
+
+            Self::If {
+                condition,
+                execute_if_true,
+                execute_if_false,
+                ..
+            } => match condition.evaluate_not_nothing(stack, heap)? {
+                Value::Boolean(true) => statement::evaluate_block(*execute_if_true, stack, heap),
+                Value::Boolean(false) => statement::evaluate_block(*execute_if_false, stack, heap),
+                condition => Err(EvaluationError::NonBooleanControlFlowCondition {
+                    condition: condition.slang_type(),
+                    control_flow: "if-expression".to_string(),
+                }),
+            },
+        }
+    }
+
+    /// Evaluates the fields of an object literal, holding each as a temporary so that it is not freed while the rest are evaluated.
+    fn evaluate_fields(
+        unevaluated_fields: Vec<(String, Expression)>,
+        stack: &mut Stack,
+        heap: &mut ManagedHeap,
+    ) -> Result<Object, EvaluationError> {
+        let mut fields = HashMap::new();
+
+        for (identifier, expression) in unevaluated_fields.into_iter() {
+            /* We evaluate the expression, and if it is an Object, then the Object itself will be inserted into fields,
+            but if it is an ObjectReference then the pointer will be inserted into fields. Note that that the reference count
+            is not incremented, but this is correct, as the Object being evaluated has not yet been assigned to anything, so its children
+            should not have their reference counts incremented.
+            */
+            let value = expression.evaluate_not_nothing(stack, heap)?;
+
+            stack.hold_temporary(&value);
+            fields.insert(identifier.into(), value);
         }
+
+        Ok(fields)
     }
 
     /// Evaluates a ternary expression.
     fn evaluate_ternary(
         stack: &mut Stack,
         heap: &mut ManagedHeap,
-        logger: &mut Logger,
         condition: Box<Expression>,
         left: Box<Expression>,
         right: Box<Expression>,
     ) -> Result<Option<Value>, EvaluationError> {
-        let condition = condition.evaluate_not_nothing(stack, heap, logger)?;
+        let condition = condition.evaluate_not_nothing(stack, heap)?;
 
         if let Value::Boolean(condition) = condition {
             if condition {
-                return left.evaluate(stack, heap, logger);
+                return left.evaluate(stack, heap);
             } else {
-                return right.evaluate(stack, heap, logger);
+                return right.evaluate(stack, heap);
             }
         } else {
             return Err(EvaluationError::NonBooleanTernaryCondition {
@@ -418,18 +915,20 @@ impl Expression {
     fn evaluate_binary(
         stack: &mut Stack,
         heap: &mut ManagedHeap,
-        logger: &mut Logger,
         left: Box<Expression>,
         operator: BinaryOperator,
         right: Box<Expression>,
     ) -> Result<Option<Value>, EvaluationError> {
         Ok(Some(match operator {
-            BinaryOperator::Add => match Self::binary_operands(left, right, stack, heap, logger)? {
+            BinaryOperator::Add => match Self::binary_operands(left, right, stack, heap)? {
                 (Value::String(left), Value::String(right)) => {
-                    let mut new = left;
+                    let mut new = left.to_string();
                     new.push_str(&right);
-                    Value::String(new)
+                    Value::String(new.into())
                 }
+                // Anything added to a string is concatenated in the form `print` writes it in.
+                (Value::String(left), right) => Value::String(format!("{}{}", left, right).into()),
+                (left, Value::String(right)) => Value::String(format!("{}{}", left, right).into()),
                 (Value::Integer(left), Value::Integer(right)) => Value::Integer(left + right),
                 (Value::Float(left), Value::Float(right)) => Value::Float(left + right),
                 (left, right) => Err(EvaluationError::InvalidBinaryTypes {
@@ -439,92 +938,140 @@ impl Expression {
                 })?,
             },
 
-            BinaryOperator::Subtract => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::Integer(left), Value::Integer(right)) => Value::Integer(left - right),
-                    (Value::Float(left), Value::Float(right)) => Value::Float(left - right),
-                    (left, right) => Err(EvaluationError::InvalidBinaryTypes {
-                        left: left.slang_type(),
-                        operator,
-                        right: Some(right.slang_type()),
-                    })?,
-                }
-            }
+            BinaryOperator::Subtract => match Self::binary_operands(left, right, stack, heap)? {
+                (Value::Integer(left), Value::Integer(right)) => Value::Integer(left - right),
+                (Value::Float(left), Value::Float(right)) => Value::Float(left - right),
+                (left, right) => Err(EvaluationError::InvalidBinaryTypes {
+                    left: left.slang_type(),
+                    operator,
+                    right: Some(right.slang_type()),
+                })?,
+            },
 
-            BinaryOperator::Multiply => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::Integer(left), Value::Integer(right)) => Value::Integer(left * right),
-                    (Value::Float(left), Value::Float(right)) => Value::Float(left * right),
-                    (left, right) => Err(EvaluationError::InvalidBinaryTypes {
-                        left: left.slang_type(),
-                        operator,
-                        right: Some(right.slang_type()),
-                    })?,
-                }
-            }
+            BinaryOperator::Multiply => match Self::binary_operands(left, right, stack, heap)? {
+                (Value::Integer(left), Value::Integer(right)) => Value::Integer(left * right),
+                (Value::Float(left), Value::Float(right)) => Value::Float(left * right),
+                (left, right) => Err(EvaluationError::InvalidBinaryTypes {
+                    left: left.slang_type(),
+                    operator,
+                    right: Some(right.slang_type()),
+                })?,
+            },
 
-            BinaryOperator::Divide => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::Integer(left), Value::Integer(right)) => {
-                        if right == 0 {
-                            return Err(EvaluationError::DivisionByZero);
-                        }
+            BinaryOperator::Divide => match Self::binary_operands(left, right, stack, heap)? {
+                (Value::Integer(left), Value::Integer(right)) => {
+                    if right == 0 {
+                        return Err(EvaluationError::DivisionByZero);
+                    }
 
-                        Value::Integer(left / right)
+                    Value::Integer(left / right)
+                }
+                (Value::Float(left), Value::Float(right)) => {
+                    if right == 0.0 {
+                        return Err(EvaluationError::DivisionByZero);
                     }
-                    (Value::Float(left), Value::Float(right)) => {
-                        if right == 0.0 {
+
+                    Value::Float(left / right)
+                }
+                (left, right) => Err(EvaluationError::InvalidBinaryTypes {
+                    left: left.slang_type(),
+                    operator,
+                    right: Some(right.slang_type()),
+                })?,
+            },
+
+            BinaryOperator::Exponent => match Self::binary_operands(left, right, stack, heap)? {
+                (Value::Integer(left), Value::Integer(right)) => {
+                    if right < 0 {
+                        if left == 0 {
                             return Err(EvaluationError::DivisionByZero);
                         }
 
-                        Value::Float(left / right)
+                        Value::Integer(0)
+                    } else {
+                        Value::Integer(left.pow(right as u32))
                     }
-                    (left, right) => Err(EvaluationError::InvalidBinaryTypes {
-                        left: left.slang_type(),
-                        operator,
-                        right: Some(right.slang_type()),
-                    })?,
                 }
-            }
+                (Value::Float(left), Value::Float(right)) => Value::Float(left.powf(right)),
+                (left, right) => Err(EvaluationError::InvalidBinaryTypes {
+                    left: left.slang_type(),
+                    operator: BinaryOperator::Exponent,
+                    right: Some(right.slang_type()),
+                })?,
+            },
 
-            BinaryOperator::Exponent => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::Integer(left), Value::Integer(right)) => {
-                        if right < 0 {
-                            if left == 0 {
-                                return Err(EvaluationError::DivisionByZero);
-                            }
+            BinaryOperator::EqualTo => match Self::binary_operands(left, right, stack, heap)? {
+                (Value::String(left), Value::String(right)) => Value::Boolean(left == right),
+                (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left == right),
+                (Value::Float(left), Value::Float(right)) => Value::Boolean(left == right),
+                (Value::Boolean(left), Value::Boolean(right)) => Value::Boolean(left == right),
+                (
+                    left @ (Value::Object(_) | Value::ObjectReference(_)),
+                    right @ (Value::Object(_) | Value::ObjectReference(_)),
+                ) => Value::Boolean(heap.values_equal(&left, &right)?),
+                (left, right) => Err(EvaluationError::InvalidBinaryTypes {
+                    left: left.slang_type(),
+                    operator,
+                    right: Some(right.slang_type()),
+                })?,
+            },
 
-                            Value::Integer(0)
-                        } else {
-                            Value::Integer(left.pow(right as u32))
-                        }
-                    }
-                    (Value::Float(left), Value::Float(right)) => Value::Float(left.powf(right)),
+            BinaryOperator::NotEqualTo => match Self::binary_operands(left, right, stack, heap)? {
+                (Value::String(left), Value::String(right)) => Value::Boolean(left != right),
+                (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left != right),
+                (Value::Float(left), Value::Float(right)) => Value::Boolean(left != right),
+                (Value::Boolean(left), Value::Boolean(right)) => Value::Boolean(left != right),
+                (
+                    left @ (Value::Object(_) | Value::ObjectReference(_)),
+                    right @ (Value::Object(_) | Value::ObjectReference(_)),
+                ) => Value::Boolean(!heap.values_equal(&left, &right)?),
+                (left, right) => Err(EvaluationError::InvalidBinaryTypes {
+                    left: left.slang_type(),
+                    operator,
+                    right: Some(right.slang_type()),
+                })?,
+            },
+
+            BinaryOperator::GreaterThan => match Self::binary_operands(left, right, stack, heap)? {
+                (Value::String(left), Value::String(right)) => Value::Boolean(left > right),
+                (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left > right),
+                (Value::Float(left), Value::Float(right)) => Value::Boolean(left > right),
+                (left, right) => Err(EvaluationError::InvalidBinaryTypes {
+                    left: left.slang_type(),
+                    operator,
+                    right: Some(right.slang_type()),
+                })?,
+            },
+
+            BinaryOperator::GreaterThanOrEqualTo => {
+                match Self::binary_operands(left, right, stack, heap)? {
+                    (Value::String(left), Value::String(right)) => Value::Boolean(left >= right),
+                    (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left >= right),
+                    (Value::Float(left), Value::Float(right)) => Value::Boolean(left >= right),
                     (left, right) => Err(EvaluationError::InvalidBinaryTypes {
                         left: left.slang_type(),
-                        operator: BinaryOperator::Exponent,
+                        operator,
                         right: Some(right.slang_type()),
                     })?,
                 }
             }
 
-            BinaryOperator::EqualTo => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::String(left), Value::String(right)) => Value::Boolean(left == right),
-                    (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left == right),
-                    (Value::Float(left), Value::Float(right)) => Value::Boolean(left == right),
-                    (Value::Boolean(left), Value::Boolean(right)) => Value::Boolean(left == right),
-                    (Value::Object(left), Value::Object(right)) => Value::Boolean(left == right),
-                    (Value::Object(left), Value::ObjectReference(right)) => {
-                        Value::Boolean(left == right.borrow().data)
-                    }
-                    (Value::ObjectReference(left), Value::Object(right)) => {
-                        Value::Boolean(left.borrow().data == right)
-                    }
-                    (Value::ObjectReference(left), Value::ObjectReference(right)) => {
-                        Value::Boolean(left == right)
-                    }
+            BinaryOperator::LessThan => match Self::binary_operands(left, right, stack, heap)? {
+                (Value::String(left), Value::String(right)) => Value::Boolean(left < right),
+                (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left < right),
+                (Value::Float(left), Value::Float(right)) => Value::Boolean(left < right),
+                (left, right) => Err(EvaluationError::InvalidBinaryTypes {
+                    left: left.slang_type(),
+                    operator,
+                    right: Some(right.slang_type()),
+                })?,
+            },
+
+            BinaryOperator::LessThanOrEqualTo => {
+                match Self::binary_operands(left, right, stack, heap)? {
+                    (Value::String(left), Value::String(right)) => Value::Boolean(left <= right),
+                    (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left <= right),
+                    (Value::Float(left), Value::Float(right)) => Value::Boolean(left <= right),
                     (left, right) => Err(EvaluationError::InvalidBinaryTypes {
                         left: left.slang_type(),
                         operator,
@@ -533,86 +1080,10 @@ impl Expression {
                 }
             }
 
-            BinaryOperator::NotEqualTo => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::String(left), Value::String(right)) => Value::Boolean(left != right),
-                    (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left != right),
-                    (Value::Float(left), Value::Float(right)) => Value::Boolean(left != right),
-                    (Value::Boolean(left), Value::Boolean(right)) => Value::Boolean(left != right),
-                    (Value::Object(left), Value::Object(right)) => Value::Boolean(left != right),
-                    (Value::Object(left), Value::ObjectReference(right)) => {
-                        Value::Boolean(left != right.borrow().data)
-                    }
-                    (Value::ObjectReference(left), Value::Object(right)) => {
-                        Value::Boolean(left.borrow().data != right)
-                    }
-                    (Value::ObjectReference(left), Value::ObjectReference(right)) => {
-                        Value::Boolean(left != right)
-                    }
-                    (left, right) => Err(EvaluationError::InvalidBinaryTypes {
-                        left: left.slang_type(),
-                        operator,
-                        right: Some(right.slang_type()),
-                    })?,
-                }
-            }
-
-            BinaryOperator::GreaterThan => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::String(left), Value::String(right)) => Value::Boolean(left > right),
-                    (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left > right),
-                    (Value::Float(left), Value::Float(right)) => Value::Boolean(left > right),
-                    (left, right) => Err(EvaluationError::InvalidBinaryTypes {
-                        left: left.slang_type(),
-                        operator,
-                        right: Some(right.slang_type()),
-                    })?,
-                }
-            }
-
-            BinaryOperator::GreaterThanOrEqualTo => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::String(left), Value::String(right)) => Value::Boolean(left >= right),
-                    (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left >= right),
-                    (Value::Float(left), Value::Float(right)) => Value::Boolean(left >= right),
-                    (left, right) => Err(EvaluationError::InvalidBinaryTypes {
-                        left: left.slang_type(),
-                        operator,
-                        right: Some(right.slang_type()),
-                    })?,
-                }
-            }
-
-            BinaryOperator::LessThan => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::String(left), Value::String(right)) => Value::Boolean(left < right),
-                    (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left < right),
-                    (Value::Float(left), Value::Float(right)) => Value::Boolean(left < right),
-                    (left, right) => Err(EvaluationError::InvalidBinaryTypes {
-                        left: left.slang_type(),
-                        operator,
-                        right: Some(right.slang_type()),
-                    })?,
-                }
-            }
-
-            BinaryOperator::LessThanOrEqualTo => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::String(left), Value::String(right)) => Value::Boolean(left <= right),
-                    (Value::Integer(left), Value::Integer(right)) => Value::Boolean(left <= right),
-                    (Value::Float(left), Value::Float(right)) => Value::Boolean(left <= right),
-                    (left, right) => Err(EvaluationError::InvalidBinaryTypes {
-                        left: left.slang_type(),
-                        operator,
-                        right: Some(right.slang_type()),
-                    })?,
-                }
-            }
-
-            BinaryOperator::AND => match left.evaluate_not_nothing(stack, heap, logger)? {
+            BinaryOperator::AND => match left.evaluate_not_nothing(stack, heap)? {
                 Value::Boolean(left) => {
                     if left {
-                        match right.evaluate_not_nothing(stack, heap, logger)? {
+                        match right.evaluate_not_nothing(stack, heap)? {
                             Value::Boolean(right) => Value::Boolean(left && right),
                             right => Err(EvaluationError::InvalidBinaryTypes {
                                 left: Type::Boolean,
@@ -631,12 +1102,12 @@ impl Expression {
                 })?,
             },
 
-            BinaryOperator::OR => match left.evaluate_not_nothing(stack, heap, logger)? {
+            BinaryOperator::OR => match left.evaluate_not_nothing(stack, heap)? {
                 Value::Boolean(left) => {
                     if left {
                         Value::Boolean(true)
                     } else {
-                        match right.evaluate_not_nothing(stack, heap, logger)? {
+                        match right.evaluate_not_nothing(stack, heap)? {
                             Value::Boolean(right) => Value::Boolean(left || right),
                             right => Err(EvaluationError::InvalidBinaryTypes {
                                 left: Type::Boolean,
@@ -653,29 +1124,25 @@ impl Expression {
                 })?,
             },
 
-            BinaryOperator::BitwiseAND => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::Integer(left), Value::Integer(right)) => Value::Integer(left & right),
-                    (Value::Boolean(left), Value::Boolean(right)) => Value::Boolean(left & right),
-                    (left, right) => Err(EvaluationError::InvalidBinaryTypes {
-                        left: left.slang_type(),
-                        operator,
-                        right: Some(right.slang_type()),
-                    })?,
-                }
-            }
+            BinaryOperator::BitwiseAND => match Self::binary_operands(left, right, stack, heap)? {
+                (Value::Integer(left), Value::Integer(right)) => Value::Integer(left & right),
+                (Value::Boolean(left), Value::Boolean(right)) => Value::Boolean(left & right),
+                (left, right) => Err(EvaluationError::InvalidBinaryTypes {
+                    left: left.slang_type(),
+                    operator,
+                    right: Some(right.slang_type()),
+                })?,
+            },
 
-            BinaryOperator::BitwiseOR => {
-                match Self::binary_operands(left, right, stack, heap, logger)? {
-                    (Value::Integer(left), Value::Integer(right)) => Value::Integer(left | right),
-                    (Value::Boolean(left), Value::Boolean(right)) => Value::Boolean(left | right),
-                    (left, right) => Err(EvaluationError::InvalidBinaryTypes {
-                        left: left.slang_type(),
-                        operator,
-                        right: Some(right.slang_type()),
-                    })?,
-                }
-            }
+            BinaryOperator::BitwiseOR => match Self::binary_operands(left, right, stack, heap)? {
+                (Value::Integer(left), Value::Integer(right)) => Value::Integer(left | right),
+                (Value::Boolean(left), Value::Boolean(right)) => Value::Boolean(left | right),
+                (left, right) => Err(EvaluationError::InvalidBinaryTypes {
+                    left: left.slang_type(),
+                    operator,
+                    right: Some(right.slang_type()),
+                })?,
+            },
         }))
     }
 
@@ -683,11 +1150,10 @@ impl Expression {
     fn evaluate_unary(
         stack: &mut Stack,
         heap: &mut ManagedHeap,
-        logger: &mut Logger,
         operator: UnaryOperator,
         operand: Box<Expression>,
     ) -> Result<Option<Value>, EvaluationError> {
-        let operand = operand.evaluate_not_nothing(stack, heap, logger)?;
+        let operand = operand.evaluate_not_nothing(stack, heap)?;
 
         Ok(Some(match operator {
             UnaryOperator::Minus => match operand {
@@ -713,12 +1179,19 @@ impl Expression {
     fn evaluate_call(
         stack: &mut Stack,
         heap: &mut ManagedHeap,
-        logger: &mut Logger,
         function: Box<Expression>,
         arguments: Vec<Box<Expression>>,
     ) -> Result<Option<Value>, EvaluationError> {
-        match function.evaluate_not_nothing(stack, heap, logger)? {
-            Value::Function(Function::UserDefined { parameters, block }) => {
+        if stack.interrupt.take() {
+            return Err(EvaluationError::Interrupted);
+        }
+
+        match function.evaluate_not_nothing(stack, heap)? {
+            Value::Function(Function::UserDefined {
+                identifier,
+                parameters,
+                block,
+            }) => {
                 if parameters.len() != arguments.len() {
                     return Err(EvaluationError::IncorrectArgumentCount {
                         expected: parameters.len(),
@@ -726,47 +1199,60 @@ impl Expression {
                     });
                 }
 
-                let mut evaluated_arguments = Vec::new();
+                let temporaries = stack.temporaries_count();
+                let evaluated_arguments = Self::evaluate_arguments(arguments, stack, heap);
+                stack.release_temporaries(temporaries);
+                let evaluated_arguments = evaluated_arguments?;
 
-                for argument in arguments.into_iter() {
-                    let argument = argument.evaluate_not_nothing(stack, heap, logger)?;
-
-                    evaluated_arguments.push(match argument {
-                        Value::Object(data) => Value::ObjectReference(heap.allocate(data)),
-                        Value::ObjectReference(ref pointer) => {
-                            if let ManagedHeap::ReferenceCounted(heap) = heap {
-                                heap.increment(Pointer::clone(pointer));
-                            }
+                let call_scope = stack.push();
+                stats::enter_function(&identifier, heap);
 
-                            argument
-                        }
-                        _ => argument,
-                    });
+                if let ManagedHeap::Region(heap) = heap {
+                    heap.enter();
                 }
 
-                let call_scope = stack.push();
+                // Under reference counting, each parameter holds a reference of its own to its argument, so that assigning to the parameter releases only that reference, and not the one the caller's argument holds.
+                for (parameter, argument) in parameters.into_iter().zip(evaluated_arguments.clone())
+                {
+                    if let (ManagedHeap::ReferenceCounted(heap), Value::ObjectReference(pointer)) =
+                        (&mut *heap, &argument)
+                    {
+                        heap.increment(*pointer);
+                    }
 
-                parameters
-                    .into_iter()
-                    .zip(evaluated_arguments.clone())
-                    .for_each(|(parameter, argument)| {
-                        call_scope.borrow_mut().define(parameter, Some(argument))
-                    });
+                    call_scope.borrow_mut().define(parameter, Some(argument));
+                }
 
-                let return_value =
-                    block
-                        .execute(stack, heap, logger)
-                        .map(|control| match control {
-                            ControlFlow::Break(value) => value,
-                            ControlFlow::Continue => None,
-                        });
+                let return_value = block.execute(stack, heap).map(|control| match control {
+                    ControlFlow::Break(value) => value,
+                    ControlFlow::Continue | ControlFlow::ExitLoop => None,
+                });
 
                 if let ManagedHeap::ReferenceCounted(heap) = heap {
+                    for value in call_scope.borrow().values() {
+                        heap.conditionally_decrement(value);
+                    }
+
                     for value in evaluated_arguments {
                         heap.conditionally_decrement(value);
                     }
                 }
 
+                if return_value.is_err() {
+                    stack.record_frame(&identifier);
+                }
+
+                // This also frees the regions of any blocks within the call which an error left without exiting.
+                if let ManagedHeap::Region(heap) = heap {
+                    let escaping = match &return_value {
+                        Ok(Some(value)) => Some(value),
+                        _ => None,
+                    };
+
+                    heap.exit(call_scope.borrow().depth(), escaping);
+                }
+
+                stats::exit_function(heap);
                 stack.pop();
 
                 /*
@@ -788,28 +1274,47 @@ impl Expression {
                 // within the scope, and when that scope is exited, its reference count is decremented.
 
                 if let Ok(Some(Value::ObjectReference(pointer))) = &return_value {
-                    stack.add_returned_object_reference(Pointer::clone(pointer));
+                    stack.add_returned_object_reference(*pointer);
                 }
 
                 return_value
             }
             Value::Function(Function::Native(function)) => match function {
-                NativeFunction::Print => match &arguments[..] {
-                    [] => {
-                        println!();
-                        Ok(None)
-                    }
-                    [expression] => {
-                        println!(
-                            "{}",
-                            expression
-                                .clone()
-                                .evaluate_not_nothing(stack, heap, logger)?
-                        );
+                NativeFunction::Print => {
+                    let line = Self::display_arguments(&arguments, stack, heap)?;
+
+                    let _ = writeln!(stack.console.output, "{}", line.join(" "));
+                    Ok(None)
+                }
+                NativeFunction::ErrorPrint => {
+                    let line = Self::display_arguments(&arguments, stack, heap)?;
+
+                    let _ = writeln!(stack.console.error, "{}", line.join(" "));
+                    Ok(None)
+                }
+                NativeFunction::Write => {
+                    let values = Self::display_arguments(&arguments, stack, heap)?;
+
+                    // Without a new line, the output may not be written until later, so it is flushed for the values to appear straight away.
+                    let _ = write!(stack.console.output, "{}", values.join(" "));
+                    let _ = stack.console.output.flush();
+                    Ok(None)
+                }
+                NativeFunction::PrintWith => match &arguments[..] {
+                    [separator, end, values @ ..] => {
+                        let separator: Shared<str> = separator
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+                        let end: Shared<str> =
+                            end.clone().evaluate_not_nothing(stack, heap)?.try_into()?;
+                        let values = Self::display_arguments(values, stack, heap)?;
+
+                        let _ = write!(stack.console.output, "{}{}", values.join(&separator), end);
                         Ok(None)
                     }
                     _ => Err(EvaluationError::IncorrectArgumentCount {
-                        expected: 1,
+                        expected: 2,
                         passed: arguments.len(),
                     }),
                 },
@@ -817,23 +1322,21 @@ impl Expression {
                     [] => {
                         let mut line = String::new();
 
-                        let _ = io::stdout().flush();
-                        let _ = io::stdin().read_line(&mut line);
+                        let _ = stack.console.output.flush();
+                        let _ = stack.console.input.read_line(&mut line);
 
-                        Ok(Some(Value::String(line.trim().to_string())))
+                        Ok(Some(Value::String(line.trim().into())))
                     }
                     [prompt] => {
-                        print!(
-                            "{}",
-                            prompt.clone().evaluate_not_nothing(stack, heap, logger)?
-                        );
+                        let prompt = prompt.clone().evaluate_not_nothing(stack, heap)?;
+                        let _ = write!(stack.console.output, "{}", prompt);
 
                         let mut line = String::new();
 
-                        let _ = io::stdout().flush();
-                        let _ = io::stdin().read_line(&mut line);
+                        let _ = stack.console.output.flush();
+                        let _ = stack.console.input.read_line(&mut line);
 
-                        Ok(Some(Value::String(line.trim().to_string())))
+                        Ok(Some(Value::String(line.trim().into())))
                     }
                     _ => Err(EvaluationError::IncorrectArgumentCount {
                         expected: 1,
@@ -845,18 +1348,15 @@ impl Expression {
                     let mut buffer = String::new();
 
                     for argument in arguments {
-                        buffer.push_str(&format!(
-                            "{}",
-                            argument.evaluate_not_nothing(stack, heap, logger)?
-                        ));
+                        buffer
+                            .push_str(&format!("{}", argument.evaluate_not_nothing(stack, heap)?));
                     }
 
-                    Ok(Some(Value::String(buffer)))
+                    Ok(Some(Value::String(buffer.into())))
                 }
                 NativeFunction::Int => match &arguments[..] {
                     [argument] => {
-                        let argument =
-                            argument.clone().evaluate_not_nothing(stack, heap, logger)?;
+                        let argument = argument.clone().evaluate_not_nothing(stack, heap)?;
 
                         match argument {
                             Value::Integer(integer) => Ok(Some(Value::Integer(integer))),
@@ -895,8 +1395,7 @@ impl Expression {
                 },
                 NativeFunction::Float => match &arguments[..] {
                     [argument] => {
-                        let argument =
-                            argument.clone().evaluate_not_nothing(stack, heap, logger)?;
+                        let argument = argument.clone().evaluate_not_nothing(stack, heap)?;
 
                         match argument {
                             Value::Integer(integer) => Ok(Some(Value::Float(integer as f64))),
@@ -922,6 +1421,667 @@ impl Expression {
                         passed: arguments.len(),
                     }),
                 },
+                NativeFunction::Gc => match &arguments[..] {
+                    [] => {
+                        let freed = heap.collect(&stack.roots());
+
+                        Ok(Some(Value::try_from(freed)?))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 0,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::HeapStats => match &arguments[..] {
+                    [] => Ok(Some(Value::Object(heap.stats().to_object()))),
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 0,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::HeapObjects => match &arguments[..] {
+                    [] => Ok(Some(Value::try_from(heap.objects_count())?)),
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 0,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::StackFrames => match &arguments[..] {
+                    [] => Ok(Some(Value::try_from(stack.frames_count())?)),
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 0,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::MemoryManagement => match &arguments[..] {
+                    [] => Ok(Some(Value::String(heap.get_technique_code().into()))),
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 0,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::GcConfig => match &arguments[..] {
+                    [settings] => {
+                        let settings = match settings.clone().evaluate_not_nothing(stack, heap)? {
+                            Value::ObjectReference(pointer) => heap.get(pointer)?.data.clone(),
+                            settings => Object::try_from(settings)?,
+                        };
+
+                        tuning::configure(&settings, heap)
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::GcStats => match &arguments[..] {
+                    [] => Ok(Some(Value::Object(heap.gc_stats().to_object()))),
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 0,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::HeapDump => match &arguments[..] {
+                    [path] => {
+                        let path: Shared<str> =
+                            path.clone().evaluate_not_nothing(stack, heap)?.try_into()?;
+
+                        fs::write(&*path, dump::dump(heap, &stack.roots(), &stack.sources))
+                            .map_err(|error| EvaluationError::HeapDumpFailed {
+                                path: path.to_string(),
+                                error: error.to_string(),
+                            })?;
+
+                        Ok(None)
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Finalize => match &arguments[..] {
+                    [object, function] => {
+                        let (pointer, temporary) = match object
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                        {
+                            Value::ObjectReference(pointer) => (pointer, false),
+                            Value::Object(data) => (heap.allocate(data, || stack.roots())?, true),
+                            other => {
+                                return Err(EvaluationError::InvalidArgumentType {
+                                    expected: Type::Object,
+                                    passed: other.slang_type(),
+                                });
+                            }
+                        };
+
+                        let temporaries = stack.temporaries_count();
+                        stack.hold_temporary(&Value::ObjectReference(pointer));
+
+                        let function = function.clone().evaluate_not_nothing(stack, heap);
+                        stack.release_temporaries(temporaries);
+
+                        let function = Function::try_from(function?)?;
+
+                        heap.finalize(pointer, function)?;
+
+                        // An object literal is unreachable as soon as its finalizer is registered.
+                        if let (true, ManagedHeap::ReferenceCounted(heap)) = (temporary, heap) {
+                            heap.decrement(pointer);
+                        }
+
+                        Ok(None)
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 2,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Spawn => {
+                    if arguments.is_empty() {
+                        return Err(EvaluationError::IncorrectArgumentCount {
+                            expected: 1,
+                            passed: 0,
+                        });
+                    }
+
+                    // Each argument is held until the thread has its copy, so that evaluating the rest cannot free it.
+                    let temporaries = stack.temporaries_count();
+                    let mut values = Vec::new();
+
+                    for argument in arguments {
+                        match argument.evaluate_not_nothing(stack, heap) {
+                            Ok(value) => {
+                                stack.hold_temporary(&value);
+                                values.push(value);
+                            }
+                            Err(error) => {
+                                stack.release_temporaries(temporaries);
+                                return Err(error);
+                            }
+                        }
+                    }
+
+                    // The first argument is the function, which is called with the rest.
+                    let spawned = match Function::try_from(values[0].clone()) {
+                        Ok(_) => thread::spawn(&values[0], &values[1..], stack, heap),
+                        Err(error) => Err(error.into()),
+                    };
+                    stack.release_temporaries(temporaries);
+
+                    spawned.map(Some)
+                }
+                NativeFunction::Join => match &arguments[..] {
+                    [handle] => {
+                        let handle: i32 = handle
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+
+                        thread::join(handle, stack, heap)
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Channel => match &arguments[..] {
+                    [] => Ok(Some(channel::create(stack)?)),
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 0,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Send => match &arguments[..] {
+                    [handle, value] => {
+                        let handle: i32 = handle
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+                        let value = value.clone().evaluate_not_nothing(stack, heap)?;
+
+                        channel::send(handle, &value, stack, heap)?;
+
+                        Ok(None)
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 2,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Receive => match &arguments[..] {
+                    [handle] => {
+                        let handle: i32 = handle
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+
+                        channel::receive(handle, stack, heap)
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Mutex => match &arguments[..] {
+                    [value] => {
+                        let value = value.clone().evaluate_not_nothing(stack, heap)?;
+
+                        Ok(Some(sync::create_mutex(&value, stack, heap)?))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Lock => match &arguments[..] {
+                    [handle, function] => {
+                        let handle: i32 = handle
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+                        let function = Function::try_from(
+                            function.clone().evaluate_not_nothing(stack, heap)?,
+                        )?;
+
+                        sync::lock(handle, function, stack, heap)
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 2,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Atomic => match &arguments[..] {
+                    [value] => {
+                        let value: i32 = value
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+
+                        Ok(Some(sync::create_atomic(value, stack)?))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::AtomicAdd => match &arguments[..] {
+                    [handle, amount] => {
+                        let handle: i32 = handle
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+                        let amount: i32 = amount
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+
+                        Ok(Some(sync::add(handle, amount, stack)?))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 2,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Exec => match &arguments[..] {
+                    [command] => {
+                        stack.capabilities.require_exec()?;
+
+                        let command: Shared<str> = command
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+
+                        Ok(Some(capabilities::exec(&command)?))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::ReadFile => match &arguments[..] {
+                    [path] => {
+                        stack.capabilities.require_fs("read_file")?;
+
+                        let path: Shared<str> =
+                            path.clone().evaluate_not_nothing(stack, heap)?.try_into()?;
+
+                        Ok(Some(files::read(&path)?))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::WriteFile => match &arguments[..] {
+                    [path, contents] => {
+                        stack.capabilities.require_fs("write_file")?;
+
+                        let path: Shared<str> =
+                            path.clone().evaluate_not_nothing(stack, heap)?.try_into()?;
+                        let contents: Shared<str> = contents
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+
+                        files::write(&path, &contents)?;
+
+                        Ok(None)
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 2,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Exists => match &arguments[..] {
+                    [path] => {
+                        stack.capabilities.require_fs("exists")?;
+
+                        let path: Shared<str> =
+                            path.clone().evaluate_not_nothing(stack, heap)?.try_into()?;
+
+                        Ok(Some(files::exists(&path)))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::MakeDirectory => match &arguments[..] {
+                    [path] => {
+                        stack.capabilities.require_fs("mkdir")?;
+
+                        let path: Shared<str> =
+                            path.clone().evaluate_not_nothing(stack, heap)?.try_into()?;
+
+                        files::make_directory(&path)?;
+
+                        Ok(None)
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Remove => match &arguments[..] {
+                    [path] => {
+                        stack.capabilities.require_fs("remove")?;
+
+                        let path: Shared<str> =
+                            path.clone().evaluate_not_nothing(stack, heap)?.try_into()?;
+
+                        files::remove(&path)?;
+
+                        Ok(None)
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::ListDirectory => match &arguments[..] {
+                    [path, function] => {
+                        stack.capabilities.require_fs("list_dir")?;
+
+                        let path: Shared<str> =
+                            path.clone().evaluate_not_nothing(stack, heap)?.try_into()?;
+                        let function = Function::try_from(
+                            function.clone().evaluate_not_nothing(stack, heap)?,
+                        )?;
+
+                        Ok(Some(files::list_directory(&path, function, stack, heap)?))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 2,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::RegexMatch => match &arguments[..] {
+                    [pattern, text] => {
+                        let pattern: Shared<str> = pattern
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+                        let text: Shared<str> =
+                            text.clone().evaluate_not_nothing(stack, heap)?.try_into()?;
+
+                        Ok(Some(patterns::is_match(&pattern, &text)?))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 2,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::RegexFindAll => match &arguments[..] {
+                    [pattern, text, function] => {
+                        let pattern: Shared<str> = pattern
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+                        let text: Shared<str> =
+                            text.clone().evaluate_not_nothing(stack, heap)?.try_into()?;
+                        let function = Function::try_from(
+                            function.clone().evaluate_not_nothing(stack, heap)?,
+                        )?;
+
+                        Ok(Some(patterns::find_all(
+                            &pattern, &text, function, stack, heap,
+                        )?))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 3,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::RegexReplace => match &arguments[..] {
+                    [pattern, text, replacement] => {
+                        let pattern: Shared<str> = pattern
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+                        let text: Shared<str> =
+                            text.clone().evaluate_not_nothing(stack, heap)?.try_into()?;
+                        let replacement: Shared<str> = replacement
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+
+                        Ok(Some(patterns::replace_all(&pattern, &text, &replacement)?))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 3,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Error => match &arguments[..] {
+                    [message] => {
+                        let message = message.clone().evaluate_not_nothing(stack, heap)?;
+
+                        Err(EvaluationError::Raised {
+                            message: message.to_string(),
+                        })
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Assert => {
+                    let (condition, message) = match &arguments[..] {
+                        [condition] => (condition, None),
+                        [condition, message] => (condition, Some(message)),
+                        _ => {
+                            return Err(EvaluationError::IncorrectArgumentCount {
+                                expected: 1,
+                                passed: arguments.len(),
+                            });
+                        }
+                    };
+
+                    let condition: bool = condition
+                        .clone()
+                        .evaluate_not_nothing(stack, heap)?
+                        .try_into()?;
+
+                    if condition {
+                        return Ok(None);
+                    }
+
+                    let message = match message {
+                        Some(message) => message
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .to_string(),
+                        None => String::from("the condition was false."),
+                    };
+
+                    Err(EvaluationError::AssertionFailed { message })
+                }
+                NativeFunction::AssertEqual => match &arguments[..] {
+                    [left, right] => {
+                        let left = left.clone().evaluate_not_nothing(stack, heap)?;
+                        let right = right.clone().evaluate_not_nothing(stack, heap)?;
+
+                        // The values are compared as `==` would compare them, except that values of different types are unequal rather than an error.
+                        let equal = Expression::Binary {
+                            left: Box::new(Expression::Literal {
+                                value: left.clone(),
+                            }),
+                            operator: BinaryOperator::EqualTo,
+                            right: Box::new(Expression::Literal {
+                                value: right.clone(),
+                            }),
+                            span: None,
+                        }
+                        .evaluate_not_nothing(stack, heap);
+
+                        match equal {
+                            Ok(Value::Boolean(true)) => Ok(None),
+                            Ok(_) | Err(EvaluationError::InvalidBinaryTypes { .. }) => {
+                                Err(EvaluationError::AssertionFailed {
+                                    message: format!("expected {:?} to equal {:?}.", left, right),
+                                })
+                            }
+                            Err(error) => Err(error),
+                        }
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 2,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::ToFixed => match &arguments[..] {
+                    [number, digits] => {
+                        let number = number.clone().evaluate_not_nothing(stack, heap)?;
+                        let digits: usize = digits
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+
+                        Ok(Some(Value::String(text::to_fixed(&number, digits)?.into())))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 2,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Thousands => match &arguments[..] {
+                    [number] => {
+                        let number = number.clone().evaluate_not_nothing(stack, heap)?;
+
+                        Ok(Some(Value::String(text::thousands(&number)?.into())))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Inspect => {
+                    let (value, depth) = match &arguments[..] {
+                        [value] => (value, None),
+                        [value, depth] => (value, Some(depth)),
+                        _ => {
+                            return Err(EvaluationError::IncorrectArgumentCount {
+                                expected: 1,
+                                passed: arguments.len(),
+                            });
+                        }
+                    };
+
+                    let value = value.clone().evaluate_not_nothing(stack, heap)?;
+
+                    let temporaries = stack.temporaries_count();
+                    stack.hold_temporary(&value);
+
+                    let depth = match depth {
+                        Some(depth) => depth
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)
+                            .and_then(|depth| Ok(depth.try_into()?)),
+                        None => Ok(inspect::DEFAULT_DEPTH),
+                    };
+                    stack.release_temporaries(temporaries);
+
+                    Ok(Some(Value::String(
+                        inspect::inspect(&value, heap, depth?).into(),
+                    )))
+                }
+                NativeFunction::Builder => match &arguments[..] {
+                    [] => Ok(Some(text::create_builder(stack)?)),
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 0,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Append => match &arguments[..] {
+                    [handle, values @ ..] => {
+                        let handle: i32 = handle
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+
+                        for value in Self::display_arguments(values, stack, heap)? {
+                            text::append(handle, &value, stack)?;
+                        }
+
+                        Ok(None)
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::Build => match &arguments[..] {
+                    [handle] => {
+                        let handle: i32 = handle
+                            .clone()
+                            .evaluate_not_nothing(stack, heap)?
+                            .try_into()?;
+
+                        Ok(Some(text::build(handle, stack)?))
+                    }
+                    _ => Err(EvaluationError::IncorrectArgumentCount {
+                        expected: 1,
+                        passed: arguments.len(),
+                    }),
+                },
+                NativeFunction::PadLeft | NativeFunction::PadRight => {
+                    let (value, width, fill) = match &arguments[..] {
+                        [value, width] => (value, width, None),
+                        [value, width, fill] => (value, width, Some(fill)),
+                        _ => {
+                            return Err(EvaluationError::IncorrectArgumentCount {
+                                expected: 2,
+                                passed: arguments.len(),
+                            });
+                        }
+                    };
+
+                    let value = value.clone().evaluate_not_nothing(stack, heap)?;
+                    let width: usize = width
+                        .clone()
+                        .evaluate_not_nothing(stack, heap)?
+                        .try_into()?;
+                    let fill: Shared<str> = match fill {
+                        Some(fill) => fill.clone().evaluate_not_nothing(stack, heap)?.try_into()?,
+                        None => Shared::from(" "),
+                    };
+
+                    let side = match function {
+                        NativeFunction::PadLeft => text::Side::Left,
+                        _ => text::Side::Right,
+                    };
+
+                    Ok(Some(Value::String(
+                        text::pad(&value.to_string(), width, &fill, side)?.into(),
+                    )))
+                }
+                NativeFunction::Plugin(plugin) => {
+                    // Each argument is held until the call returns, so that evaluating the rest cannot free it.
+                    let temporaries = stack.temporaries_count();
+                    let mut values = Vec::new();
+
+                    for argument in arguments {
+                        match argument.evaluate_not_nothing(stack, heap) {
+                            Ok(value) => {
+                                stack.hold_temporary(&value);
+                                values.push(value);
+                            }
+                            Err(error) => {
+                                stack.release_temporaries(temporaries);
+                                return Err(error);
+                            }
+                        }
+                    }
+
+                    let result = (plugin.function)(&values);
+                    stack.release_temporaries(temporaries);
+
+                    result.map_err(|message| EvaluationError::PluginFailed {
+                        function: plugin.name.to_string(),
+                        message,
+                    })
+                }
             },
             other => Err(EvaluationError::AttemptedCallOfNonFunction {
                 attempt: other.slang_type(),
@@ -929,18 +2089,73 @@ impl Expression {
         }
     }
 
+    /// Evaluates the arguments to a user-defined function, allocating any objects, and holding each as a temporary so that it is not freed while the rest are evaluated.
+    fn evaluate_arguments(
+        arguments: impl IntoIterator<Item = Box<Expression>>,
+        stack: &mut Stack,
+        heap: &mut ManagedHeap,
+    ) -> Result<Vec<Value>, EvaluationError> {
+        let mut evaluated_arguments = Vec::new();
+
+        for argument in arguments {
+            let argument = argument.evaluate_not_nothing(stack, heap)?;
+
+            let argument = match argument {
+                Value::Object(data) => {
+                    Value::ObjectReference(heap.allocate(data, || stack.roots())?)
+                }
+                Value::ObjectReference(ref pointer) => {
+                    if let ManagedHeap::ReferenceCounted(heap) = heap {
+                        heap.increment(*pointer);
+                    }
+
+                    argument
+                }
+                _ => argument,
+            };
+
+            stack.hold_temporary(&argument);
+            evaluated_arguments.push(argument);
+        }
+
+        Ok(evaluated_arguments)
+    }
+
+    /// Evaluates each argument in turn, writing its value in the form `print` writes it in. Each value is written as soon as it is evaluated, so that evaluating the rest cannot free an object it refers to first.
+    fn display_arguments(
+        arguments: &[Box<Expression>],
+        stack: &mut Stack,
+        heap: &mut ManagedHeap,
+    ) -> Result<Vec<String>, EvaluationError> {
+        arguments
+            .iter()
+            .map(|argument| {
+                argument
+                    .clone()
+                    .evaluate_not_nothing(stack, heap)
+                    .map(|value| value.to_string())
+            })
+            .collect()
+    }
+
     /// Evaluates a set of binary operands, ensuring that they are not nothing.
+    ///
+    /// The left operand is held as a temporary while the right is evaluated, so that it is not freed.
     fn binary_operands(
         left: Box<Expression>,
         right: Box<Expression>,
         stack: &mut Stack,
         heap: &mut ManagedHeap,
-        logger: &mut Logger,
     ) -> Result<(Value, Value), EvaluationError> {
-        Ok((
-            left.evaluate_not_nothing(stack, heap, logger)?,
-            right.evaluate_not_nothing(stack, heap, logger)?,
-        ))
+        let left = left.evaluate_not_nothing(stack, heap)?;
+
+        let temporaries = stack.temporaries_count();
+        stack.hold_temporary(&left);
+
+        let right = right.evaluate_not_nothing(stack, heap);
+        stack.release_temporaries(temporaries);
+
+        Ok((left, right?))
     }
 }
 
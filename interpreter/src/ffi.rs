@@ -0,0 +1,124 @@
+//! A C interface for embedding slang in programs written in other languages, enabled by the `ffi` feature and built as a cdylib.
+//!
+//! An interpreter is created with [slang_new], runs source code with [slang_eval], and is freed with [slang_free]. After each run, [slang_result] gives what the program printed if it succeeded, or its errors if it failed. The header `include/slang.h` is generated from this module by cbindgen.
+
+use std::{
+    ffi::{CStr, CString, c_char, c_int},
+    ptr,
+};
+
+use crate::{
+    console::Capture,
+    heap::{ManagedHeap, Strategy},
+    interpreter::Interpreter,
+};
+
+/// Returned by [slang_eval] when the source code ran successfully.
+pub const SLANG_OK: c_int = 0;
+/// Returned by [slang_eval] when the source code could not be lexed, parsed or executed.
+pub const SLANG_ERROR: c_int = 1;
+/// Returned by [slang_eval] when the interpreter or source code was null, or the source code was not valid UTF-8.
+pub const SLANG_INVALID_ARGUMENT: c_int = 2;
+
+/// An interpreter created by [slang_new], along with the output it has captured and the result of its last run.
+pub struct SlangInterpreter {
+    interpreter: Interpreter,
+    output: Capture,
+    result: CString,
+}
+
+/// Creates an interpreter whose heap is managed with a strategy, given by its code (`gc`, `gen`, `mc`, `cp`, `rc`, `rg` or `na`), or with the garbage collected heap if the code is null, and defines the functions of the prelude. Returns null if the code is not a known strategy.
+///
+/// # Safety
+///
+/// `heap` must be null or a valid nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slang_new(heap: *const c_char) -> *mut SlangInterpreter {
+    let strategy = if heap.is_null() {
+        Some(Strategy::GarbageCollected)
+    } else {
+        unsafe { CStr::from_ptr(heap) }
+            .to_str()
+            .ok()
+            .and_then(Strategy::from_code)
+    };
+
+    let Some(strategy) = strategy else {
+        return ptr::null_mut();
+    };
+
+    let output = Capture::default();
+
+    let mut interpreter = Interpreter::with_heap(ManagedHeap::new(strategy));
+    interpreter.set_output(output.clone());
+
+    if interpreter.load_prelude().is_err() {
+        return ptr::null_mut();
+    }
+
+    Box::into_raw(Box::new(SlangInterpreter {
+        interpreter,
+        output,
+        result: CString::default(),
+    }))
+}
+
+/// Lexes, parses and executes some source code, against the variables and functions defined by earlier runs. Returns [SLANG_OK] if it succeeded, after which [slang_result] gives what it printed, or [SLANG_ERROR] if it failed, after which [slang_result] gives its errors, one per line.
+///
+/// # Safety
+///
+/// `interpreter` must be null or a pointer returned by [slang_new] which has not been freed, and `source` must be null or a valid nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slang_eval(
+    interpreter: *mut SlangInterpreter,
+    source: *const c_char,
+) -> c_int {
+    if interpreter.is_null() || source.is_null() {
+        return SLANG_INVALID_ARGUMENT;
+    }
+
+    let interpreter = unsafe { &mut *interpreter };
+
+    let Ok(source) = unsafe { CStr::from_ptr(source) }.to_str() else {
+        return SLANG_INVALID_ARGUMENT;
+    };
+
+    let (status, result) = match interpreter.interpreter.run(source) {
+        Ok(()) => (SLANG_OK, interpreter.output.take()),
+        Err(error) => {
+            interpreter.output.take();
+            (SLANG_ERROR, error.to_string())
+        }
+    };
+
+    // C strings end at the first nul, so any within the result are removed.
+    interpreter.result = CString::new(result.replace('\0', "")).unwrap_or_default();
+
+    status
+}
+
+/// Returns what the last run printed if it succeeded, or its errors if it failed, as a nul-terminated string. The string is owned by the interpreter, and is valid until its next run or until it is freed. Returns null if the interpreter is null.
+///
+/// # Safety
+///
+/// `interpreter` must be null or a pointer returned by [slang_new] which has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slang_result(interpreter: *const SlangInterpreter) -> *const c_char {
+    if interpreter.is_null() {
+        return ptr::null();
+    }
+
+    unsafe { &*interpreter }.result.as_ptr()
+}
+
+/// Frees an interpreter, along with its heap and its last result. Does nothing if the interpreter is null.
+///
+/// # Safety
+///
+/// `interpreter` must be null or a pointer returned by [slang_new] which has not already been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slang_free(interpreter: *mut SlangInterpreter) {
+    if !interpreter.is_null() {
+        drop(unsafe { Box::from_raw(interpreter) });
+    }
+}
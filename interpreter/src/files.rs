@@ -0,0 +1,102 @@
+//! Native functions which use the filesystem, which a program may only call once it has been given the capability to (see [capabilities](crate::capabilities)).
+//!
+//! Paths are relative to the directory the interpreter was started in. There are no lists, so the entries of a directory are passed, one at a time, to a function.
+
+use std::{fs, io, path::Path};
+
+use crate::{
+    expression::{EvaluationError, Expression},
+    heap::{ManagedHeap, Object},
+    shared::Shared,
+    stack::Stack,
+    value::{Function, Value},
+};
+
+/// Returns the contents of a file, which must be valid UTF-8.
+pub fn read(path: &str) -> Result<Value, EvaluationError> {
+    let contents = fs::read_to_string(path).map_err(|error| failed(path, error))?;
+
+    Ok(Value::String(Shared::from(contents)))
+}
+
+/// Writes a file, creating it if it does not exist and replacing its contents if it does.
+pub fn write(path: &str, contents: &str) -> Result<(), EvaluationError> {
+    fs::write(path, contents).map_err(|error| failed(path, error))
+}
+
+/// Returns whether a file or directory exists.
+pub fn exists(path: &str) -> Value {
+    Value::Boolean(Path::new(path).exists())
+}
+
+/// Creates a directory, along with any of its parents which do not exist. Does nothing if it already exists.
+pub fn make_directory(path: &str) -> Result<(), EvaluationError> {
+    fs::create_dir_all(path).map_err(|error| failed(path, error))
+}
+
+/// Removes a file, or a directory which is empty.
+pub fn remove(path: &str) -> Result<(), EvaluationError> {
+    let removed = if Path::new(path).is_dir() {
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    };
+
+    removed.map_err(|error| failed(path, error))
+}
+
+/// Calls a function with each entry of a directory, in order of name, returning the number of entries. Each entry is an object with its `name`, its `path`, whether it `is_dir`, and its `size` in bytes.
+pub fn list_directory(
+    path: &str,
+    function: Function,
+    stack: &mut Stack,
+    heap: &mut ManagedHeap,
+) -> Result<Value, EvaluationError> {
+    let mut entries = fs::read_dir(path)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .map_err(|error| failed(path, error))?;
+
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in &entries {
+        let metadata = entry.metadata().map_err(|error| failed(path, error))?;
+
+        let object: Object = [
+            (
+                "name",
+                Value::String(Shared::from(entry.file_name().to_string_lossy().as_ref())),
+            ),
+            (
+                "path",
+                Value::String(Shared::from(entry.path().to_string_lossy().as_ref())),
+            ),
+            ("is_dir", Value::Boolean(metadata.is_dir())),
+            ("size", Value::try_from(metadata.len() as usize)?),
+        ]
+        .into_iter()
+        .map(|(field, value)| (Shared::from(field), value))
+        .collect();
+
+        let call = Expression::Call {
+            function: Box::new(Expression::Literal {
+                value: Value::Function(function.clone()),
+            }),
+            arguments: vec![Box::new(Expression::Literal {
+                value: Value::Object(object),
+            })],
+            span: None,
+        };
+
+        call.evaluate(stack, heap)?;
+    }
+
+    Ok(Value::try_from(entries.len())?)
+}
+
+/// Wraps an error using the file or directory at a path.
+fn failed(path: &str, error: io::Error) -> EvaluationError {
+    EvaluationError::FileSystemFailed {
+        path: path.to_string(),
+        error: error.to_string(),
+    }
+}
@@ -0,0 +1,671 @@
+//! The canonical formatter for the slang programming language.
+
+use std::{
+    error::Error,
+    fmt::{Debug, Display},
+};
+
+use crate::{
+    expression::Expression,
+    lexer::{Lexer, LexerError},
+    parser::{Parser, ParserError},
+    source::{FileId, Location, Source, Span},
+    statement::Statement,
+    token::{Token, TokenData, TokenKind},
+    token_stream::TokenStream,
+    value::Value,
+    visit::{VisitorMut, walk_expression_mut, walk_statement_mut},
+};
+
+/// All errors which can occur while formatting.
+pub enum FormatterError {
+    /// When the source code could not be lexed.
+    Lexer(LexerError),
+    /// When the source code could not be parsed.
+    Parser(ParserError),
+    /// When a comment appears beside tokens which the formatter does not keep, and so cannot be preserved.
+    MisplacedComment(Span),
+    /// When the formatted source code does not have the same meaning as the original source code.
+    ChangedMeaning,
+}
+
+impl Display for FormatterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lexer(error) => write!(f, "{}", error),
+            Self::Parser(error) => write!(f, "{}", error),
+            Self::MisplacedComment(span) => write!(
+                f,
+                "{} The comment could not be kept beside the code it was next to.",
+                span
+            ),
+            Self::ChangedMeaning => write!(
+                f,
+                "Formatting would change the meaning of the program, so it has been left unchanged."
+            ),
+        }
+    }
+}
+
+impl Debug for FormatterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Error for FormatterError {}
+
+impl FormatterError {
+    /// Returns the source code which caused the error, if it was caused by any in particular.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Lexer(error) => Some(error.span()),
+            Self::Parser(error) => Some(error.span()),
+            Self::MisplacedComment(span) => Some(*span),
+            Self::ChangedMeaning => None,
+        }
+    }
+}
+
+/// The string used for each level of indentation.
+const INDENTATION: &str = "    ";
+
+/// Re-prints a parsed program with consistent indentation, spacing, and brace placement.
+pub struct Formatter {
+    /// The formatted source code produced so far.
+    output: String,
+    /// The current level of indentation.
+    depth: usize,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Formatter {
+    /// Creates a new formatter.
+    pub fn new() -> Self {
+        Self {
+            output: String::new(),
+            depth: 0,
+        }
+    }
+
+    /// Formats a program, which must have been parsed with trivia retained for comments to be preserved.
+    pub fn format(mut self, statements: &[Statement]) -> String {
+        self.statements(statements);
+
+        self.output
+    }
+
+    /// Formats source code, checking that every comment is preserved and that the meaning of the program is unchanged.
+    pub fn format_source(source: &str) -> Result<String, Vec<FormatterError>> {
+        Self::format_named(Source::new(source), source)
+    }
+
+    /// Formats source code read from a file, like [Formatter::format_source], so that the locations of any errors name the file.
+    pub fn format_file(source: &str, file: FileId) -> Result<String, Vec<FormatterError>> {
+        Self::format_named(Source::new(source).with_file(file), source)
+    }
+
+    fn format_named(named: Source, source: &str) -> Result<String, Vec<FormatterError>> {
+        let (tokens, errors) = Lexer::with_trivia(named).lex();
+
+        if !errors.is_empty() {
+            return Err(errors.into_iter().map(FormatterError::Lexer).collect());
+        }
+
+        let statements = Parser::new(TokenStream::new(tokens.clone()))
+            .parse()
+            .map_err(|errors| {
+                errors
+                    .into_iter()
+                    .map(FormatterError::Parser)
+                    .collect::<Vec<_>>()
+            })?;
+
+        let formatted = Self::comment(&tokens, Self::new().format(&statements))?;
+
+        if Self::parse(source) != Self::parse(&formatted) {
+            return Err(vec![FormatterError::ChangedMeaning]);
+        }
+
+        Ok(formatted)
+    }
+
+    /// Puts the comments which the parser discarded, as they were not between statements, back into formatted source code beside the tokens they were next to.
+    ///
+    /// The tokens of the source code and of the formatted source code are matched up, allowing for the trailing commas the formatter removes and the semicolons it adds. A line comment ends the line, with the code after it indented one level further. A block comment is kept on the line, before the token after it, or after the token before it when the token after it is `)`, `}`, `,` or `;`.
+    fn comment(tokens: &[Token], formatted: String) -> Result<String, Vec<FormatterError>> {
+        let (output, errors) = Lexer::with_trivia(Source::new(&formatted)).lex();
+
+        if !errors.is_empty() {
+            return Err(vec![FormatterError::ChangedMeaning]);
+        }
+
+        let mut source = Gap::split(tokens).into_iter().peekable();
+        let mut output = Gap::split(&output).into_iter().peekable();
+
+        // The comments before the tokens matched so far, which are carried forward past tokens which only appear on one side.
+        let mut original: Vec<(Span, String)> = Vec::new();
+        let mut kept: Vec<String> = Vec::new();
+        let mut previous: Option<Span> = None;
+        let mut insertions: Vec<(usize, usize, String)> = Vec::new();
+
+        while let (Some(left), Some(right)) = (source.peek(), output.peek()) {
+            let (left_kind, right_kind) = (left.token.kind(), right.token.kind());
+
+            if left_kind != right_kind {
+                if left_kind == TokenKind::Comma {
+                    original.extend(source.next().into_iter().flat_map(|gap| gap.comments));
+                    continue;
+                }
+
+                if right_kind == TokenKind::Semicolon {
+                    kept.extend(
+                        output
+                            .next()
+                            .into_iter()
+                            .flat_map(|gap| gap.comments)
+                            .map(|(_, text)| text),
+                    );
+                    continue;
+                }
+
+                break;
+            }
+
+            let (Some(left), Some(right)) = (source.next(), output.next()) else {
+                break;
+            };
+
+            original.extend(left.comments);
+            kept.extend(right.comments.into_iter().map(|(_, text)| text));
+
+            let mut misplaced = Vec::new();
+            let mut kept_comments = kept.drain(..).peekable();
+
+            for (span, text) in original.drain(..) {
+                if kept_comments.next_if(|kept| kept == &text).is_none() {
+                    misplaced.push((span, text));
+                }
+            }
+
+            if let Some((span, _)) = misplaced.first() {
+                // The comments between statements are already in place, so there is nothing to put comments beside.
+                if kept_comments.next().is_some() {
+                    return Err(vec![FormatterError::MisplacedComment(*span)]);
+                }
+
+                let start = previous.map_or(0, |previous| previous.end().index());
+                let end = right.token.span().start().index();
+
+                let whitespace: String = formatted.chars().skip(start).take(end - start).collect();
+                let line = formatted.chars().take(start).collect::<String>();
+                let line = line.rsplit('\n').next().unwrap_or_default();
+                let indentation = line.len() - line.trim_start().len();
+
+                insertions.push((
+                    start,
+                    end,
+                    Self::around(
+                        &misplaced,
+                        &whitespace,
+                        right.token.kind(),
+                        &format!("{}{}", &line[..indentation], INDENTATION),
+                    ),
+                ));
+            }
+
+            if right.token.kind() == TokenKind::EndOfFile {
+                break;
+            }
+
+            previous = Some(right.token.span());
+        }
+
+        // Any comments left over could not be matched up with tokens in the formatted source code.
+        let mut kept = kept
+            .into_iter()
+            .chain(output.flat_map(|gap| gap.comments).map(|(_, text)| text))
+            .peekable();
+
+        for (span, text) in original
+            .into_iter()
+            .chain(source.flat_map(|gap| gap.comments))
+        {
+            if kept.next_if(|kept| kept == &text).is_none() {
+                return Err(vec![FormatterError::MisplacedComment(span)]);
+            }
+        }
+
+        let mut formatted: Vec<char> = formatted.chars().collect();
+
+        for (start, end, text) in insertions.into_iter().rev() {
+            formatted.splice(start..end, text.chars());
+        }
+
+        Ok(formatted.into_iter().collect())
+    }
+
+    /// Lays out comments which were not between statements, replacing the whitespace between the tokens they were beside.
+    fn around(
+        comments: &[(Span, String)],
+        whitespace: &str,
+        next: TokenKind,
+        indentation: &str,
+    ) -> String {
+        let closing = matches!(
+            next,
+            TokenKind::RightParenthesis
+                | TokenKind::RightBrace
+                | TokenKind::Comma
+                | TokenKind::Semicolon
+                | TokenKind::EndOfFile
+        );
+
+        let mut text = String::new();
+        let mut line = false;
+
+        for (index, (_, comment)) in comments.iter().enumerate() {
+            if line {
+                text.push('\n');
+                text.push_str(indentation);
+            } else if index > 0 || !whitespace.is_empty() || closing || comment.starts_with("//") {
+                text.push(' ');
+            }
+
+            text.push_str(comment);
+            line = comment.starts_with("//");
+        }
+
+        if whitespace.contains('\n') {
+            text.push_str(whitespace);
+        } else if line {
+            text.push('\n');
+            text.push_str(indentation);
+        } else if closing {
+            text.push_str(whitespace);
+        } else {
+            text.push(' ');
+        }
+
+        text
+    }
+
+    /// Parses source code without trivia, returning [None] if there are any errors.
+    ///
+    /// Every location in the syntax tree is reset to the start of the source code, so that the trees of the same program laid out differently are equal.
+    fn parse(source: &str) -> Option<Vec<Statement>> {
+        let (tokens, errors) = Lexer::new(Source::new(source)).lex();
+
+        if !errors.is_empty() {
+            return None;
+        }
+
+        let mut statements = Parser::new(TokenStream::new(tokens)).parse().ok()?;
+
+        for statement in &mut statements {
+            Unlocated.visit_statement(statement);
+        }
+
+        Some(statements)
+    }
+
+    /// Formats a sequence of statements, each on their own line(s).
+    ///
+    /// Blank lines are collapsed, and are removed entirely from the start and end of the sequence.
+    fn statements(&mut self, statements: &[Statement]) {
+        let first = statements
+            .iter()
+            .position(|statement| statement != &Statement::BlankLine);
+
+        let last = statements
+            .iter()
+            .rposition(|statement| statement != &Statement::BlankLine);
+
+        if let (Some(first), Some(last)) = (first, last) {
+            for statement in &statements[first..=last] {
+                self.statement(statement);
+            }
+        }
+    }
+
+    /// Formats a statement, followed by a newline.
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Comment {
+                text,
+                trailing: true,
+            } if self.output.ends_with('\n') => {
+                self.output.pop();
+                self.output.push(' ');
+                self.output.push_str(text);
+                self.output.push('\n');
+
+                return;
+            }
+            Statement::BlankLine => {
+                self.output.push('\n');
+
+                return;
+            }
+            _ => {}
+        }
+
+        self.indent();
+        self.inline_statement(statement);
+        self.output.push('\n');
+    }
+
+    /// Adds indentation for the current depth.
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.output.push_str(INDENTATION);
+        }
+    }
+
+    /// Formats a statement without any leading indentation or trailing newline.
+    fn inline_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::VariableDeclaration {
+                identifier,
+                initialiser,
+                ..
+            } => {
+                self.output.push_str(&format!("let {}", identifier));
+
+                if let Some(initialiser) = initialiser {
+                    self.output
+                        .push_str(&format!(" = {}", self.expression(initialiser)));
+                }
+
+                self.output.push(';');
+            }
+            Statement::FunctionDefinition {
+                identifier,
+                parameters,
+                block,
+                ..
+            } => {
+                self.output
+                    .push_str(&format!("fu {}({}) ", identifier, parameters.join(", ")));
+                self.inline_statement(block);
+            }
+            Statement::Test { name, block } => {
+                self.output.push_str(&format!("test \"{}\" ", name));
+                self.inline_statement(block);
+            }
+            Statement::Return(expression) => match expression {
+                Some(expression) => self
+                    .output
+                    .push_str(&format!("return {};", self.expression(expression))),
+                None => self.output.push_str("return;"),
+            },
+            Statement::IfStatement {
+                condition,
+                execute_if_true,
+                execute_if_false,
+                ..
+            } => self.if_statement(condition, execute_if_true, execute_if_false.as_deref()),
+            Statement::WhileLoop {
+                condition, block, ..
+            } => {
+                self.output
+                    .push_str(&format!("while {} ", self.expression(condition)));
+                self.inline_statement(block);
+            }
+            Statement::Loop { block, .. } => {
+                self.output.push_str("loop ");
+                self.inline_statement(block);
+            }
+            Statement::Break => self.output.push_str("break;"),
+            Statement::Block(statements) => {
+                if statements.is_empty() {
+                    self.output.push_str("{}");
+                    return;
+                }
+
+                self.output.push_str("{\n");
+
+                self.depth += 1;
+                self.statements(statements);
+                self.depth -= 1;
+
+                self.indent();
+                self.output.push('}');
+            }
+            Statement::Expression(expression) => {
+                self.output
+                    .push_str(&format!("{};", self.expression(expression)));
+            }
+            Statement::Yield(expression) => {
+                let expression = self.expression(expression);
+                self.output.push_str(&expression);
+            }
+            Statement::Comment { text, trailing: _ } => self.output.push_str(text),
+            Statement::BlankLine => {}
+        }
+    }
+
+    /// Formats an if-statement (or if-expression) without any leading indentation or trailing newline.
+    fn if_statement(
+        &mut self,
+        condition: &Expression,
+        execute_if_true: &Statement,
+        execute_if_false: Option<&Statement>,
+    ) {
+        self.output
+            .push_str(&format!("if {} ", self.expression(condition)));
+        self.inline_statement(execute_if_true);
+
+        if let Some(execute_if_false) = execute_if_false {
+            self.output.push_str(" else ");
+            self.inline_statement(execute_if_false);
+        }
+    }
+
+    /// Formats an expression.
+    ///
+    /// Groupings are preserved from the source code, so no additional parentheses are needed to retain precedence.
+    fn expression(&self, expression: &Expression) -> String {
+        match expression {
+            Expression::Ternary {
+                condition,
+                left,
+                right,
+                ..
+            } => format!(
+                "{} ? {} : {}",
+                self.expression(condition),
+                self.expression(left),
+                self.expression(right)
+            ),
+            Expression::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => format!(
+                "{} {} {}",
+                self.expression(left),
+                operator.raw(),
+                self.expression(right)
+            ),
+            Expression::Unary {
+                operator, operand, ..
+            } => {
+                format!("{}{}", operator.raw(), self.expression(operand))
+            }
+            Expression::Call {
+                function,
+                arguments,
+                ..
+            } => format!(
+                "{}({})",
+                self.expression(function),
+                arguments
+                    .iter()
+                    .map(|argument| self.expression(argument))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Expression::Assignment {
+                identifier, value, ..
+            } => {
+                format!("{} = {}", identifier, self.expression(value))
+            }
+            Expression::Grouping { contained } => format!("({})", self.expression(contained)),
+            Expression::Literal { value } => Self::literal(value),
+            Expression::Variable { identifier, .. } => identifier.clone(),
+            Expression::GetField { object, field, .. } => {
+                format!("{}.{}", self.expression(object), field)
+            }
+            Expression::SetField {
+                object,
+                field,
+                value,
+                ..
+            } => format!(
+                "{}.{} = {}",
+                self.expression(object),
+                field,
+                self.expression(value)
+            ),
+            Expression::Object { fields, .. } => {
+                if fields.is_empty() {
+                    return String::from("{}");
+                }
+
+                format!(
+                    "{{ {} }}",
+                    fields
+                        .iter()
+                        .map(|(identifier, expression)| format!(
+                            "{}: {}",
+                            identifier,
+                            self.expression(expression)
+                        ))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            }
+            Expression::Block { block } => {
+                self.nested(|formatter| formatter.inline_statement(block))
+            }
+            Expression::If {
+                condition,
+                execute_if_true,
+                execute_if_false,
+                ..
+            } => self.nested(|formatter| {
+                formatter.if_statement(condition, execute_if_true, Some(execute_if_false))
+            }),
+        }
+    }
+
+    /// Formats statements within an expression, such as the branches of an if-expression, indented from the statement containing the expression.
+    fn nested(&self, format: impl FnOnce(&mut Self)) -> String {
+        let mut formatter = Self {
+            output: String::new(),
+            depth: self.depth,
+        };
+
+        format(&mut formatter);
+
+        formatter.output
+    }
+
+    /// Formats a literal value exactly as it would need to be written in source code.
+    fn literal(value: &Value) -> String {
+        match value {
+            Value::String(string) => format!("\"{}\"", string),
+            Value::Float(float) => {
+                // Floats must always contain a `.` to be lexed as floats again.
+                let float = float.to_string();
+
+                if float.contains('.') {
+                    float
+                } else {
+                    format!("{}.0", float)
+                }
+            }
+            Value::Integer(integer) => integer.to_string(),
+            Value::Boolean(boolean) => boolean.to_string(),
+            // The parser only ever produces literals of the types above.
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// A token other than trivia, along with the comments before it.
+struct Gap {
+    comments: Vec<(Span, String)>,
+    token: Token,
+}
+
+impl Gap {
+    /// Splits tokens, ending with an end of file token, into the gaps before each token other than trivia.
+    fn split(tokens: &[Token]) -> Vec<Gap> {
+        let mut gaps = Vec::new();
+        let mut comments = Vec::new();
+
+        for token in tokens.iter().cloned() {
+            let span = token.span();
+
+            match token.kind() {
+                TokenKind::Comment => {
+                    if let TokenData::Comment { text, trailing: _ } = token.data() {
+                        comments.push((span, text));
+                    }
+                }
+                TokenKind::BlankLine => {}
+                _ => gaps.push(Gap {
+                    comments: std::mem::take(&mut comments),
+                    token,
+                }),
+            }
+        }
+
+        gaps
+    }
+}
+
+/// Resets every location in the syntax tree to the start of the source code, and removes every span.
+struct Unlocated;
+
+impl VisitorMut for Unlocated {
+    fn visit_statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::VariableDeclaration { span, .. }
+            | Statement::FunctionDefinition { span, .. }
+            | Statement::IfStatement { span, .. }
+            | Statement::WhileLoop { span, .. }
+            | Statement::Loop { span, .. } => *span = None,
+            _ => {}
+        }
+
+        walk_statement_mut(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::Object { location, .. } => *location = Location::start(),
+            Expression::Ternary { span, .. }
+            | Expression::Binary { span, .. }
+            | Expression::Unary { span, .. }
+            | Expression::Call { span, .. }
+            | Expression::Assignment { span, .. }
+            | Expression::Variable { span, .. }
+            | Expression::GetField { span, .. }
+            | Expression::SetField { span, .. }
+            | Expression::If { span, .. } => *span = None,
+            Expression::Grouping { .. } | Expression::Literal { .. } | Expression::Block { .. } => {
+            }
+        }
+
+        walk_expression_mut(self, expression);
+    }
+}
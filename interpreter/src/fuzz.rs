@@ -0,0 +1,103 @@
+//! Entry points for fuzzing the lexer and parser, which must report errors for any input, rather than panicking or overflowing the stack.
+//!
+//! The targets in the `fuzz` directory call these with arbitrary bytes under `cargo fuzz`, and `slang --fuzz-check <filename>...` calls [check] with the contents of files, such as the inputs which the fuzzer has saved, so that a failure can be reproduced without a nightly toolchain.
+
+use crate::{
+    interpreter::parse_source,
+    lexer::Lexer,
+    parser::Parser,
+    source::{Location, Source, Span},
+    token::{Token, TokenData},
+    token_stream::TokenStream,
+};
+
+/// Lexes arbitrary bytes, both with and without trivia.
+pub fn lex(data: &[u8]) {
+    let _ = Lexer::new(Source::from_reader(data)).lex();
+    let _ = Lexer::with_trivia(Source::from_reader(data)).lex();
+}
+
+/// Lexes and parses arbitrary bytes, both as the interpreter does and with trivia, as the formatter does.
+pub fn parse(data: &[u8]) {
+    let _ = parse_source(Source::from_reader(data));
+
+    let tokens = Lexer::with_trivia(Source::from_reader(data)).filter_map(Result::ok);
+    let _ = Parser::new(TokenStream::new(tokens)).parse();
+}
+
+/// Parses a sequence of tokens made from arbitrary bytes, one token for each byte, so that the parser is given sequences of tokens (such as trivia in unusual places) which the lexer may never produce.
+pub fn parse_tokens(data: &[u8]) {
+    let span = Span::new(Location::start(), Location::start());
+
+    let tokens = data
+        .iter()
+        .map(|byte| token(*byte))
+        .chain([TokenData::EndOfFile])
+        .map(|data| Token::new(data, span));
+
+    let _ = Parser::new(TokenStream::new(tokens)).parse();
+}
+
+/// Runs every fuzzing entry point with some bytes.
+pub fn check(data: &[u8]) {
+    lex(data);
+    parse(data);
+    parse_tokens(data);
+}
+
+/// Returns the token made from a byte, other than the end of file token, which only ends the sequence.
+fn token(byte: u8) -> TokenData {
+    match byte % 45 {
+        0 => TokenData::LeftParenthesis,
+        1 => TokenData::RightParenthesis,
+        2 => TokenData::LeftBrace,
+        3 => TokenData::RightBrace,
+        4 => TokenData::Comma,
+        5 => TokenData::Dot,
+        6 => TokenData::Semicolon,
+        7 => TokenData::QuestionMark,
+        8 => TokenData::Colon,
+        9 => TokenData::Plus,
+        10 => TokenData::Minus,
+        11 => TokenData::Star,
+        12 => TokenData::Slash,
+        13 => TokenData::Exponent,
+        14 => TokenData::Bang,
+        15 => TokenData::BangEqual,
+        16 => TokenData::Equal,
+        17 => TokenData::DoubleEqual,
+        18 => TokenData::Greater,
+        19 => TokenData::GreaterEqual,
+        20 => TokenData::Less,
+        21 => TokenData::LessEqual,
+        22 => TokenData::Ampersand,
+        23 => TokenData::DoubleAmpersand,
+        24 => TokenData::Pipe,
+        25 => TokenData::DoublePipe,
+        26 => TokenData::String(String::from("string")),
+        27 => TokenData::Float(1.5),
+        28 => TokenData::Integer(1),
+        29 => TokenData::Boolean(true),
+        30 => TokenData::If,
+        31 => TokenData::Else,
+        32 => TokenData::While,
+        33 => TokenData::Loop,
+        34 => TokenData::Break,
+        35 => TokenData::Return,
+        36 => TokenData::Let,
+        37 => TokenData::Fu,
+        38 => TokenData::Identifier(String::from("identifier")),
+        39 => TokenData::Identifier(String::from("test")),
+        40 => TokenData::Comment {
+            text: String::from("// comment"),
+            trailing: false,
+        },
+        41 => TokenData::Comment {
+            text: String::from("/// doc comment"),
+            trailing: true,
+        },
+        42 => TokenData::BlankLine,
+        43 => TokenData::Integer(0),
+        _ => TokenData::String(String::new()),
+    }
+}
@@ -0,0 +1,288 @@
+use std::{collections::VecDeque, mem};
+
+use crate::{
+    heap::{CollectionTotals, Finalizer, HeapObject, Limits, Pointer, interner::Interner},
+    source::Location,
+    value::Value,
+};
+
+/// An entry in an [Arena]'s indirection table, which records where the object a pointer refers to is stored.
+struct Handle {
+    /// The number of times an object with this handle has been freed, so that pointers to those objects can be told apart from pointers to the current one.
+    generation: usize,
+    /// The slot which holds the object, if it has not been freed.
+    slot: Option<usize>,
+}
+
+/// A slot in an [Arena], which holds an object along with the index of its handle.
+struct Slot {
+    handle: usize,
+    object: HeapObject,
+}
+
+/// A slab of objects, owned by a heap and addressed by [Pointer]s.
+///
+/// Pointers index an indirection table of handles, rather than the slots themselves, so that objects can be moved between slots without any pointers changing. Freeing an object drops it immediately and makes both its handle and its slot available for reuse. A handle's generation is incremented whenever this happens, so a pointer to a freed object never resolves to whichever object reuses the handle.
+#[derive(Default)]
+pub struct Arena {
+    handles: Vec<Handle>,
+    /// The indices of handles which are free.
+    free_handles: Vec<usize>,
+    slots: Vec<Option<Slot>>,
+    /// The indices of slots which are free.
+    free_slots: Vec<usize>,
+    /// The number of objects in the arena.
+    len: usize,
+    /// The finalizers of objects which have been freed, in the order they were freed.
+    finalizers: VecDeque<Finalizer>,
+    /// Whether finalizers are running, in which case any more which are queued wait until they have finished.
+    pub finalizing: bool,
+    /// The strings stored in the objects, which are shared between equal strings.
+    pub interner: Interner,
+    /// The most the heap may hold, which is enforced when objects are allocated.
+    pub limits: Limits,
+    /// Whether a full collection is forced before every allocation.
+    pub stress: bool,
+    /// The number of threads which mark the heap once it is large, where 0 or 1 marks it on the current thread.
+    pub gc_threads: usize,
+    /// The allocation site recorded on objects as they are inserted.
+    pub site: Option<Location>,
+    /// The totals of the collections which have been taken from the heap.
+    pub collected: CollectionTotals,
+}
+
+impl Arena {
+    /// Moves an object into the arena, reusing a free handle and slot if there are any, and interns its strings.
+    pub fn insert(&mut self, mut object: HeapObject) -> Pointer {
+        self.len += 1;
+
+        object.data = self.interner.intern_object(object.data);
+        object.site = self.site;
+
+        let handle = match self.free_handles.pop() {
+            Some(handle) => handle,
+            None => {
+                self.handles.push(Handle {
+                    generation: 0,
+                    slot: None,
+                });
+
+                self.handles.len() - 1
+            }
+        };
+
+        let slot = match self.free_slots.pop() {
+            Some(slot) => {
+                self.slots[slot] = Some(Slot { handle, object });
+
+                slot
+            }
+            None => {
+                self.slots.push(Some(Slot { handle, object }));
+
+                self.slots.len() - 1
+            }
+        };
+
+        self.handles[handle].slot = Some(slot);
+
+        Pointer {
+            index: handle,
+            generation: self.handles[handle].generation,
+        }
+    }
+
+    /// Returns the slot which holds the object a pointer refers to, or [None] if it has been freed.
+    fn slot(&self, pointer: Pointer) -> Option<usize> {
+        self.handles
+            .get(pointer.index)
+            .filter(|handle| handle.generation == pointer.generation)
+            .and_then(|handle| handle.slot)
+    }
+
+    /// Returns the object a pointer refers to, or [None] if it has been freed.
+    pub fn get(&self, pointer: Pointer) -> Option<&HeapObject> {
+        let slot = self.slot(pointer)?;
+
+        self.slots[slot].as_ref().map(|slot| &slot.object)
+    }
+
+    /// Returns the object a pointer refers to mutably, or [None] if it has been freed.
+    pub fn get_mut(&mut self, pointer: Pointer) -> Option<&mut HeapObject> {
+        let slot = self.slot(pointer)?;
+
+        self.slots[slot].as_mut().map(|slot| &mut slot.object)
+    }
+
+    /// Frees the object a pointer refers to, returning it if it had not already been freed.
+    pub fn remove(&mut self, pointer: Pointer) -> Option<HeapObject> {
+        let slot = self.slot(pointer)?;
+        let removed = self.slots[slot].take()?;
+
+        self.free_slots.push(slot);
+        self.release_handle(pointer.index, &removed.object);
+
+        Some(removed.object)
+    }
+
+    /// Makes the handle of an object which has been taken out of its slot available for reuse, and queues its finalizer.
+    fn release_handle(&mut self, index: usize, object: &HeapObject) {
+        let handle = &mut self.handles[index];
+        handle.generation += 1;
+        handle.slot = None;
+
+        self.free_handles.push(index);
+        self.len -= 1;
+
+        if let Some(function) = &object.finalizer {
+            let fields = object
+                .data
+                .iter()
+                .filter(|(_, value)| !matches!(value, Value::ObjectReference(_)))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+
+            self.finalizers.push_back(Finalizer {
+                function: function.clone(),
+                fields,
+            });
+        }
+    }
+
+    /// Takes the finalizer of the earliest freed object which has not yet been run.
+    pub fn next_finalizer(&mut self) -> Option<Finalizer> {
+        self.finalizers.pop_front()
+    }
+
+    /// Returns a pointer to every object in the arena.
+    pub fn pointers(&self) -> Vec<Pointer> {
+        self.handles
+            .iter()
+            .enumerate()
+            .filter(|(_, handle)| handle.slot.is_some())
+            .map(|(index, handle)| Pointer {
+                index,
+                generation: handle.generation,
+            })
+            .collect()
+    }
+
+    /// Returns every object referenced by the fields of an object, once for each field, or nothing if it has been freed.
+    pub fn references(&self, pointer: Pointer) -> Vec<Pointer> {
+        self.get(pointer)
+            .map(|object| {
+                object
+                    .data
+                    .values()
+                    .filter_map(|value| match value {
+                        Value::ObjectReference(pointer) => Some(*pointer),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Slides every object towards the start of the slots, so that there are no free slots between them, and updates their handles. Returns the number of objects which were moved.
+    pub fn compact(&mut self) -> usize {
+        let mut moved = 0;
+        let mut next = 0;
+
+        for slot in 0..self.slots.len() {
+            let Some(occupant) = self.slots[slot].take() else {
+                continue;
+            };
+
+            if slot != next {
+                moved += 1;
+            }
+
+            self.handles[occupant.handle].slot = Some(next);
+            self.slots[next] = Some(occupant);
+            next += 1;
+        }
+
+        self.slots.truncate(next);
+        self.free_slots.clear();
+
+        moved
+    }
+
+    /// Copies every object reachable from the roots into a fresh set of slots, and frees every object left behind. Returns the objects which were freed.
+    ///
+    /// The roots are copied first, and then the copied objects are scanned in order, copying anything they reference which has not been copied already, so the survivors end up in breadth-first order with no free slots between them.
+    pub fn evacuate(&mut self, roots: &[Pointer]) -> Vec<HeapObject> {
+        let mut from_space = mem::take(&mut self.slots);
+        self.free_slots.clear();
+
+        for &root in roots {
+            self.copy(root, &mut from_space);
+        }
+
+        let mut scan = 0;
+
+        while let Some(handle) = self
+            .slots
+            .get(scan)
+            .and_then(|slot| slot.as_ref())
+            .map(|slot| slot.handle)
+        {
+            let pointer = Pointer {
+                index: handle,
+                generation: self.handles[handle].generation,
+            };
+
+            for reference in self.references(pointer) {
+                self.copy(reference, &mut from_space);
+            }
+
+            scan += 1;
+        }
+
+        let mut freed = Vec::new();
+
+        for slot in from_space.into_iter().flatten() {
+            self.release_handle(slot.handle, &slot.object);
+            freed.push(slot.object);
+        }
+
+        freed
+    }
+
+    /// Moves the object a pointer refers to out of the from-space and into the end of the slots, unless it has been freed or already copied.
+    fn copy(&mut self, pointer: Pointer, from_space: &mut [Option<Slot>]) {
+        let Some(slot) = self.slot(pointer) else {
+            return;
+        };
+
+        // A copied object's handle refers to a slot in the new slots, which may hold some other object (or nothing) in the from-space.
+        if from_space[slot]
+            .as_ref()
+            .is_none_or(|occupant| occupant.handle != pointer.index)
+        {
+            return;
+        }
+
+        self.slots.push(from_space[slot].take());
+        self.handles[pointer.index].slot = Some(self.slots.len() - 1);
+    }
+
+    /// Returns the fraction of slots which are free, which are wasted until they are reused or compacted away.
+    pub fn fragmentation(&self) -> f64 {
+        if self.slots.is_empty() {
+            return 0.0;
+        }
+
+        self.free_slots.len() as f64 / self.slots.len() as f64
+    }
+
+    /// Returns the number of objects in the arena.
+    pub fn objects_count(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the number of handles in the indirection table, which is more than the index of any pointer into the arena, including pointers to objects which have been freed.
+    pub fn handles_count(&self) -> usize {
+        self.handles.len()
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    clock::Instant,
+    heap::{Allocations, Collection, HeapObject, Object, Pointer, Usage, arena::Arena, parallel},
+    value::Value,
+};
+
+/// A mark-compact garbage collected heap.
+///
+/// Whenever a block exits, every object reachable from the roots is marked and the rest are freed. The surviving objects are then slid towards the start of the arena, so that no free slots are left between them, and new objects are always allocated after every surviving one. Pointers are unaffected, as they refer to objects through the arena's indirection table.
+pub struct CompactingHeap {
+    /// The objects on the heap, which are compacted after every collection.
+    pub objects: Arena,
+    /// Running totals of the allocations made.
+    pub allocations: Allocations,
+    /// The current and peak usage of the heap.
+    pub usage: Usage,
+    /// Records of the collections which have not yet been taken to be recorded as stats.
+    pub collections: Vec<Collection>,
+}
+
+impl Default for CompactingHeap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompactingHeap {
+    pub fn new() -> Self {
+        Self {
+            objects: Arena::default(),
+            allocations: Allocations::default(),
+            usage: Usage::default(),
+            collections: Vec::new(),
+        }
+    }
+
+    pub fn allocate(&mut self, data: Object) -> Pointer {
+        let data = data
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::Object(object) => (key, Value::ObjectReference(self.allocate(object))),
+                value => (key, value),
+            })
+            .collect();
+
+        let heap_object = HeapObject::new(data);
+
+        self.allocations.record(&heap_object);
+        self.usage
+            .allocated(&heap_object, self.objects.objects_count() + 1);
+
+        self.objects.insert(heap_object)
+    }
+
+    /// Called when a block exits. Performs a collection.
+    pub fn manage(&mut self, roots: &[Pointer]) {
+        self.collect(roots);
+    }
+
+    /// Marks every object reachable from the roots, frees the rest, and compacts the survivors.
+    pub fn collect(&mut self, roots: &[Pointer]) {
+        let start = Instant::now();
+        let before = self.objects.objects_count();
+
+        let threads = parallel::mark(&mut self.objects, roots).unwrap_or_else(|| {
+            let mut pending = roots.to_vec();
+
+            while let Some(pointer) = pending.pop() {
+                let Some(object) = self.objects.get_mut(pointer) else {
+                    continue;
+                };
+
+                if object.marked {
+                    continue;
+                }
+
+                object.marked = true;
+                pending.extend(self.objects.references(pointer));
+            }
+
+            1
+        });
+
+        for pointer in self.objects.pointers() {
+            let Some(object) = self.objects.get_mut(pointer) else {
+                continue;
+            };
+
+            if object.marked {
+                object.marked = false;
+            } else if let Some(object) = self.objects.remove(pointer) {
+                self.usage.freed(&object);
+            }
+        }
+
+        let moved = self.objects.compact();
+
+        let mut collection = Collection::finish(start, before, self.objects.objects_count());
+        collection.moved = moved;
+        collection.threads = threads;
+
+        self.collections.push(collection);
+    }
+
+    pub fn objects_count(&self) -> usize {
+        self.objects.objects_count()
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    clock::Instant,
+    heap::{Allocations, Collection, HeapObject, Object, Pointer, Usage, arena::Arena},
+    value::Value,
+};
+
+/// A semi-space copying garbage collected heap.
+///
+/// Whenever a block exits, every object reachable from the roots is copied into a fresh space, in the breadth-first order of Cheney's algorithm, and everything left in the old space is freed. Nothing is marked, so the cost of a collection depends only on the surviving objects, but both spaces are in use at once while copying. Pointers are unaffected, as they refer to objects through the arena's indirection table.
+pub struct CopyingHeap {
+    /// The objects on the heap, which are copied into a fresh space on every collection.
+    pub objects: Arena,
+    /// Running totals of the allocations made.
+    pub allocations: Allocations,
+    /// The current and peak usage of the heap, where the peak includes the space being copied into.
+    pub usage: Usage,
+    /// Records of the collections which have not yet been taken to be recorded as stats.
+    pub collections: Vec<Collection>,
+}
+
+impl Default for CopyingHeap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CopyingHeap {
+    pub fn new() -> Self {
+        Self {
+            objects: Arena::default(),
+            allocations: Allocations::default(),
+            usage: Usage::default(),
+            collections: Vec::new(),
+        }
+    }
+
+    pub fn allocate(&mut self, data: Object) -> Pointer {
+        let data = data
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::Object(object) => (key, Value::ObjectReference(self.allocate(object))),
+                value => (key, value),
+            })
+            .collect();
+
+        let heap_object = HeapObject::new(data);
+
+        self.allocations.record(&heap_object);
+        self.usage
+            .allocated(&heap_object, self.objects.objects_count() + 1);
+
+        self.objects.insert(heap_object)
+    }
+
+    /// Called when a block exits. Performs a collection.
+    pub fn manage(&mut self, roots: &[Pointer]) {
+        self.collect(roots);
+    }
+
+    /// Copies every object reachable from the roots into a fresh space, and frees the rest.
+    pub fn collect(&mut self, roots: &[Pointer]) {
+        let start = Instant::now();
+        let before = self.objects.objects_count();
+
+        let freed = self.objects.evacuate(roots);
+
+        let freed_bytes: usize = freed.iter().map(|object| object.size).sum();
+        self.usage
+            .copied(self.usage.bytes.saturating_sub(freed_bytes));
+
+        for object in &freed {
+            self.usage.freed(object);
+        }
+
+        let mut collection = Collection::finish(start, before, self.objects.objects_count());
+        collection.moved = self.objects.objects_count();
+
+        self.collections.push(collection);
+    }
+
+    pub fn objects_count(&self) -> usize {
+        self.objects.objects_count()
+    }
+}
@@ -0,0 +1,117 @@
+//! Snapshots of the heap, written as JSON for offline inspection.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    heap::{ManagedHeap, Pointer},
+    shared::Shared,
+    source::SourceMap,
+    value::Value,
+};
+
+/// Serialises every object on the heap as JSON, along with the roots it is reachable from.
+///
+/// Each object is identified by the index of its pointer, and records its generation, reference count, mark bit, age, estimated size, whether it has a finalizer and the position of the object literal it was allocated from (if any), as well as the name and kind of each of its fields. Every field which references another object is also written as an edge, which is marked as freed if the object it references has been freed.
+pub fn dump(heap: &ManagedHeap, roots: &[Pointer], sources: &SourceMap) -> String {
+    let objects = heap.objects();
+
+    let roots: BTreeSet<usize> = roots.iter().map(|root| root.index).collect();
+    let roots = roots
+        .iter()
+        .map(|root| root.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let mut entries = Vec::new();
+    let mut edges = Vec::new();
+
+    for pointer in objects.pointers() {
+        let Some(object) = objects.get(pointer) else {
+            continue;
+        };
+
+        let mut fields: Vec<(&Shared<str>, &Value)> = object.data.iter().collect();
+        fields.sort_by_key(|(name, _)| *name);
+
+        let fields = fields
+            .into_iter()
+            .map(|(name, value)| {
+                let reference = match value {
+                    Value::ObjectReference(target) => {
+                        edges.push(format!(
+                            "    {{\"from\": {}, \"to\": {}, \"field\": \"{}\", \"freed\": {}}}",
+                            pointer.index,
+                            target.index,
+                            escape(name),
+                            objects.get(*target).is_none()
+                        ));
+
+                        format!(", \"reference\": {}", target.index)
+                    }
+                    _ => String::new(),
+                };
+
+                format!(
+                    "{{\"name\": \"{}\", \"kind\": \"{}\"{}}}",
+                    escape(name),
+                    value.slang_type(),
+                    reference
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        entries.push(format!(
+            "    {{\"id\": {}, \"generation\": {}, \"reference_count\": {}, \"marked\": {}, \"age\": {}, \"size\": {}, \"finalizer\": {}, \"site\": {}, \"fields\": [{}]}}",
+            pointer.index,
+            pointer.generation,
+            object.reference_count,
+            object.marked,
+            object.age,
+            object.size,
+            object.finalizer.is_some(),
+            match object.site {
+                Some(location) => format!("\"{}\"", escape(&sources.position(location))),
+                None => String::from("null"),
+            },
+            fields
+        ));
+    }
+
+    format!(
+        "{{\n  \"strategy\": \"{}\",\n  \"roots\": [{}],\n  \"objects\": {},\n  \"edges\": {}\n}}\n",
+        heap.get_technique_code(),
+        roots,
+        array(&entries),
+        array(&edges)
+    )
+}
+
+/// Joins lines which are already indented into a JSON array.
+fn array(lines: &[String]) -> String {
+    if lines.is_empty() {
+        return String::from("[]");
+    }
+
+    format!("[\n{}\n  ]", lines.join(",\n"))
+}
+
+/// Escapes a string so that it can be written within quotes in JSON.
+fn escape(string: &str) -> String {
+    let mut escaped = String::new();
+
+    for character in string.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            character if character.is_control() => {
+                escaped.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => escaped.push(character),
+        }
+    }
+
+    escaped
+}
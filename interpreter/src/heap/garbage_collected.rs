@@ -1,17 +1,131 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{mem, time::Duration};
 
 use crate::{
-    heap::{HeapObject, Object, Pointer},
+    clock::Instant,
+    heap::{Allocations, Collection, HeapObject, Object, Pointer, Usage, arena::Arena, parallel},
     value::Value,
 };
 
+/// The number of gray objects traversed in each slice of incremental marking.
+const MARK_BUDGET: usize = 64;
+
+/// The fewest objects which count as allocation pressure.
+const MINIMUM_PRESSURE_THRESHOLD: usize = 256;
+
+/// Parameters which control when collections are started.
+#[derive(Clone, Copy)]
+pub struct Tuning {
+    /// The fewest objects the heap must contain for a collection to start.
+    pub threshold: usize,
+    /// After a collection, the heap must grow to this multiple of the surviving objects before another can start.
+    pub growth_factor: f64,
+    /// Starts collections every N statements, instead of whenever a block exits.
+    pub every_n_statements: Option<usize>,
+    /// Whether marking is spread over many statements, or done in one pause.
+    pub mode: Mode,
+}
+
+impl Default for Tuning {
+    /// Starts a collection whenever a block exits.
+    fn default() -> Self {
+        Self {
+            threshold: 0,
+            growth_factor: 1.0,
+            every_n_statements: None,
+            mode: Mode::Incremental,
+        }
+    }
+}
+
+/// How a collection marks the heap once it has started.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// A limited number of objects are marked before each statement.
+    Incremental,
+    /// Every object is marked and swept in a single pause, as soon as the collection starts.
+    StopTheWorld,
+}
+
+impl Mode {
+    /// Returns the mode with a name (`incremental` or `stop-the-world`), if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "incremental" => Some(Self::Incremental),
+            "stop-the-world" => Some(Self::StopTheWorld),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Incremental => "incremental",
+            Self::StopTheWorld => "stop-the-world",
+        }
+    }
+}
+
+/// A collection whose marking is in progress.
+struct Cycle {
+    start: Instant,
+    /// The number of objects on the heap when the collection started.
+    before: usize,
+    /// The total time spent in slices of the collection so far.
+    paused: Duration,
+    slices: usize,
+    longest_slice: Duration,
+    /// The number of threads which finished marking.
+    threads: usize,
+}
+
+/// A mark and sweep garbage collected heap, which marks incrementally.
+///
+/// A collection starts when a block exits (or as its [Tuning] says) or when allocation pressure requests one, by shading the roots gray. A limited number of gray objects are then traversed before each statement, and once none are left, the roots are shaded again and every white object is swept.
+///
+/// While marking, a write barrier shades any object stored in a field, and new objects are allocated gray, so that a black object never references a white one.
 pub struct GarbageCollectedHeap {
-    heap: Vec<Pointer>,
+    /// The objects on the heap.
+    pub objects: Arena,
+    /// Objects which have been marked, but whose fields have not yet been traversed.
+    gray: Vec<Pointer>,
+    /// The collection in progress, if any.
+    cycle: Option<Cycle>,
+    tuning: Tuning,
+    /// The number of objects the heap must contain for the next collection to start.
+    next_threshold: usize,
+    /// The number of objects which survived the last collection.
+    surviving: usize,
+    /// The number of statements executed, for starting collections every N statements.
+    statements: usize,
+    /// The number of objects at which allocation alone requests a collection, which doubles the surviving objects after each one.
+    pressure_threshold: usize,
+    /// Whether allocation has requested a collection.
+    ///
+    /// Collections cannot start within an allocation, because values which are still being evaluated are not yet reachable from the stack, so this is checked before the next statement.
+    requested: bool,
+    /// Running totals of the allocations made.
+    pub allocations: Allocations,
+    /// The current and peak usage of the heap.
+    pub usage: Usage,
+    /// Records of the collections which have not yet been taken to be recorded as stats.
+    pub collections: Vec<Collection>,
 }
 
 impl GarbageCollectedHeap {
-    pub fn new() -> Self {
-        Self { heap: Vec::new() }
+    pub fn new(tuning: Tuning) -> Self {
+        Self {
+            objects: Arena::default(),
+            gray: Vec::new(),
+            cycle: None,
+            tuning,
+            next_threshold: tuning.threshold,
+            surviving: 0,
+            statements: 0,
+            pressure_threshold: MINIMUM_PRESSURE_THRESHOLD,
+            requested: false,
+            allocations: Allocations::default(),
+            usage: Usage::default(),
+            collections: Vec::new(),
+        }
     }
 
     pub fn allocate(&mut self, data: Object) -> Pointer {
@@ -23,54 +137,233 @@ impl GarbageCollectedHeap {
             })
             .collect();
 
-        let heap_object = HeapObject {
-            data,
-            marked: false,
-            reference_count: 1,
-        };
+        let heap_object = HeapObject::new(data);
+
+        self.allocations.record(&heap_object);
+        self.usage
+            .allocated(&heap_object, self.objects.objects_count() + 1);
 
-        let pointer = Pointer::new(RefCell::new(heap_object));
-        self.heap.push(Rc::clone(&pointer));
+        let pointer = self.objects.insert(heap_object);
+
+        // Objects allocated while marking must survive the collection, along with anything they reference.
+        if self.cycle.is_some() {
+            self.shade(pointer);
+        } else if self.objects.objects_count() >= self.pressure_threshold {
+            self.requested = true;
+        }
 
         pointer
     }
 
+    pub fn tuning(&self) -> Tuning {
+        self.tuning
+    }
+
+    /// Changes when collections are started, and how they mark the heap, from the next statement onwards. The next collection is due once the heap reaches the new threshold, or has grown by the new growth factor since the last collection.
+    ///
+    /// A collection which is already marking incrementally is finished in a single pause when it next runs, if the mode is changed to [Mode::StopTheWorld].
+    pub fn set_tuning(&mut self, tuning: Tuning) {
+        self.tuning = tuning;
+        self.next_threshold = self.next_threshold(self.surviving);
+    }
+
+    /// Returns the number of objects the heap must contain for a collection to start, after a collection which some objects survived.
+    fn next_threshold(&self, surviving: usize) -> usize {
+        let grown = (surviving as f64 * self.tuning.growth_factor).ceil() as usize;
+
+        self.tuning.threshold.max(grown)
+    }
+
+    /// Returns whether a collection is in progress.
+    pub fn collecting(&self) -> bool {
+        self.cycle.is_some()
+    }
+
+    /// Called when a block exits. Starts a collection if one is due, and shades the roots gray if one is in progress.
     pub fn manage(&mut self, roots: &[Pointer]) {
+        if self.cycle.is_some()
+            || (self.tuning.every_n_statements.is_none()
+                && self.objects.objects_count() >= self.next_threshold)
+        {
+            self.start(roots);
+        }
+    }
+
+    /// Called before each statement. Returns whether a collection should be started with the current roots, either because allocation requested one or when collecting every N statements.
+    pub fn statement_due(&mut self) -> bool {
+        self.statements += 1;
+
+        if self.cycle.is_some() {
+            return false;
+        }
+
+        if mem::take(&mut self.requested) {
+            return true;
+        }
+
+        self.tuning
+            .every_n_statements
+            .is_some_and(|every_n_statements| {
+                self.statements.is_multiple_of(every_n_statements)
+                    && self.objects.objects_count() >= self.next_threshold
+            })
+    }
+
+    /// Starts a collection if one is not already in progress, and shades the roots gray. In [Mode::StopTheWorld], the collection is finished straight away.
+    pub fn start(&mut self, roots: &[Pointer]) {
+        let slice = Instant::now();
+
+        if self.cycle.is_none() {
+            self.cycle = Some(Cycle {
+                start: slice,
+                before: self.objects.objects_count(),
+                paused: Duration::ZERO,
+                slices: 0,
+                longest_slice: Duration::ZERO,
+                threads: 1,
+            });
+        }
+
+        if self.tuning.mode == Mode::StopTheWorld {
+            self.finish(roots);
+            return;
+        }
+
         for root in roots {
-            self.traverse(Rc::clone(&root));
+            self.shade(*root);
+        }
+
+        self.end_slice(slice);
+    }
+
+    /// Traverses up to `MARK_BUDGET` gray objects, if a collection is in progress.
+    ///
+    /// Returns whether marking has run out of gray objects, in which case [GarbageCollectedHeap::finish] should be called with the current roots. In [Mode::StopTheWorld], a collection which was started incrementally is finished by the next call instead.
+    pub fn step(&mut self) -> bool {
+        if self.cycle.is_none() {
+            return false;
+        }
+
+        if self.tuning.mode == Mode::StopTheWorld {
+            return true;
         }
 
-        self.heap.retain(|value| value.borrow().marked);
+        let slice = Instant::now();
+
+        for _ in 0..MARK_BUDGET {
+            let Some(object) = self.gray.pop() else {
+                break;
+            };
 
-        for object in &self.heap {
-            object.borrow_mut().marked = false;
+            self.scan(object);
         }
+
+        self.end_slice(slice);
+
+        self.gray.is_empty()
     }
 
-    fn traverse(&mut self, root: Pointer) {
-        {
-            let root = root.borrow();
+    /// Shades the current roots, finishes marking, and sweeps every object which is still white.
+    pub fn finish(&mut self, roots: &[Pointer]) {
+        let slice = Instant::now();
 
-            if root.marked {
-                return;
+        for root in roots {
+            self.shade(*root);
+        }
+
+        // Marking is finished in a single pause, which is split between threads if the heap is large enough.
+        if let Some(threads) = parallel::mark(&mut self.objects, &self.gray) {
+            self.gray.clear();
+
+            if let Some(cycle) = &mut self.cycle {
+                cycle.threads = threads;
             }
         }
 
-        {
-            let mut root = root.borrow_mut();
-            root.marked = true;
+        while let Some(object) = self.gray.pop() {
+            self.scan(object);
         }
 
-        let root = root.borrow();
+        for pointer in self.objects.pointers() {
+            let Some(object) = self.objects.get_mut(pointer) else {
+                continue;
+            };
 
-        for value in root.data.values() {
-            if let Value::ObjectReference(pointer) = value {
-                self.traverse(Rc::clone(&pointer));
+            if object.marked {
+                object.marked = false;
+            } else if let Some(object) = self.objects.remove(pointer) {
+                self.usage.freed(&object);
             }
         }
+
+        self.end_slice(slice);
+
+        let surviving = self.objects.objects_count();
+        self.surviving = surviving;
+        self.next_threshold = self.next_threshold(surviving);
+        self.pressure_threshold = MINIMUM_PRESSURE_THRESHOLD.max(surviving * 2);
+
+        if let Some(cycle) = self.cycle.take() {
+            let mut collection = Collection::from_slices(
+                cycle.start,
+                cycle.paused,
+                cycle.slices,
+                cycle.longest_slice,
+                cycle.before,
+                surviving,
+            );
+            collection.threads = cycle.threads;
+
+            self.collections.push(collection);
+        }
+    }
+
+    /// Performs a complete collection without pausing, finishing any collection which is already in progress.
+    pub fn collect(&mut self, roots: &[Pointer]) {
+        self.start(roots);
+        self.finish(roots);
+    }
+
+    /// Records that a field of an object has been set to a value, shading the value if marking is in progress.
+    pub fn write_barrier(&mut self, value: &Value) {
+        if let (Some(_), Value::ObjectReference(pointer)) = (&self.cycle, value) {
+            self.shade(*pointer);
+        }
+    }
+
+    /// Marks a white object and adds it to the gray objects.
+    fn shade(&mut self, pointer: Pointer) {
+        let Some(object) = self.objects.get_mut(pointer) else {
+            return;
+        };
+
+        if object.marked {
+            return;
+        }
+
+        object.marked = true;
+        self.gray.push(pointer);
+    }
+
+    /// Shades every object referenced by a gray object, turning it black.
+    fn scan(&mut self, pointer: Pointer) {
+        for reference in self.objects.references(pointer) {
+            self.shade(reference);
+        }
+    }
+
+    /// Adds the time since `slice` to the collection in progress.
+    fn end_slice(&mut self, slice: Instant) {
+        if let Some(cycle) = &mut self.cycle {
+            let duration = slice.elapsed();
+
+            cycle.paused += duration;
+            cycle.slices += 1;
+            cycle.longest_slice = cycle.longest_slice.max(duration);
+        }
     }
 
     pub fn objects_count(&self) -> usize {
-        self.heap.len()
+        self.objects.objects_count()
     }
 }
@@ -0,0 +1,271 @@
+use crate::{
+    clock::Instant,
+    heap::{Allocations, Collection, HeapObject, Object, Pointer, Usage, arena::Arena, parallel},
+    value::Value,
+};
+
+/// The number of minor collections an object must survive before it is promoted to the old generation.
+const PROMOTION_AGE: usize = 2;
+
+/// The smallest size the old generation can reach before a major collection is performed.
+const MINIMUM_MAJOR_THRESHOLD: usize = 64;
+
+/// A garbage collected heap which divides objects into a nursery and an old generation.
+///
+/// Most collections are minor, and only mark and sweep the nursery. Old objects are assumed to be reachable, so any references they hold to young objects are tracked by a write barrier. A major collection of both generations is performed when the old generation has doubled in size since the last one.
+pub struct GenerationalHeap {
+    /// The objects in both generations.
+    pub objects: Arena,
+    /// Objects which have survived fewer than [PROMOTION_AGE] collections.
+    nursery: Vec<Pointer>,
+    /// Objects which have been promoted from the nursery.
+    old: Vec<Pointer>,
+    /// Old objects which may hold references to young objects, whose fields are treated as roots in a minor collection.
+    remembered: Vec<Pointer>,
+    /// The size of the old generation at which the next major collection is performed.
+    major_threshold: usize,
+    /// Running totals of the allocations made.
+    pub allocations: Allocations,
+    /// The current and peak usage of the heap.
+    pub usage: Usage,
+    /// Records of the collections which have not yet been taken to be recorded as stats.
+    pub collections: Vec<Collection>,
+}
+
+impl Default for GenerationalHeap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GenerationalHeap {
+    pub fn new() -> Self {
+        Self {
+            objects: Arena::default(),
+            nursery: Vec::new(),
+            old: Vec::new(),
+            remembered: Vec::new(),
+            major_threshold: MINIMUM_MAJOR_THRESHOLD,
+            allocations: Allocations::default(),
+            usage: Usage::default(),
+            collections: Vec::new(),
+        }
+    }
+
+    /// Allocates an object in the nursery.
+    pub fn allocate(&mut self, data: Object) -> Pointer {
+        let data = data
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::Object(object) => (key, Value::ObjectReference(self.allocate(object))),
+                value => (key, value),
+            })
+            .collect();
+
+        let heap_object = HeapObject::new(data);
+
+        self.allocations.record(&heap_object);
+        self.usage.allocated(&heap_object, self.objects_count() + 1);
+
+        let pointer = self.objects.insert(heap_object);
+        self.nursery.push(pointer);
+
+        pointer
+    }
+
+    /// Records that a field of an object has been set to a value, so that references from old objects to young objects are not missed by minor collections.
+    pub fn write_barrier(&mut self, object: Pointer, value: &Value) {
+        let Value::ObjectReference(target) = value else {
+            return;
+        };
+
+        let young = !is_old(&self.objects, *target);
+
+        let Some(object_data) = self.objects.get_mut(object) else {
+            return;
+        };
+
+        if young && object_data.age >= PROMOTION_AGE && !object_data.remembered {
+            object_data.remembered = true;
+            self.remembered.push(object);
+        }
+    }
+
+    /// Performs a minor collection, followed by a major collection if the old generation has grown enough.
+    pub fn manage(&mut self, roots: &[Pointer]) {
+        self.minor(roots);
+
+        if self.old.len() >= self.major_threshold {
+            self.major(roots);
+        }
+    }
+
+    /// Performs a major collection of both generations.
+    pub fn collect(&mut self, roots: &[Pointer]) {
+        self.major(roots);
+    }
+
+    /// Marks and sweeps the nursery, promoting objects which have survived enough collections.
+    fn minor(&mut self, roots: &[Pointer]) {
+        let start = Instant::now();
+        let before = self.objects_count();
+
+        for root in roots {
+            mark(&mut self.objects, *root, true);
+        }
+
+        for object in self.remembered.clone() {
+            for reference in self.objects.references(object) {
+                mark(&mut self.objects, reference, true);
+            }
+        }
+
+        let mut promoted = Vec::new();
+
+        self.nursery.retain(|&pointer| {
+            let Some(object_data) = self.objects.get_mut(pointer) else {
+                return false;
+            };
+
+            if !object_data.marked {
+                if let Some(object_data) = self.objects.remove(pointer) {
+                    self.usage.freed(&object_data);
+                }
+
+                return false;
+            }
+
+            object_data.marked = false;
+            object_data.age += 1;
+
+            if object_data.age >= PROMOTION_AGE {
+                promoted.push(pointer);
+                return false;
+            }
+
+            true
+        });
+
+        // Promoted objects may still reference young objects, which must now be remembered.
+        for &object in &promoted {
+            if references_young(&self.objects, object) {
+                if let Some(object_data) = self.objects.get_mut(object) {
+                    object_data.remembered = true;
+                }
+
+                self.remembered.push(object);
+            }
+        }
+
+        self.old.append(&mut promoted);
+
+        self.remembered.retain(|&object| {
+            let keep = references_young(&self.objects, object);
+
+            if let Some(object_data) = self.objects.get_mut(object) {
+                object_data.remembered = keep;
+            }
+
+            keep
+        });
+
+        self.collections
+            .push(Collection::finish(start, before, self.objects_count()));
+    }
+
+    /// Marks and sweeps both generations.
+    fn major(&mut self, roots: &[Pointer]) {
+        let start = Instant::now();
+        let before = self.objects_count();
+
+        let threads = parallel::mark(&mut self.objects, roots).unwrap_or_else(|| {
+            for root in roots {
+                mark(&mut self.objects, *root, false);
+            }
+
+            1
+        });
+
+        for generation in [&mut self.nursery, &mut self.old] {
+            generation.retain(|&pointer| {
+                let Some(object_data) = self.objects.get_mut(pointer) else {
+                    return false;
+                };
+
+                if !object_data.marked {
+                    if let Some(object_data) = self.objects.remove(pointer) {
+                        self.usage.freed(&object_data);
+                    }
+
+                    return false;
+                }
+
+                object_data.marked = false;
+
+                true
+            });
+        }
+
+        // Remembered objects may have been freed, so the set is rebuilt from the surviving old generation.
+        self.remembered.clear();
+
+        for &object in &self.old {
+            let remembered = references_young(&self.objects, object);
+
+            if let Some(object_data) = self.objects.get_mut(object) {
+                object_data.remembered = remembered;
+            }
+
+            if remembered {
+                self.remembered.push(object);
+            }
+        }
+
+        self.major_threshold = MINIMUM_MAJOR_THRESHOLD.max(self.old.len() * 2);
+
+        let mut collection = Collection::finish(start, before, self.objects_count());
+        collection.threads = threads;
+
+        self.collections.push(collection);
+    }
+
+    pub fn objects_count(&self) -> usize {
+        self.objects.objects_count()
+    }
+}
+
+/// Returns whether an object has been promoted to the old generation.
+fn is_old(objects: &Arena, object: Pointer) -> bool {
+    objects
+        .get(object)
+        .is_some_and(|object| object.age >= PROMOTION_AGE)
+}
+
+/// Returns whether any field of an object references a young object.
+fn references_young(objects: &Arena, object: Pointer) -> bool {
+    objects
+        .references(object)
+        .into_iter()
+        .any(|reference| !is_old(objects, reference))
+}
+
+/// Marks an object and everything reachable from it. In a minor collection, old objects are neither marked nor traversed.
+///
+/// The objects still to be traversed are kept in a worklist rather than on the call stack, so that a long chain of objects (such as a linked list) cannot overflow it.
+fn mark(objects: &mut Arena, object: Pointer, minor: bool) {
+    let mut pending = vec![object];
+
+    while let Some(object) = pending.pop() {
+        let Some(object_data) = objects.get_mut(object) else {
+            continue;
+        };
+
+        if object_data.marked || (minor && object_data.age >= PROMOTION_AGE) {
+            continue;
+        }
+
+        object_data.marked = true;
+
+        pending.extend(objects.references(object));
+    }
+}
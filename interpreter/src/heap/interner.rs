@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+use crate::{heap::Object, shared::Shared, value::Value};
+
+/// The fewest strings the table can hold before strings which are no longer used are purged.
+const MINIMUM_PURGE_THRESHOLD: usize = 64;
+
+/// A table of the strings stored in heap objects, so that equal field names and string values share storage.
+///
+/// Strings which are no longer used by any object are purged from the table once it has doubled in size since the last purge.
+pub struct Interner {
+    strings: HashSet<Shared<str>>,
+    /// The number of strings at which the next purge is performed.
+    purge_threshold: usize,
+    /// The total bytes which did not need to be stored again, because an equal string had already been interned.
+    pub deduplicated_bytes: usize,
+}
+
+impl Default for Interner {
+    fn default() -> Self {
+        Self {
+            strings: HashSet::new(),
+            purge_threshold: MINIMUM_PURGE_THRESHOLD,
+            deduplicated_bytes: 0,
+        }
+    }
+}
+
+impl Interner {
+    /// Returns the interned string equal to `string`, interning it if there is none.
+    pub fn intern(&mut self, string: Shared<str>) -> Shared<str> {
+        if let Some(interned) = self.strings.get(&string) {
+            // A string which already shares the interned storage has not been duplicated.
+            if !Shared::ptr_eq(interned, &string) {
+                self.deduplicated_bytes += string.len();
+            }
+
+            return Shared::clone(interned);
+        }
+
+        if self.strings.len() >= self.purge_threshold {
+            self.purge();
+        }
+
+        self.strings.insert(Shared::clone(&string));
+
+        string
+    }
+
+    /// Interns the field names and string values of an object.
+    pub fn intern_object(&mut self, data: Object) -> Object {
+        data.into_iter()
+            .map(|(key, value)| (self.intern(key), self.intern_value(value)))
+            .collect()
+    }
+
+    /// Interns a value, if it is a string.
+    pub fn intern_value(&mut self, value: Value) -> Value {
+        match value {
+            Value::String(string) => Value::String(self.intern(string)),
+            value => value,
+        }
+    }
+
+    /// Removes every string which is only held by the table.
+    fn purge(&mut self) {
+        self.strings
+            .retain(|string| Shared::strong_count(string) > 1);
+        self.purge_threshold = MINIMUM_PURGE_THRESHOLD.max(self.strings.len() * 2);
+    }
+}
@@ -1,55 +1,895 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+//! The heap, which stores objects and manages their lifetimes with one of several strategies.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Debug, Display},
+    mem,
+    time::Duration,
+};
 
 use crate::{
+    clock::Instant,
     heap::{
-        garbage_collected::GarbageCollectedHeap, naive::NaiveHeap,
-        reference_counted::ReferenceCountedHeap,
+        arena::Arena,
+        compacting::CompactingHeap,
+        copying::CopyingHeap,
+        garbage_collected::{GarbageCollectedHeap, Tuning},
+        generational::GenerationalHeap,
+        naive::NaiveHeap,
+        reference_counted::{Colour, ReferenceCountedHeap},
+        region::RegionHeap,
     },
-    value::Value,
+    shared::Shared,
+    source::Location,
+    trace,
+    value::{Function, Value},
 };
 
+pub mod arena;
+pub mod compacting;
+pub mod copying;
+pub mod dump;
 pub mod garbage_collected;
+pub mod generational;
+pub mod interner;
 pub mod naive;
+pub mod parallel;
 pub mod reference_counted;
+pub mod region;
+pub mod sites;
+
+pub type Object = HashMap<Shared<str>, Value>;
+
+/// A reference to an object on the heap, as an index into the heap's [arena::Arena].
+///
+/// The generation identifies which object in the slot the pointer refers to, so that using a pointer after its object has been freed is detected, rather than reaching whichever object has reused the slot.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pointer {
+    pub index: usize,
+    pub generation: usize,
+}
+
+/// All errors which can occur while accessing the heap.
+pub enum HeapError {
+    /// When a pointer is used after the object it refers to has been freed.
+    UseAfterFree { pointer: Pointer },
+    /// When an allocation would exceed one of the heap's limits, even after a collection.
+    OutOfMemory { limit: Limit },
+}
+
+impl Display for HeapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UseAfterFree { pointer } => write!(
+                f,
+                "Attempted to use an object (at index {}, generation {}) after it was freed.",
+                pointer.index, pointer.generation
+            ),
+            Self::OutOfMemory { limit } => match limit {
+                Limit::Objects(max) => {
+                    write!(f, "Out of memory: the heap is limited to {} objects.", max)
+                }
+                Limit::Bytes(max) => {
+                    write!(f, "Out of memory: the heap is limited to {} bytes.", max)
+                }
+            },
+        }
+    }
+}
+
+impl Debug for HeapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Error for HeapError {}
+
+/// The most a heap may hold, beyond which allocations fail.
+#[derive(Clone, Copy, Default)]
+pub struct Limits {
+    /// The most objects which may be on the heap at once.
+    pub max_objects: Option<usize>,
+    /// The most estimated bytes which may be used by the heap at once.
+    pub max_bytes: Option<usize>,
+}
 
-pub type Object = HashMap<String, Value>;
+impl Limits {
+    /// Returns the limit which would be exceeded if the heap held a number of objects, using a number of bytes.
+    pub fn exceeded(&self, objects: usize, bytes: usize) -> Option<Limit> {
+        if let Some(max) = self.max_objects.filter(|max| objects > *max) {
+            return Some(Limit::Objects(max));
+        }
+
+        self.max_bytes.filter(|max| bytes > *max).map(Limit::Bytes)
+    }
+}
 
-pub type Pointer = Rc<RefCell<HeapObject>>;
+/// A single limit on the size of a heap.
+#[derive(Clone, Copy)]
+pub enum Limit {
+    Objects(usize),
+    Bytes(usize),
+}
 
 #[derive(PartialEq)]
 pub struct HeapObject {
     pub data: Object,
     pub marked: bool,
     pub reference_count: usize,
+    /// The number of minor collections the object has survived, under generational garbage collection.
+    pub age: usize,
+    /// Whether the object is in the remembered set, under generational garbage collection.
+    pub remembered: bool,
+    /// The colour of the object in the collection of garbage cycles, under reference counting.
+    pub colour: Colour,
+    /// Whether the object is buffered as a possible root of a garbage cycle, under reference counting.
+    pub buffered: bool,
+    /// The depth of the scope whose region the object is in, under region-based allocation.
+    pub region: usize,
+    /// The estimated size of the object when it was allocated, which is released from the heap's usage when it is freed.
+    pub size: usize,
+    /// A function to run once the object has been reclaimed.
+    pub finalizer: Option<Function>,
+    /// The location of the object literal it was allocated from, if it was allocated from one.
+    pub site: Option<Location>,
+}
+
+impl HeapObject {
+    /// Creates an object with a reference count of one, estimating its size.
+    pub fn new(data: Object) -> Self {
+        let mut object = Self {
+            data,
+            marked: false,
+            reference_count: 1,
+            age: 0,
+            remembered: false,
+            colour: Colour::Black,
+            buffered: false,
+            region: 0,
+            size: 0,
+            finalizer: None,
+            site: None,
+        };
+
+        object.size = estimate_size(&object.data);
+
+        object
+    }
+}
+
+/// A function registered to run when an object is reclaimed, which is queued once it has been.
+pub struct Finalizer {
+    pub function: Function,
+    /// A copy of the object's fields, excluding references to other objects, which may have been reclaimed along with it.
+    pub fields: Object,
+}
+
+/// A record of a single collection, in which unreachable objects were freed.
+pub struct Collection {
+    /// When the collection started.
+    pub start: Instant,
+    /// How long the collection paused the program for, in total.
+    pub duration: Duration,
+    /// The number of separate pauses the collection was split into.
+    pub slices: usize,
+    /// The longest of those pauses.
+    pub longest_slice: Duration,
+    /// The number of objects which were freed.
+    pub freed: usize,
+    /// The number of objects which remained on the heap afterwards.
+    pub surviving: usize,
+    /// Whether this was a collection of reference cycles, under reference counting.
+    pub cycles: bool,
+    /// The number of surviving objects which were moved to compact the heap, under mark-compact collection, copied into a fresh space, under copying collection, or promoted to an outer region, under region-based allocation.
+    pub moved: usize,
+    /// The number of threads which marked the heap.
+    pub threads: usize,
+}
+
+impl Collection {
+    /// Finishes timing a collection which started at `start` and ran in a single pause, given the number of objects before and after it.
+    pub fn finish(start: Instant, before: usize, after: usize) -> Self {
+        let duration = start.elapsed();
+
+        Self::from_slices(start, duration, 1, duration, before, after)
+    }
+
+    /// Records a collection which was split into several pauses, given the number of objects before and after it.
+    pub fn from_slices(
+        start: Instant,
+        duration: Duration,
+        slices: usize,
+        longest_slice: Duration,
+        before: usize,
+        after: usize,
+    ) -> Self {
+        let collection = Self {
+            start,
+            duration,
+            slices,
+            longest_slice,
+            freed: before.saturating_sub(after),
+            surviving: after,
+            cycles: false,
+            moved: 0,
+            threads: 1,
+        };
+
+        tracing::debug!(
+            target: trace::GC,
+            milliseconds = collection.duration.as_secs_f64() * 1000.0,
+            slices = collection.slices,
+            freed = collection.freed,
+            surviving = collection.surviving,
+            "Collected."
+        );
+
+        collection
+    }
+}
+
+/// Running totals of the allocations made by a heap.
+#[derive(Clone, Copy, Default)]
+pub struct Allocations {
+    /// The number of objects allocated.
+    pub count: usize,
+    /// The estimated number of bytes allocated.
+    pub bytes: usize,
+    /// The number of objects stored inline in the environment, which would otherwise have been allocated.
+    pub elided: usize,
+}
+
+impl Allocations {
+    /// Records the allocation of an object.
+    pub fn record(&mut self, object: &HeapObject) {
+        self.count += 1;
+        self.bytes += object.size;
+    }
+
+    /// Returns the allocations made since an earlier snapshot of the same totals.
+    pub fn since(self, earlier: Allocations) -> Allocations {
+        Allocations {
+            count: self.count - earlier.count,
+            bytes: self.bytes - earlier.bytes,
+            elided: self.elided - earlier.elided,
+        }
+    }
+}
+
+/// Running totals of the changes made to reference counts, under reference counting.
+#[derive(Clone, Copy, Default)]
+pub struct CountOperations {
+    /// The number of increments and decrements which were made.
+    pub requested: usize,
+    /// The number of times an object's count was actually updated. This is the same as the number requested, unless counting is deferred, in which case each object's changes between safe points are coalesced into one.
+    pub applied: usize,
+}
+
+/// Running totals of the collections a heap has performed.
+#[derive(Clone, Copy, Default)]
+pub struct CollectionTotals {
+    pub collections: usize,
+    pub objects_freed: usize,
+    /// The number of separate pauses the collections were split into.
+    pub pauses: usize,
+    /// How long the collections paused the program for, in total.
+    pub pause_time: Duration,
+    /// The longest single pause of any collection.
+    pub longest_pause: Duration,
+}
+
+impl CollectionTotals {
+    /// Adds a collection to the totals.
+    pub fn record(&mut self, collection: &Collection) {
+        self.collections += 1;
+        self.objects_freed += collection.freed;
+        self.pauses += collection.slices;
+        self.pause_time += collection.duration;
+        self.longest_pause = self.longest_pause.max(collection.longest_slice);
+    }
+}
+
+/// A summary of the collections a heap has performed, and of the objects on it.
+#[derive(Clone, Copy)]
+pub struct HeapStats {
+    pub collections: usize,
+    pub objects_freed: usize,
+    pub objects: usize,
+    pub peak_objects: usize,
+    /// The estimated bytes used by the objects currently on the heap.
+    pub bytes: usize,
+    pub peak_bytes: usize,
+}
+
+impl HeapStats {
+    /// Converts the stats to an object, with a field for each.
+    pub fn to_object(self) -> Object {
+        [
+            ("collections", self.collections),
+            ("objects_freed", self.objects_freed),
+            ("objects", self.objects),
+            ("peak_objects", self.peak_objects),
+            ("bytes", self.bytes),
+            ("peak_bytes", self.peak_bytes),
+        ]
+        .into_iter()
+        .map(|(field, value)| (Shared::from(field), Value::Integer(value as i32)))
+        .collect()
+    }
+}
+
+/// A summary of how a heap's collections have paused the program, for tuning the collector while a program runs.
+#[derive(Clone, Copy)]
+pub struct GcStats {
+    pub totals: CollectionTotals,
+    /// Whether a collection has started marking, but not yet finished.
+    pub collecting: bool,
+}
+
+impl GcStats {
+    /// Converts the stats to an object, with a field for each, giving times in milliseconds.
+    pub fn to_object(self) -> Object {
+        let milliseconds = |duration: Duration| Value::Float(duration.as_secs_f64() * 1000.0);
+
+        [
+            (
+                "collections",
+                Value::Integer(self.totals.collections as i32),
+            ),
+            (
+                "objects_freed",
+                Value::Integer(self.totals.objects_freed as i32),
+            ),
+            ("pauses", Value::Integer(self.totals.pauses as i32)),
+            ("pause_time", milliseconds(self.totals.pause_time)),
+            ("longest_pause", milliseconds(self.totals.longest_pause)),
+            ("collecting", Value::Boolean(self.collecting)),
+        ]
+        .into_iter()
+        .map(|(field, value)| (Shared::from(field), value))
+        .collect()
+    }
+}
+
+/// The estimated bytes currently used by a heap, and the highest usage it has reached.
+#[derive(Clone, Copy, Default)]
+pub struct Usage {
+    /// The estimated bytes used by the objects currently on the heap.
+    pub bytes: usize,
+    /// The most objects which have been on the heap at once.
+    pub peak_objects: usize,
+    /// The most estimated bytes which have been used by the heap at once.
+    pub peak_bytes: usize,
+    /// The total bytes of strings which shared storage with an equal string, rather than being stored again.
+    pub deduplicated_bytes: usize,
+}
+
+impl Usage {
+    /// Records the allocation of an object, given the number of objects on the heap afterwards.
+    pub fn allocated(&mut self, object: &HeapObject, objects: usize) {
+        self.bytes += object.size;
+        self.peak_objects = self.peak_objects.max(objects);
+        self.peak_bytes = self.peak_bytes.max(self.bytes);
+    }
+
+    /// Records the bytes of the objects copied by a copying collection, which are in use twice over until the old space is freed.
+    pub fn copied(&mut self, bytes: usize) {
+        self.peak_bytes = self.peak_bytes.max(self.bytes + bytes);
+    }
+
+    /// Records an object being freed.
+    pub fn freed(&mut self, object: &HeapObject) {
+        self.bytes = self.bytes.saturating_sub(object.size);
+    }
+}
+
+/// Estimates the number of bytes used by an object.
+///
+/// This includes the object's slot in the arena, the buckets of the field map, and the contents of field names and string values, but not nested objects, which are allocated separately.
+pub fn estimate_size(data: &Object) -> usize {
+    // A slot in the arena holds a generation alongside the object.
+    let slot = size_of::<usize>() + size_of::<Option<HeapObject>>();
+
+    let buckets = data.capacity() * size_of::<(String, Value)>();
+
+    let contents: usize = data
+        .iter()
+        .map(|(key, value)| {
+            key.len()
+                + match value {
+                    Value::String(string) => string.len(),
+                    _ => 0,
+                }
+        })
+        .sum();
+
+    slot + buckets + contents
+}
+
+/// Returns the number of objects, and the estimated bytes, needed to allocate an object along with any objects nested within it.
+fn requirements(data: &Object) -> (usize, usize) {
+    data.values().fold(
+        (1, estimate_size(data)),
+        |(objects, bytes), value| match value {
+            Value::Object(nested) => {
+                let (nested_objects, nested_bytes) = requirements(nested);
+
+                (objects + nested_objects, bytes + nested_bytes)
+            }
+            _ => (objects, bytes),
+        },
+    )
+}
+
+/// Adds every object referenced by an object, or any objects nested within it, to a list.
+fn references(data: &Object, pointers: &mut Vec<Pointer>) {
+    for value in data.values() {
+        match value {
+            Value::ObjectReference(pointer) => pointers.push(*pointer),
+            Value::Object(nested) => references(nested, pointers),
+            _ => {}
+        }
+    }
+}
+
+/// The techniques which can be used to manage the heap.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Strategy {
+    GarbageCollected,
+    Generational,
+    Compacting,
+    Copying,
+    ReferenceCounted,
+    Region,
+    Naive,
+}
+
+impl Strategy {
+    /// Returns the strategy for a technique code (`gc`, `gen`, `mc`, `cp`, `rc`, `rg` or `na`), if it is valid.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "gc" => Some(Self::GarbageCollected),
+            "gen" => Some(Self::Generational),
+            "mc" => Some(Self::Compacting),
+            "cp" => Some(Self::Copying),
+            "rc" => Some(Self::ReferenceCounted),
+            "rg" => Some(Self::Region),
+            "na" => Some(Self::Naive),
+            _ => None,
+        }
+    }
+
+    /// Returns the technique code for the strategy.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::GarbageCollected => "gc",
+            Self::Generational => "gen",
+            Self::Compacting => "mc",
+            Self::Copying => "cp",
+            Self::ReferenceCounted => "rc",
+            Self::Region => "rg",
+            Self::Naive => "na",
+        }
+    }
 }
 
 pub enum ManagedHeap {
     GarbageCollected(GarbageCollectedHeap),
+    Generational(GenerationalHeap),
+    Compacting(CompactingHeap),
+    Copying(CopyingHeap),
     Naive(NaiveHeap),
     ReferenceCounted(ReferenceCountedHeap),
+    Region(RegionHeap),
 }
 
 impl ManagedHeap {
-    pub fn allocate(&mut self, data: Object) -> Pointer {
+    /// Creates a new, empty heap managed with a specific strategy.
+    pub fn new(strategy: Strategy) -> Self {
+        Self::with_tuning(strategy, Tuning::default())
+    }
+
+    /// Creates a new, empty heap managed with a specific strategy, where garbage collection is tuned with specific parameters.
+    pub fn with_tuning(strategy: Strategy, tuning: Tuning) -> Self {
+        match strategy {
+            Strategy::GarbageCollected => Self::GarbageCollected(GarbageCollectedHeap::new(tuning)),
+            Strategy::Generational => Self::Generational(GenerationalHeap::new()),
+            Strategy::Compacting => Self::Compacting(CompactingHeap::new()),
+            Strategy::Copying => Self::Copying(CopyingHeap::new()),
+            Strategy::ReferenceCounted => Self::ReferenceCounted(ReferenceCountedHeap::new()),
+            Strategy::Region => Self::Region(RegionHeap::new()),
+            Strategy::Naive => Self::Naive(NaiveHeap::new()),
+        }
+    }
+
+    /// Records the location of the object literal most recently evaluated, which is the allocation site of the next objects allocated.
+    pub fn set_site(&mut self, location: Location) {
+        self.objects_mut().site = Some(location);
+    }
+
+    /// Sets the most the heap may hold, which is enforced whenever objects are allocated.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.objects_mut().limits = limits;
+    }
+
+    /// Sets whether a full collection is forced before every allocation, so that any object which is in use without being rooted is freed (and its use reported) as soon as possible.
+    pub fn set_stress(&mut self, stress: bool) {
+        self.objects_mut().stress = stress;
+    }
+
+    /// Sets the number of threads which mark the heap once it has at least [parallel::PARALLEL_THRESHOLD] objects. This affects the mark and sweep, generational and mark-compact heaps, but not the copying heap (which evacuates rather than marks) or reference counting.
+    pub fn set_gc_threads(&mut self, threads: usize) {
+        self.objects_mut().gc_threads = threads;
+    }
+
+    /// Sets whether changes to reference counts are buffered and applied at safe points, coalescing the changes made to each object in between. This only affects reference counting.
+    pub fn set_deferred_counting(&mut self, deferred: bool) {
+        if let Self::ReferenceCounted(heap) = self {
+            if !deferred {
+                heap.flush();
+            }
+
+            heap.deferred = deferred;
+        }
+    }
+
+    /// Applies any changes to reference counts which have been buffered, so that every object which is no longer referenced is freed.
+    pub fn apply_buffered_counts(&mut self) {
+        if let Self::ReferenceCounted(heap) = self {
+            heap.flush();
+        }
+    }
+
+    /// Reaches a point where buffered changes to reference counts may be applied, which they are if enough have been buffered.
+    pub fn safe_point(&mut self) {
+        if let Self::ReferenceCounted(heap) = self {
+            heap.safe_point();
+        }
+    }
+
+    /// Returns the running totals of the changes made to reference counts, which are all zero unless the heap is reference counted.
+    pub fn count_operations(&self) -> CountOperations {
         match self {
+            Self::ReferenceCounted(heap) => heap.operations,
+            _ => CountOperations::default(),
+        }
+    }
+
+    /// Allocates an object, along with any objects nested within it.
+    ///
+    /// If this would exceed the heap's limits, or the heap is being stress tested, a full collection is forced first, treating the roots (which are only found if needed) and anything the new objects reference as reachable. The allocation fails if the limits would still be exceeded.
+    pub fn allocate(
+        &mut self,
+        data: Object,
+        roots: impl FnOnce() -> Vec<Pointer>,
+    ) -> Result<Pointer, HeapError> {
+        let (objects, bytes) = requirements(&data);
+
+        if self.objects().stress || self.exceeded_limit(objects, bytes).is_some() {
+            let mut roots = roots();
+            references(&data, &mut roots);
+
+            self.collect(&roots);
+
+            if let Some(limit) = self.exceeded_limit(objects, bytes) {
+                return Err(HeapError::OutOfMemory { limit });
+            }
+        }
+
+        let pointer = match self {
             Self::GarbageCollected(heap) => heap.allocate(data),
+            Self::Generational(heap) => heap.allocate(data),
+            Self::Compacting(heap) => heap.allocate(data),
+            Self::Copying(heap) => heap.allocate(data),
             Self::Naive(heap) => heap.allocate(data),
             Self::ReferenceCounted(heap) => heap.allocate(data),
-        }
+            Self::Region(heap) => heap.allocate(data),
+        };
+
+        // The site only applies to the objects allocated from that literal.
+        self.objects_mut().site = None;
+
+        Ok(pointer)
+    }
+
+    /// Returns the limit which would be exceeded by allocating a number of objects, using a number of bytes.
+    fn exceeded_limit(&self, objects: usize, bytes: usize) -> Option<Limit> {
+        self.objects()
+            .limits
+            .exceeded(self.objects_count() + objects, self.usage().bytes + bytes)
     }
 
     pub fn objects_count(&self) -> usize {
         match self {
             Self::GarbageCollected(heap) => heap.objects_count(),
+            Self::Generational(heap) => heap.objects_count(),
+            Self::Compacting(heap) => heap.objects_count(),
+            Self::Copying(heap) => heap.objects_count(),
             Self::Naive(heap) => heap.objects_count(),
             Self::ReferenceCounted(heap) => heap.objects_count(),
+            Self::Region(heap) => heap.objects_count(),
+        }
+    }
+
+    /// Returns the arena which holds the heap's objects.
+    pub fn objects(&self) -> &Arena {
+        match self {
+            Self::GarbageCollected(heap) => &heap.objects,
+            Self::Generational(heap) => &heap.objects,
+            Self::Compacting(heap) => &heap.objects,
+            Self::Copying(heap) => &heap.objects,
+            Self::Naive(heap) => &heap.objects,
+            Self::ReferenceCounted(heap) => &heap.objects,
+            Self::Region(heap) => &heap.objects,
+        }
+    }
+
+    /// Returns the arena which holds the heap's objects mutably.
+    fn objects_mut(&mut self) -> &mut Arena {
+        match self {
+            Self::GarbageCollected(heap) => &mut heap.objects,
+            Self::Generational(heap) => &mut heap.objects,
+            Self::Compacting(heap) => &mut heap.objects,
+            Self::Copying(heap) => &mut heap.objects,
+            Self::Naive(heap) => &mut heap.objects,
+            Self::ReferenceCounted(heap) => &mut heap.objects,
+            Self::Region(heap) => &mut heap.objects,
+        }
+    }
+
+    /// Returns the object a pointer refers to, or an error if it has been freed.
+    pub fn get(&self, pointer: Pointer) -> Result<&HeapObject, HeapError> {
+        self.objects()
+            .get(pointer)
+            .ok_or(HeapError::UseAfterFree { pointer })
+    }
+
+    /// Returns the object a pointer refers to mutably, or an error if it has been freed.
+    pub fn get_mut(&mut self, pointer: Pointer) -> Result<&mut HeapObject, HeapError> {
+        self.objects_mut()
+            .get_mut(pointer)
+            .ok_or(HeapError::UseAfterFree { pointer })
+    }
+
+    /// Returns whether two values are equal, comparing objects by their fields rather than by identity.
+    pub fn values_equal(&self, left: &Value, right: &Value) -> Result<bool, HeapError> {
+        match (left, right) {
+            (Value::ObjectReference(left), Value::ObjectReference(right)) if left == right => {
+                Ok(true)
+            }
+            (Value::ObjectReference(pointer), _) => {
+                self.objects_equal(&self.get(*pointer)?.data, right)
+            }
+            (Value::Object(data), _) => self.objects_equal(data, right),
+            (_, Value::ObjectReference(_) | Value::Object(_)) => Ok(false),
+            (left, right) => Ok(left == right),
+        }
+    }
+
+    /// Returns whether the fields of an object are equal to a value, which must also be an object.
+    fn objects_equal(&self, left: &Object, right: &Value) -> Result<bool, HeapError> {
+        let right = match right {
+            Value::ObjectReference(pointer) => &self.get(*pointer)?.data,
+            Value::Object(data) => data,
+            _ => return Ok(false),
+        };
+
+        if left.len() != right.len() {
+            return Ok(false);
+        }
+
+        for (key, value) in left {
+            match right.get(key) {
+                Some(other) if self.values_equal(value, other)? => {}
+                _ => return Ok(false),
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Forces a full collection, returning the number of objects freed.
+    ///
+    /// Under reference counting, only reference cycles are collected, after any buffered changes to counts are applied. Region-based allocation only frees objects as their scopes exit, and naive allocation never frees anything.
+    pub fn collect(&mut self, roots: &[Pointer]) -> usize {
+        let before = self.objects_count();
+
+        match self {
+            Self::GarbageCollected(heap) => heap.collect(roots),
+            Self::Generational(heap) => heap.collect(roots),
+            Self::Compacting(heap) => heap.collect(roots),
+            Self::Copying(heap) => heap.collect(roots),
+            Self::ReferenceCounted(heap) => heap.collect_cycles(),
+            Self::Region(_) => {}
+            Self::Naive(_) => {}
+        }
+
+        before - self.objects_count()
+    }
+
+    /// Returns the running totals of the allocations made by the heap.
+    pub fn allocations(&self) -> Allocations {
+        match self {
+            Self::GarbageCollected(heap) => heap.allocations,
+            Self::Generational(heap) => heap.allocations,
+            Self::Compacting(heap) => heap.allocations,
+            Self::Copying(heap) => heap.allocations,
+            Self::Naive(heap) => heap.allocations,
+            Self::ReferenceCounted(heap) => heap.allocations,
+            Self::Region(heap) => heap.allocations,
+        }
+    }
+
+    /// Records an object being stored inline in the environment, rather than being allocated on the heap.
+    pub fn elide(&mut self) {
+        match self {
+            Self::GarbageCollected(heap) => heap.allocations.elided += 1,
+            Self::Generational(heap) => heap.allocations.elided += 1,
+            Self::Compacting(heap) => heap.allocations.elided += 1,
+            Self::Copying(heap) => heap.allocations.elided += 1,
+            Self::Naive(heap) => heap.allocations.elided += 1,
+            Self::ReferenceCounted(heap) => heap.allocations.elided += 1,
+            Self::Region(heap) => heap.allocations.elided += 1,
+        }
+    }
+
+    /// Returns a summary of how the heap's collections have paused the program.
+    pub fn gc_stats(&self) -> GcStats {
+        GcStats {
+            totals: self.collection_totals(),
+            collecting: match self {
+                Self::GarbageCollected(heap) => heap.collecting(),
+                _ => false,
+            },
+        }
+    }
+
+    /// Returns the parameters which control when collections start, if the heap is garbage collected with the `gc` strategy, which is the only one that can be tuned.
+    pub fn tuning(&self) -> Option<Tuning> {
+        match self {
+            Self::GarbageCollected(heap) => Some(heap.tuning()),
+            _ => None,
+        }
+    }
+
+    /// Changes the parameters which control when collections start, if the heap is garbage collected with the `gc` strategy.
+    pub fn set_tuning(&mut self, tuning: Tuning) {
+        if let Self::GarbageCollected(heap) = self {
+            heap.set_tuning(tuning);
+        }
+    }
+
+    /// Returns the current and peak usage of the heap.
+    pub fn usage(&self) -> Usage {
+        let usage = match self {
+            Self::GarbageCollected(heap) => heap.usage,
+            Self::Generational(heap) => heap.usage,
+            Self::Compacting(heap) => heap.usage,
+            Self::Copying(heap) => heap.usage,
+            Self::Naive(heap) => heap.usage,
+            Self::ReferenceCounted(heap) => heap.usage,
+            Self::Region(heap) => heap.usage,
+        };
+
+        Usage {
+            deduplicated_bytes: self.objects().interner.deduplicated_bytes,
+            ..usage
+        }
+    }
+
+    /// Sets a field of an object, interning its name and value, and returns the previous value of the field.
+    pub fn set_field(
+        &mut self,
+        pointer: Pointer,
+        field: &str,
+        value: Value,
+    ) -> Result<Option<Value>, HeapError> {
+        let objects = self.objects_mut();
+
+        let field = objects.interner.intern(Shared::from(field));
+        let value = objects.interner.intern_value(value);
+
+        Ok(self.get_mut(pointer)?.data.insert(field, value))
+    }
+
+    /// Registers a function to run once an object has been reclaimed, replacing any registered before.
+    pub fn finalize(&mut self, pointer: Pointer, function: Function) -> Result<(), HeapError> {
+        self.get_mut(pointer)?.finalizer = Some(function);
+
+        Ok(())
+    }
+
+    /// Takes the finalizer of the earliest reclaimed object which has not yet been run.
+    pub fn next_finalizer(&mut self) -> Option<Finalizer> {
+        self.objects_mut().next_finalizer()
+    }
+
+    /// Sets whether finalizers are running, so that they are never nested.
+    pub fn set_finalizing(&mut self, finalizing: bool) {
+        self.objects_mut().finalizing = finalizing;
+    }
+
+    /// Returns whether finalizers are running.
+    pub fn finalizing(&self) -> bool {
+        self.objects().finalizing
+    }
+
+    /// Returns the fraction of the heap's slots which are free, which are wasted until they are reused or compacted away.
+    pub fn fragmentation(&self) -> f64 {
+        self.objects().fragmentation()
+    }
+
+    /// Takes the records of all collections since this was last called, adding them to the heap's totals.
+    pub fn take_collections(&mut self) -> Vec<Collection> {
+        let collections = match self {
+            Self::GarbageCollected(heap) => mem::take(&mut heap.collections),
+            Self::Generational(heap) => mem::take(&mut heap.collections),
+            Self::Compacting(heap) => mem::take(&mut heap.collections),
+            Self::Copying(heap) => mem::take(&mut heap.collections),
+            Self::Naive(_) => Vec::new(),
+            Self::ReferenceCounted(heap) => mem::take(&mut heap.collections),
+            Self::Region(heap) => mem::take(&mut heap.collections),
+        };
+
+        let totals = &mut self.objects_mut().collected;
+
+        for collection in &collections {
+            totals.record(collection);
+        }
+
+        collections
+    }
+
+    /// Returns the records of the collections which have not yet been taken.
+    fn pending_collections(&self) -> &[Collection] {
+        match self {
+            Self::GarbageCollected(heap) => &heap.collections,
+            Self::Generational(heap) => &heap.collections,
+            Self::Compacting(heap) => &heap.collections,
+            Self::Copying(heap) => &heap.collections,
+            Self::Naive(_) => &[],
+            Self::ReferenceCounted(heap) => &heap.collections,
+            Self::Region(heap) => &heap.collections,
+        }
+    }
+
+    /// Returns the running totals of the collections the heap has performed, including those which have not yet been taken.
+    fn collection_totals(&self) -> CollectionTotals {
+        let mut totals = self.objects().collected;
+
+        for collection in self.pending_collections() {
+            totals.record(collection);
+        }
+
+        totals
+    }
+
+    /// Returns a summary of the collections the heap has performed, and of the objects on it.
+    pub fn stats(&self) -> HeapStats {
+        let totals = self.collection_totals();
+
+        let usage = self.usage();
+
+        HeapStats {
+            collections: totals.collections,
+            objects_freed: totals.objects_freed,
+            objects: self.objects_count(),
+            peak_objects: usage.peak_objects,
+            bytes: usage.bytes,
+            peak_bytes: usage.peak_bytes,
         }
     }
 
     pub fn get_technique_code(&self) -> String {
         match self {
             Self::GarbageCollected(_) => "gc",
+            Self::Generational(_) => "gen",
+            Self::Compacting(_) => "mc",
+            Self::Copying(_) => "cp",
             Self::ReferenceCounted(_) => "rc",
+            Self::Region(_) => "rg",
             Self::Naive(_) => "na",
         }
         .to_string()
@@ -1,17 +1,30 @@
-use std::{cell::RefCell, rc::Rc};
-
 use crate::{
-    heap::{HeapObject, Object, Pointer},
+    heap::{Allocations, HeapObject, Object, Pointer, Usage, arena::Arena},
     value::Value,
 };
 
 pub struct NaiveHeap {
-    heap: Vec<Pointer>,
+    /// Every object which has been allocated, none of which are ever freed.
+    pub objects: Arena,
+    /// Running totals of the allocations made.
+    pub allocations: Allocations,
+    /// The current and peak usage of the heap.
+    pub usage: Usage,
+}
+
+impl Default for NaiveHeap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl NaiveHeap {
     pub fn new() -> Self {
-        Self { heap: Vec::new() }
+        Self {
+            objects: Arena::default(),
+            allocations: Allocations::default(),
+            usage: Usage::default(),
+        }
     }
 
     pub fn allocate(&mut self, data: Object) -> Pointer {
@@ -23,19 +36,16 @@ impl NaiveHeap {
             })
             .collect();
 
-        let heap_object = HeapObject {
-            data,
-            marked: false,
-            reference_count: 1,
-        };
+        let heap_object = HeapObject::new(data);
 
-        let pointer = Pointer::new(RefCell::new(heap_object));
-        self.heap.push(Rc::clone(&pointer));
+        self.allocations.record(&heap_object);
+        self.usage
+            .allocated(&heap_object, self.objects.objects_count() + 1);
 
-        pointer
+        self.objects.insert(heap_object)
     }
 
     pub fn objects_count(&self) -> usize {
-        self.heap.len()
+        self.objects.objects_count()
     }
 }
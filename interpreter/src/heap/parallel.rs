@@ -0,0 +1,131 @@
+//! Marking the reachable objects of a large heap using multiple threads.
+
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use crate::{
+    heap::{Pointer, arena::Arena},
+    value::Value,
+};
+
+/// The fewest objects the heap must contain for marking to be split between threads, below which starting them costs more than it saves.
+pub const PARALLEL_THRESHOLD: usize = 4096;
+
+/// The most objects a thread takes from the shared work at once.
+const BATCH: usize = 64;
+
+/// The number of objects a thread may have waiting to be traversed before it shares half of them with the other threads.
+const SHARE_THRESHOLD: usize = 256;
+
+/// An arena whose objects are read by the marking threads.
+struct Marking<'a>(&'a Arena);
+
+// SAFETY: an arena is not `Sync` because its objects hold values which are reference counted without atomics. The marking threads only read the fields of objects and copy the pointers held by them, without cloning or dropping any value, so no reference count is changed. The arena cannot be changed while they run either, as [mark] borrows it mutably until every thread has finished.
+unsafe impl Sync for Marking<'_> {}
+
+/// Marks every object reachable from the roots by splitting the traversal between the arena's marking threads, if there are several and the heap has at least [PARALLEL_THRESHOLD] objects.
+///
+/// The threads read the fields of each object directly from the arena, sharing an atomic mark for each handle, so that each object is traversed by only one of them. Every object reached is then marked, and objects which were already marked are left marked, so roots which were marked but not yet traversed (such as gray objects) are still traversed.
+///
+/// Returns the number of threads which marked the heap, or [None] if it should be marked on the current thread instead.
+pub fn mark(objects: &mut Arena, roots: &[Pointer]) -> Option<usize> {
+    let threads = objects.gc_threads;
+
+    if threads <= 1 || objects.objects_count() < PARALLEL_THRESHOLD {
+        return None;
+    }
+
+    let marks: Vec<AtomicBool> = (0..objects.handles_count())
+        .map(|_| AtomicBool::new(false))
+        .collect();
+
+    let work = Mutex::new(roots.to_vec());
+
+    // The number of threads which are traversing objects they have taken, and so may still share more.
+    let active = AtomicUsize::new(0);
+
+    let arena = Marking(objects);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| traverse(&arena, &marks, &work, &active));
+        }
+    });
+
+    for pointer in objects.pointers() {
+        if !marks[pointer.index].load(Ordering::Relaxed) {
+            continue;
+        }
+
+        if let Some(object) = objects.get_mut(pointer) {
+            object.marked = true;
+        }
+    }
+
+    Some(threads)
+}
+
+/// Repeatedly takes a batch of objects from the shared work and marks everything reachable from them, until there is no work left and no other thread can share any more.
+fn traverse(
+    arena: &Marking,
+    marks: &[AtomicBool],
+    work: &Mutex<Vec<Pointer>>,
+    active: &AtomicUsize,
+) {
+    loop {
+        let mut pending = {
+            let mut work = work.lock().unwrap();
+
+            if work.is_empty() {
+                if active.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+
+                drop(work);
+                thread::yield_now();
+
+                continue;
+            }
+
+            active.fetch_add(1, Ordering::SeqCst);
+
+            let taken = work.len().min(BATCH);
+            let remaining = work.len() - taken;
+
+            work.split_off(remaining)
+        };
+
+        while let Some(pointer) = pending.pop() {
+            // A pointer to an object which has been freed must not mark whichever object reuses its handle.
+            let Some(object) = arena.0.get(pointer) else {
+                continue;
+            };
+
+            if marks[pointer.index].swap(true, Ordering::Relaxed) {
+                continue;
+            }
+
+            pending.extend(object.data.values().filter_map(|value| match value {
+                Value::ObjectReference(reference)
+                    if !marks[reference.index].load(Ordering::Relaxed) =>
+                {
+                    Some(*reference)
+                }
+                _ => None,
+            }));
+
+            if pending.len() > SHARE_THRESHOLD {
+                let shared = pending.len() / 2;
+
+                work.lock().unwrap().extend(pending.drain(..shared));
+            }
+        }
+
+        active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
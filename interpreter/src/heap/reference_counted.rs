@@ -1,17 +1,72 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{collections::HashMap, mem};
 
 use crate::{
-    heap::{HeapObject, Object, Pointer},
+    clock::Instant,
+    heap::{
+        Allocations, Collection, CountOperations, HeapObject, Object, Pointer, Usage, arena::Arena,
+    },
     value::Value,
 };
 
+/// The fewest possible roots of garbage cycles which are buffered before cycles are collected.
+const CYCLE_BUFFER_LIMIT: usize = 64;
+
+/// The fraction of the live objects which must be buffered as possible roots (if more than [CYCLE_BUFFER_LIMIT]) before cycles are collected, so that the time spent collecting cycles grows in proportion to the heap, rather than with its square.
+const CYCLE_BUFFER_FRACTION: usize = 4;
+
+/// The number of objects whose count changes are buffered, under deferred reference counting, before they are applied at the next safe point.
+pub const DEFERRED_BUFFER_LIMIT: usize = 256;
+
+/// The colour of an object while garbage cycles are collected, as in Bacon and Rajan's synchronous cycle collector.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Colour {
+    /// In use, or not yet known to be garbage.
+    Black,
+    /// Possibly a member of a garbage cycle, having been traversed while trial deleting the references within the subgraph of the possible roots.
+    Gray,
+    /// A member of a garbage cycle, which is freed at the end of the collection.
+    White,
+    /// A possible root of a garbage cycle, whose count was decremented without reaching zero.
+    Purple,
+}
+
 pub struct ReferenceCountedHeap {
-    heap: Vec<Pointer>,
+    /// The objects on the heap, each of which is freed as soon as its reference count reaches zero.
+    pub objects: Arena,
+    /// Objects whose reference count was decremented without reaching zero, which may be part of a garbage cycle. Each is only buffered once, which is recorded by its `buffered` flag.
+    possible_roots: Vec<Pointer>,
+    /// Running totals of the allocations made.
+    pub allocations: Allocations,
+    /// The current and peak usage of the heap.
+    pub usage: Usage,
+    /// Records of the decrements which freed objects, which have not yet been taken to be recorded as stats.
+    pub collections: Vec<Collection>,
+    /// Whether changes to reference counts are buffered and applied at safe points, rather than as soon as they are made.
+    pub deferred: bool,
+    /// The net change to each object's count which has been buffered, under deferred reference counting, and whether any of the changes were decrements.
+    buffered: HashMap<Pointer, (isize, bool)>,
+    /// Running totals of the changes to reference counts which were made, and which were applied.
+    pub operations: CountOperations,
+}
+
+impl Default for ReferenceCountedHeap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ReferenceCountedHeap {
     pub fn new() -> Self {
-        Self { heap: Vec::new() }
+        Self {
+            objects: Arena::default(),
+            possible_roots: Vec::new(),
+            allocations: Allocations::default(),
+            usage: Usage::default(),
+            collections: Vec::new(),
+            deferred: false,
+            buffered: HashMap::new(),
+            operations: CountOperations::default(),
+        }
     }
 
     pub fn allocate(&mut self, data: Object) -> Pointer {
@@ -19,7 +74,7 @@ impl ReferenceCountedHeap {
             .into_iter()
             .map(|(key, value)| match value {
                 Value::ObjectReference(pointer) => {
-                    self.increment(Rc::clone(&pointer));
+                    self.increment(pointer);
                     (key, Value::ObjectReference(pointer))
                 }
                 Value::Object(object) => (key, Value::ObjectReference(self.allocate(object))),
@@ -27,53 +82,315 @@ impl ReferenceCountedHeap {
             })
             .collect();
 
-        let heap_object = HeapObject {
-            data,
-            marked: false,
-            reference_count: 1,
-        };
+        let heap_object = HeapObject::new(data);
 
-        let pointer = Pointer::new(RefCell::new(heap_object));
-        self.heap.push(Rc::clone(&pointer));
+        self.allocations.record(&heap_object);
+        self.usage
+            .allocated(&heap_object, self.objects.objects_count() + 1);
 
-        pointer
+        self.objects.insert(heap_object)
     }
 
+    /// Increments the reference count of an object, or buffers the increment under deferred reference counting.
     pub fn increment(&mut self, object: Pointer) {
-        object.borrow_mut().reference_count += 1;
+        self.operations.requested += 1;
+
+        if self.deferred {
+            self.buffered.entry(object).or_default().0 += 1;
+            return;
+        }
+
+        self.operations.applied += 1;
+
+        if let Some(object) = self.objects.get_mut(object) {
+            object.reference_count += 1;
+            object.colour = Colour::Black;
+        }
     }
 
+    /// Decrements the reference count of an object, freeing it (and anything only it referenced) if it reaches zero, or buffers the decrement under deferred reference counting.
+    ///
+    /// A decrement which frees any objects is recorded as a collection.
     pub fn decrement(&mut self, object: Pointer) {
-        let count = object.borrow().reference_count;
+        self.operations.requested += 1;
+
+        if self.deferred {
+            let (change, decremented) = self.buffered.entry(object).or_default();
+            *change -= 1;
+            *decremented = true;
+
+            return;
+        }
+
+        self.operations.applied += 1;
+        self.release_all(&[object]);
+    }
+
+    /// Applies the buffered count changes if enough objects have been buffered, at a point where the counts can safely be brought up to date, such as when a block exits.
+    pub fn safe_point(&mut self) {
+        if self.buffered.len() >= DEFERRED_BUFFER_LIMIT {
+            self.flush();
+        }
+    }
+
+    /// Applies every buffered count change, leaving the counts as they would have been had each change been applied as it was made.
+    ///
+    /// Changes to the same object are coalesced into one, and those which cancel out are dropped. Increments are applied before any decrements, so that an object is never freed while an increment which would keep it alive is still buffered. An object which was decremented is still buffered as a possible root of a garbage cycle, even if its count has not fallen overall, as the reference it lost may have been the last one from outside a cycle.
+    pub fn flush(&mut self) {
+        let mut changes = self.buffered.drain().collect::<Vec<_>>();
+
+        // Objects are freed in the order they were allocated, rather than in whichever order they were buffered.
+        changes.sort_by_key(|(pointer, _)| (pointer.index, pointer.generation));
+
+        let mut decrements = Vec::new();
+
+        for (pointer, (change, decremented)) in changes {
+            if change < 0 {
+                self.operations.applied += 1;
+                decrements.extend((0..change.unsigned_abs()).map(|_| pointer));
+
+                continue;
+            }
+
+            let Some(object) = self.objects.get_mut(pointer) else {
+                continue;
+            };
+
+            if change > 0 {
+                self.operations.applied += 1;
+                object.reference_count += change as usize;
+                object.colour = Colour::Black;
+            }
+
+            if decremented {
+                self.possible_root(pointer);
+            }
+        }
+
+        self.release_all(&decrements);
+    }
+
+    /// Releases each object in turn, recording a collection if any were freed, and collecting cycles if enough possible roots have been buffered.
+    fn release_all(&mut self, objects: &[Pointer]) {
+        let start = Instant::now();
+        let before = self.objects.objects_count();
+
+        for object in objects {
+            self.release(*object);
+        }
+
+        if self.objects.objects_count() < before {
+            self.collections.push(Collection::finish(
+                start,
+                before,
+                self.objects.objects_count(),
+            ));
+        }
+
+        let limit = CYCLE_BUFFER_LIMIT.max(self.objects.objects_count() / CYCLE_BUFFER_FRACTION);
+
+        if self.possible_roots.len() >= limit {
+            self.collect_cycles();
+        }
+    }
+
+    /// Decrements the reference count of an object, freeing it and releasing its fields if it reaches zero.
+    ///
+    /// The objects still to be released are kept in a worklist rather than on the call stack, so that freeing a long chain of objects (such as a linked list) cannot overflow it. They are released in the same order as they would be recursively, each object before the objects it references.
+    fn release(&mut self, pointer: Pointer) {
+        let mut pending = vec![pointer];
+
+        while let Some(pointer) = pending.pop() {
+            let Some(object) = self.objects.get_mut(pointer) else {
+                continue;
+            };
+
+            object.reference_count = object.reference_count.saturating_sub(1);
+
+            if object.reference_count > 0 {
+                self.possible_root(pointer);
+                continue;
+            }
+
+            pending.extend(self.objects.references(pointer).into_iter().rev());
+
+            if let Some(object) = self.objects.remove(pointer) {
+                self.usage.freed(&object);
+            }
+        }
+    }
 
-        match count {
-            0 => {
-                self.heap
-                    .retain(|object| object.borrow().reference_count > 0);
+    /// Colours an object purple as a possible root of a garbage cycle, buffering it unless it already has been.
+    fn possible_root(&mut self, pointer: Pointer) {
+        let Some(object) = self.objects.get_mut(pointer) else {
+            return;
+        };
+
+        object.colour = Colour::Purple;
+
+        if !object.buffered {
+            object.buffered = true;
+            self.possible_roots.push(pointer);
+        }
+    }
+
+    /// Frees any garbage cycles which include the possible roots, using trial deletion with Bacon and Rajan's colouring. Any buffered count changes are applied first, as trial deletion relies on the counts being up to date.
+    ///
+    /// The subgraph reachable from the possible roots which are still purple is coloured gray, decrementing the count of each object for every reference to it from within the subgraph. An object whose count is still above zero is referenced from outside, so it (and everything reachable from it) is coloured black again, restoring its counts. Everything left white is only referenced by garbage, so it is freed. Each object is traversed at most once per phase, however many possible roots reach it.
+    pub fn collect_cycles(&mut self) {
+        if !self.buffered.is_empty() {
+            // Applying the changes collects cycles itself once enough possible roots are buffered, so this must come before anything is taken.
+            self.flush();
+        }
+
+        let start = Instant::now();
+        let before = self.objects.objects_count();
+
+        let mut roots = Vec::new();
+
+        for root in mem::take(&mut self.possible_roots) {
+            let Some(object) = self.objects.get_mut(root) else {
+                continue;
+            };
+
+            object.buffered = false;
+
+            // A root which has been incremented since it was buffered is black, and one reached from an earlier root is already gray.
+            if object.colour == Colour::Purple {
+                self.mark_gray(root);
+                roots.push(root);
+            }
+        }
+
+        for &root in &roots {
+            self.scan(root);
+        }
+
+        let garbage = self.collect_white(roots);
+
+        for object in garbage {
+            if let Some(object) = self.objects.remove(object) {
+                self.usage.freed(&object);
+            }
+        }
+
+        if self.objects.objects_count() < before {
+            let mut collection = Collection::finish(start, before, self.objects.objects_count());
+            collection.cycles = true;
+
+            self.collections.push(collection);
+        }
+    }
+
+    /// Colours everything reachable from a possible root gray, decrementing the count of each object for every reference to it from a gray object.
+    fn mark_gray(&mut self, root: Pointer) {
+        let mut pending = vec![root];
+
+        while let Some(pointer) = pending.pop() {
+            let Some(object) = self.objects.get_mut(pointer) else {
+                continue;
+            };
+
+            if object.colour == Colour::Gray {
+                continue;
+            }
+
+            object.colour = Colour::Gray;
+
+            for reference in self.objects.references(pointer) {
+                if let Some(object) = self.objects.get_mut(reference) {
+                    object.reference_count = object.reference_count.saturating_sub(1);
+                    pending.push(reference);
+                }
+            }
+        }
+    }
+
+    /// Colours the gray objects reachable from a possible root white if they are only referenced from within the subgraph, or black (along with everything reachable from them) if they are referenced from outside it.
+    fn scan(&mut self, root: Pointer) {
+        let mut pending = vec![root];
+
+        while let Some(pointer) = pending.pop() {
+            let Some(object) = self.objects.get_mut(pointer) else {
+                continue;
+            };
+
+            if object.colour != Colour::Gray {
+                continue;
+            }
+
+            if object.reference_count > 0 {
+                self.scan_black(pointer);
+            } else {
+                object.colour = Colour::White;
+                pending.extend(self.objects.references(pointer));
             }
-            1 => {
-                object.borrow_mut().reference_count -= 1;
+        }
+    }
+
+    /// Colours an object which is referenced from outside the subgraph black, along with everything reachable from it, restoring the counts which were decremented for references from them.
+    fn scan_black(&mut self, root: Pointer) {
+        if let Some(object) = self.objects.get_mut(root) {
+            object.colour = Colour::Black;
+        }
+
+        let mut pending = vec![root];
+
+        while let Some(pointer) = pending.pop() {
+            for reference in self.objects.references(pointer) {
+                let Some(object) = self.objects.get_mut(reference) else {
+                    continue;
+                };
+
+                object.reference_count += 1;
 
-                for value in object.borrow().data.values() {
-                    if let Value::ObjectReference(pointer) = value {
-                        self.decrement(Rc::clone(pointer));
-                    }
+                if object.colour != Colour::Black {
+                    object.colour = Colour::Black;
+                    pending.push(reference);
                 }
+            }
+        }
+    }
 
-                self.heap
-                    .retain(|object| object.borrow().reference_count > 0);
+    /// Returns every white object reachable from the possible roots, colouring each black so that it is only returned once.
+    ///
+    /// References from garbage to live objects were decremented by [ReferenceCountedHeap::mark_gray] and never restored, so the live objects are already counted as no longer referenced by the garbage.
+    fn collect_white(&mut self, roots: Vec<Pointer>) -> Vec<Pointer> {
+        let mut garbage = Vec::new();
+        let mut pending = roots;
+
+        while let Some(pointer) = pending.pop() {
+            let Some(object) = self.objects.get_mut(pointer) else {
+                continue;
+            };
+
+            if object.colour != Colour::White {
+                continue;
             }
-            2.. => object.borrow_mut().reference_count -= 1,
+
+            object.colour = Colour::Black;
+            garbage.push(pointer);
+
+            pending.extend(self.objects.references(pointer));
         }
+
+        garbage
     }
 
+    /// Decrements the reference count of the object a value refers to, or of every object referenced by an object stored inline.
     pub fn conditionally_decrement(&mut self, value: Value) {
-        if let Value::ObjectReference(pointer) = value {
-            self.decrement(pointer);
+        match value {
+            Value::ObjectReference(pointer) => self.decrement(pointer),
+            Value::Object(data) => {
+                for value in data.into_values() {
+                    self.conditionally_decrement(value);
+                }
+            }
+            _ => {}
         }
     }
 
     pub fn objects_count(&self) -> usize {
-        self.heap.len()
+        self.objects.objects_count()
     }
 }
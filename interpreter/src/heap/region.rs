@@ -0,0 +1,165 @@
+use std::mem;
+
+use crate::{
+    clock::Instant,
+    heap::{Allocations, Collection, HeapObject, Object, Pointer, Usage, arena::Arena},
+    value::Value,
+};
+
+/// A heap which allocates each object into the region of the innermost scope, and frees a region wholesale when its scope exits.
+///
+/// Regions are numbered by the depth of their scope, so the global scope's region is 0. An object may only be referenced from its own region or deeper ones, so one which escapes to an outer region is promoted there, along with everything it references.
+pub struct RegionHeap {
+    pub objects: Arena,
+    /// The objects allocated into or promoted to each region, from the outermost. An object which has since been promoted or freed may still be listed in a region it has left.
+    regions: Vec<Vec<Pointer>>,
+    /// Running totals of the allocations made.
+    pub allocations: Allocations,
+    /// The current and peak usage of the heap.
+    pub usage: Usage,
+    /// Records of the regions which were freed, which have not yet been taken to be recorded as stats.
+    pub collections: Vec<Collection>,
+    /// The number of objects which have been promoted to an outer region since a region was last freed, which are recorded as moved by the next collection.
+    promoted: usize,
+}
+
+impl Default for RegionHeap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegionHeap {
+    pub fn new() -> Self {
+        Self {
+            objects: Arena::default(),
+            regions: vec![Vec::new()],
+            allocations: Allocations::default(),
+            usage: Usage::default(),
+            collections: Vec::new(),
+            promoted: 0,
+        }
+    }
+
+    /// Allocates an object, along with any objects nested within it, into the region of the innermost scope.
+    pub fn allocate(&mut self, data: Object) -> Pointer {
+        let data = data
+            .into_iter()
+            .map(|(key, value)| match value {
+                Value::Object(object) => (key, Value::ObjectReference(self.allocate(object))),
+                value => (key, value),
+            })
+            .collect();
+
+        let mut heap_object = HeapObject::new(data);
+        heap_object.region = self.depth();
+
+        self.allocations.record(&heap_object);
+        self.usage
+            .allocated(&heap_object, self.objects.objects_count() + 1);
+
+        let pointer = self.objects.insert(heap_object);
+        self.region(self.depth()).push(pointer);
+
+        pointer
+    }
+
+    /// Returns the depth of the innermost scope's region.
+    pub fn depth(&self) -> usize {
+        self.regions.len() - 1
+    }
+
+    /// Returns the objects listed in a region, adding regions up to it if there are not yet that many.
+    fn region(&mut self, depth: usize) -> &mut Vec<Pointer> {
+        if self.regions.len() <= depth {
+            self.regions.resize_with(depth + 1, Vec::new);
+        }
+
+        &mut self.regions[depth]
+    }
+
+    /// Starts the region of a scope which has been entered.
+    pub fn enter(&mut self) {
+        self.regions.push(Vec::new());
+    }
+
+    /// Frees the regions of a scope which is exiting, at a depth, and of any scopes within it which were left without exiting (such as by an error), after promoting the objects a value references to the enclosing scope's region, as the value is escaping the scope.
+    pub fn exit(&mut self, depth: usize, escaping: Option<&Value>) {
+        // The global scope's region is never freed.
+        let depth = depth.max(1);
+
+        if let Some(value) = escaping {
+            self.promote_value(value, depth - 1);
+        }
+
+        if self.regions.len() <= depth {
+            return;
+        }
+
+        let start = Instant::now();
+        let before = self.objects.objects_count();
+
+        for region in self.regions.split_off(depth) {
+            for pointer in region {
+                if self
+                    .objects
+                    .get(pointer)
+                    .is_some_and(|object| object.region >= depth)
+                    && let Some(object) = self.objects.remove(pointer)
+                {
+                    self.usage.freed(&object);
+                }
+            }
+        }
+
+        if self.objects.objects_count() < before || self.promoted > 0 {
+            let mut collection = Collection::finish(start, before, self.objects.objects_count());
+            collection.moved = mem::take(&mut self.promoted);
+
+            self.collections.push(collection);
+        }
+    }
+
+    /// Promotes the objects a value references, and those they reference, to a region if they are in a deeper one.
+    pub fn promote_value(&mut self, value: &Value, depth: usize) {
+        match value {
+            Value::ObjectReference(pointer) => self.promote(*pointer, depth),
+            Value::Object(data) => data
+                .values()
+                .for_each(|value| self.promote_value(value, depth)),
+            _ => {}
+        }
+    }
+
+    /// Promotes an object, and those it references, to a region if they are in a deeper one.
+    ///
+    /// The objects an object references are never in a deeper region than it, so the objects which are already in the region (or an outer one) are not followed.
+    pub fn promote(&mut self, pointer: Pointer, depth: usize) {
+        let mut pending = vec![pointer];
+
+        while let Some(pointer) = pending.pop() {
+            let Some(object) = self.objects.get_mut(pointer) else {
+                continue;
+            };
+
+            if object.region <= depth {
+                continue;
+            }
+
+            object.region = depth;
+            self.promoted += 1;
+
+            pending.extend(self.objects.references(pointer));
+            self.region(depth).push(pointer);
+        }
+    }
+
+    /// Returns the region an object is in, or [None] if it has been freed.
+    pub fn region_of(&self, pointer: Pointer) -> Option<usize> {
+        self.objects.get(pointer).map(|object| object.region)
+    }
+
+    pub fn objects_count(&self) -> usize {
+        self.objects.objects_count()
+    }
+}
@@ -0,0 +1,74 @@
+//! Reports of the live objects on the heap, grouped by the object literal they were allocated from.
+
+use std::collections::HashMap;
+
+use crate::{
+    heap::ManagedHeap,
+    source::{Location, SourceMap},
+};
+
+/// The live objects which were allocated from a single site.
+pub struct Site {
+    /// The location of the object literal, or [None] for objects which were not allocated from a literal, such as those returned by native functions.
+    pub location: Option<Location>,
+    pub objects: usize,
+    /// The estimated bytes used by the objects.
+    pub bytes: usize,
+}
+
+/// Groups the live objects on the heap by their allocation site, with the sites holding the most objects first.
+pub fn live_sites(heap: &ManagedHeap) -> Vec<Site> {
+    let objects = heap.objects();
+    let key = |location: &Option<Location>| {
+        location.map(|location| (location.file(), location.line(), location.column()))
+    };
+
+    let mut sites = HashMap::new();
+
+    for pointer in objects.pointers() {
+        let Some(object) = objects.get(pointer) else {
+            continue;
+        };
+
+        let site = sites.entry(key(&object.site)).or_insert(Site {
+            location: object.site,
+            objects: 0,
+            bytes: 0,
+        });
+
+        site.objects += 1;
+        site.bytes += object.size;
+    }
+
+    let mut sites: Vec<Site> = sites.into_values().collect();
+
+    sites.sort_by(|a, b| {
+        b.objects
+            .cmp(&a.objects)
+            .then_with(|| key(&a.location).cmp(&key(&b.location)))
+    });
+
+    sites
+}
+
+/// Renders a table of the live objects on the heap for each allocation site, naming the file of each site from a source map.
+pub fn report(heap: &ManagedHeap, sources: &SourceMap) -> String {
+    let mut report = format!(
+        "{:<16}  {:>7}  {:>17}",
+        "site", "objects", "bytes (estimated)"
+    );
+
+    for site in live_sites(heap) {
+        let location = match site.location {
+            Some(location) => sources.position(location),
+            None => String::from("(unknown)"),
+        };
+
+        report.push_str(&format!(
+            "\n{:<16}  {:>7}  {:>17}",
+            location, site.objects, site.bytes
+        ));
+    }
+
+    report
+}
@@ -0,0 +1,74 @@
+//! Rendering any value as a string for debugging, with `inspect`, following references to objects to show their fields, unlike `print`.
+//!
+//! Objects nested more deeply than a limit are shown as `{ ... }`, and a reference back to an object which contains it (such as a parent referenced by its child) is shown as `<circular>`, so that any object graph can be rendered, however deep or cyclic it is.
+
+use std::collections::HashSet;
+
+use crate::{
+    heap::{ManagedHeap, Object, Pointer},
+    value::Value,
+};
+
+/// How many objects deep a value is rendered if no limit is given.
+pub const DEFAULT_DEPTH: usize = 4;
+
+/// Renders a value as it would be written in source code where possible, showing the fields of objects in order of name, down to a depth.
+pub fn inspect(value: &Value, heap: &ManagedHeap, depth: usize) -> String {
+    render(value, heap, depth, &mut HashSet::new())
+}
+
+/// Renders a value, with the objects which contain it (whose fields are being rendered) as its ancestors.
+fn render(
+    value: &Value,
+    heap: &ManagedHeap,
+    depth: usize,
+    ancestors: &mut HashSet<Pointer>,
+) -> String {
+    match value {
+        Value::String(value) => format!("{:?}", value.as_ref()),
+        Value::Object(data) => fields(data, heap, depth, ancestors),
+        Value::ObjectReference(pointer) => {
+            if ancestors.contains(pointer) {
+                return String::from("<circular>");
+            }
+
+            let object = match heap.get(*pointer) {
+                Ok(object) => object,
+                Err(error) => return format!("<{}>", error),
+            };
+
+            ancestors.insert(*pointer);
+            let rendered = fields(&object.data, heap, depth, ancestors);
+            ancestors.remove(pointer);
+
+            rendered
+        }
+        value => value.to_string(),
+    }
+}
+
+/// Renders the fields of an object, or `{ ... }` if the depth has been reached.
+fn fields(
+    data: &Object,
+    heap: &ManagedHeap,
+    depth: usize,
+    ancestors: &mut HashSet<Pointer>,
+) -> String {
+    if data.is_empty() {
+        return String::from("{}");
+    }
+
+    if depth == 0 {
+        return String::from("{ ... }");
+    }
+
+    let mut fields = data.iter().collect::<Vec<_>>();
+    fields.sort_by_key(|(field, _)| *field);
+
+    let fields = fields
+        .into_iter()
+        .map(|(field, value)| format!("{}: {}", field, render(value, heap, depth - 1, ancestors)))
+        .collect::<Vec<_>>();
+
+    format!("{{ {} }}", fields.join(", "))
+}
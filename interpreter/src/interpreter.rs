@@ -0,0 +1,550 @@
+//! The entry point for embedding the slang programming language.
+
+use std::{
+    error::Error,
+    fmt::{Debug, Display},
+    fs,
+    path::Path,
+};
+
+use crate::{
+    capabilities::Capabilities,
+    channel::Channels,
+    console::{Input, Output},
+    escape,
+    expression::{EvaluationError, Expression},
+    heap::{ManagedHeap, Strategy},
+    interrupt::Interrupt,
+    lexer::{Lexer, LexerError},
+    parser::{Parser, ParserError},
+    post_mortem::{self, PostMortem},
+    prelude,
+    session::{self, SessionError},
+    source::{FileId, Source, SourceMap},
+    stack::Stack,
+    statement::{self, ControlFlow, Statement},
+    sync::Primitives,
+    token_stream::TokenStream,
+    trace,
+    value::Value,
+    watch::Watches,
+};
+
+#[cfg(not(target_family = "wasm"))]
+use crate::{
+    plugin::{self, PluginError},
+    value::{Function, NativeFunction},
+};
+
+/// All errors which can occur while running source code.
+pub enum InterpreterError {
+    /// When the source code could not be lexed.
+    Lexer(Vec<LexerError>),
+    /// When the source code could not be parsed.
+    Parser(Vec<ParserError>),
+    /// When a statement could not be executed, or a finalizer failed.
+    Evaluation(EvaluationError),
+}
+
+impl Display for InterpreterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lexer(errors) => write!(
+                f,
+                "{}",
+                errors
+                    .iter()
+                    .map(|error| error.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+            Self::Parser(errors) => write!(
+                f,
+                "{}",
+                errors
+                    .iter()
+                    .map(|error| error.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+            Self::Evaluation(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Debug for InterpreterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Error for InterpreterError {}
+
+impl InterpreterError {
+    /// Returns the errors as they are displayed, with the file each occurred in named from a source map (such as [Interpreter::sources]), and the source code which caused it underlined beneath it.
+    ///
+    /// An error in a file which is not in the map, such as in source code run with [Interpreter::run], is underlined in the source code given, which should be the code that was run. An evaluation error without a location (such as one raised by an embedder's own native function) is displayed as it is.
+    pub fn underlined(&self, sources: &SourceMap, source: &str) -> String {
+        match self {
+            Self::Lexer(errors) => errors
+                .iter()
+                .map(|error| sources.render(&error.to_string(), error.span(), source))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Self::Parser(errors) => errors
+                .iter()
+                .map(|error| sources.render(&error.to_string(), error.span(), source))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Self::Evaluation(error) => match error.span() {
+                Some(span) => sources.render(&error.to_string(), span, source),
+                None => error.to_string(),
+            },
+        }
+    }
+}
+
+impl From<EvaluationError> for InterpreterError {
+    fn from(error: EvaluationError) -> Self {
+        Self::Evaluation(error)
+    }
+}
+
+/// An interpreter, which owns a stack and a heap that persist between runs.
+///
+/// Each call to [Interpreter::run] executes more source code against the same state, so variables and functions defined by one run can be used by the next, as in the REPL.
+pub struct Interpreter {
+    stack: Stack,
+    heap: ManagedHeap,
+    /// Whether object literals which never escape their block are stored inline, rather than on the heap.
+    escape_analysis: bool,
+    /// The globals defined by the prelude, which are left out of snapshots as they are defined again when the prelude is loaded.
+    prelude: Vec<(String, Option<Value>)>,
+}
+
+// Interpreters must be able to move between threads with the `send` feature, which this fails to compile without.
+#[cfg(feature = "send")]
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<Interpreter>();
+};
+
+impl Interpreter {
+    /// Creates an interpreter whose heap is managed with a strategy, using its default tuning.
+    pub fn new(strategy: Strategy) -> Self {
+        Self::with_heap(ManagedHeap::new(strategy))
+    }
+
+    /// Creates an interpreter with a heap which has already been configured.
+    pub fn with_heap(heap: ManagedHeap) -> Self {
+        Self {
+            stack: Stack::new(),
+            heap,
+            escape_analysis: false,
+            prelude: Vec::new(),
+        }
+    }
+
+    /// Sets where the program's output is written, which is stdout by default. A [Capture](crate::console::Capture) collects the output so that it can be read afterwards.
+    pub fn set_output(&mut self, output: impl Output + 'static) {
+        self.stack.console.output = Box::new(output);
+    }
+
+    /// Sets where the program's diagnostics, such as those written by `eprint()`, are written, which is stderr by default.
+    pub fn set_error_output(&mut self, output: impl Output + 'static) {
+        self.stack.console.error = Box::new(output);
+    }
+
+    /// Sets where the program's input is read from, which is stdin by default. Canned input can be given with an [io::Cursor](std::io::Cursor).
+    pub fn set_input(&mut self, input: impl Input + 'static) {
+        self.stack.console.input = Box::new(input);
+    }
+
+    /// Returns a handle which stops the program being run, from any thread, such as to stop a program which runs for too long.
+    pub fn interrupt(&self) -> Interrupt {
+        self.stack.interrupt.clone()
+    }
+
+    /// Sets the handle which stops the program being run, so that one handle can stop whichever of several interpreters is running.
+    pub fn set_interrupt(&mut self, interrupt: Interrupt) {
+        self.stack.interrupt = interrupt;
+    }
+
+    /// Sets the most steps (statements executed and expressions evaluated) each run or call may take before it is stopped with an evaluation error, or removes the limit, which is the default. Limiting steps makes it safe to run code which may never finish, such as untrusted snippets.
+    pub fn set_max_steps(&mut self, max_steps: Option<usize>) {
+        self.stack.set_max_steps(max_steps);
+    }
+
+    /// Sets the capabilities the program has, which allow it to call native functions that reach outside of the interpreter. Every capability is denied by default.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.stack.capabilities = capabilities;
+    }
+
+    /// Sets the variables and fields whose assignments are logged to stderr (see [watch](crate::watch)). Nothing is watched by default.
+    pub fn set_watches(&mut self, watches: Watches) {
+        self.stack.watches = watches;
+    }
+
+    /// Sets the channels the program can send and receive messages on (see [channel](crate::channel)), such as to share them with another interpreter. Each interpreter has channels of its own by default.
+    pub fn set_channels(&mut self, channels: Channels) {
+        self.stack.channels = channels;
+    }
+
+    /// Sets the mutexes and atomic integers the program can use (see [sync](crate::sync)), such as to share them with another interpreter. Each interpreter has its own by default.
+    pub fn set_primitives(&mut self, primitives: Primitives) {
+        self.stack.primitives = primitives;
+    }
+
+    /// Sets whether object literals which never escape their block are stored inline, rather than on the heap.
+    pub fn set_escape_analysis(&mut self, escape_analysis: bool) {
+        self.escape_analysis = escape_analysis;
+    }
+
+    /// Sets whether the state of the program is recorded when an evaluation error escapes it, to be dumped with [Interpreter::post_mortem]. Nothing is recorded by default.
+    pub fn set_dump_on_error(&mut self, dump_on_error: bool) {
+        self.stack.post_mortem = dump_on_error.then(PostMortem::default);
+    }
+
+    /// Returns a dump of the call stack, each frame's bindings, the globals and a summary of the heap, as they were when an evaluation error escaped the last run or call, if one did and dumps on error are enabled. See [post_mortem] for what is recorded.
+    pub fn post_mortem(&self) -> Option<String> {
+        self.stack
+            .post_mortem
+            .as_ref()?
+            .render(&self.heap, &self.stack.sources)
+    }
+
+    /// Forgets the state recorded for an earlier error.
+    fn clear_post_mortem(&mut self) {
+        if let Some(post_mortem) = &mut self.stack.post_mortem {
+            post_mortem.clear();
+        }
+    }
+
+    /// Records the top level of the program and its globals, if an evaluation error escaped it and dumps on error are enabled, and returns the result.
+    fn record_post_mortem<T>(
+        &mut self,
+        result: Result<T, InterpreterError>,
+    ) -> Result<T, InterpreterError> {
+        if let Err(InterpreterError::Evaluation(error)) = &result
+            && self.stack.post_mortem.is_some()
+        {
+            self.stack.record_frame("<top level>");
+
+            let globals = post_mortem::state(self.stack.globals());
+            let message = match error.span() {
+                Some(span) => self.stack.sources.locate(&error.to_string(), span),
+                None => error.to_string(),
+            };
+
+            if let Some(post_mortem) = &mut self.stack.post_mortem {
+                post_mortem.globals = globals;
+                post_mortem.error = Some(message);
+            }
+        }
+
+        result
+    }
+
+    /// Lexes, parses and executes some source code.
+    ///
+    /// Function definitions are executed first, so that functions can be called before they are defined. Finalizers of objects which are reclaimed by the final statement are run before returning.
+    pub fn run(&mut self, source: &str) -> Result<(), InterpreterError> {
+        self.run_source(Source::new(source))
+    }
+
+    /// Lexes, parses and executes source code, like [Interpreter::run], naming it as a file in the source map, so that errors within it name the file and can be underlined without the source code.
+    pub fn run_named(&mut self, name: &str, source: &str) -> Result<(), InterpreterError> {
+        let file = self.add_source(name, source);
+
+        self.run_source(Source::new(source).with_file(file))
+    }
+
+    /// Lexes, parses and executes source code, like [Interpreter::run], from a [Source] which may name the file it was read from, so that errors give their location within the file.
+    pub fn run_source(&mut self, source: Source) -> Result<(), InterpreterError> {
+        self.execute(parse_source(source)?)
+    }
+
+    /// Executes statements which have already been parsed, like [Interpreter::run]. Tests are skipped, but the block of a test can be executed on its own, as `slang test` does.
+    pub fn execute(&mut self, statements: Vec<Statement>) -> Result<(), InterpreterError> {
+        self.clear_post_mortem();
+        let result = self.execute_statements(statements);
+
+        self.record_post_mortem(result)
+    }
+
+    fn execute_statements(
+        &mut self,
+        mut statements: Vec<Statement>,
+    ) -> Result<(), InterpreterError> {
+        self.stack.reset_steps();
+
+        if self.escape_analysis {
+            escape::analyse(&mut statements);
+        }
+
+        let _span = tracing::debug_span!(target: trace::EXECUTE, "execution").entered();
+        let mut non_definitions = Vec::new();
+
+        for statement in statements {
+            match statement {
+                Statement::FunctionDefinition { .. } => {
+                    statement.execute(&mut self.stack, &mut self.heap)?;
+                }
+                _ => non_definitions.push(statement),
+            }
+        }
+
+        for statement in non_definitions {
+            match statement.execute(&mut self.stack, &mut self.heap)? {
+                ControlFlow::Continue => continue,
+                // The parser does not allow a break statement outside of a loop.
+                ControlFlow::Break(_) | ControlFlow::ExitLoop => break,
+            }
+        }
+
+        // Objects reclaimed by the final statement would otherwise never have their finalizers run, nor would those only freed once buffered changes to their counts are applied.
+        self.heap.apply_buffered_counts();
+        statement::run_finalizers(&mut self.stack, &mut self.heap)?;
+
+        Ok(())
+    }
+
+    /// Calls a function which has been defined by the source code run so far, returning its return value, if any.
+    ///
+    /// The call is made from the global scope, exactly as if `function(arguments...)` were a statement at the top level: objects among the arguments are allocated (or, under reference counting, have their counts incremented) like any other arguments, and finalizers of objects reclaimed during the call are run before returning. A returned reference to an object only keeps it alive until the next statement which can collect it, unless the program also holds on to it.
+    pub fn call(
+        &mut self,
+        function: &str,
+        arguments: &[Value],
+    ) -> Result<Option<Value>, InterpreterError> {
+        self.clear_post_mortem();
+        let result = self.call_function(function, arguments);
+
+        self.record_post_mortem(result)
+    }
+
+    /// Lexes, parses and evaluates a single expression in the global scope, returning its value, if any, as `slang calc` does.
+    ///
+    /// Like [Interpreter::call], finalizers of objects reclaimed while evaluating the expression are run before returning.
+    pub fn evaluate(&mut self, source: &str) -> Result<Option<Value>, InterpreterError> {
+        let expression = parse_expression(Source::new(source))?;
+
+        self.clear_post_mortem();
+        let result = self.evaluate_expression(expression);
+
+        self.record_post_mortem(result)
+    }
+
+    /// Evaluates an expression which has already been parsed, in the global scope, returning its value, if any, exactly as [Interpreter::evaluate] does.
+    pub fn evaluate_parsed(
+        &mut self,
+        expression: Expression,
+    ) -> Result<Option<Value>, InterpreterError> {
+        self.clear_post_mortem();
+        let result = self.evaluate_expression(expression);
+
+        self.record_post_mortem(result)
+    }
+
+    /// Lexes, parses and evaluates a single expression, like [Interpreter::evaluate], naming it as a file in the source map, as [Interpreter::run_named] does.
+    pub fn evaluate_named(
+        &mut self,
+        name: &str,
+        source: &str,
+    ) -> Result<Option<Value>, InterpreterError> {
+        let file = self.add_source(name, source);
+        let expression = parse_expression(Source::new(source).with_file(file))?;
+
+        self.clear_post_mortem();
+        let result = self.evaluate_expression(expression);
+
+        self.record_post_mortem(result)
+    }
+
+    fn call_function(
+        &mut self,
+        function: &str,
+        arguments: &[Value],
+    ) -> Result<Option<Value>, InterpreterError> {
+        let call = Expression::Call {
+            function: Box::new(Expression::Variable {
+                identifier: function.to_string(),
+                span: None,
+            }),
+            arguments: arguments
+                .iter()
+                .map(|argument| {
+                    Box::new(Expression::Literal {
+                        value: argument.clone(),
+                    })
+                })
+                .collect(),
+            span: None,
+        };
+
+        self.evaluate_expression(call)
+    }
+
+    fn evaluate_expression(
+        &mut self,
+        expression: Expression,
+    ) -> Result<Option<Value>, InterpreterError> {
+        self.stack.reset_steps();
+        let value = expression.evaluate(&mut self.stack, &mut self.heap)?;
+
+        // Finalizers may collect, so the returned value is held until they have run.
+        let temporaries = self.stack.temporaries_count();
+
+        if let Some(value) = &value {
+            self.stack.hold_temporary(value);
+        }
+
+        self.heap.apply_buffered_counts();
+
+        let finalized = statement::run_finalizers(&mut self.stack, &mut self.heap);
+        self.stack.release_temporaries(temporaries);
+        finalized?;
+
+        Ok(value)
+    }
+
+    /// Defines the functions of the [prelude] in the global scope.
+    pub fn load_prelude(&mut self) -> Result<(), InterpreterError> {
+        let before = self.stack.globals();
+        self.run_source(Source::new(prelude::SOURCE).with_file(FileId::PRELUDE))?;
+
+        self.prelude = self
+            .stack
+            .globals()
+            .into_iter()
+            .filter(|global| !before.contains(global))
+            .collect();
+
+        Ok(())
+    }
+
+    /// Returns a program which restores the current session when run by a fresh interpreter. See [session] for what a session includes.
+    pub fn snapshot(&self) -> Result<String, SessionError> {
+        session::snapshot(&self.stack, &self.heap, &self.prelude)
+    }
+
+    /// Saves the current session to a file, which can be restored with [Interpreter::restore].
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SessionError> {
+        fs::write(path, self.snapshot()?)?;
+
+        Ok(())
+    }
+
+    /// Restores a session saved to a file, by running it, along with the values recorded alongside it which cannot be written in source code. Variables and functions already defined are kept, unless the session defines them too.
+    pub fn restore(&mut self, path: impl AsRef<Path>) -> Result<(), SessionError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)?;
+        let file = self.stack.sources.add(&path.to_string_lossy(), &source);
+        self.execute(session::parse(&source, file)?)?;
+
+        Ok(())
+    }
+
+    /// Loads a plugin, defining each of the native functions it registers in the global scope.
+    ///
+    /// # Safety
+    ///
+    /// Loading a plugin runs its code, and its descriptor is trusted to be what it claims, so only trusted plugins may be loaded. See [plugin] for how plugins are built.
+    #[cfg(not(target_family = "wasm"))]
+    pub unsafe fn load_plugin(&mut self, path: impl AsRef<Path>) -> Result<(), PluginError> {
+        for function in unsafe { plugin::load(path.as_ref()) }? {
+            self.stack.define_global(
+                function.name.to_string(),
+                Some(Value::Function(Function::Native(NativeFunction::Plugin(
+                    function,
+                )))),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the files of the source code which has been run, which errors are reported with (see [InterpreterError::underlined]).
+    pub fn sources(&self) -> &SourceMap {
+        &self.stack.sources
+    }
+
+    /// Adds a file with a name and its source code to the source map, returning the id to run it with, so that locations within it name the file.
+    pub fn add_source(&mut self, name: &str, text: &str) -> FileId {
+        self.stack.sources.add(name, text)
+    }
+
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    pub fn heap(&self) -> &ManagedHeap {
+        &self.heap
+    }
+
+    /// Takes the stack and heap out of the interpreter, for inspecting or reporting on once it has finished.
+    pub fn into_parts(self) -> (Stack, ManagedHeap) {
+        (self.stack, self.heap)
+    }
+}
+
+/// Lexes and parses some source code into statements, without executing them.
+pub fn parse(source: &str) -> Result<Vec<Statement>, InterpreterError> {
+    parse_source(Source::new(source))
+}
+
+/// Lexes and parses source code into statements, without executing them. Tokens are lexed only as the parser needs them, so with a source which is read as it goes, such as one from [Source::from_reader], neither the whole program nor all of its tokens are held at once.
+pub fn parse_source(source: Source) -> Result<Vec<Statement>, InterpreterError> {
+    let statements = parse_with(source, |parser| parser.parse())?;
+
+    tracing::debug!(
+        target: trace::PARSER,
+        statements = statements.len(),
+        "Parsed the source code."
+    );
+
+    Ok(statements)
+}
+
+/// Lexes and parses source code into a single expression, without evaluating it, as `slang calc` does.
+pub fn parse_expression(source: Source) -> Result<Expression, InterpreterError> {
+    parse_with(source, |parser| parser.parse_expression())
+}
+
+/// Lexes source code, and parses the tokens with a method of the parser, reporting any lexer errors in place of the parser's.
+fn parse_with<T>(
+    source: Source,
+    parse: impl FnOnce(Parser) -> Result<T, Vec<ParserError>>,
+) -> Result<T, InterpreterError> {
+    let mut count = 0;
+    let mut errors = Vec::new();
+
+    let tokens = Lexer::new(source).filter_map(|result| match result {
+        Ok(token) => {
+            count += 1;
+            Some(token)
+        }
+        Err(error) => {
+            errors.push(error);
+            None
+        }
+    });
+
+    let span = tracing::debug_span!(target: trace::PARSER, "parsing").entered();
+    let result = parse(Parser::new(TokenStream::new(tokens)));
+    drop(span);
+
+    tracing::debug!(
+        target: trace::LEXER,
+        tokens = count,
+        errors = errors.len(),
+        "Lexed the source code."
+    );
+
+    // Any parser errors are likely to have been caused by the lexer errors, so only the lexer errors are reported.
+    if !errors.is_empty() {
+        return Err(InterpreterError::Lexer(errors));
+    }
+
+    result.map_err(InterpreterError::Parser)
+}
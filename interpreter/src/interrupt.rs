@@ -0,0 +1,66 @@
+//! Cooperative cancellation of a running program, such as when Ctrl-C is pressed.
+
+use std::sync::{
+    Arc, Mutex, Weak,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// A handle which stops the program an interpreter is running, from any thread.
+///
+/// Interrupting sets a flag, which is checked before every statement and function call. Once the program sees the flag it is cleared, and the program stops with an evaluation error, so only one run is stopped for each interrupt. If no program is running, the next one to be run is stopped instead. Clones share the same flag.
+///
+/// Each thread a program spawns has a [child](Interrupt::child) with a flag of its own, so that a thread seeing its interrupt does not clear the program's.
+#[derive(Clone, Default)]
+pub struct Interrupt(Arc<Flag>);
+
+/// The flag of an interrupt, along with those of its children.
+#[derive(Default)]
+struct Flag {
+    interrupted: AtomicBool,
+    children: Mutex<Vec<Weak<Flag>>>,
+}
+
+impl Flag {
+    /// Sets the flag, and those of the children which still exist.
+    fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+
+        let children = self
+            .children
+            .lock()
+            .expect("the children of the interrupt were poisoned");
+
+        for child in children.iter().filter_map(Weak::upgrade) {
+            child.interrupt();
+        }
+    }
+}
+
+impl Interrupt {
+    /// Stops the program which is running, before its next statement or function call, along with every thread it has spawned.
+    pub fn interrupt(&self) {
+        self.0.interrupt();
+    }
+
+    /// Returns whether the program has been interrupted, clearing the interrupt.
+    pub fn take(&self) -> bool {
+        self.0.interrupted.swap(false, Ordering::Relaxed)
+    }
+
+    /// Returns a new interrupt, for a thread the program spawns, which is interrupted whenever this one is.
+    pub fn child(&self) -> Self {
+        let child = Arc::new(Flag::default());
+
+        let mut children = self
+            .0
+            .children
+            .lock()
+            .expect("the children of the interrupt were poisoned");
+
+        // The children of threads which have finished are dropped as new ones are added.
+        children.retain(|child| child.strong_count() > 0);
+        children.push(Arc::downgrade(&child));
+
+        Self(child)
+    }
+}
@@ -1,52 +1,63 @@
 //! The lexer for the slang programming language.
 
 use std::{
+    collections::VecDeque,
     error::Error,
     fmt::{Debug, Display},
 };
 
 use crate::{
-    source::{Location, Source},
-    token::{Token, TokenData},
+    source::{Location, Source, Span},
+    token::{Token, TokenData, TokenKind},
 };
 
 /// All the errors which can occur while lexing.
 pub enum LexerError {
     /// A string without the enclosing `"`.
-    UnterminatedString(Location),
+    UnterminatedString(Span),
     /// A block comment without the enclosing `*/`.
-    UnterminatedBlockComment(Location),
+    UnterminatedBlockComment(Span),
     /// An unexpected character. Optionally specify which character was expected.
     UnexpectedCharacter {
-        location: Location,
+        span: Span,
         character: char,
         expected: Option<char>,
     },
+    /// When the source code could not be read any further, such as when it is not valid UTF-8.
+    UnreadableSource { span: Span, message: String },
+    /// An integer which is too large to fit in 32 bits.
+    IntegerTooLarge(Span),
 }
 
 impl Display for LexerError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::UnterminatedString(location) => {
-                write!(f, "{} Unterminated string.", location)
+            Self::UnterminatedString(span) => {
+                write!(f, "{} Unterminated string.", span)
             }
-            Self::UnterminatedBlockComment(location) => {
-                write!(f, "{} Unterminated block comment.", location)
+            Self::UnterminatedBlockComment(span) => {
+                write!(f, "{} Unterminated block comment.", span)
             }
             Self::UnexpectedCharacter {
-                location,
+                span,
                 character,
                 expected,
             } => write!(
                 f,
                 "{} Unexpected character: `{}`{}",
-                location,
+                span,
                 character,
                 match expected {
                     Some(expected) => format!(" (expected `{}`)", expected),
                     None => String::new(),
                 }
             ),
+            Self::UnreadableSource { span, message } => {
+                write!(f, "{} Unable to read the source code: {}", span, message)
+            }
+            Self::IntegerTooLarge(span) => {
+                write!(f, "{} Integers must be at most {}.", span, i32::MAX)
+            }
         }
     }
 }
@@ -59,89 +70,220 @@ impl Debug for LexerError {
 
 impl Error for LexerError {}
 
+impl LexerError {
+    /// Returns the characters which caused the error.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::UnterminatedString(span)
+            | Self::UnterminatedBlockComment(span)
+            | Self::UnexpectedCharacter { span, .. }
+            | Self::UnreadableSource { span, .. }
+            | Self::IntegerTooLarge(span) => *span,
+        }
+    }
+}
+
 /// An instance of a lexer, for a specific source code string.
-pub struct Lexer {
-    source: Source,
-    tokens: Vec<Token>,
+///
+/// A lexer is an iterator over the tokens (or errors) it lexes, which lexes each one only as it is needed, so that a parser can pull tokens from it as it goes. The last token is always an end of file token, after which the iterator ends.
+pub struct Lexer<'a> {
+    source: Source<'a>,
+    /// The tokens and errors which have been lexed, but not returned yet.
+    pending: VecDeque<Result<Token, LexerError>>,
     current_token_start: Location,
+    /// Whether comments and blank lines should be emitted as trivia tokens.
+    retain_trivia: bool,
+    /// Whether a token has been added on the current line.
+    line_has_token: bool,
+    /// The kind of the last token which was added.
+    last_kind: Option<TokenKind>,
+    /// Whether the end of file token has been added, after which there is nothing more to lex.
+    finished: bool,
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.finished {
+            self.step();
+        }
+
+        self.pending.pop_front()
+    }
 }
 
-impl Lexer {
+impl<'a> Lexer<'a> {
     /// Creates a new lexer for a specific source.
-    pub fn new(source: Source) -> Self {
-        Self {
+    pub fn new(source: Source<'a>) -> Self {
+        let mut lexer = Self {
+            current_token_start: source.location(),
             source: source,
-            tokens: Vec::new(),
-            current_token_start: Location::start(),
+            pending: VecDeque::new(),
+            retain_trivia: false,
+            line_has_token: false,
+            last_kind: None,
+            finished: false,
+        };
+
+        lexer.skip_shebang();
+
+        lexer
+    }
+
+    /// Creates a new lexer for a specific source, which emits comments and blank lines as trivia tokens.
+    ///
+    /// This is used by tools such as the formatter, which must reproduce more than just the meaning of the source code.
+    pub fn with_trivia(source: Source<'a>) -> Self {
+        Self {
+            retain_trivia: true,
+            ..Self::new(source)
         }
     }
 
     /// Attempts to lexically analyse the source code to produce a sequence of tokens.
     ///
-    /// Will consume the entire source code, returning all valid tokens, and any errors.
-    pub fn lex(mut self) -> (Vec<Token>, Vec<LexerError>) {
+    /// Will consume the entire source code, returning all valid tokens followed by an end of file token, and any errors.
+    pub fn lex(self) -> (Vec<Token>, Vec<LexerError>) {
+        let mut tokens = Vec::new();
         let mut errors = Vec::new();
 
-        while let Some(character) = self.source.advance() {
-            let result = match character {
-                '(' => Ok(self.add_token(TokenData::LeftParenthesis)),
-                ')' => Ok(self.add_token(TokenData::RightParenthesis)),
-                '{' => Ok(self.add_token(TokenData::LeftBrace)),
-                '}' => Ok(self.add_token(TokenData::RightBrace)),
-                ',' => Ok(self.add_token(TokenData::Comma)),
-                '.' => Ok(self.add_token(TokenData::Dot)),
-                ';' => Ok(self.add_token(TokenData::Semicolon)),
-                '?' => Ok(self.add_token(TokenData::QuestionMark)),
-                ':' => Ok(self.add_token(TokenData::Colon)),
-
-                // Arithmetic operators
-                '+' => Ok(self.add_token(TokenData::Plus)),
-                '-' => Ok(self.add_token(TokenData::Minus)),
-                '*' => Ok(self.add_token(TokenData::Star)),
-                '/' => self.handle_slash(),
-                '^' => Ok(self.add_token(TokenData::Exponent)),
-
-                // Logical and bitwise operators
-                '!' => Ok(self.handle_bang()),
-                '=' => Ok(self.handle_equal()),
-                '>' => Ok(self.handle_greater()),
-                '<' => Ok(self.handle_less()),
-                '&' => Ok(self.handle_ampersand()),
-                '|' => Ok(self.handle_pipe()),
-
-                // Literals (not including booleans)
-                '"' => self.handle_string(),
-                character if character.is_ascii_digit() => Ok(self.handle_number(character)),
-
-                // Identifiers and keywords
-                character if character.is_ascii_alphabetic() || character == '_' => {
-                    Ok(self.handle_word(character))
-                }
+        for result in self {
+            match result {
+                Ok(token) => tokens.push(token),
+                Err(error) => errors.push(error),
+            }
+        }
 
-                // Whitespace
-                ' ' | '\r' | '\t' | '\n' => Ok(()),
+        (tokens, errors)
+    }
+
+    /// Lexes the next token from the source code, adding it (or an error) to those pending, or adds the end of file token if the source code has been consumed. Whitespace and discarded trivia add nothing.
+    fn step(&mut self) {
+        let Some(character) = self.source.advance() else {
+            if let Some(error) = self.source.error() {
+                self.pending.push_back(Err(LexerError::UnreadableSource {
+                    span: self.span(),
+                    message: error.to_string(),
+                }));
+            }
 
-                // Unexpected characters
-                _ => Err(LexerError::UnexpectedCharacter {
-                    location: self.current_token_start,
-                    character: character,
-                    expected: None,
-                }),
-            };
+            self.add_token(TokenData::EndOfFile);
+            self.finished = true;
 
-            if let Err(error) = result {
-                errors.push(error);
+            return;
+        };
+
+        let result = match character {
+            '(' => Ok(self.add_token(TokenData::LeftParenthesis)),
+            ')' => Ok(self.add_token(TokenData::RightParenthesis)),
+            '{' => Ok(self.add_token(TokenData::LeftBrace)),
+            '}' => Ok(self.add_token(TokenData::RightBrace)),
+            ',' => Ok(self.add_token(TokenData::Comma)),
+            '.' => Ok(self.add_token(TokenData::Dot)),
+            ';' => Ok(self.add_token(TokenData::Semicolon)),
+            '?' => Ok(self.add_token(TokenData::QuestionMark)),
+            ':' => Ok(self.add_token(TokenData::Colon)),
+
+            // Arithmetic operators
+            '+' => Ok(self.add_token(TokenData::Plus)),
+            '-' => Ok(self.add_token(TokenData::Minus)),
+            '*' => Ok(self.add_token(TokenData::Star)),
+            '/' => self.handle_slash(),
+            '^' => Ok(self.add_token(TokenData::Exponent)),
+
+            // Logical and bitwise operators
+            '!' => Ok(self.handle_bang()),
+            '=' => Ok(self.handle_equal()),
+            '>' => Ok(self.handle_greater()),
+            '<' => Ok(self.handle_less()),
+            '&' => Ok(self.handle_ampersand()),
+            '|' => Ok(self.handle_pipe()),
+
+            // Literals (not including booleans)
+            '"' => self.handle_string(),
+            character if character.is_ascii_digit() => self.handle_number(character),
+
+            // Identifiers and keywords
+            character if character.is_ascii_alphabetic() || character == '_' => {
+                Ok(self.handle_word(character))
             }
 
-            self.current_token_start = self.source.location();
+            // Whitespace
+            '\n' => Ok(self.handle_newline()),
+            ' ' | '\r' | '\t' => Ok(()),
+
+            // Unexpected characters
+            _ => Err(LexerError::UnexpectedCharacter {
+                span: self.span(),
+                character: character,
+                expected: None,
+            }),
+        };
+
+        if let Err(error) = result {
+            self.pending.push_back(Err(error));
         }
 
-        (self.tokens, errors)
+        self.start_token();
+    }
+
+    /// Skips a leading `#!` line, so that scripts can be made executable on Unix.
+    fn skip_shebang(&mut self) {
+        if self.source.peek() == Some('#') && self.source.peek_after() == Some('!') {
+            while self
+                .source
+                .peek()
+                .is_some_and(|character| character != '\n')
+            {
+                self.source.advance();
+            }
+
+            self.start_token();
+        }
+    }
+
+    /// Starts the next token at the next character.
+    fn start_token(&mut self) {
+        self.current_token_start = self.source.location();
+        self.source.mark();
+    }
+
+    /// Returns the span from the start of the current token up to the next character.
+    fn span(&self) -> Span {
+        Span::new(self.current_token_start, self.source.location())
     }
 
-    /// Adds a token to the internal list of tokens.
+    /// Adds a token to those pending.
     fn add_token(&mut self, data: TokenData) {
-        self.tokens.push(Token::new(data, self.current_token_start));
+        self.last_kind = Some(data.kind());
+        self.pending.push_back(Ok(Token::new(data, self.span())));
+        self.line_has_token = true;
+    }
+
+    /// Called when a `\n` character is encountered.
+    fn handle_newline(&mut self) {
+        // A line with no tokens on it is a blank line, but runs of blank lines are collapsed into one.
+        if self.retain_trivia
+            && !self.line_has_token
+            && self
+                .last_kind
+                .is_some_and(|kind| kind != TokenKind::BlankLine)
+        {
+            self.add_token(TokenData::BlankLine);
+        }
+
+        self.line_has_token = false;
+    }
+
+    /// Adds a comment token for the current token's text, but only if trivia is being retained.
+    fn add_comment(&mut self) {
+        if self.retain_trivia {
+            let text = self.source.text().trim_end().to_string();
+            let trailing = self.line_has_token;
+
+            self.add_token(TokenData::Comment { text, trailing });
+        }
     }
 
     /// Called when a `!` character is encountered.
@@ -215,11 +357,11 @@ impl Lexer {
                 self.source.advance();
                 self.source.advance();
 
+                self.add_comment();
+
                 return Ok(());
             } else {
-                return Err(LexerError::UnterminatedBlockComment(
-                    self.current_token_start,
-                ));
+                return Err(LexerError::UnterminatedBlockComment(self.span()));
             }
         }
         // Single line comments
@@ -231,6 +373,8 @@ impl Lexer {
             {
                 self.source.advance();
             }
+
+            self.add_comment();
         } else {
             self.add_token(TokenData::Slash);
         }
@@ -252,7 +396,7 @@ impl Lexer {
         }
 
         if self.source.at_end() {
-            return Err(LexerError::UnterminatedString(self.current_token_start));
+            return Err(LexerError::UnterminatedString(self.span()));
         }
 
         // Consume the enclosing "
@@ -264,7 +408,7 @@ impl Lexer {
     }
 
     /// Called when a digit is encountered.
-    fn handle_number(&mut self, first_digit: char) {
+    fn handle_number(&mut self, first_digit: char) -> Result<(), LexerError> {
         let mut number = String::new();
 
         number.push(first_digit);
@@ -295,14 +439,19 @@ impl Lexer {
                 self.source.advance();
             }
 
-            let number: f64 = number.parse().unwrap();
+            let number: f64 = number.parse().expect("digits with a point are a float");
 
             self.add_token(TokenData::Float(number))
         } else {
-            let number: i32 = number.parse().unwrap();
+            // Only digits were lexed, so the only way parsing can fail is if the integer is too large.
+            let number: i32 = number
+                .parse()
+                .map_err(|_| LexerError::IntegerTooLarge(self.span()))?;
 
             self.add_token(TokenData::Integer(number));
         }
+
+        Ok(())
     }
 
     /// Called when the start of an identifier or keyword is encountered.
@@ -329,6 +478,8 @@ impl Lexer {
             "if" => self.add_token(TokenData::If),
             "else" => self.add_token(TokenData::Else),
             "while" => self.add_token(TokenData::While),
+            "loop" => self.add_token(TokenData::Loop),
+            "break" => self.add_token(TokenData::Break),
             "return" => self.add_token(TokenData::Return),
 
             // Identifier related
@@ -0,0 +1,77 @@
+//! The core of the slang programming language: its lexer, parser, values, heaps and interpreter, for embedding in other programs.
+//!
+//! Source code is run with an [Interpreter], whose heap is managed with one of several [HeapStrategy]s. A program's output is written to stdout, unless the interpreter is given another output.
+//!
+//! ```
+//! use slang_core::{HeapStrategy, Interpreter, console::Capture};
+//!
+//! let mut interpreter = Interpreter::new(HeapStrategy::GarbageCollected);
+//!
+//! let output = Capture::default();
+//! interpreter.set_output(output.clone());
+//!
+//! interpreter.run("let point = { x: 1, y: 2 };").unwrap();
+//! interpreter.run("print(point.x + point.y);").unwrap();
+//!
+//! assert_eq!(output.contents(), "3\n");
+//! assert_eq!(interpreter.heap().objects_count(), 1);
+//! ```
+//!
+//! Rust values convert to and from slang [Value]s with the traits in [convert], and with the `serde` feature, values can be serialized and deserialized (see `serialize`).
+//!
+//! Source code can also be parsed without being executed, with [parse], [parse_source] or [parse_expression]. A lexer created with [lexer::Lexer::with_trivia] also emits comments and blank lines, for tools such as the formatter.
+
+pub mod capabilities;
+pub mod channel;
+pub mod clock;
+pub mod console;
+pub mod convert;
+pub mod doc;
+pub mod environment;
+pub mod escape;
+pub mod expression;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod files;
+pub mod formatter;
+pub mod fuzz;
+pub mod heap;
+pub mod inspect;
+pub mod interpreter;
+pub mod interrupt;
+pub mod lexer;
+pub mod lint;
+pub mod memory;
+pub mod parser;
+pub mod patterns;
+#[cfg(not(target_family = "wasm"))]
+pub mod plugin;
+pub mod post_mortem;
+pub mod prelude;
+pub mod profiler;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod session;
+pub mod shared;
+pub mod source;
+pub mod stack;
+pub mod statement;
+pub mod stats;
+pub mod suggest;
+pub mod sync;
+pub mod text;
+pub mod thread;
+pub mod token;
+pub mod token_stream;
+pub mod trace;
+pub mod tuning;
+pub mod value;
+pub mod visit;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watch;
+
+/// The techniques which can be used to manage an interpreter's heap.
+pub use heap::Strategy as HeapStrategy;
+pub use interpreter::{Interpreter, InterpreterError, parse, parse_expression, parse_source};
+pub use value::Value;
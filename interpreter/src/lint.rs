@@ -0,0 +1,372 @@
+//! The linter, which finds code that is valid but is likely to be a mistake, for `slang lint`.
+//!
+//! Each [Rule] has a [Level], which is [Level::Warn] unless it is changed. Each diagnostic is located at the statement or expression which broke the rule, such as the if-statement whose condition is constant, or the definition of an unused function.
+
+use std::{
+    collections::HashSet,
+    fmt::{Debug, Display},
+};
+
+use crate::{
+    environment::NATIVE_FUNCTIONS,
+    expression::Expression,
+    source::{SourceMap, Span},
+    statement::Statement,
+    visit::{Visitor, walk_expression, walk_statement},
+};
+
+/// The mistakes which the linter looks for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// When the condition of an if-statement, loop, if-expression or ternary is made only of literals, so it always has the same value.
+    ConstantCondition,
+    /// When the condition of an if-statement, loop, if-expression or ternary is an assignment, which was probably meant to be a comparison.
+    AssignmentInCondition,
+    /// When a function is never referred to, except from within itself.
+    UnusedFunction,
+    /// When a variable, function or parameter has the same identifier as a native function (such as `print` or `format`), which it hides.
+    ShadowedBuiltin,
+    /// When a loop statement has no break statement or return statement which leaves it, so it never ends.
+    InfiniteLoop,
+}
+
+impl Rule {
+    /// Every rule, in the order they are listed.
+    pub const ALL: [Self; 5] = [
+        Self::ConstantCondition,
+        Self::AssignmentInCondition,
+        Self::UnusedFunction,
+        Self::ShadowedBuiltin,
+        Self::InfiniteLoop,
+    ];
+
+    /// Returns the name of the rule, as it is given on the command line and in configuration files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ConstantCondition => "constant-condition",
+            Self::AssignmentInCondition => "assignment-in-condition",
+            Self::UnusedFunction => "unused-function",
+            Self::ShadowedBuiltin => "shadowed-builtin",
+            Self::InfiniteLoop => "infinite-loop",
+        }
+    }
+
+    /// Returns the rule with a name, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// What the linter does when a rule is broken.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    /// Nothing is reported.
+    Allow,
+    /// A warning is reported.
+    Warn,
+    /// An error is reported, and linting fails.
+    Deny,
+}
+
+impl Level {
+    /// Returns the level with a name (`allow`, `warn` or `deny`), if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "allow" => Some(Self::Allow),
+            "warn" => Some(Self::Warn),
+            "deny" => Some(Self::Deny),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Allow => write!(f, "allow"),
+            Self::Warn => write!(f, "warning"),
+            Self::Deny => write!(f, "error"),
+        }
+    }
+}
+
+/// The level of each rule.
+#[derive(Clone)]
+pub struct Levels([Level; Rule::ALL.len()]);
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self([Level::Warn; Rule::ALL.len()])
+    }
+}
+
+impl Levels {
+    /// Returns the level of a rule.
+    pub fn get(&self, rule: Rule) -> Level {
+        self.0[rule as usize]
+    }
+
+    /// Sets the level of a rule.
+    pub fn set(&mut self, rule: Rule, level: Level) {
+        self.0[rule as usize] = level;
+    }
+}
+
+/// A rule which was broken.
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub level: Level,
+    pub message: String,
+    /// The statement or expression which broke the rule, or [None] if it was not parsed from source code.
+    pub span: Option<Span>,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(span) = self.span {
+            write!(f, "{} ", span)?;
+        }
+
+        write!(f, "{}[{}]: {}", self.level, self.rule, self.message)
+    }
+}
+
+impl Debug for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Diagnostic {
+    /// Returns the diagnostic as it is displayed, with its file named from a source map, and the source code which broke the rule underlined beneath it, as [InterpreterError::underlined](crate::InterpreterError::underlined) does.
+    pub fn underlined(&self, sources: &SourceMap, source: &str) -> String {
+        match self.span {
+            Some(span) => sources.render(&self.to_string(), span, source),
+            None => self.to_string(),
+        }
+    }
+}
+
+/// Lints a program, returning a diagnostic for each time a rule which is not allowed is broken, in the order they were found.
+pub fn lint(statements: &[Statement], levels: &Levels) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        levels,
+        functions: Vec::new(),
+        definitions: Vec::new(),
+        uses: HashSet::new(),
+        diagnostics: Vec::new(),
+    };
+
+    for statement in statements {
+        linter.visit_statement(statement);
+    }
+
+    for (identifier, span) in std::mem::take(&mut linter.definitions) {
+        if !linter.uses.contains(identifier) {
+            linter.report(
+                Rule::UnusedFunction,
+                format!("The function `{}` is never used.", identifier),
+                span,
+            );
+        }
+    }
+
+    linter.diagnostics
+}
+
+/// Finds the rules which are broken, as it visits the program.
+struct Linter<'a> {
+    levels: &'a Levels,
+    /// The functions being visited, innermost last.
+    functions: Vec<&'a str>,
+    /// Each function defined, along with its definition.
+    definitions: Vec<(&'a str, Option<Span>)>,
+    /// The identifiers referred to, other than by functions referring to themselves.
+    uses: HashSet<&'a str>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Linter<'a> {
+    /// Reports a rule as broken, unless it is allowed.
+    fn report(&mut self, rule: Rule, message: String, span: Option<Span>) {
+        let level = self.levels.get(rule);
+
+        if level != Level::Allow {
+            self.diagnostics.push(Diagnostic {
+                rule,
+                level,
+                message,
+                span,
+            });
+        }
+    }
+
+    /// Reports a declaration which hides a native function. A parameter is located at the definition of its function.
+    fn declare(&mut self, identifier: &str, kind: &str, span: Option<Span>) {
+        if NATIVE_FUNCTIONS
+            .iter()
+            .any(|(native, _)| *native == identifier)
+        {
+            self.report(
+                Rule::ShadowedBuiltin,
+                format!(
+                    "The {} `{}` hides the native function of the same name.",
+                    kind, identifier
+                ),
+                span,
+            );
+        }
+    }
+
+    /// Reports a condition which is an assignment, or which is constant, located at the construct it is the condition of.
+    fn condition(&mut self, condition: &Expression, construct: &str, span: Option<Span>) {
+        let mut inner = condition;
+
+        while let Expression::Grouping { contained } = inner {
+            inner = contained;
+        }
+
+        if let Expression::Assignment { identifier, .. } = inner {
+            self.report(
+                Rule::AssignmentInCondition,
+                format!(
+                    "The condition of {} assigns to `{}`; use `==` to compare it instead.",
+                    construct, identifier
+                ),
+                span,
+            );
+        } else if constant(condition) {
+            self.report(
+                Rule::ConstantCondition,
+                format!(
+                    "The condition of {} is constant, so it always has the same value.",
+                    construct
+                ),
+                span,
+            );
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for Linter<'a> {
+    fn visit_statement(&mut self, statement: &'a Statement) {
+        match statement {
+            Statement::VariableDeclaration {
+                identifier, span, ..
+            } => {
+                self.declare(identifier, "variable", *span);
+            }
+            Statement::FunctionDefinition {
+                identifier,
+                parameters,
+                block,
+                span,
+            } => {
+                self.declare(identifier, "function", *span);
+                self.definitions.push((identifier, *span));
+
+                self.functions.push(identifier);
+
+                for parameter in parameters {
+                    self.declare(parameter, "parameter", *span);
+                }
+
+                self.visit_statement(block);
+                self.functions.pop();
+
+                return;
+            }
+            Statement::IfStatement {
+                condition, span, ..
+            } => {
+                self.condition(condition, "an if-statement", *span);
+            }
+            Statement::WhileLoop {
+                condition, span, ..
+            } => {
+                self.condition(condition, "a while loop", *span);
+            }
+            Statement::Loop { block, span } => {
+                let mut exits = Exits {
+                    loops: 0,
+                    exits: false,
+                };
+
+                exits.visit_statement(block);
+
+                if !exits.exits {
+                    self.report(
+                        Rule::InfiniteLoop,
+                        String::from(
+                            "The loop has no break or return statement which leaves it, so it never ends.",
+                        ),
+                        *span,
+                    );
+                }
+            }
+            _ => {}
+        }
+
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &'a Expression) {
+        match expression {
+            Expression::Variable { identifier, .. }
+                if !self.functions.contains(&identifier.as_str()) =>
+            {
+                self.uses.insert(identifier);
+            }
+            Expression::Ternary {
+                condition, span, ..
+            } => self.condition(condition, "a ternary", *span),
+            Expression::If {
+                condition, span, ..
+            } => self.condition(condition, "an if-expression", *span),
+            _ => {}
+        }
+
+        walk_expression(self, expression);
+    }
+}
+
+/// Finds whether the block of a loop can leave it, with a break statement which is not within an inner loop, or with a return statement.
+struct Exits {
+    /// How many inner loops the statement being visited is within.
+    loops: usize,
+    exits: bool,
+}
+
+impl Visitor<'_> for Exits {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Break if self.loops == 0 => self.exits = true,
+            Statement::Return(_) => self.exits = true,
+            // A return statement within a function defined in the loop only leaves that function.
+            Statement::FunctionDefinition { .. } => {}
+            Statement::WhileLoop { .. } | Statement::Loop { .. } => {
+                self.loops += 1;
+                walk_statement(self, statement);
+                self.loops -= 1;
+            }
+            _ => walk_statement(self, statement),
+        }
+    }
+}
+
+/// Returns whether an expression is made only of literals, so that it always has the same value.
+fn constant(expression: &Expression) -> bool {
+    match expression {
+        Expression::Literal { .. } => true,
+        Expression::Grouping { contained } => constant(contained),
+        Expression::Unary { operand, .. } => constant(operand),
+        Expression::Binary { left, right, .. } => constant(left) && constant(right),
+        _ => false,
+    }
+}
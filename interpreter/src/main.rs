@@ -1,157 +1,908 @@
 use std::{
     env, fs,
-    io::{self, Write},
+    io::{self, IsTerminal, Write},
+    panic,
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, Instant},
 };
 
-use heap::{
-    ManagedHeap, garbage_collected::GarbageCollectedHeap, naive::NaiveHeap,
-    reference_counted::ReferenceCountedHeap,
+use tracing::Subscriber;
+use tracing_subscriber::{
+    EnvFilter, Layer, fmt, fmt::format::FmtSpan, layer::SubscriberExt, registry::LookupSpan,
+    util::SubscriberInitExt,
 };
-use lexer::Lexer;
-use parser::Parser;
-use source::Source;
-use stack::Stack;
-use statement::{ControlFlow, Statement};
-use stats::Logger;
-use token_stream::TokenStream;
-
-mod environment;
-mod expression;
-mod heap;
-mod lexer;
-mod parser;
-mod source;
-mod stack;
-mod statement;
-mod stats;
-mod token;
-mod token_stream;
-mod value;
+
+use bench::{Benchmark, Iteration};
+use cli::{BenchOptions, Command, RunOptions};
+use config::Config;
+use slang_core::{
+    Interpreter, InterpreterError,
+    console::Capture,
+    doc,
+    formatter::Formatter,
+    fuzz,
+    heap::{ManagedHeap, Strategy, dump, garbage_collected::Tuning, sites},
+    interrupt::Interrupt,
+    lint::{self, Level, Levels},
+    prelude,
+    source::{Source, SourceMap},
+    statement::Statement,
+    stats::{self, Format, Output, Sampling, Stats},
+    watch::Watches,
+};
+
+mod bench;
+mod cli;
+mod compare;
+mod config;
+
+/// Returns the layer which writes the interpreter's diagnostic events to stderr, filtered by `SLANG_LOG` (or `RUST_LOG` if it is not set), with the same syntax as `RUST_LOG`, such as `slang=debug` or `warn,slang::gc=trace`. Nothing is written when neither is set, or the filter is invalid.
+fn log<S: Subscriber + for<'a> LookupSpan<'a>>() -> impl Layer<S> {
+    let filter = EnvFilter::try_from_env("SLANG_LOG")
+        .or_else(|_| EnvFilter::try_from_default_env())
+        .unwrap_or_else(|_| EnvFilter::new("off"));
+
+    fmt::layer()
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .with_timer(fmt::time::uptime())
+        .with_span_events(FmtSpan::CLOSE)
+        .with_filter(filter)
+}
+
+/// Installs the subscriber which writes diagnostic events, for every thread.
+fn init_tracing() {
+    tracing_subscriber::registry().with(log()).init();
+}
+
+/// Records stats on the current thread, as well as writing diagnostic events, until the returned guard is dropped.
+fn record(stats: &Stats) -> tracing::subscriber::DefaultGuard {
+    tracing::subscriber::set_default(
+        tracing_subscriber::registry()
+            .with(log())
+            .with(stats.layer()),
+    )
+}
 
 fn main() {
-    let args = &env::args().collect::<Vec<String>>()[..];
+    init_tracing();
+
+    let arguments = env::args().skip(1).collect::<Vec<String>>();
+
+    let config = match Config::discover() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(2);
+        }
+    };
+
+    match cli::parse(&arguments) {
+        Ok(Command::Run(mut options)) => {
+            // Command line arguments take precedence over the configuration file.
+            let strategy = options
+                .strategy
+                .or(config.strategy)
+                .unwrap_or(Strategy::GarbageCollected);
+
+            let defaults = Tuning::default();
+            let gc_tuning = Tuning {
+                threshold: options
+                    .gc_threshold
+                    .or(config.gc_threshold)
+                    .unwrap_or(defaults.threshold),
+                growth_factor: options
+                    .gc_growth_factor
+                    .or(config.gc_growth_factor)
+                    .unwrap_or(defaults.growth_factor),
+                every_n_statements: options
+                    .gc_every_n_statements
+                    .or(config.gc_every_n_statements),
+                ..defaults
+            };
+            let gc_threads = options.gc_threads.or(config.gc_threads).unwrap_or(1);
+            let rc_deferred = options.rc_deferred.or(config.rc_deferred).unwrap_or(false);
+            options.escape_analysis = options.escape_analysis.or(config.escape_analysis);
+
+            let new_heap = || {
+                let mut heap = ManagedHeap::with_tuning(strategy, gc_tuning);
+                heap.set_limits(options.heap_limits);
+                heap.set_stress(options.gc_stress);
+                heap.set_gc_threads(gc_threads);
+                heap.set_deferred_counting(rc_deferred);
+
+                heap
+            };
+
+            let Some(filename) = options.filename.clone() else {
+                return run_prompt(new_heap(), &options);
+            };
+
+            let stats = if options.stats.or(config.stats_enabled).unwrap_or(false) {
+                // An explicit format takes precedence over one inferred from the output path.
+                let format = options
+                    .stats_format
+                    .or(options.stats_out.as_deref().and_then(Format::from_path))
+                    .or(config.stats_format)
+                    .unwrap_or(Format::Csv);
+
+                let path = options
+                    .stats_out
+                    .clone()
+                    .unwrap_or_else(|| config.stats_path(&filename, format));
+
+                let sampling = options
+                    .stats_interval
+                    .or(config.stats_interval)
+                    .unwrap_or_default();
+
+                Some(Output {
+                    path,
+                    format,
+                    sampling,
+                })
+            } else {
+                None
+            };
+
+            // Ctrl-C stops the program cleanly, so that its stats are still written.
+            let interrupt = Interrupt::default();
+            let handler = interrupt.clone();
+
+            if let Err(error) = ctrlc::set_handler(move || handler.interrupt()) {
+                eprintln!("Unable to handle Ctrl-C: {}", error);
+            }
+
+            if options.watch {
+                watch_file(&filename, new_heap, stats.as_ref(), &options, &interrupt);
+            } else if !run_file(&filename, new_heap(), stats.as_ref(), &options, &interrupt) {
+                process::exit(1);
+            }
+        }
+        Ok(Command::Format { check, filenames }) => format(check, &filenames),
+        Ok(Command::Lint {
+            levels: overrides,
+            filenames,
+        }) => {
+            let mut levels = Levels::default();
+
+            // Command line arguments take precedence over the configuration file.
+            for (rule, level) in config.lint.iter().chain(&overrides) {
+                levels.set(*rule, *level);
+            }
+
+            lint(&levels, &filenames);
+        }
+        Ok(Command::Doc {
+            format,
+            out,
+            filenames,
+        }) => doc(format, out.as_deref(), &filenames),
+        Ok(Command::Test {
+            filter,
+            strategy,
+            filenames,
+        }) => {
+            let strategy = strategy
+                .or(config.strategy)
+                .unwrap_or(Strategy::GarbageCollected);
+
+            test(filter.as_deref(), strategy, &filenames);
+        }
+        Ok(Command::Bench(options)) => {
+            let strategy = options
+                .strategy
+                .or(config.strategy)
+                .unwrap_or(Strategy::GarbageCollected);
+
+            bench(options, strategy);
+        }
+        Ok(Command::Compare {
+            filename,
+            out,
+            escape_analysis,
+            gc_threads,
+        }) => {
+            let out = out.unwrap_or_else(|| PathBuf::from(format!("{}.compare.csv", filename)));
+
+            compare(
+                &filename,
+                &out,
+                escape_analysis.or(config.escape_analysis).unwrap_or(false),
+                gc_threads.or(config.gc_threads).unwrap_or(1),
+            );
+        }
+        Ok(Command::Calc {
+            strategy,
+            expressions,
+        }) => {
+            let strategy = strategy
+                .or(config.strategy)
+                .unwrap_or(Strategy::GarbageCollected);
+
+            calc(strategy, &expressions);
+        }
+        Ok(Command::FuzzCheck { filenames }) => fuzz_check(&filenames),
+        Err(error) => {
+            eprintln!("{}", error);
+            println!("{}", cli::USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+/// Formats each file in place, or with `--check`, reports the files which are not formatted.
+///
+/// Exits with a non-zero status code if any file could not be formatted, or is not formatted when checking.
+fn format(check: bool, filenames: &[String]) {
+    let mut sources = SourceMap::default();
+    let mut success = true;
+
+    for filename in filenames {
+        let source = match fs::read_to_string(filename) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("{}: {}", filename, error);
+                success = false;
+                continue;
+            }
+        };
+
+        let file = sources.add(filename, &source);
+
+        let formatted = match Formatter::format_file(&source, file) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                for error in errors {
+                    match error.span() {
+                        Some(span) => {
+                            eprintln!("{}", sources.render(&error.to_string(), span, &source))
+                        }
+                        None => eprintln!("{}: {}", filename, error),
+                    }
+                }
+
+                success = false;
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        }
+
+        if check {
+            println!("{} is not formatted.", filename);
+            success = false;
+        } else if let Err(error) = fs::write(filename, formatted) {
+            eprintln!("{}: {}", filename, error);
+            success = false;
+        }
+    }
 
-    match args {
-        [_executable, heap] if heap == "gc" => run_prompt(gc()),
-        [_executable, heap] if heap == "rc" => run_prompt(rc()),
-        [_executable, heap] if heap == "na" => run_prompt(na()),
+    if !success {
+        process::exit(1);
+    }
+}
+
+/// Lints each file, printing a diagnostic for each rule which is broken.
+///
+/// Exits with a non-zero status code if any file could not be parsed, or breaks a rule which is denied.
+fn lint(levels: &Levels, filenames: &[String]) {
+    let mut sources = SourceMap::default();
+    let mut success = true;
+
+    for filename in filenames {
+        let source = match fs::read_to_string(filename) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("{}: {}", filename, error);
+                success = false;
+                continue;
+            }
+        };
+
+        let statements = match slang_core::parse_source(named(&mut sources, &source, filename)) {
+            Ok(statements) => statements,
+            Err(error) => {
+                report(Err(error), &sources, &source);
+                success = false;
+                continue;
+            }
+        };
+
+        for diagnostic in lint::lint(&statements, levels) {
+            println!("{}", diagnostic.underlined(&sources, &source));
+
+            if diagnostic.level == Level::Deny {
+                success = false;
+            }
+        }
+    }
+
+    if !success {
+        process::exit(1);
+    }
+}
+
+/// Writes the documentation of each file, to a path if it was given, or to stdout otherwise.
+///
+/// Exits with a non-zero status code, without writing anything, if any file could not be read or parsed.
+fn doc(format: doc::Format, out: Option<&Path>, filenames: &[String]) {
+    let mut modules = Vec::new();
+    let mut sources = SourceMap::default();
+    let mut success = true;
+
+    for filename in filenames {
+        let source = match fs::read_to_string(filename) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("{}: {}", filename, error);
+                success = false;
+                continue;
+            }
+        };
+
+        match doc::document(filename, named(&mut sources, &source, filename)) {
+            Ok(module) => modules.push(module),
+            Err(error) => {
+                report(Err(error), &sources, &source);
+                success = false;
+            }
+        }
+    }
+
+    if !success {
+        process::exit(1);
+    }
+
+    let documentation = doc::render(&modules, format);
+
+    match out {
+        Some(path) => {
+            if let Err(error) = fs::write(path, documentation) {
+                eprintln!("{}: {}", path.display(), error);
+                process::exit(1);
+            }
+        }
+        None => print!("{}", documentation),
+    }
+}
+
+/// Runs each test in the files whose name contains the filter, if there is one, reporting whether it passed.
+///
+/// Each test runs with a fresh stack and heap, which the prelude and the rest of its file are run in first, so tests cannot affect one another. The output of a test is only printed if it fails. Exits with a non-zero status code if any test failed, or any file could not be read or parsed.
+fn test(filter: Option<&str>, strategy: Strategy, filenames: &[String]) {
+    let mut sources = SourceMap::default();
+    let mut success = true;
+    let (mut passed, mut failed, mut filtered) = (0, 0, 0);
+
+    for filename in filenames {
+        let source = match fs::read_to_string(filename) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("{}: {}", filename, error);
+                success = false;
+                continue;
+            }
+        };
 
-        [_executable, heap, filename] if heap == "gc" => run_file(filename, gc()),
-        [_executable, heap, filename] if heap == "rc" => run_file(filename, rc()),
-        [_executable, heap, filename] if heap == "na" => run_file(filename, na()),
+        let statements = match slang_core::parse_source(named(&mut sources, &source, filename)) {
+            Ok(statements) => statements,
+            Err(error) => {
+                report(Err(error), &sources, &source);
+                success = false;
+                continue;
+            }
+        };
+
+        for statement in &statements {
+            let Statement::Test { name, block } = statement else {
+                continue;
+            };
+
+            if filter.is_some_and(|filter| !name.contains(filter)) {
+                filtered += 1;
+                continue;
+            }
 
-        _ => println!("Usage: slang <gc|rc|na> [filename]"),
+            let output = Capture::default();
+            let mut interpreter = Interpreter::new(strategy);
+            interpreter.set_output(output.clone());
+            interpreter.set_error_output(output.clone());
+
+            let result = interpreter
+                .load_prelude()
+                .and_then(|()| interpreter.execute(statements.clone()))
+                .and_then(|()| interpreter.execute(vec![*block.clone()]));
+
+            match result {
+                Ok(()) => {
+                    println!("test {}: {} ... ok", filename, name);
+                    passed += 1;
+                }
+                Err(error) => {
+                    println!("test {}: {} ... FAILED", filename, name);
+                    print!("{}", output.contents());
+                    println!("{}", error.underlined(&sources, &source));
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    println!(
+        "\n{} passed, {} failed, {} filtered out",
+        passed, failed, filtered
+    );
+
+    if failed > 0 || !success {
+        process::exit(1);
     }
 }
 
-fn run_prompt(heap: ManagedHeap) {
+/// Evaluates each expression in turn, printing its value, or if none were given, starts a REPL which evaluates each line as an expression. Nothing can be defined, but the natives and the prelude's functions can be called.
+///
+/// Exits with a non-zero status code if any of the expressions given could not be evaluated.
+fn calc(strategy: Strategy, expressions: &[String]) {
+    let mut interpreter = Interpreter::new(strategy);
+    report(
+        interpreter.load_prelude(),
+        interpreter.sources(),
+        prelude::SOURCE,
+    );
+
+    if !expressions.is_empty() {
+        let mut success = true;
+
+        for expression in expressions {
+            success &= evaluate(&mut interpreter, "<expression>", expression);
+        }
+
+        if !success {
+            process::exit(1);
+        }
+
+        return;
+    }
+
     let mut line = String::new();
 
     let stdin = io::stdin();
     let mut stdout = io::stdout();
 
-    let mut stack = Stack::new();
-    let mut heap = heap;
-    let mut logger = Logger::new();
-
     loop {
         line.clear();
 
         print!("> ");
         let _ = stdout.flush();
-        let _ = stdin.read_line(&mut line);
 
-        run(line.trim(), &mut stack, &mut heap, &mut logger);
+        if !matches!(stdin.read_line(&mut line), Ok(read) if read > 0) {
+            break;
+        }
+
+        let line = line.trim();
+
+        if !line.is_empty() {
+            evaluate(&mut interpreter, "<repl>", line);
+        }
     }
 }
 
-fn run_file(filename: &str, heap: ManagedHeap) {
-    let contents = fs::read_to_string(filename);
+/// Evaluates an expression, named as a file in the interpreter's source map, printing its value if it has one, or the error underlined if it could not be evaluated. Returns whether it was evaluated.
+fn evaluate(interpreter: &mut Interpreter, name: &str, source: &str) -> bool {
+    match interpreter.evaluate_named(name, source) {
+        Ok(value) => {
+            if let Some(value) = value {
+                println!("{}", value);
+            }
 
-    let mut stack = Stack::new();
-    let mut heap = heap;
-    let mut logger = Logger::new();
+            true
+        }
+        Err(error) => {
+            eprintln!("{}", error.underlined(interpreter.sources(), source));
+            false
+        }
+    }
+}
 
-    match contents {
-        Ok(source) => {
-            run(&source, &mut stack, &mut heap, &mut logger);
+/// Lexes and parses the bytes of each file as the fuzz targets do, reporting the files which cause a panic.
+///
+/// Exits with a non-zero status code if any file could not be read, or caused a panic.
+fn fuzz_check(filenames: &[String]) {
+    let mut success = true;
 
-            logger.new_entry(heap.objects_count(), stack.frames_count());
+    for filename in filenames {
+        let data = match fs::read(filename) {
+            Ok(data) => data,
+            Err(error) => {
+                eprintln!("{}: {}", filename, error);
+                success = false;
+                continue;
+            }
+        };
 
-            logger.write_to_csv(filename);
+        // The panic's message is printed by the panic hook as it happens.
+        if panic::catch_unwind(|| fuzz::check(&data)).is_err() {
+            println!("{}: panicked", filename);
+            success = false;
+        } else {
+            println!("{}: ok", filename);
         }
-        Err(error) => eprintln!("{}", error),
+    }
+
+    if !success {
+        process::exit(1);
     }
 }
 
-fn run(source: &str, stack: &mut Stack, heap: &mut ManagedHeap, logger: &mut Logger) {
-    let source = Source::new(source);
+/// Runs a file repeatedly with a fresh stack and heap each time, and reports how long the measured runs took.
+fn bench(options: BenchOptions, strategy: Strategy) {
+    let source = match fs::read_to_string(&options.filename) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("{}: {}", options.filename, error);
+            process::exit(1);
+        }
+    };
+
+    let mut iterations = Vec::new();
+
+    for run_number in 0..options.warmup + options.iterations {
+        let mut interpreter = Interpreter::new(strategy);
+        report(
+            interpreter.load_prelude(),
+            interpreter.sources(),
+            prelude::SOURCE,
+        );
+
+        let start = Instant::now();
+        report(
+            interpreter.run_named(&options.filename, &source),
+            interpreter.sources(),
+            &source,
+        );
+        let elapsed = start.elapsed();
+
+        if run_number >= options.warmup {
+            iterations.push(Iteration {
+                elapsed,
+                allocations: interpreter.heap().allocations(),
+            });
+        }
+    }
+
+    let benchmark = Benchmark {
+        strategy,
+        warmup: options.warmup,
+        iterations,
+    };
+
+    eprintln!("{}", benchmark.report());
+
+    if let Some(path) = options.out {
+        let format = Format::from_path(&path).unwrap_or(Format::Json);
+
+        if let Err(error) = fs::write(&path, benchmark.render(format)) {
+            eprintln!("{}: {}", path.display(), error);
+            process::exit(1);
+        }
+    }
+}
+
+/// Runs a file with each heap management strategy in turn, each with a fresh stack and heap, and writes their stats side by side.
+fn compare(filename: &str, out: &Path, escape_analysis: bool, gc_threads: usize) {
+    let source = match fs::read_to_string(filename) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("{}: {}", filename, error);
+            process::exit(1);
+        }
+    };
+
+    let mut runs = Vec::new();
+
+    for strategy in [
+        Strategy::GarbageCollected,
+        Strategy::Generational,
+        Strategy::Compacting,
+        Strategy::Copying,
+        Strategy::ReferenceCounted,
+        Strategy::Region,
+        Strategy::Naive,
+    ] {
+        eprintln!(
+            "[compare] Running {} with the {} heap",
+            filename,
+            strategy.code()
+        );
+
+        let mut heap = ManagedHeap::new(strategy);
+        heap.set_gc_threads(gc_threads);
+
+        let mut interpreter = Interpreter::with_heap(heap);
+        report(
+            interpreter.load_prelude(),
+            interpreter.sources(),
+            prelude::SOURCE,
+        );
+        interpreter.set_escape_analysis(escape_analysis);
 
-    let lexer = Lexer::new(source);
+        let recorded = Stats::new(Sampling::default());
+        let recording = record(&recorded);
+
+        report(
+            interpreter.run_named(filename, &source),
+            interpreter.sources(),
+            &source,
+        );
+
+        let (stack, mut heap) = interpreter.into_parts();
+        stats::finish(&mut heap, &stack);
+        drop(recording);
+
+        runs.push(compare::Run {
+            strategy,
+            entries: recorded.entries(),
+        });
+    }
 
-    let (tokens, errors) = lexer.lex();
+    eprintln!("{}", compare::summary(&runs));
 
-    for error in &errors {
-        eprintln!("{:?}", error);
+    if let Err(error) = fs::write(out, compare::csv(&runs)) {
+        eprintln!("{}: {}", out.display(), error);
+        process::exit(1);
     }
+}
+
+/// Runs each line entered as source code until the end of the input, except for `:save <path>`, which saves the session to a file, and `:load <path>`, which restores one.
+fn run_prompt(heap: ManagedHeap, options: &RunOptions) {
+    let mut line = String::new();
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    let mut interpreter = Interpreter::with_heap(heap);
 
-    if errors.len() != 0 {
+    if !load_plugins(&mut interpreter, options) {
         return;
     }
 
-    let tokens = TokenStream::new(tokens);
+    if options.prelude {
+        report(
+            interpreter.load_prelude(),
+            interpreter.sources(),
+            prelude::SOURCE,
+        );
+    }
 
-    let parser = Parser::new(tokens);
+    if let Some(path) = &options.restore
+        && let Err(error) = interpreter.restore(path)
+    {
+        eprintln!("{}: {}", path.display(), error);
+    }
 
-    match parser.parse() {
-        Ok(statements) => {
-            let mut non_definitions = Vec::new();
+    interpreter.set_max_steps(options.max_steps);
+    interpreter.set_capabilities(options.capabilities);
+    interpreter.set_watches(Watches::new(options.watch_vars.clone()));
+    interpreter.set_dump_on_error(options.dump_on_error);
 
-            for statement in statements {
-                match statement {
-                    Statement::FunctionDefinition {
-                        identifier: _,
-                        parameters: _,
-                        block: _,
-                    } => {
-                        if let Err(error) = statement.execute(stack, heap, logger) {
-                            eprintln!("{}", error);
-                            return;
-                        }
-                    }
-                    _ => non_definitions.push(statement),
+    loop {
+        line.clear();
+
+        print!("> ");
+        let _ = stdout.flush();
+
+        if !matches!(stdin.read_line(&mut line), Ok(read) if read > 0) {
+            break;
+        }
+
+        let line = line.trim();
+
+        if let Some(path) = line.strip_prefix(":save ") {
+            if let Err(error) = interpreter.save(path.trim()) {
+                eprintln!("{}: {}", path.trim(), error);
+            }
+        } else if let Some(path) = line.strip_prefix(":load ") {
+            if let Err(error) = interpreter.restore(path.trim()) {
+                eprintln!("{}: {}", path.trim(), error);
+            }
+        } else {
+            report(
+                interpreter.run_named("<repl>", line),
+                interpreter.sources(),
+                line,
+            );
+            dump_on_error(&interpreter, options);
+        }
+    }
+}
+
+/// Runs a file, writing stats if an output is given, until it finishes or is interrupted. Afterwards, as requested by the options, prints a report of per-function timings, prints a report of the live objects from each allocation site, and dumps the heap to `<filename>.heap.json`.
+///
+/// Returns whether the file ran without any errors, so that the exit code can report them.
+fn run_file(
+    filename: &str,
+    heap: ManagedHeap,
+    stats: Option<&Output>,
+    options: &RunOptions,
+    interrupt: &Interrupt,
+) -> bool {
+    let contents = fs::read_to_string(filename);
+
+    let recorded = match stats {
+        Some(output) => Stats::new(output.sampling),
+        None => Stats::disabled(),
+    };
+
+    if options.profile {
+        recorded.enable_profiling();
+    }
+
+    if options.instrument {
+        recorded.enable_instrumentation();
+    }
+
+    let mut interpreter = Interpreter::with_heap(heap);
+    interpreter.set_escape_analysis(options.escape_analysis.unwrap_or(false));
+    interpreter.set_interrupt(interrupt.clone());
+
+    if !load_plugins(&mut interpreter, options) {
+        return false;
+    }
+
+    if options.prelude
+        && !report(
+            interpreter.load_prelude(),
+            interpreter.sources(),
+            prelude::SOURCE,
+        )
+    {
+        return false;
+    }
+
+    // The prelude and the restored session is not part of the program being run, so it is not recorded in stats or limited in steps.
+    if let Some(path) = &options.restore
+        && let Err(error) = interpreter.restore(path)
+    {
+        eprintln!("{}: {}", path.display(), error);
+        return false;
+    }
+
+    let recording = (stats.is_some() || options.profile).then(|| record(&recorded));
+    interpreter.set_max_steps(options.max_steps);
+    interpreter.set_capabilities(options.capabilities);
+    interpreter.set_watches(Watches::new(options.watch_vars.clone()));
+    interpreter.set_dump_on_error(options.dump_on_error);
+
+    match contents {
+        Ok(source) => {
+            let success = report(
+                interpreter.run_named(filename, &source),
+                interpreter.sources(),
+                &source,
+            );
+            dump_on_error(&interpreter, options);
+
+            let (stack, mut heap) = interpreter.into_parts();
+            stats::finish(&mut heap, &stack);
+            drop(recording);
+
+            if let Some(report) = recorded.profile_report() {
+                eprintln!("{}", report);
+            }
+
+            if let Some(output) = stats {
+                if let Some(summary) = recorded.summary() {
+                    eprintln!("{}", summary);
                 }
+
+                recorded.write(output);
             }
 
-            for statement in non_definitions {
-                match statement.execute(stack, heap, logger) {
-                    Ok(control) => match control {
-                        ControlFlow::Continue => continue,
-                        ControlFlow::Break(_) => return,
-                    },
-                    Err(error) => {
-                        eprintln!("{}", error);
-                        return;
-                    }
+            if options.allocation_sites {
+                eprintln!("{}", sites::report(&heap, &stack.sources));
+            }
+
+            if options.heap_dump_on_exit {
+                let path = format!("{}.heap.json", filename);
+
+                if let Err(error) =
+                    fs::write(&path, dump::dump(&heap, &stack.roots(), &stack.sources))
+                {
+                    eprintln!("{}: {}", path, error);
                 }
             }
+
+            success
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            false
+        }
+    }
+}
+
+/// Writes the post-mortem dump of the last run, if an evaluation error stopped it and dumps on error are enabled, to the path given or to stderr.
+fn dump_on_error(interpreter: &Interpreter, options: &RunOptions) {
+    let Some(dump) = interpreter.post_mortem() else {
+        return;
+    };
+
+    match &options.dump_out {
+        Some(path) => {
+            if let Err(error) = fs::write(path, dump) {
+                eprintln!("{}: {}", path.display(), error);
+            }
         }
-        Err(errors) => {
-            for error in errors {
-                eprintln!("{}", error);
+        None => eprint!("\n{}", dump),
+    }
+}
+
+/// Runs a file, and then re-runs it with a fresh stack and heap (made by `new_heap`) each time it is modified, until it is interrupted while waiting.
+fn watch_file(
+    filename: &str,
+    new_heap: impl Fn() -> ManagedHeap,
+    stats: Option<&Output>,
+    options: &RunOptions,
+    interrupt: &Interrupt,
+) {
+    let modified = || {
+        fs::metadata(filename)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+
+    let mut runs = 0;
+
+    loop {
+        let last_modified = modified();
+
+        runs += 1;
+        eprintln!("[watch] Run {} of {}", runs, filename);
+
+        run_file(filename, new_heap(), stats, options, interrupt);
+
+        eprintln!("[watch] Finished, waiting for changes...");
+
+        while modified() == last_modified {
+            if interrupt.take() {
+                return;
             }
+
+            thread::sleep(Duration::from_millis(250));
         }
+
+        eprintln!();
     }
 }
 
-fn gc() -> ManagedHeap {
-    ManagedHeap::GarbageCollected(GarbageCollectedHeap::new())
+/// Loads each plugin given in the options into an interpreter, returning whether they were all loaded.
+fn load_plugins(interpreter: &mut Interpreter, options: &RunOptions) -> bool {
+    for path in &options.plugins {
+        // Plugins are given explicitly on the command line, so they are trusted.
+        if let Err(error) = unsafe { interpreter.load_plugin(path) } {
+            eprintln!("{}", error);
+            return false;
+        }
+    }
+
+    true
 }
 
-fn rc() -> ManagedHeap {
-    ManagedHeap::ReferenceCounted(ReferenceCountedHeap::new())
+/// Returns the source code read from a file, named by the path it was read from, adding the file to a source map.
+fn named<'a>(sources: &mut SourceMap, source: &'a str, filename: &str) -> Source<'a> {
+    Source::new(source).with_file(sources.add(filename, source))
 }
 
-fn na() -> ManagedHeap {
-    ManagedHeap::Naive(NaiveHeap::new())
+/// Prints the error from running some source code, if there was one, naming its file from a source map and underlining where it occurred. Returns whether it ran without an error.
+fn report(result: Result<(), InterpreterError>, sources: &SourceMap, source: &str) -> bool {
+    match result {
+        Ok(()) => true,
+        Err(error) => {
+            eprintln!("{}", error.underlined(sources, source));
+            false
+        }
+    }
 }
@@ -0,0 +1,148 @@
+//! Measurement of the interpreter's own memory usage, on each supported platform.
+
+/// Returns the resident set size of the interpreter process in bytes, or [None] if it cannot be measured on this platform.
+pub fn resident_set_size() -> Option<usize> {
+    platform::resident_set_size()
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::{
+        fs::File,
+        io::{self, BufRead},
+    };
+
+    /// Reads the `VmRSS` line of `/proc/self/status`.
+    pub fn resident_set_size() -> Option<usize> {
+        let file = File::open("/proc/self/status").ok()?;
+
+        for line in io::BufReader::new(file).lines() {
+            let Ok(line) = line else {
+                continue;
+            };
+
+            if let Some(value) = line.strip_prefix("VmRSS:") {
+                // The value is given in kibibytes, despite being labelled as `kB`.
+                let kibibytes = value.split_whitespace().next()?.parse::<usize>().ok()?;
+
+                return Some(kibibytes * 1024);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::mem;
+
+    /// The `MACH_TASK_BASIC_INFO` flavour of `task_info`.
+    const MACH_TASK_BASIC_INFO: i32 = 20;
+
+    /// Corresponds to `time_value_t`.
+    #[repr(C)]
+    #[derive(Default)]
+    struct TimeValue {
+        seconds: i32,
+        microseconds: i32,
+    }
+
+    /// Corresponds to `mach_task_basic_info`.
+    #[repr(C)]
+    #[derive(Default)]
+    struct MachTaskBasicInfo {
+        virtual_size: u64,
+        resident_size: u64,
+        resident_size_max: u64,
+        user_time: TimeValue,
+        system_time: TimeValue,
+        policy: i32,
+        suspend_count: i32,
+    }
+
+    unsafe extern "C" {
+        /// The port for the current task, which `mach_task_self()` expands to.
+        static mach_task_self_: u32;
+
+        fn task_info(
+            target_task: u32,
+            flavor: i32,
+            task_info_out: *mut i32,
+            count: *mut u32,
+        ) -> i32;
+    }
+
+    /// Asks the kernel for the resident size of the current task.
+    pub fn resident_set_size() -> Option<usize> {
+        let mut info = MachTaskBasicInfo::default();
+        let mut count = (mem::size_of::<MachTaskBasicInfo>() / mem::size_of::<i32>()) as u32;
+
+        // SAFETY: `info` is a correctly laid out `mach_task_basic_info`, and `count` is its size in natural words.
+        let result = unsafe {
+            task_info(
+                mach_task_self_,
+                MACH_TASK_BASIC_INFO,
+                &mut info as *mut MachTaskBasicInfo as *mut i32,
+                &mut count,
+            )
+        };
+
+        (result == 0).then_some(info.resident_size as usize)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{ffi::c_void, mem};
+
+    /// Corresponds to `PROCESS_MEMORY_COUNTERS`.
+    #[repr(C)]
+    #[derive(Default)]
+    struct ProcessMemoryCounters {
+        cb: u32,
+        page_fault_count: u32,
+        peak_working_set_size: usize,
+        working_set_size: usize,
+        quota_peak_paged_pool_usage: usize,
+        quota_paged_pool_usage: usize,
+        quota_peak_non_paged_pool_usage: usize,
+        quota_non_paged_pool_usage: usize,
+        pagefile_usage: usize,
+        peak_pagefile_usage: usize,
+    }
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetCurrentProcess() -> *mut c_void;
+
+        /// The kernel32 export of `GetProcessMemoryInfo`, which avoids linking against psapi.
+        fn K32GetProcessMemoryInfo(
+            process: *mut c_void,
+            counters: *mut ProcessMemoryCounters,
+            cb: u32,
+        ) -> i32;
+    }
+
+    /// Asks Windows for the working set size of the current process.
+    pub fn resident_set_size() -> Option<usize> {
+        let mut counters = ProcessMemoryCounters {
+            cb: mem::size_of::<ProcessMemoryCounters>() as u32,
+            ..Default::default()
+        };
+
+        // SAFETY: `counters` is a correctly laid out `PROCESS_MEMORY_COUNTERS`, and `cb` is its size.
+        let result =
+            unsafe { K32GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, counters.cb) };
+
+        (result != 0).then_some(counters.working_set_size)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    /// There is no way of measuring memory usage on other platforms.
+    pub fn resident_set_size() -> Option<usize> {
+        None
+    }
+}
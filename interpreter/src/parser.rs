@@ -7,8 +7,9 @@ use std::{
 
 use crate::{
     expression::{BinaryOperator, Expression, UnaryOperator},
-    source::{GeneralLocation, Location},
+    source::{Location, Span},
     statement::Statement,
+    suggest,
     token::{TokenData, TokenKind},
     token_stream::TokenStream,
     value::Value,
@@ -19,38 +20,73 @@ pub enum ParserError {
     /// When a token was expected but not found.
     ExpectedToken {
         expected: Vec<TokenKind>,
-        location: GeneralLocation,
+        span: Span,
     },
     /// When a unary expression with an unsupported unary operator is encountered.
     UnsupportedUnaryExpression {
         operator: BinaryOperator,
-        location: GeneralLocation,
+        span: Span,
     },
     /// When there is an attempt to assign a value to something which is not assignable.
-    InvalidAssignmentTarget(Location),
+    InvalidAssignmentTarget(Span),
+    /// When a keyword is used where an identifier was expected, such as the name of a variable.
+    KeywordAsIdentifier { keyword: String, span: Span },
+    /// When a break statement is not within a loop, or is within a block expression or if-expression inside the loop.
+    BreakOutsideLoop(Span),
+    /// When a return statement is within a block expression or a branch of an if-expression, which yields a value rather than leaving the function.
+    ReturnWithinExpression(Span),
+    /// When statements or expressions are nested more deeply than the parser's maximum depth ([MAX_DEPTH] unless it was given another), which could otherwise overflow the stack.
+    TooDeeplyNested { max_depth: usize, span: Span },
+    /// When a statement which could not be parsed begins with an identifier close to a keyword, which was probably meant instead, such as `wile` for `while`.
+    MisspelledKeyword { keyword: &'static str, span: Span },
 }
 
 impl Display for ParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::ExpectedToken { expected, location } => {
+            Self::ExpectedToken { expected, span } => {
                 write!(
                     f,
                     "{} Expected one of the following tokens: {:?}",
-                    location, expected
+                    span, expected
                 )
             }
-            Self::UnsupportedUnaryExpression { operator, location } => {
+            Self::UnsupportedUnaryExpression { operator, span } => {
                 write!(
                     f,
                     "{} The unary `{}` operator is not supported.",
-                    location,
+                    span,
                     operator.raw(),
                 )
             }
-            Self::InvalidAssignmentTarget(location) => {
-                write!(f, "{} Invalid assignment target.", location)
+            Self::InvalidAssignmentTarget(span) => {
+                write!(f, "{} Invalid assignment target.", span)
             }
+            Self::KeywordAsIdentifier { keyword, span } => write!(
+                f,
+                "{} `{}` is a keyword and cannot be used as a name. Try another name, such as `{}_`.",
+                span, keyword, keyword
+            ),
+            Self::BreakOutsideLoop(span) => write!(
+                f,
+                "{} A break statement can only be used within a loop.",
+                span
+            ),
+            Self::ReturnWithinExpression(span) => write!(
+                f,
+                "{} A return statement cannot be used within a block expression or if-expression, as it yields a value instead.",
+                span
+            ),
+            Self::TooDeeplyNested { max_depth, span } => write!(
+                f,
+                "{} Statements and expressions cannot be nested more than {} deep.",
+                span, max_depth
+            ),
+            Self::MisspelledKeyword { keyword, span } => write!(
+                f,
+                "{} This is not a keyword. Did you mean `{}`?",
+                span, keyword
+            ),
         }
     }
 }
@@ -63,15 +99,64 @@ impl Debug for ParserError {
 
 impl Error for ParserError {}
 
+impl ParserError {
+    /// Returns the characters which caused the error.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::ExpectedToken { span, .. }
+            | Self::UnsupportedUnaryExpression { span, .. }
+            | Self::InvalidAssignmentTarget(span)
+            | Self::KeywordAsIdentifier { span, .. }
+            | Self::BreakOutsideLoop(span)
+            | Self::ReturnWithinExpression(span)
+            | Self::TooDeeplyNested { span, .. }
+            | Self::MisspelledKeyword { span, .. } => *span,
+        }
+    }
+}
+
+/// How deeply statements and expressions can be nested within each other, such as parentheses within parentheses, or blocks within blocks, unless a parser is given another limit with [Parser::with_max_depth].
+pub const MAX_DEPTH: usize = 256;
+
+/// The keywords which a statement can begin with (counting the `else` after an if-statement), which an identifier beginning a statement may be a misspelling of.
+const STATEMENT_KEYWORDS: [&str; 8] = [
+    "let", "fu", "return", "if", "else", "while", "loop", "break",
+];
+
 /// A parser for a specific token stream.
-pub struct Parser {
-    tokens: TokenStream,
+pub struct Parser<'a> {
+    tokens: TokenStream<'a>,
+    /// The errors which have been recovered from so far.
+    errors: Vec<ParserError>,
+    /// Whether the statements being parsed are within a block expression or a branch of an if-expression (and not a function defined there), where a return statement is not allowed.
+    yielding: bool,
+    /// Whether the statements being parsed are within a loop (and not a function, block expression or if-expression inside it), where a break statement is allowed.
+    looping: bool,
+    /// How deeply the statement or expression being parsed is nested.
+    depth: usize,
+    /// How deeply statements and expressions may be nested.
+    max_depth: usize,
 }
 
-impl Parser {
+impl<'a> Parser<'a> {
     /// Creates a new parser for a specific token stream.
-    pub fn new(tokens: TokenStream) -> Self {
-        Self { tokens }
+    pub fn new(tokens: TokenStream<'a>) -> Self {
+        Self {
+            tokens,
+            errors: Vec::new(),
+            yielding: false,
+            looping: false,
+            depth: 0,
+            max_depth: MAX_DEPTH,
+        }
+    }
+
+    /// Sets how deeply statements and expressions may be nested before a [ParserError::TooDeeplyNested] is reported, which is [MAX_DEPTH] by default.
+    ///
+    /// Parsing, and every pass over the parsed syntax tree (such as executing it), recurses once for each level of nesting, so a limit much higher than the default may overflow the stack on deeply nested input.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
     }
 
     /// Attempts to parse the token stream. Corresponds to `program` in the grammar.
@@ -79,31 +164,75 @@ impl Parser {
     /// Consumes the entire token stream. Will attempt to find all errors, while minimising cascading errors.
     pub fn parse(mut self) -> Result<Vec<Statement>, Vec<ParserError>> {
         let mut statements: Vec<Statement> = Vec::new();
-        let mut errors: Vec<ParserError> = Vec::new();
 
-        while !self.tokens.at_end() {
+        loop {
+            self.trivia(&mut statements);
+
+            if self.tokens.at_end() {
+                break;
+            }
+
             match self.statement() {
                 Ok(statement) => statements.push(statement),
+                // Input nested too deeply is likely to be generated or malicious, so rather than reporting an error for each level it was nested too deeply, parsing stops.
+                Err(error @ ParserError::TooDeeplyNested { .. }) => {
+                    self.errors.push(error);
+                    break;
+                }
                 Err(error) => {
-                    errors.push(error);
+                    self.errors.push(error);
                     self.synchronize();
                 }
             }
         }
 
-        if errors.is_empty() {
+        if self.errors.is_empty() {
             Ok(statements)
         } else {
-            Err(errors)
+            Err(self.errors)
+        }
+    }
+
+    /// Attempts to parse the token stream as a single expression, as `slang calc` does. Corresponds to `expression` in the grammar.
+    ///
+    /// Consumes the entire token stream, so anything after the expression is an error.
+    pub fn parse_expression(mut self) -> Result<Expression, Vec<ParserError>> {
+        let result = self.expression().and_then(|expression| {
+            self.tokens.consume(TokenKind::EndOfFile)?;
+            Ok(expression)
+        });
+
+        match result {
+            Ok(expression) if self.errors.is_empty() => Ok(expression),
+            Ok(_) => Err(self.errors),
+            Err(error) => {
+                self.errors.push(error);
+                Err(self.errors)
+            }
+        }
+    }
+
+    /// Converts any trivia tokens at the start of a statement into trivia statements.
+    ///
+    /// The lexer only emits trivia when asked to, so this has no effect when executing programs.
+    fn trivia(&mut self, statements: &mut Vec<Statement>) {
+        while let Some(token) = self.tokens.trivia() {
+            match token.data() {
+                TokenData::Comment { text, trailing } => {
+                    statements.push(Statement::Comment { text, trailing })
+                }
+                TokenData::BlankLine => statements.push(Statement::BlankLine),
+                _ => unreachable!(),
+            }
         }
     }
 
-    /// Consumes tokens until the end of a statement is reached.
+    /// Consumes tokens until the end of a statement, or the end of the block containing it, is reached.
     fn synchronize(&mut self) {
         self.tokens.advance();
 
-        while let Some(token) = self.tokens.peek() {
-            match token.kind() {
+        loop {
+            match self.tokens.peek().kind() {
                 TokenKind::Semicolon => {
                     self.tokens.advance();
                     return;
@@ -113,7 +242,11 @@ impl Parser {
                 | TokenKind::Let
                 | TokenKind::If
                 | TokenKind::While
-                | TokenKind::Return => return,
+                | TokenKind::Loop
+                | TokenKind::Break
+                | TokenKind::Return
+                | TokenKind::RightBrace
+                | TokenKind::EndOfFile => return,
 
                 _ => {
                     self.tokens.advance();
@@ -122,24 +255,152 @@ impl Parser {
         }
     }
 
+    /// Recovers from an error within a statement by skipping to the next of some targets, such as the `,` before the next argument, recording the error so that the rest of the statement can still be parsed. Returns the error instead if none of the targets are found before the end of the statement, or if it was nested too deeply, which is never recovered from.
+    fn recover(&mut self, error: ParserError, targets: &[TokenKind]) -> Result<(), ParserError> {
+        if !matches!(error, ParserError::TooDeeplyNested { .. }) && self.tokens.skip_to(targets) {
+            self.errors.push(error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Consumes the token which closes a list, such as the `)` of a call. If anything else comes first, the error is recovered from by skipping to the closing token.
+    fn close(&mut self, kind: TokenKind) -> Result<(), ParserError> {
+        if let Err(error) = self.tokens.consume(kind) {
+            self.recover(error, &[kind])?;
+            self.tokens.advance();
+        }
+
+        Ok(())
+    }
+
+    /// Consumes an identifier. A keyword used in its place is recovered from by recording the error and treating the keyword as the identifier, so that the rest of the statement can still be parsed.
+    fn identifier(&mut self) -> Result<String, ParserError> {
+        match self.tokens.consume_identifier() {
+            Err(ParserError::KeywordAsIdentifier { keyword, span }) => {
+                self.tokens.advance();
+                self.errors.push(ParserError::KeywordAsIdentifier {
+                    keyword: keyword.clone(),
+                    span,
+                });
+
+                Ok(keyword)
+            }
+            result => result,
+        }
+    }
+
+    /// Parses something within a context where break and return statements may or may not be allowed, restoring the previous context afterwards.
+    fn within<T>(
+        &mut self,
+        yielding: bool,
+        looping: bool,
+        parse: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let yielding = std::mem::replace(&mut self.yielding, yielding);
+        let looping = std::mem::replace(&mut self.looping, looping);
+
+        let parsed = parse(self);
+
+        self.yielding = yielding;
+        self.looping = looping;
+
+        parsed
+    }
+
+    /// Parses something nested within the statement or expression being parsed, failing if it is nested too deeply.
+    ///
+    /// The depth is restored afterwards, even if something within it (such as a chain of binary expressions) failed without restoring its own depth.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParserError>,
+    ) -> Result<T, ParserError> {
+        if self.depth >= self.max_depth {
+            return self.too_deeply_nested();
+        }
+
+        let depth = self.depth;
+
+        self.depth += 1;
+        let parsed = parse(self);
+        self.depth = depth;
+
+        parsed
+    }
+
+    /// Fails because the next token would be nested more deeply than the maximum depth.
+    ///
+    /// Besides statements and expressions within one another, each operator in a chain of binary expressions, and each call or field access in a chain of them (such as `a.b.c()`), counts as a level, as each is nested within the expression before it once parsed. Every level of nesting takes up space on the stack while it is parsed, so the error is returned directly rather than with `?`, which would take up more.
+    fn too_deeply_nested<T>(&mut self) -> Result<T, ParserError> {
+        Err(ParserError::TooDeeplyNested {
+            max_depth: self.max_depth,
+            span: self.tokens.peek().span(),
+        })
+    }
+
+    /// Returns the location of the next token, where the statement or expression about to be parsed starts.
+    fn start(&mut self) -> Location {
+        self.tokens.peek().location()
+    }
+
+    /// Returns the source code from a location up to the end of the last token consumed, which is that of the statement or expression parsed since the location was taken with [Parser::start].
+    fn since(&self, start: Location) -> Option<Span> {
+        let end = self
+            .tokens
+            .previous()
+            .map_or(start, |previous| previous.end());
+
+        Some(Span::new(start, end))
+    }
+
     /// Attempts to parse a statement. Corresponds to `statement` in the grammar.
     fn statement(&mut self) -> Result<Statement, ParserError> {
-        match self.tokens.peek().map(|token| token.kind()) {
-            Some(TokenKind::Let) => self.variable_declaration(),
-            Some(TokenKind::Fu) => self.function_definition(),
-            Some(TokenKind::Return) => self.return_statement(),
-            Some(TokenKind::If) => self.if_statement(),
-            Some(TokenKind::While) => self.while_loop(),
-            Some(TokenKind::LeftBrace) => self.block(),
+        self.nested(Self::unnested_statement)
+    }
+
+    /// Attempts to parse a statement, without counting it as nested.
+    fn unnested_statement(&mut self) -> Result<Statement, ParserError> {
+        match self.tokens.peek().kind() {
+            TokenKind::Let => self.variable_declaration(),
+            TokenKind::Fu => self.function_definition(),
+            TokenKind::Return => self.return_statement(),
+            TokenKind::If => self.if_statement(),
+            TokenKind::While => self.while_loop(),
+            TokenKind::Loop => self.loop_statement(),
+            TokenKind::Break => self.break_statement(),
+            TokenKind::LeftBrace => self.block(),
+            // `test` is only a keyword when it is followed by the name of a test, so it can still be used as an identifier.
+            TokenKind::Identifier
+                if matches!(self.tokens.peek().clone().data(), TokenData::Identifier(identifier) if identifier == "test")
+                    && self.tokens.peek_nth(1).kind() == TokenKind::String =>
+            {
+                self.test()
+            }
             _ => self.expression_statement(),
         }
     }
 
+    /// Attempts to parse a test. Corresponds to `test` in the grammar.
+    fn test(&mut self) -> Result<Statement, ParserError> {
+        self.tokens.consume(TokenKind::Identifier)?;
+
+        let TokenData::String(name) = self.tokens.consume(TokenKind::String)?.data() else {
+            unreachable!()
+        };
+
+        // Like a function's block, a return statement leaves the test, but a break statement cannot reach a loop around it.
+        let block = Box::new(self.within(false, false, Self::block)?);
+
+        Ok(Statement::Test { name, block })
+    }
+
     /// Attempts to parse a variable declaration. Corresponds to `variableDeclaration` in the grammar.
     fn variable_declaration(&mut self) -> Result<Statement, ParserError> {
+        let start = self.start();
         self.tokens.consume(TokenKind::Let)?;
 
-        let identifier = self.tokens.consume_identifier()?;
+        let identifier = self.identifier()?;
 
         let initialiser = if self.tokens.matches(&[TokenKind::Equal]) {
             Some(self.expression()?)
@@ -147,58 +408,79 @@ impl Parser {
             None
         };
 
-        self.tokens.consume(TokenKind::Semicolon)?;
+        let span = self.since(start);
+        self.tokens.terminate()?;
 
         Ok(Statement::VariableDeclaration {
             identifier,
             initialiser,
+            inline: false,
+            span,
         })
     }
 
     /// Attempts to parse a function definition. Corresponds to `functionDefinition` in the grammar.
     fn function_definition(&mut self) -> Result<Statement, ParserError> {
+        let start = self.start();
         self.tokens.consume(TokenKind::Fu)?;
 
-        let identifier = self.tokens.consume_identifier()?;
+        let identifier = self.identifier()?;
 
         self.tokens.consume(TokenKind::LeftParenthesis)?;
 
         let mut parameters = Vec::new();
 
-        if let Ok(parameter) = self.tokens.consume_identifier() {
+        if let Ok(parameter) = self.identifier() {
             parameters.push(parameter);
 
-            while self.tokens.matches(&[TokenKind::Comma]) {
-                parameters.push(self.tokens.consume_identifier()?);
+            // A trailing comma is allowed before the closing parenthesis.
+            while self.tokens.matches(&[TokenKind::Comma])
+                && self.tokens.peek().kind() != TokenKind::RightParenthesis
+            {
+                parameters.push(self.identifier()?);
             }
         }
 
         self.tokens.consume(TokenKind::RightParenthesis)?;
 
-        let block = Box::new(self.block()?);
+        // A return statement leaves the function, even within a block expression, but a break statement cannot reach a loop around the definition.
+        let block = Box::new(self.within(false, false, Self::block)?);
 
         Ok(Statement::FunctionDefinition {
             identifier,
             parameters,
             block,
+            span: self.since(start),
         })
     }
 
     /// Attempts to parse a return statement. Corresponds to `returnStatement` in the grammar.
     fn return_statement(&mut self) -> Result<Statement, ParserError> {
-        self.tokens.consume(TokenKind::Return)?;
+        let token = self.tokens.consume(TokenKind::Return)?;
 
-        if self.tokens.matches(&[TokenKind::Semicolon]) {
+        if self.yielding {
+            self.errors
+                .push(ParserError::ReturnWithinExpression(token.span()));
+        }
+
+        // Without a `;`, a value on the next line is still returned, so only a closing brace or the end of the source code ends a return statement with no value.
+        if self.tokens.matches(&[TokenKind::Semicolon])
+            || matches!(
+                self.tokens.peek().kind(),
+                TokenKind::RightBrace | TokenKind::EndOfFile
+            )
+        {
             Ok(Statement::Return(None))
         } else {
             let expression = self.expression()?;
-            self.tokens.consume(TokenKind::Semicolon)?;
+            self.tokens.terminate()?;
             Ok(Statement::Return(Some(expression)))
         }
     }
 
     /// Attempts to parse an if-statement. Corresponds to `ifStatement` in the grammar.
     fn if_statement(&mut self) -> Result<Statement, ParserError> {
+        let start = self.start();
         self.tokens.consume(TokenKind::If)?;
 
         let condition = self.expression()?;
@@ -206,20 +488,15 @@ impl Parser {
         let execute_if_true = Box::new(self.block()?);
 
         let execute_if_false = if self.tokens.matches(&[TokenKind::Else]) {
-            match self
-                .tokens
-                .peek()
-                .map(|token| (token.kind(), token.location()))
-            {
-                Some((TokenKind::If, _)) => Some(Box::new(self.if_statement()?)),
-                Some((TokenKind::LeftBrace, _)) => Some(Box::new(self.block()?)),
-                Some((_, location)) => Err(ParserError::ExpectedToken {
-                    expected: vec![TokenKind::If, TokenKind::LeftBrace],
-                    location: GeneralLocation::Location(location),
-                })?,
-                None => Err(ParserError::ExpectedToken {
+            let next = self.tokens.peek();
+
+            match next.kind() {
+                // Each `else if` is nested within the if-statement before it.
+                TokenKind::If => Some(Box::new(self.nested(Self::if_statement)?)),
+                TokenKind::LeftBrace => Some(Box::new(self.block()?)),
+                _ => Err(ParserError::ExpectedToken {
                     expected: vec![TokenKind::If, TokenKind::LeftBrace],
-                    location: GeneralLocation::EndOfFile,
+                    span: next.span(),
                 })?,
             }
         } else {
@@ -230,18 +507,100 @@ impl Parser {
             condition,
             execute_if_true,
             execute_if_false,
+            span: self.since(start),
         })
     }
 
+    /// Attempts to parse a block expression. Corresponds to `blockExpression` in the grammar.
+    fn block_expression(&mut self) -> Result<Expression, ParserError> {
+        let block = self.within(true, false, Self::block)?;
+
+        Ok(Expression::Block {
+            block: Box::new(block),
+        })
+    }
+
+    /// Attempts to parse an if-expression, which must have an `else` branch so that it always yields a value. Corresponds to `ifExpression` in the grammar.
+    fn if_expression(&mut self) -> Result<Expression, ParserError> {
+        let start = self.start();
+        let (condition, execute_if_true, execute_if_false) =
+            self.within(true, false, Self::if_branches)?;
+
+        Ok(Expression::If {
+            condition: Box::new(condition),
+            execute_if_true,
+            execute_if_false,
+            span: self.since(start),
+        })
+    }
+
+    /// Attempts to parse the condition and branches of an if-expression. An `else if` is held as an if-statement, whose branches yield values in the same way.
+    fn if_branches(&mut self) -> Result<(Expression, Box<Statement>, Box<Statement>), ParserError> {
+        self.tokens.consume(TokenKind::If)?;
+
+        let condition = self.expression()?;
+        let execute_if_true = Box::new(self.block()?);
+
+        self.tokens.consume(TokenKind::Else)?;
+
+        let execute_if_false = if self.tokens.peek().kind() == TokenKind::If {
+            let start = self.start();
+            let (condition, execute_if_true, execute_if_false) = self.nested(Self::if_branches)?;
+
+            Statement::IfStatement {
+                condition,
+                execute_if_true,
+                execute_if_false: Some(execute_if_false),
+                span: self.since(start),
+            }
+        } else {
+            self.block()?
+        };
+
+        Ok((condition, execute_if_true, Box::new(execute_if_false)))
+    }
+
     /// Attempts to parse a while-loop. Corresponds to `whileLoop` in the grammar.
     fn while_loop(&mut self) -> Result<Statement, ParserError> {
+        let start = self.start();
         self.tokens.consume(TokenKind::While)?;
 
         let condition = self.expression()?;
 
-        let block = Box::new(self.block()?);
+        let block = Box::new(self.within(self.yielding, true, Self::block)?);
 
-        Ok(Statement::WhileLoop { condition, block })
+        Ok(Statement::WhileLoop {
+            condition,
+            block,
+            span: self.since(start),
+        })
+    }
+
+    /// Attempts to parse a loop statement. Corresponds to `loopStatement` in the grammar.
+    fn loop_statement(&mut self) -> Result<Statement, ParserError> {
+        let start = self.start();
+        self.tokens.consume(TokenKind::Loop)?;
+
+        let block = Box::new(self.within(self.yielding, true, Self::block)?);
+
+        Ok(Statement::Loop {
+            block,
+            span: self.since(start),
+        })
+    }
+
+    /// Attempts to parse a break statement. Corresponds to `breakStatement` in the grammar.
+    fn break_statement(&mut self) -> Result<Statement, ParserError> {
+        let token = self.tokens.consume(TokenKind::Break)?;
+
+        if !self.looping {
+            self.errors
+                .push(ParserError::BreakOutsideLoop(token.span()));
+        }
+
+        self.tokens.terminate()?;
+
+        Ok(Statement::Break)
     }
 
     /// Attempts to parse a block statement. Corresponds to `block` in the grammar.
@@ -250,12 +609,26 @@ impl Parser {
 
         let mut statements = Vec::new();
 
-        while self
-            .tokens
-            .peek()
-            .is_some_and(|token| token.kind() != TokenKind::RightBrace)
-        {
-            statements.push(self.statement()?);
+        self.trivia(&mut statements);
+
+        while !matches!(
+            self.tokens.peek().kind(),
+            TokenKind::RightBrace | TokenKind::EndOfFile
+        ) {
+            match self.statement() {
+                Ok(statement) => statements.push(statement),
+                Err(error @ ParserError::TooDeeplyNested { .. }) => return Err(error),
+                // The rest of the block is still parsed, unless the error was at its closing brace.
+                Err(error) => {
+                    self.errors.push(error);
+
+                    if self.tokens.peek().kind() != TokenKind::RightBrace {
+                        self.synchronize();
+                    }
+                }
+            }
+
+            self.trivia(&mut statements);
         }
 
         self.tokens.consume(TokenKind::RightBrace)?;
@@ -264,37 +637,85 @@ impl Parser {
     }
 
     /// Attempts to parse an expression statement. Corresponds to `expressionStatement` in the grammar.
+    ///
+    /// If the statement cannot be parsed, and it begins with an identifier close to a keyword (such as `retrun x;`), the keyword is reported as misspelled instead, as the rest of the statement was probably written for it.
     fn expression_statement(&mut self) -> Result<Statement, ParserError> {
-        let expression = self.expression()?;
+        let misspelling = self.misspelled_keyword();
+
+        let expression = match self.expression() {
+            Ok(expression) => expression,
+            Err(error) => return Err(Self::misspelled(misspelling, error)),
+        };
+
+        if self.tokens.matches(&[TokenKind::Semicolon]) {
+            return Ok(Statement::Expression(expression));
+        }
+
+        if let Err(error) = self.tokens.terminate() {
+            return Err(Self::misspelled(misspelling, error));
+        }
+
+        // Without a `;`, the final expression of a block which yields a value is the value it yields.
+        if self.yielding && self.tokens.peek().kind() == TokenKind::RightBrace {
+            Ok(Statement::Yield(expression))
+        } else {
+            Ok(Statement::Expression(expression))
+        }
+    }
+
+    /// Returns an error for the next token if it is an identifier close to a keyword which begins a statement, in case the statement cannot be parsed.
+    fn misspelled_keyword(&mut self) -> Option<ParserError> {
+        let token = self.tokens.peek();
+
+        if token.kind() != TokenKind::Identifier {
+            return None;
+        }
+
+        let span = token.span();
+        let TokenData::Identifier(word) = token.clone().data() else {
+            unreachable!()
+        };
+
+        let keyword = suggest::closest(&word, STATEMENT_KEYWORDS)?;
 
-        self.tokens.consume(TokenKind::Semicolon)?;
+        Some(ParserError::MisspelledKeyword { keyword, span })
+    }
 
-        Ok(Statement::Expression(expression))
+    /// Reports a misspelled keyword in place of a token which was expected after it, as the token was only expected because the keyword was misspelled.
+    fn misspelled(misspelling: Option<ParserError>, error: ParserError) -> ParserError {
+        match (misspelling, error) {
+            (Some(misspelling), ParserError::ExpectedToken { .. }) => misspelling,
+            (_, error) => error,
+        }
     }
 
     /// Attempts to parse an expression. Corresponds to `expression` in the grammar.
     fn expression(&mut self) -> Result<Expression, ParserError> {
-        self.assignment()
+        self.nested(Self::assignment)
     }
 
     /// Attempts to parse an assignment. Corresponds to `assignment` in the grammar.
     fn assignment(&mut self) -> Result<Expression, ParserError> {
+        let start = self.start();
         let expression = self.ternary()?;
 
         if let Some(equals) = self.tokens.only_take(&[TokenKind::Equal]) {
-            let value = self.assignment()?;
+            let value = self.nested(Self::assignment)?;
+            let span = self.since(start);
 
             match expression {
-                Expression::GetField { object, field } => Ok(Expression::SetField {
+                Expression::GetField { object, field, .. } => Ok(Expression::SetField {
                     object,
                     field,
                     value: Box::new(value),
+                    span,
                 }),
-                Expression::Variable { identifier } => Ok(Expression::Assignment {
+                Expression::Variable { identifier, .. } => Ok(Expression::Assignment {
                     identifier,
                     value: Box::new(value),
+                    span,
                 }),
-                _ => Err(ParserError::InvalidAssignmentTarget(equals.location())),
+                _ => Err(ParserError::InvalidAssignmentTarget(equals.span())),
             }
         } else {
             Ok(expression)
@@ -303,6 +724,7 @@ impl Parser {
 
     /// Attempts to parse a ternary expression. Corresponds to `ternary` in the grammar.
     fn ternary(&mut self) -> Result<Expression, ParserError> {
+        let start = self.start();
         let mut expression = self.logical()?;
 
         if self.tokens.matches(&[TokenKind::QuestionMark]) {
@@ -316,6 +738,7 @@ impl Parser {
                 condition: Box::new(expression),
                 left: Box::new(left),
                 right: Box::new(right),
+                span: self.since(start),
             }
         }
 
@@ -324,42 +747,66 @@ impl Parser {
 
     /// Attempts to parse a logical expression. Corresponds to `logical` in the grammar.
     fn logical(&mut self) -> Result<Expression, ParserError> {
+        let depth = self.depth;
+        let start = self.start();
         let mut expression = self.equality()?;
 
         while let Some((operator, _)) = self
             .tokens
             .binary_operator(&[BinaryOperator::AND, BinaryOperator::OR])
         {
+            if self.depth >= self.max_depth {
+                return self.too_deeply_nested();
+            }
+
+            self.depth += 1;
+
             expression = Expression::Binary {
                 left: Box::new(expression),
-                operator: operator,
+                operator,
                 right: Box::new(self.equality()?),
+                span: self.since(start),
             }
         }
 
+        self.depth = depth;
+
         Ok(expression)
     }
 
     /// Attempts to parse an equality expression. Corresponds to `equality` in the grammar.
     fn equality(&mut self) -> Result<Expression, ParserError> {
+        let depth = self.depth;
+        let start = self.start();
         let mut expression = self.comparison()?;
 
         while let Some((operator, _)) = self
             .tokens
             .binary_operator(&[BinaryOperator::NotEqualTo, BinaryOperator::EqualTo])
         {
+            if self.depth >= self.max_depth {
+                return self.too_deeply_nested();
+            }
+
+            self.depth += 1;
+
             expression = Expression::Binary {
                 left: Box::new(expression),
                 operator,
                 right: Box::new(self.comparison()?),
+                span: self.since(start),
             }
         }
 
+        self.depth = depth;
+
         Ok(expression)
     }
 
     /// Attempts to parse a comparison expression. Corresponds to `comparison` in the grammar.
     fn comparison(&mut self) -> Result<Expression, ParserError> {
+        let depth = self.depth;
+        let start = self.start();
         let mut expression = self.bitwise()?;
 
         while let Some((operator, _)) = self.tokens.binary_operator(&[
@@ -368,72 +815,116 @@ impl Parser {
             BinaryOperator::LessThan,
             BinaryOperator::LessThanOrEqualTo,
         ]) {
+            if self.depth >= self.max_depth {
+                return self.too_deeply_nested();
+            }
+
+            self.depth += 1;
+
             expression = Expression::Binary {
                 left: Box::new(expression),
                 operator,
                 right: Box::new(self.bitwise()?),
+                span: self.since(start),
             }
         }
 
+        self.depth = depth;
+
         Ok(expression)
     }
 
     /// Attempts to parse a bitwise expression. Corresponds to `bitwise` in the grammar.
     fn bitwise(&mut self) -> Result<Expression, ParserError> {
+        let depth = self.depth;
+        let start = self.start();
         let mut expression = self.term()?;
 
         while let Some((operator, _)) = self
             .tokens
             .binary_operator(&[BinaryOperator::BitwiseAND, BinaryOperator::BitwiseOR])
         {
+            if self.depth >= self.max_depth {
+                return self.too_deeply_nested();
+            }
+
+            self.depth += 1;
+
             expression = Expression::Binary {
                 left: Box::new(expression),
                 operator,
                 right: Box::new(self.term()?),
+                span: self.since(start),
             }
         }
 
+        self.depth = depth;
+
         Ok(expression)
     }
 
     /// Attempts to parse a term. Corresponds to `term` in the grammar.
     fn term(&mut self) -> Result<Expression, ParserError> {
+        let depth = self.depth;
+        let start = self.start();
         let mut expression = self.factor()?;
 
         while let Some((operator, _)) = self
             .tokens
             .binary_operator(&[BinaryOperator::Add, BinaryOperator::Subtract])
         {
+            if self.depth >= self.max_depth {
+                return self.too_deeply_nested();
+            }
+
+            self.depth += 1;
+
             expression = Expression::Binary {
                 left: Box::new(expression),
                 operator,
                 right: Box::new(self.factor()?),
+                span: self.since(start),
             }
         }
 
+        self.depth = depth;
+
         Ok(expression)
     }
 
     /// Attempts to parse a factor. Corresponds to `factor` in the grammar.
     fn factor(&mut self) -> Result<Expression, ParserError> {
+        let depth = self.depth;
+        let start = self.start();
         let mut expression = self.unary()?;
 
         while let Some((operator, _)) = self
             .tokens
             .binary_operator(&[BinaryOperator::Multiply, BinaryOperator::Divide])
         {
+            if self.depth >= self.max_depth {
+                return self.too_deeply_nested();
+            }
+
+            self.depth += 1;
+
             expression = Expression::Binary {
                 left: Box::new(expression),
                 operator,
                 right: Box::new(self.unary()?),
+                span: self.since(start),
             }
         }
 
+        self.depth = depth;
+
         Ok(expression)
     }
 
     /// Attempts to parse a unary expression. Corresponds to `unary` in the grammar.
     fn unary(&mut self) -> Result<Expression, ParserError> {
+        let start = self.start();
+
         if let Some((operator, _)) = self
             .tokens
             .unary_operator(&[UnaryOperator::Minus, UnaryOperator::NOT])
@@ -441,8 +932,9 @@ impl Parser {
             Ok(Expression::Unary {
                 operator: operator,
                 operand: Box::new(self.exponent()?),
+                span: self.since(start),
             })
-        } else if let Some((operator, location)) = self.tokens.binary_operator(&[
+        } else if let Some((operator, span)) = self.tokens.binary_operator(&[
             BinaryOperator::Add,
             BinaryOperator::Multiply,
             BinaryOperator::Divide,
@@ -458,7 +950,7 @@ impl Parser {
             let _ = self.exponent();
 
             Err(ParserError::UnsupportedUnaryExpression {
-                location: GeneralLocation::Location(location),
+                span,
                 operator: operator,
             })
         } else {
@@ -468,13 +960,15 @@ impl Parser {
 
     /// Attempts to parse an exponent expression. Corresponds to `exponent` in the grammar.
     fn exponent(&mut self) -> Result<Expression, ParserError> {
+        let start = self.start();
         let mut expression = self.call()?;
 
         if self.tokens.matches(&[TokenKind::Exponent]) {
             expression = Expression::Binary {
                 left: Box::new(expression),
                 operator: BinaryOperator::Exponent,
-                right: Box::new(self.exponent()?),
+                right: Box::new(self.nested(Self::exponent)?),
+                span: self.since(start),
             }
         }
 
@@ -482,48 +976,72 @@ impl Parser {
     }
 
     /// Attempt to parse a call expression. Corresponds to `call` in the grammar.
+    ///
+    /// Like a chain of binary expressions, each call or field access in a chain counts as one level of nesting.
     fn call(&mut self) -> Result<Expression, ParserError> {
+        let depth = self.depth;
+        let start = self.start();
         let mut expression = self.primary()?;
 
         while let Some(token) = self
             .tokens
             .only_take(&[TokenKind::LeftParenthesis, TokenKind::Dot])
         {
+            if self.depth >= self.max_depth {
+                return self.too_deeply_nested();
+            }
+
+            self.depth += 1;
+
             match token.kind() {
                 TokenKind::LeftParenthesis => {
                     let mut arguments = Vec::new();
 
-                    if self
-                        .tokens
-                        .peek()
-                        .is_some_and(|token| token.kind() != TokenKind::RightParenthesis)
-                    {
-                        arguments.push(Box::new(self.expression()?));
+                    if !matches!(
+                        self.tokens.peek().kind(),
+                        TokenKind::RightParenthesis | TokenKind::EndOfFile
+                    ) {
+                        loop {
+                            match self.expression() {
+                                Ok(argument) => arguments.push(Box::new(argument)),
+                                Err(error) => self.recover(
+                                    error,
+                                    &[TokenKind::Comma, TokenKind::RightParenthesis],
+                                )?,
+                            }
 
-                        while self.tokens.matches(&[TokenKind::Comma]) {
-                            arguments.push(Box::new(self.expression()?));
+                            // A trailing comma is allowed before the closing parenthesis.
+                            if !self.tokens.matches(&[TokenKind::Comma])
+                                || self.tokens.peek().kind() == TokenKind::RightParenthesis
+                            {
+                                break;
+                            }
                         }
                     }
 
-                    self.tokens.consume(TokenKind::RightParenthesis)?;
+                    self.close(TokenKind::RightParenthesis)?;
 
                     expression = Expression::Call {
                         function: Box::new(expression),
                         arguments,
+                        span: self.since(start),
                     }
                 }
                 TokenKind::Dot => {
-                    let field = self.tokens.consume_identifier()?;
+                    let field = self.identifier()?;
 
                     expression = Expression::GetField {
                         object: Box::new(expression),
                         field,
+                        span: self.since(start),
                     }
                 }
                 _ => unreachable!(),
             }
         }
 
+        self.depth = depth;
+
         Ok(expression)
     }
 
@@ -537,22 +1055,41 @@ impl Parser {
             TokenKind::Boolean,
             TokenKind::Identifier,
             TokenKind::LeftBrace,
+            TokenKind::If,
         ];
 
+        if self.tokens.peek().kind() == TokenKind::If {
+            return self.if_expression();
+        }
+
+        // A brace opens an object literal if it is closed straight away, or is followed by the `name:` of its first field, and opens a block expression otherwise.
+        if self.tokens.peek().kind() == TokenKind::LeftBrace
+            && !matches!(
+                self.tokens.peek_nth(1).kind(),
+                TokenKind::RightBrace | TokenKind::EndOfFile
+            )
+            && self.tokens.peek_nth(2).kind() != TokenKind::Colon
+        {
+            return self.block_expression();
+        }
+
         if let Some(token) = self.tokens.only_take(&expected) {
+            let location = token.location();
+            let span = token.span();
+
             Ok(Expression::Literal {
                 value: match token.data() {
                     TokenData::LeftParenthesis => {
                         let expression = self.expression()?;
 
-                        self.tokens.consume(TokenKind::RightParenthesis)?;
+                        self.close(TokenKind::RightParenthesis)?;
 
                         return Ok(Expression::Grouping {
                             contained: Box::new(expression),
                         });
                     }
 
-                    TokenData::String(string) => Value::String(string),
+                    TokenData::String(string) => Value::String(string.into()),
 
                     TokenData::Float(float) => Value::Float(float),
 
@@ -561,48 +1098,59 @@ impl Parser {
                     TokenData::Boolean(boolean) => Value::Boolean(boolean),
 
                     TokenData::Identifier(identifier) => {
-                        return Ok(Expression::Variable { identifier });
+                        return Ok(Expression::Variable {
+                            identifier,
+                            span: Some(span),
+                        });
                     }
 
                     TokenData::LeftBrace => {
                         let mut fields = Vec::new();
 
-                        if self
-                            .tokens
-                            .peek()
-                            .is_some_and(|token| token.kind() != TokenKind::RightBrace)
-                        {
-                            let identifier = self.tokens.consume_identifier()?;
-                            self.tokens.consume(TokenKind::Colon)?;
-                            let expression = self.expression()?;
-                            fields.push((identifier, expression));
-
-                            while self.tokens.matches(&[TokenKind::Comma]) {
-                                let identifier = self.tokens.consume_identifier()?;
-                                self.tokens.consume(TokenKind::Colon)?;
-                                let expression = self.expression()?;
-                                fields.push((identifier, expression));
+                        if !matches!(
+                            self.tokens.peek().kind(),
+                            TokenKind::RightBrace | TokenKind::EndOfFile
+                        ) {
+                            loop {
+                                match self.field() {
+                                    Ok(field) => fields.push(field),
+                                    Err(error) => self.recover(
+                                        error,
+                                        &[TokenKind::Comma, TokenKind::RightBrace],
+                                    )?,
+                                }
+
+                                // A trailing comma is allowed before the closing brace.
+                                if !self.tokens.matches(&[TokenKind::Comma])
+                                    || self.tokens.peek().kind() == TokenKind::RightBrace
+                                {
+                                    break;
+                                }
                             }
                         }
 
-                        self.tokens.consume(TokenKind::RightBrace)?;
+                        self.close(TokenKind::RightBrace)?;
 
-                        return Ok(Expression::Object(fields.into_iter().collect()));
+                        return Ok(Expression::Object { fields, location });
                     }
 
                     _ => unreachable!(),
                 },
             })
-        } else if let Some(token) = self.tokens.peek() {
-            Err(ParserError::ExpectedToken {
-                expected: expected.to_vec(),
-                location: GeneralLocation::Location(token.location()),
-            })
         } else {
             Err(ParserError::ExpectedToken {
                 expected: expected.to_vec(),
-                location: GeneralLocation::EndOfFile,
+                span: self.tokens.peek().span(),
             })
         }
     }
+
+    /// Attempts to parse a field of an object literal, as its identifier and the expression giving its value.
+    fn field(&mut self) -> Result<(String, Expression), ParserError> {
+        let identifier = self.identifier()?;
+        self.tokens.consume(TokenKind::Colon)?;
+        let expression = self.expression()?;
+
+        Ok((identifier, expression))
+    }
 }
@@ -0,0 +1,101 @@
+//! Native functions which match text against regular expressions, with the syntax of the `regex` crate.
+//!
+//! Each pattern is compiled the first time it is used, and kept for the lifetime of the process, so a pattern used within a loop is only compiled once. Positions within text are counted in characters, from zero.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use regex::Regex;
+
+use crate::{
+    expression::{EvaluationError, Expression},
+    heap::{ManagedHeap, Object},
+    shared::Shared,
+    stack::Stack,
+    value::{Function, Value},
+};
+
+/// The patterns which have been compiled so far.
+static PATTERNS: LazyLock<Mutex<HashMap<String, Regex>>> = LazyLock::new(Mutex::default);
+
+/// Returns whether a pattern matches anywhere within some text.
+pub fn is_match(pattern: &str, text: &str) -> Result<Value, EvaluationError> {
+    Ok(Value::Boolean(compile(pattern)?.is_match(text)))
+}
+
+/// Calls a function with each match of a pattern within some text, in order, returning the number of matches. Each match is an object with its `text`, and the positions it `start`s and `end`s at, along with a field for each named group which took part in it.
+pub fn find_all(
+    pattern: &str,
+    text: &str,
+    function: Function,
+    stack: &mut Stack,
+    heap: &mut ManagedHeap,
+) -> Result<Value, EvaluationError> {
+    let regex = compile(pattern)?;
+    let position = |index: usize| Value::try_from(text[..index].chars().count());
+    let mut count: usize = 0;
+
+    for captures in regex.captures_iter(text) {
+        let whole = captures.get(0).expect("every match has its whole text");
+
+        let mut object: Object = [
+            ("text", Value::String(Shared::from(whole.as_str()))),
+            ("start", position(whole.start())?),
+            ("end", position(whole.end())?),
+        ]
+        .into_iter()
+        .map(|(field, value)| (Shared::from(field), value))
+        .collect();
+
+        for name in regex.capture_names().flatten() {
+            if let Some(group) = captures.name(name) {
+                object.insert(
+                    Shared::from(name),
+                    Value::String(Shared::from(group.as_str())),
+                );
+            }
+        }
+
+        let call = Expression::Call {
+            function: Box::new(Expression::Literal {
+                value: Value::Function(function.clone()),
+            }),
+            arguments: vec![Box::new(Expression::Literal {
+                value: Value::Object(object),
+            })],
+            span: None,
+        };
+
+        call.evaluate(stack, heap)?;
+        count += 1;
+    }
+
+    Ok(Value::try_from(count)?)
+}
+
+/// Returns some text with every match of a pattern replaced. Within the replacement, `$1` or `${1}` is replaced by the text of the first group, and `$name` or `${name}` by the text of a named group.
+pub fn replace_all(pattern: &str, text: &str, replacement: &str) -> Result<Value, EvaluationError> {
+    let replaced = compile(pattern)?.replace_all(text, replacement);
+
+    Ok(Value::String(Shared::from(replaced.as_ref())))
+}
+
+/// Returns a compiled pattern, compiling it if it has not been used before.
+fn compile(pattern: &str) -> Result<Regex, EvaluationError> {
+    let mut patterns = PATTERNS.lock().expect("the patterns were poisoned");
+
+    if let Some(regex) = patterns.get(pattern) {
+        return Ok(regex.clone());
+    }
+
+    let regex = Regex::new(pattern).map_err(|error| EvaluationError::InvalidPattern {
+        pattern: pattern.to_string(),
+        error: error.to_string(),
+    })?;
+
+    patterns.insert(pattern.to_string(), regex.clone());
+
+    Ok(regex)
+}
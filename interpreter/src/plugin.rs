@@ -0,0 +1,220 @@
+//! Plugins: shared libraries of native functions, which are loaded while the interpreter is running so that it can be extended without being rebuilt.
+//!
+//! A plugin is a `cdylib` crate which depends on this one, and registers its functions with [export_plugin](crate::export_plugin). It exports a [Descriptor], which must match the interpreter's version, compiler and features before its functions are called.
+//!
+//! ```ignore
+//! use slang_core::{Value, plugin::Registry};
+//!
+//! fn square(arguments: &[Value]) -> Result<Option<Value>, String> {
+//!     match arguments {
+//!         [Value::Integer(value)] => Ok(Some(Value::Integer(value * value))),
+//!         _ => Err(String::from("expected one Integer")),
+//!     }
+//! }
+//!
+//! fn register(registry: &mut Registry) {
+//!     registry.register("square", square);
+//! }
+//!
+//! slang_core::export_plugin!(register);
+//! ```
+
+use std::{
+    error::Error,
+    ffi::{CStr, c_char},
+    fmt::{Debug, Display},
+    path::{Path, PathBuf},
+};
+
+use libloading::Library;
+
+use crate::value::{PluginFunction, Value};
+
+/// The version of the [Descriptor], which is checked before anything else in it is read.
+pub const ABI: u32 = 1;
+
+/// The version of this crate, which a plugin must have been built against.
+pub const VERSION: &CStr = c_string(concat!(env!("CARGO_PKG_VERSION"), "\0"));
+
+/// The compiler this crate was built with, and the features which change the layout of values passed to plugins, which a plugin must have been built with.
+#[cfg(feature = "send")]
+pub const FINGERPRINT: &CStr = c_string(concat!(env!("SLANG_RUSTC_VERSION"), " +send\0"));
+
+/// The compiler this crate was built with, and the features which change the layout of values passed to plugins, which a plugin must have been built with.
+#[cfg(not(feature = "send"))]
+pub const FINGERPRINT: &CStr = c_string(concat!(env!("SLANG_RUSTC_VERSION"), "\0"));
+
+const fn c_string(text: &str) -> &CStr {
+    match CStr::from_bytes_with_nul(text.as_bytes()) {
+        Ok(text) => text,
+        Err(_) => panic!("the text ends with its only nul"),
+    }
+}
+
+/// What a plugin exports to be loaded, which is returned by its `slang_plugin` function.
+#[repr(C)]
+pub struct Descriptor {
+    /// The [ABI] the plugin was built for.
+    pub abi: u32,
+    /// The [VERSION] of this crate the plugin was built against.
+    pub version: *const c_char,
+    /// The [FINGERPRINT] of the compiler and features the plugin was built with.
+    pub fingerprint: *const c_char,
+    /// Registers the plugin's native functions, which may only be called once the rest of the descriptor has been checked.
+    pub register: unsafe extern "C" fn(*mut Registry),
+}
+
+// The descriptor only points to strings which are never changed.
+unsafe impl Sync for Descriptor {}
+
+/// All errors which can occur while loading a plugin.
+pub enum PluginError {
+    /// When the shared library could not be loaded, or does not export the symbols of a plugin.
+    Load { path: PathBuf, error: String },
+    /// When the plugin exports a different version of the descriptor.
+    AbiMismatch { path: PathBuf, abi: u32 },
+    /// When the plugin was built against a different version of this crate.
+    VersionMismatch { path: PathBuf, version: String },
+    /// When the plugin was built with a different compiler, or different features of this crate.
+    FingerprintMismatch { path: PathBuf, fingerprint: String },
+}
+
+impl Display for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Load { path, error } => {
+                write!(
+                    f,
+                    "Unable to load the plugin `{}`: {}",
+                    path.display(),
+                    error
+                )
+            }
+            Self::AbiMismatch { path, abi } => write!(
+                f,
+                "The plugin `{}` exports version {} of the plugin interface, but this is version {}.",
+                path.display(),
+                abi,
+                ABI
+            ),
+            Self::VersionMismatch { path, version } => write!(
+                f,
+                "The plugin `{}` was built for version {} of slang, but this is version {}.",
+                path.display(),
+                version,
+                VERSION.to_string_lossy()
+            ),
+            Self::FingerprintMismatch { path, fingerprint } => write!(
+                f,
+                "The plugin `{}` was built with `{}`, but the interpreter was built with `{}`.",
+                path.display(),
+                fingerprint,
+                FINGERPRINT.to_string_lossy()
+            ),
+        }
+    }
+}
+
+impl Debug for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Error for PluginError {}
+
+/// The native functions registered by a plugin as it is loaded.
+#[derive(Default)]
+pub struct Registry {
+    functions: Vec<PluginFunction>,
+}
+
+impl Registry {
+    /// Registers a native function, which will be defined in the global scope under a name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        function: fn(&[Value]) -> Result<Option<Value>, String>,
+    ) {
+        self.functions.push(PluginFunction {
+            name: name.into(),
+            function,
+        });
+    }
+}
+
+/// Loads a plugin, returning the native functions it registers.
+///
+/// The plugin stays loaded until the process exits, as its functions may be held by any value.
+///
+/// # Safety
+///
+/// Loading a shared library runs its initialisation code, and its descriptor is trusted to be what it claims, so only trusted plugins may be loaded.
+pub unsafe fn load(path: &Path) -> Result<Vec<PluginFunction>, PluginError> {
+    let load_error = |error: libloading::Error| PluginError::Load {
+        path: path.to_path_buf(),
+        error: error.to_string(),
+    };
+
+    let library = unsafe { Library::new(path) }.map_err(load_error)?;
+
+    let descriptor =
+        unsafe { library.get::<extern "C" fn() -> *const Descriptor>(b"slang_plugin") }
+            .map_err(load_error)?();
+
+    // Only the version is read until it is known to be this version of the descriptor.
+    let abi = unsafe { (*descriptor).abi };
+    if abi != ABI {
+        return Err(PluginError::AbiMismatch {
+            path: path.to_path_buf(),
+            abi,
+        });
+    }
+
+    let descriptor = unsafe { &*descriptor };
+
+    let version = unsafe { CStr::from_ptr(descriptor.version) };
+    if version != VERSION {
+        return Err(PluginError::VersionMismatch {
+            path: path.to_path_buf(),
+            version: version.to_string_lossy().into_owned(),
+        });
+    }
+
+    let fingerprint = unsafe { CStr::from_ptr(descriptor.fingerprint) };
+    if fingerprint != FINGERPRINT {
+        return Err(PluginError::FingerprintMismatch {
+            path: path.to_path_buf(),
+            fingerprint: fingerprint.to_string_lossy().into_owned(),
+        });
+    }
+
+    let mut registry = Registry::default();
+    unsafe { (descriptor.register)(&mut registry) };
+
+    std::mem::forget(library);
+
+    Ok(registry.functions)
+}
+
+/// Exports the [Descriptor](crate::plugin::Descriptor) which makes a `cdylib` crate a plugin, given a function which registers its native functions with a [Registry](crate::plugin::Registry).
+#[macro_export]
+macro_rules! export_plugin {
+    ($register:path) => {
+        unsafe extern "C" fn slang_register(registry: *mut $crate::plugin::Registry) {
+            $register(unsafe { &mut *registry })
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn slang_plugin() -> *const $crate::plugin::Descriptor {
+            static DESCRIPTOR: $crate::plugin::Descriptor = $crate::plugin::Descriptor {
+                abi: $crate::plugin::ABI,
+                version: $crate::plugin::VERSION.as_ptr(),
+                fingerprint: $crate::plugin::FINGERPRINT.as_ptr(),
+                register: slang_register,
+            };
+
+            &DESCRIPTOR
+        }
+    };
+}
@@ -0,0 +1,137 @@
+//! Post-mortem dumps, which describe the state of a program when an evaluation error stopped it, given by `--dump-on-error`.
+//!
+//! Each function call records its frame as the error passes through it, before the frame is popped, so that the call stack is recorded from the innermost call outwards. The global scope is recorded once the error escapes the program, and the heap is summarised when the dump is rendered.
+
+use crate::{
+    heap::{ManagedHeap, Object, sites},
+    source::SourceMap,
+    value::Value,
+};
+
+/// The frames and globals recorded as an error escaped a program, which is only recorded once it is enabled.
+#[derive(Default)]
+pub struct PostMortem {
+    /// The frames of the function calls the error passed through, innermost first, followed by the top level of the program.
+    pub frames: Vec<Frame>,
+    /// The global variables, once the error has escaped the program.
+    pub globals: Vec<(String, Option<Value>)>,
+    /// The error which stopped the program, once it has escaped.
+    pub error: Option<String>,
+}
+
+/// The frame of a function call, or of the top level of the program.
+pub struct Frame {
+    /// The identifier of the function, or `<top level>`.
+    pub function: String,
+    /// The variables local to the frame, including those within blocks it had entered, in order of identifier.
+    pub bindings: Vec<(String, Option<Value>)>,
+}
+
+impl PostMortem {
+    /// Forgets what was recorded for an earlier error.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.globals.clear();
+        self.error = None;
+    }
+
+    /// Renders the dump, with the values of object references and the summary of the heap read from a heap, and allocation sites named by a source map, or returns [None] if no error has escaped the program.
+    pub fn render(&self, heap: &ManagedHeap, sources: &SourceMap) -> Option<String> {
+        let error = self.error.as_ref()?;
+
+        let mut output = format!(
+            "Post-mortem dump: {}\n\nCall stack (innermost first):\n",
+            error
+        );
+
+        for (index, frame) in self.frames.iter().enumerate() {
+            output.push_str(&format!("  {}: {}\n", index, frame.function));
+            bindings(&mut output, &frame.bindings, heap, "       ");
+        }
+
+        output.push_str("\nGlobals:\n");
+        bindings(&mut output, &self.globals, heap, "  ");
+
+        let stats = heap.stats();
+
+        output.push_str(&format!(
+            "\nHeap ({}): {} objects ({} bytes estimated), at most {} objects ({} bytes estimated), {} collections which freed {} objects\n{}\n",
+            heap.get_technique_code(),
+            stats.objects,
+            stats.bytes,
+            stats.peak_objects,
+            stats.peak_bytes,
+            stats.collections,
+            stats.objects_freed,
+            sites::report(heap, sources)
+        ));
+
+        Some(output)
+    }
+}
+
+/// Leaves out the bindings which are not part of the program's state, which are its functions.
+pub fn state(bindings: Vec<(String, Option<Value>)>) -> Vec<(String, Option<Value>)> {
+    bindings
+        .into_iter()
+        .filter(|(_, value)| !matches!(value, Some(Value::Function(_))))
+        .collect()
+}
+
+/// Writes each binding on a line of its own, indented.
+fn bindings(
+    output: &mut String,
+    bindings: &[(String, Option<Value>)],
+    heap: &ManagedHeap,
+    indent: &str,
+) {
+    if bindings.is_empty() {
+        output.push_str(&format!("{}(none)\n", indent));
+    }
+
+    for (identifier, value) in bindings {
+        let value = match value {
+            Some(value) => describe(value, heap, true),
+            None => String::from("(uninitialised)"),
+        };
+
+        output.push_str(&format!("{}{} = {}\n", indent, identifier, value));
+    }
+}
+
+/// Describes a value as it would be written in source code where possible, following a reference to an object to show its fields, and the fields of the objects within it if `nested`.
+fn describe(value: &Value, heap: &ManagedHeap, nested: bool) -> String {
+    let fields = |data: &Object| {
+        let mut fields = data
+            .iter()
+            .map(|(field, value)| {
+                let value = match value {
+                    Value::ObjectReference(_) | Value::Object(_) if !nested => {
+                        String::from("{ ... }")
+                    }
+                    value => describe(value, heap, false),
+                };
+
+                format!("{}: {}", field, value)
+            })
+            .collect::<Vec<_>>();
+
+        fields.sort();
+
+        if fields.is_empty() {
+            String::from("{}")
+        } else {
+            format!("{{ {} }}", fields.join(", "))
+        }
+    };
+
+    match value {
+        Value::String(value) => format!("{:?}", value.as_ref()),
+        Value::Object(data) => fields(data),
+        Value::ObjectReference(pointer) => match heap.get(*pointer) {
+            Ok(object) => fields(&object.data),
+            Err(error) => format!("<{}>", error),
+        },
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,7 @@
+//! The prelude: helper functions written in slang, which are defined in the global scope before any other code is run, so that the native functions can stay few.
+
+/// The source code of the prelude, which defines `min`, `max`, `abs`, `range` and `repeat`, along with `ok`, `err`, `is_ok`, `is_err`, `unwrap` and `unwrap_or` for results.
+pub const SOURCE: &str = include_str!("prelude.slang");
+
+/// The name which locations within the prelude give as their file.
+pub const NAME: &str = "<prelude>";
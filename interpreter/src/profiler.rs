@@ -0,0 +1,136 @@
+//! A profiler which records the time spent in, and the objects allocated by, each user-defined function.
+
+use std::{collections::HashMap, time::Duration};
+
+use crate::{clock::Instant, heap::Allocations};
+
+/// The totals recorded for a single function, across all of its calls.
+#[derive(Default)]
+struct FunctionProfile {
+    calls: usize,
+    /// Time spent in the function, including any functions it calls.
+    inclusive: Duration,
+    /// Time spent in the function itself.
+    exclusive: Duration,
+    /// Objects allocated by the function itself.
+    allocations: Allocations,
+}
+
+/// A call which has not yet returned.
+struct Call {
+    identifier: String,
+    start: Instant,
+    /// The heap's allocation totals when the call started.
+    allocations: Allocations,
+    /// Time spent in functions called by this call.
+    children_time: Duration,
+    /// Objects allocated by functions called by this call.
+    children_allocations: Allocations,
+}
+
+/// Records call counts, timings and allocations for each user-defined function, keyed by the name it was defined with.
+pub struct Profiler {
+    functions: HashMap<String, FunctionProfile>,
+    calls: Vec<Call>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            functions: HashMap::new(),
+            calls: Vec::new(),
+        }
+    }
+
+    /// Records the start of a call, given the heap's current allocation totals.
+    pub fn enter(&mut self, identifier: &str, allocations: Allocations) {
+        self.calls.push(Call {
+            identifier: identifier.to_string(),
+            start: Instant::now(),
+            allocations,
+            children_time: Duration::ZERO,
+            children_allocations: Allocations::default(),
+        });
+    }
+
+    /// Records the end of the most recent call, given the heap's current allocation totals.
+    pub fn exit(&mut self, allocations: Allocations) {
+        let Some(call) = self.calls.pop() else {
+            return;
+        };
+
+        let elapsed = call.start.elapsed();
+        let allocated = allocations.since(call.allocations);
+
+        // Time spent in a recursive call is already included in the outermost call to the same function.
+        let outermost = !self
+            .calls
+            .iter()
+            .any(|outer| outer.identifier == call.identifier);
+
+        let profile = self.functions.entry(call.identifier).or_default();
+
+        profile.calls += 1;
+        profile.exclusive += elapsed.saturating_sub(call.children_time);
+        profile.allocations.count += allocated.count - call.children_allocations.count;
+        profile.allocations.bytes += allocated.bytes - call.children_allocations.bytes;
+
+        if outermost {
+            profile.inclusive += elapsed;
+        }
+
+        if let Some(caller) = self.calls.last_mut() {
+            caller.children_time += elapsed;
+            caller.children_allocations.count += allocated.count;
+            caller.children_allocations.bytes += allocated.bytes;
+        }
+    }
+
+    /// Renders a table of every function which was called, sorted by the time spent in the function itself.
+    pub fn report(&self) -> String {
+        let mut functions: Vec<(&String, &FunctionProfile)> = self.functions.iter().collect();
+
+        functions.sort_by(|(a_identifier, a), (b_identifier, b)| {
+            b.exclusive
+                .cmp(&a.exclusive)
+                .then_with(|| a_identifier.cmp(b_identifier))
+        });
+
+        let width = functions
+            .iter()
+            .map(|(identifier, _)| identifier.len())
+            .max()
+            .unwrap_or(0)
+            .max("function".len());
+
+        let mut report = format!(
+            "{:<width$}  {:>8}  {:>14}  {:>14}  {:>11}  {:>15}",
+            "function",
+            "calls",
+            "inclusive (ms)",
+            "exclusive (ms)",
+            "allocations",
+            "bytes allocated",
+        );
+
+        for (identifier, profile) in functions {
+            report.push_str(&format!(
+                "\n{:<width$}  {:>8}  {:>14.3}  {:>14.3}  {:>11}  {:>15}",
+                identifier,
+                profile.calls,
+                profile.inclusive.as_secs_f64() * 1000.0,
+                profile.exclusive.as_secs_f64() * 1000.0,
+                profile.allocations.count,
+                profile.allocations.bytes,
+            ));
+        }
+
+        report
+    }
+}
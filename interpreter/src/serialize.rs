@@ -0,0 +1,161 @@
+//! Serde support for slang values, enabled by the `serde` feature.
+//!
+//! Values are serialized as the closest serde data type, with objects as maps ordered by field name. Functions cannot be serialized, and neither can references to objects on the heap, unless the value is wrapped in [Resolved] to follow them.
+//!
+//! Deserializing produces objects which have not yet been allocated on the heap. Integers become Integers if they fit within 32 bits and fail otherwise, and sequences, bytes and null values fail, as slang has no equivalent.
+
+use std::fmt;
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, MapAccess, Visitor},
+    ser::{self, SerializeMap},
+};
+
+use crate::{
+    heap::{ManagedHeap, Object, Pointer},
+    shared::Shared,
+    value::Value,
+};
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self, None, &[], serializer)
+    }
+}
+
+/// A value whose references to objects on the heap are followed when it is serialized.
+pub struct Resolved<'a> {
+    value: &'a Value,
+    heap: &'a ManagedHeap,
+}
+
+impl<'a> Resolved<'a> {
+    pub fn new(value: &'a Value, heap: &'a ManagedHeap) -> Self {
+        Self { value, heap }
+    }
+}
+
+impl Serialize for Resolved<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self.value, Some(self.heap), &[], serializer)
+    }
+}
+
+/// A field of an object being serialized, along with the references which were followed to reach it.
+struct Field<'a> {
+    value: &'a Value,
+    heap: Option<&'a ManagedHeap>,
+    path: &'a [Pointer],
+}
+
+impl Serialize for Field<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self.value, self.heap, self.path, serializer)
+    }
+}
+
+/// Serializes a value, following references into the heap if there is one. `path` holds the references which have been followed to reach the value, so that cycles are detected.
+fn serialize<S: Serializer>(
+    value: &Value,
+    heap: Option<&ManagedHeap>,
+    path: &[Pointer],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Value::String(value) => serializer.serialize_str(value),
+        Value::Float(value) => serializer.serialize_f64(*value),
+        Value::Integer(value) => serializer.serialize_i32(*value),
+        Value::Boolean(value) => serializer.serialize_bool(*value),
+        Value::Function(_) => Err(ser::Error::custom("functions cannot be serialized")),
+        Value::Object(object) => serialize_object(object, heap, path, serializer),
+        Value::ObjectReference(pointer) => {
+            let Some(heap) = heap else {
+                return Err(ser::Error::custom(
+                    "object references cannot be serialized without the heap",
+                ));
+            };
+
+            if path.contains(pointer) {
+                return Err(ser::Error::custom(
+                    "objects which reference each other in a cycle cannot be serialized",
+                ));
+            }
+
+            let object = heap.get(*pointer).map_err(ser::Error::custom)?;
+
+            let mut path = path.to_vec();
+            path.push(*pointer);
+
+            serialize_object(&object.data, Some(heap), &path, serializer)
+        }
+    }
+}
+
+/// Serializes the fields of an object as a map, in order of field name.
+fn serialize_object<S: Serializer>(
+    object: &Object,
+    heap: Option<&ManagedHeap>,
+    path: &[Pointer],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut fields = object.iter().collect::<Vec<_>>();
+    fields.sort_by_key(|(name, _)| *name);
+
+    let mut map = serializer.serialize_map(Some(fields.len()))?;
+
+    for (name, value) in fields {
+        map.serialize_entry(&**name, &Field { value, heap, path })?;
+    }
+
+    map.end()
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+/// Builds a value from any serde data type which slang has an equivalent for.
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a string, number, boolean or map")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Boolean(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Value, E> {
+        Value::try_from(value).map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Value, E> {
+        i64::try_from(value)
+            .map_err(|_| E::custom(format!("{} is out of range for an Integer", value)))
+            .and_then(|value| self.visit_i64(value))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Value, E> {
+        Ok(Value::Float(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.into()))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut object = Object::new();
+
+        while let Some((name, value)) = map.next_entry::<String, Value>()? {
+            object.insert(Shared::from(name), value);
+        }
+
+        Ok(Value::Object(object))
+    }
+}
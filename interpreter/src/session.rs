@@ -0,0 +1,617 @@
+//! Snapshots of an interpreter's session, which can be saved to a file and restored later.
+//!
+//! A snapshot is a slang program which defines the globals and allocates every object reachable from them, with any values which cannot be written in source code recorded in comments at its top (see [parse]). Plugins, and the prelude, must be loaded before a session which uses them is restored.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Debug, Display},
+    io,
+};
+
+use crate::{
+    environment::NATIVE_FUNCTIONS,
+    expression::{BinaryOperator, Expression},
+    formatter::Formatter,
+    heap::{HeapError, ManagedHeap, Object, Pointer},
+    interpreter::{InterpreterError, parse_source},
+    source::{FileId, Location, Source},
+    stack::Stack,
+    statement::Statement,
+    value::{Function, NativeFunction, Value},
+    visit::{VisitorMut, walk_expression_mut},
+};
+
+/// All errors which can occur while saving or restoring a session.
+pub enum SessionError {
+    /// When the snapshot file could not be read or written.
+    Io(io::Error),
+    /// When a function is still being called, so there are variables outside of the global scope.
+    NotAtTopLevel,
+    /// When a comment recording a value which cannot be written in source code is not valid.
+    InvalidLiteral { comment: String },
+    /// When an object reachable from the global scope has been freed.
+    Heap(HeapError),
+    /// When the snapshot could not be run.
+    Restore(InterpreterError),
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{}", error),
+            Self::NotAtTopLevel => write!(
+                f,
+                "A session can only be saved between statements at the top level."
+            ),
+            Self::InvalidLiteral { comment } => write!(
+                f,
+                "The saved value `{}` is not valid, so the session cannot be restored.",
+                comment
+            ),
+            Self::Heap(error) => write!(f, "{}", error),
+            Self::Restore(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Debug for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Error for SessionError {}
+
+impl From<io::Error> for SessionError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<HeapError> for SessionError {
+    fn from(error: HeapError) -> Self {
+        Self::Heap(error)
+    }
+}
+
+impl From<InterpreterError> for SessionError {
+    fn from(error: InterpreterError) -> Self {
+        Self::Restore(error)
+    }
+}
+
+/// The prefix of the variables which hold each object while a session is being restored.
+const OBJECT_PREFIX: &str = "__object_";
+
+/// The start of each comment at the top of a snapshot which records a value which cannot be written in source code, followed by the variable the snapshot refers to it by, its type, and the value.
+const LITERAL_COMMENT: &str = "// literal ";
+
+/// The start of the variables which refer to values which cannot be written in source code, until they are named. A space cannot be part of an identifier in source code.
+const LITERAL_PLACEHOLDER: &str = "literal ";
+
+/// The objects a snapshot is made of, each referred to by a pointer.
+pub trait Objects {
+    /// Returns the fields of the object a pointer refers to, along with its finalizer, if any, or an error if there is no such object.
+    fn object(&self, pointer: Pointer) -> Result<(&Object, Option<&Function>), HeapError>;
+}
+
+impl Objects for ManagedHeap {
+    fn object(&self, pointer: Pointer) -> Result<(&Object, Option<&Function>), HeapError> {
+        self.get(pointer)
+            .map(|object| (&object.data, object.finalizer.as_ref()))
+    }
+}
+
+/// Writes the session of an interpreter with a stack and heap as a program, which restores it when run, leaving out the globals defined by the prelude.
+pub fn snapshot(
+    stack: &Stack,
+    heap: &ManagedHeap,
+    prelude: &[(String, Option<Value>)],
+) -> Result<String, SessionError> {
+    if stack.frames_count() > 1 {
+        return Err(SessionError::NotAtTopLevel);
+    }
+
+    let globals = stack.globals();
+    let mut snapshot = Snapshot::new(heap, &globals, false);
+
+    let mut statements = vec![Statement::Comment {
+        text: String::from("// A saved slang session, which is restored by running it."),
+        trailing: false,
+    }];
+
+    statements.append(&mut snapshot.declarations(&globals, prelude)?);
+
+    let mut restoration = snapshot.allocations;
+    restoration.append(&mut snapshot.links);
+
+    if !restoration.is_empty() {
+        statements.push(Statement::BlankLine);
+        statements.push(Statement::Block(restoration));
+    }
+
+    if !snapshot.literals.is_empty() {
+        name_literals(&mut statements, &snapshot.literals);
+    }
+
+    Ok(Formatter::new().format(&statements))
+}
+
+/// Names the variables which refer to values which cannot be written in source code, with a prefix which appears nowhere else in the snapshot, and records each value in a comment after the first.
+fn name_literals(statements: &mut Vec<Statement>, literals: &[Value]) {
+    let source = Formatter::new().format(statements);
+    let mut prefix = String::from("__literal_");
+
+    while source.contains(&prefix) {
+        prefix.push('_');
+    }
+
+    let mut names = Names(&prefix);
+
+    for statement in statements.iter_mut() {
+        names.visit_statement(statement);
+    }
+
+    let comments = literals.iter().enumerate().map(|(index, value)| {
+        let value = match value {
+            Value::String(string) => format!(
+                "string {}",
+                string
+                    .bytes()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect::<String>()
+            ),
+            Value::Float(float) => format!("float {}", float),
+            _ => unreachable!("only strings and floats cannot be written in source code"),
+        };
+
+        Statement::Comment {
+            text: format!("{}{}{} {}", LITERAL_COMMENT, prefix, index, value),
+            trailing: false,
+        }
+    });
+
+    statements.splice(1..1, comments);
+}
+
+/// Names each variable which refers to a value which cannot be written in source code, with a prefix.
+struct Names<'a>(&'a str);
+
+impl VisitorMut for Names<'_> {
+    fn visit_expression(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::Variable { identifier, .. } => {
+                if let Some(index) = identifier.strip_prefix(LITERAL_PLACEHOLDER) {
+                    *identifier = format!("{}{}", self.0, index);
+                }
+            }
+            _ => walk_expression_mut(self, expression),
+        }
+    }
+}
+
+/// Parses a snapshot written by [snapshot], replacing each variable which refers to a value which cannot be written in source code with the value recorded at the top of the snapshot.
+pub fn parse(source: &str, file: FileId) -> Result<Vec<Statement>, SessionError> {
+    let mut literals = HashMap::new();
+
+    for comment in source.lines().take_while(|line| line.starts_with("//")) {
+        let Some(literal) = comment.strip_prefix(LITERAL_COMMENT) else {
+            continue;
+        };
+
+        let invalid = || SessionError::InvalidLiteral {
+            comment: comment.to_string(),
+        };
+
+        let value = match literal.splitn(3, ' ').collect::<Vec<_>>()[..] {
+            [identifier, "string", hexadecimal] if hexadecimal.len() % 2 == 0 => {
+                let bytes = (0..hexadecimal.len())
+                    .step_by(2)
+                    .map(|index| u8::from_str_radix(hexadecimal.get(index..index + 2)?, 16).ok())
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(invalid)?;
+
+                let string = String::from_utf8(bytes).map_err(|_| invalid())?;
+                (identifier, Value::from(string.as_str()))
+            }
+            [identifier, "float", float] => (
+                identifier,
+                Value::Float(float.parse().map_err(|_| invalid())?),
+            ),
+            _ => return Err(invalid()),
+        };
+
+        literals.insert(value.0.to_string(), value.1);
+    }
+
+    let mut statements = parse_source(Source::new(source).with_file(file))?;
+
+    if !literals.is_empty() {
+        let mut substitution = Substitution(literals);
+
+        for statement in &mut statements {
+            substitution.visit_statement(statement);
+        }
+    }
+
+    Ok(statements)
+}
+
+/// Replaces each variable which refers to a value recorded at the top of a snapshot with the value.
+struct Substitution(HashMap<String, Value>);
+
+impl VisitorMut for Substitution {
+    fn visit_expression(&mut self, expression: &mut Expression) {
+        let value = match expression {
+            Expression::Variable { identifier, .. } => self.0.get(identifier),
+            _ => None,
+        };
+
+        match value {
+            Some(value) => {
+                *expression = Expression::Literal {
+                    value: value.clone(),
+                }
+            }
+            None => walk_expression_mut(self, expression),
+        }
+    }
+}
+
+/// Returns the statements which declare some global variables and functions, along with an expression which allocates every object reachable from them or from some values, assigns the globals, and evaluates to the result of a function of expressions for the values.
+///
+/// Unlike a [snapshot], nothing is written as source code: every value is a literal, so that values which cannot be written, and native functions from plugins, are restored exactly. The variables which hold each object cannot be written either, so they never replace a variable of the program.
+pub fn restoration(
+    globals: &[(String, Option<Value>)],
+    objects: &dyn Objects,
+    values: &[Value],
+    result: impl FnOnce(Vec<Expression>) -> Expression,
+) -> Result<(Vec<Statement>, Expression), SessionError> {
+    let mut snapshot = Snapshot::new(objects, globals, true);
+    let declarations = snapshot.declarations(globals, &[])?;
+
+    let values = values
+        .iter()
+        .map(|value| match snapshot.value(value)? {
+            Restored::Immediate(expression) | Restored::Object(expression) => Ok(expression),
+            Restored::Function { .. } => unreachable!("functions are restored as literals"),
+        })
+        .collect::<Result<Vec<_>, SessionError>>()?;
+
+    let mut statements = snapshot.allocations;
+    statements.append(&mut snapshot.links);
+
+    if statements.is_empty() {
+        return Ok((declarations, result(values)));
+    }
+
+    // The objects are allocated within a block expression, which yields the result.
+    statements.push(Statement::Yield(result(values)));
+
+    Ok((
+        declarations,
+        Expression::Block {
+            block: Box::new(Statement::Block(statements)),
+        },
+    ))
+}
+
+/// How a value is restored.
+enum Restored {
+    /// By an expression which can be evaluated at any point.
+    Immediate(Expression),
+    /// By the variable holding an object, which can only be evaluated once every object has been allocated.
+    Object(Expression),
+    /// By a function which must be defined before it is referred to.
+    Function {
+        definition: Statement,
+        identifier: String,
+    },
+}
+
+/// The statements which restore the objects of a session, as they are built.
+struct Snapshot<'a> {
+    heap: &'a dyn Objects,
+    /// Whether every value is restored by a literal, rather than by source code.
+    evaluated: bool,
+    /// The user-defined functions which are defined under their own name in the global scope.
+    functions: HashMap<String, Function>,
+    /// The variable holding each object on the heap which has been allocated so far.
+    objects: HashMap<Pointer, String>,
+    /// The number of objects which have been allocated so far.
+    count: usize,
+    /// The statements which allocate each object, with every field which does not reference another object or function.
+    allocations: Vec<Statement>,
+    /// The statements which set the remaining fields, assign objects to globals, and register finalizers.
+    links: Vec<Statement>,
+    /// The values which cannot be written in source code, which are recorded alongside the snapshot.
+    literals: Vec<Value>,
+}
+
+impl<'a> Snapshot<'a> {
+    fn new(heap: &'a dyn Objects, globals: &[(String, Option<Value>)], evaluated: bool) -> Self {
+        Self {
+            heap,
+            evaluated,
+            functions: globals
+                .iter()
+                .filter_map(|(identifier, value)| match value {
+                    Some(Value::Function(function @ Function::UserDefined { .. })) => {
+                        Some((identifier.clone(), function.clone()))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            objects: HashMap::new(),
+            count: 0,
+            allocations: Vec::new(),
+            links: Vec::new(),
+            literals: Vec::new(),
+        }
+    }
+
+    /// Returns the statements which define some global variables and functions, leaving out those defined by the prelude, and adding the statements which restore the objects reachable from them.
+    fn declarations(
+        &mut self,
+        globals: &[(String, Option<Value>)],
+        prelude: &[(String, Option<Value>)],
+    ) -> Result<Vec<Statement>, SessionError> {
+        let mut statements = Vec::new();
+
+        for (identifier, value) in globals {
+            let declaration = |initialiser| Statement::VariableDeclaration {
+                identifier: identifier.clone(),
+                initialiser,
+                inline: false,
+                span: None,
+            };
+
+            // These are defined again when the prelude is loaded, anyway.
+            if prelude.contains(&(identifier.clone(), value.clone())) {
+                continue;
+            }
+
+            let Some(value) = value else {
+                statements.push(declaration(None));
+                continue;
+            };
+
+            match value {
+                // Native functions are already defined in a fresh session, and those from plugins are defined once the plugin is loaded, unless they are restored as literals.
+                Value::Function(Function::Native(function))
+                    if NATIVE_FUNCTIONS.contains(&(identifier.as_str(), function.clone())) =>
+                {
+                    continue;
+                }
+                Value::Function(Function::Native(NativeFunction::Plugin(plugin)))
+                    if *plugin.name == **identifier && !self.evaluated =>
+                {
+                    continue;
+                }
+                Value::Function(Function::UserDefined {
+                    identifier: name,
+                    parameters,
+                    block,
+                }) if name == identifier => {
+                    statements.push(Statement::FunctionDefinition {
+                        identifier: identifier.clone(),
+                        parameters: parameters.clone(),
+                        block: block.clone(),
+                        span: None,
+                    });
+                    continue;
+                }
+                _ => {}
+            }
+
+            match self.value(value)? {
+                Restored::Immediate(expression) => statements.push(declaration(Some(expression))),
+                restored => {
+                    statements.push(declaration(None));
+                    self.link(restored, |value| {
+                        Statement::Expression(Expression::Assignment {
+                            identifier: identifier.clone(),
+                            value: Box::new(value),
+                            span: None,
+                        })
+                    });
+                }
+            }
+        }
+
+        Ok(statements)
+    }
+
+    /// Returns how a value is restored, allocating any objects it references.
+    fn value(&mut self, value: &Value) -> Result<Restored, SessionError> {
+        let literal = |value: &Value| Expression::Literal {
+            value: value.clone(),
+        };
+
+        Ok(match value {
+            Value::ObjectReference(pointer) => {
+                let (data, _) = self.heap.object(*pointer)?;
+
+                Restored::Object(self.object(data, Some(*pointer))?)
+            }
+            Value::Object(data) => Restored::Object(self.object(data, None)?),
+            _ if self.evaluated => Restored::Immediate(literal(value)),
+            Value::String(string) if string.contains('"') => self.unwritable(value),
+            Value::Float(float) if !float.is_finite() => self.unwritable(value),
+            // The smallest integer's magnitude is too large to be written as a literal.
+            Value::Integer(i32::MIN) => Restored::Immediate(Expression::Grouping {
+                contained: Box::new(Expression::Binary {
+                    left: Box::new(literal(&Value::Integer(-i32::MAX))),
+                    operator: BinaryOperator::Subtract,
+                    right: Box::new(literal(&Value::Integer(1))),
+                    span: None,
+                }),
+            }),
+            Value::String(_) | Value::Float(_) | Value::Integer(_) | Value::Boolean(_) => {
+                Restored::Immediate(literal(value))
+            }
+            Value::Function(function) => self.function(function),
+        })
+    }
+
+    /// Returns the variable which refers to a value which cannot be written in source code, until it is named, recording the value.
+    fn unwritable(&mut self, value: &Value) -> Restored {
+        self.literals.push(value.clone());
+
+        Restored::Immediate(Expression::Variable {
+            identifier: format!("{}{}", LITERAL_PLACEHOLDER, self.literals.len() - 1),
+            span: None,
+        })
+    }
+
+    /// Returns how a function is restored: by the identifier it is defined as, or by defining it first.
+    fn function(&self, function: &Function) -> Restored {
+        let variable = |identifier: &str| Expression::Variable {
+            identifier: identifier.to_string(),
+            span: None,
+        };
+
+        match function {
+            _ if self.evaluated => Restored::Immediate(Expression::Literal {
+                value: Value::Function(function.clone()),
+            }),
+            Function::Native(NativeFunction::Plugin(plugin)) => {
+                Restored::Immediate(variable(&plugin.name))
+            }
+            Function::Native(native) => {
+                let (identifier, _) = NATIVE_FUNCTIONS
+                    .iter()
+                    .find(|(_, function)| function == native)
+                    .expect("every native function is defined");
+
+                Restored::Immediate(variable(identifier))
+            }
+            Function::UserDefined { identifier, .. }
+                if self.functions.get(identifier) == Some(function) =>
+            {
+                Restored::Immediate(variable(identifier))
+            }
+            Function::UserDefined {
+                identifier,
+                parameters,
+                block,
+            } => Restored::Function {
+                definition: Statement::FunctionDefinition {
+                    identifier: identifier.clone(),
+                    parameters: parameters.clone(),
+                    block: block.clone(),
+                    span: None,
+                },
+                identifier: identifier.clone(),
+            },
+        }
+    }
+
+    /// Allocates an object (or returns the variable holding it, if it has already been allocated), along with every object it references, and returns the variable holding it. Objects stored inline have no pointer, and are allocated on the heap when restored.
+    fn object(
+        &mut self,
+        data: &Object,
+        pointer: Option<Pointer>,
+    ) -> Result<Expression, SessionError> {
+        if let Some(identifier) = pointer.and_then(|pointer| self.objects.get(&pointer)) {
+            return Ok(Expression::Variable {
+                identifier: identifier.clone(),
+                span: None,
+            });
+        }
+
+        // A space cannot be part of an identifier in source code, so a restoration which is never written out cannot replace any variable.
+        let identifier = match self.evaluated {
+            true => format!("object {}", self.count),
+            false => format!("{}{}", OBJECT_PREFIX, self.count),
+        };
+        self.count += 1;
+
+        if let Some(pointer) = pointer {
+            self.objects.insert(pointer, identifier.clone());
+        }
+
+        let variable = Expression::Variable {
+            identifier: identifier.clone(),
+            span: None,
+        };
+
+        let mut data = data.iter().collect::<Vec<_>>();
+        data.sort_by_key(|(field, _)| *field);
+
+        let mut fields = Vec::new();
+
+        for (field, value) in data {
+            match self.value(value)? {
+                Restored::Immediate(expression) => fields.push((field.to_string(), expression)),
+                restored => self.link(restored, |value| {
+                    Statement::Expression(Expression::SetField {
+                        object: Box::new(variable.clone()),
+                        field: field.to_string(),
+                        value: Box::new(value),
+                        span: None,
+                    })
+                }),
+            }
+        }
+
+        let finalizer = match pointer {
+            Some(pointer) => self.heap.object(pointer)?.1.cloned(),
+            None => None,
+        };
+
+        if let Some(finalizer) = finalizer {
+            let restored = self.function(&finalizer);
+            let finalize = match self.evaluated {
+                true => Expression::Literal {
+                    value: Value::Function(Function::Native(NativeFunction::Finalize)),
+                },
+                false => Expression::Variable {
+                    identifier: String::from("finalize"),
+                    span: None,
+                },
+            };
+
+            self.link(restored, |function| {
+                Statement::Expression(Expression::Call {
+                    function: Box::new(finalize),
+                    arguments: vec![Box::new(variable.clone()), Box::new(function)],
+                    span: None,
+                })
+            });
+        }
+
+        self.allocations.push(Statement::VariableDeclaration {
+            identifier,
+            initialiser: Some(Expression::Object {
+                fields,
+                location: Location::start(),
+            }),
+            inline: false,
+            span: None,
+        });
+
+        Ok(variable)
+    }
+
+    /// Adds a statement which uses a restored value once every object has been allocated, defining the value first if it is a function.
+    fn link(&mut self, restored: Restored, statement: impl FnOnce(Expression) -> Statement) {
+        match restored {
+            Restored::Immediate(expression) | Restored::Object(expression) => {
+                self.links.push(statement(expression))
+            }
+            // Functions are defined within a block of their own, so that they do not replace any other definitions.
+            Restored::Function {
+                definition,
+                identifier,
+            } => self.links.push(Statement::Block(vec![
+                definition,
+                statement(Expression::Variable {
+                    identifier,
+                    span: None,
+                }),
+            ])),
+        }
+    }
+}
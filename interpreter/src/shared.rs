@@ -0,0 +1,70 @@
+//! The pointer types used for state which is shared within an interpreter, such as strings and environments.
+//!
+//! By default these are reference counted and only usable by one thread, which is cheapest. With the `send` feature they are atomically reference counted and locked instead, so that an [Interpreter](crate::Interpreter) is [Send], and can be moved to another thread, such as by a thread pool or an async runtime. An interpreter is never [Sync], as running a program always needs exclusive access to it.
+
+/// A pointer to a value which is shared, and freed once the last pointer to it is dropped.
+#[cfg(not(feature = "send"))]
+pub use std::rc::Rc as Shared;
+/// A pointer to a value which is shared, and freed once the last pointer to it is dropped.
+#[cfg(feature = "send")]
+pub use std::sync::Arc as Shared;
+
+pub use platform::*;
+
+#[cfg(not(feature = "send"))]
+mod platform {
+    use std::cell::{Ref, RefCell, RefMut};
+
+    /// A value which can be mutated through a [Shared](super::Shared) pointer, checking at runtime that it is not mutated while borrowed.
+    #[derive(Default)]
+    pub struct Mutable<T>(RefCell<T>);
+
+    impl<T> Mutable<T> {
+        pub fn new(value: T) -> Self {
+            Self(RefCell::new(value))
+        }
+
+        pub fn borrow(&self) -> Ref<'_, T> {
+            self.0.borrow()
+        }
+
+        pub fn borrow_mut(&self) -> RefMut<'_, T> {
+            self.0.borrow_mut()
+        }
+    }
+
+    /// Types which can be held by an interpreter: any type, unless the `send` feature is enabled.
+    pub trait MaybeSend {}
+
+    impl<T> MaybeSend for T {}
+}
+
+#[cfg(feature = "send")]
+mod platform {
+    use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+    /// A value which can be mutated through a [Shared](super::Shared) pointer, locking it while it is borrowed.
+    #[derive(Default)]
+    pub struct Mutable<T>(RwLock<T>);
+
+    impl<T> Mutable<T> {
+        pub fn new(value: T) -> Self {
+            Self(RwLock::new(value))
+        }
+
+        /// Panics if a thread panicked while mutating the value, as the value may then be inconsistent.
+        pub fn borrow(&self) -> RwLockReadGuard<'_, T> {
+            self.0.read().expect("the value was poisoned")
+        }
+
+        /// Panics if a thread panicked while mutating the value, as the value may then be inconsistent.
+        pub fn borrow_mut(&self) -> RwLockWriteGuard<'_, T> {
+            self.0.write().expect("the value was poisoned")
+        }
+    }
+
+    /// Types which can be held by an interpreter: those which are [Send], as the `send` feature is enabled.
+    pub trait MaybeSend: Send {}
+
+    impl<T: Send> MaybeSend for T {}
+}
@@ -1,16 +1,107 @@
-//! Code relating to the raw source code string.
+//! Code relating to the raw source code, and locations within it.
 
-use std::fmt::Display;
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    io::{self, BufReader, Bytes, Read},
+};
+
+use crate::prelude;
+
+/// The number of columns between tab stops, unless a source is given another width with [Source::with_tab_width].
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// The files which source code has been read from, indexed by [FileId], along with their source code, so that errors within them can be reported with the file's name and underlined.
+///
+/// The [prelude] is always the first file, whether or not it has been loaded.
+pub struct SourceMap {
+    files: Vec<(String, String)>,
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        Self {
+            files: vec![(prelude::NAME.to_string(), prelude::SOURCE.to_string())],
+        }
+    }
+}
+
+impl SourceMap {
+    /// Adds a file with a name (typically its path, as it was given, or a name in angle brackets such as `<repl>` for source code which was not read from a file) and its source code, returning its id.
+    pub fn add(&mut self, name: &str, text: &str) -> FileId {
+        self.files.push((name.to_string(), text.to_string()));
+
+        FileId(self.files.len() - 1)
+    }
+
+    /// Returns the name of a file, if it is in the map.
+    pub fn name(&self, file: FileId) -> Option<&str> {
+        self.files.get(file.0).map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the source code of a file, if it is in the map.
+    pub fn text(&self, file: FileId) -> Option<&str> {
+        self.files.get(file.0).map(|(_, text)| text.as_str())
+    }
+
+    /// Returns the position of a location as `file:line:column`, or as `line:column` if it is not in a file of the map.
+    pub fn position(&self, location: Location) -> String {
+        match location.file.and_then(|file| self.name(file)) {
+            Some(name) => format!("{}:{}:{}", name, location.line, location.column),
+            None => location.position(),
+        }
+    }
+
+    /// Returns a message which starts with the location of a span, with the file the span is in named in the location.
+    pub fn locate(&self, message: &str, span: Span) -> String {
+        let location = span.start();
+
+        match location.file.and_then(|file| self.name(file)) {
+            Some(_) => format!(
+                "[{}]{}",
+                self.position(location),
+                message
+                    .strip_prefix(&location.to_string())
+                    .unwrap_or(message)
+            ),
+            None => message.to_string(),
+        }
+    }
+
+    /// Returns a message which starts with the location of a span, as [SourceMap::locate] does, with the source code of the span underlined beneath it. A span which is not in a file of the map is underlined in the source code given instead.
+    pub fn render(&self, message: &str, span: Span, source: &str) -> String {
+        let text = span
+            .start()
+            .file
+            .and_then(|file| self.text(file))
+            .unwrap_or(source);
+
+        format!("{}\n{}", self.locate(message, span), span.underline(text))
+    }
+}
+
+/// Identifies a file in a [SourceMap], so that locations within it can name the file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(usize);
+
+impl FileId {
+    /// The id of the [prelude] in every source map.
+    pub const PRELUDE: Self = Self(0);
+}
 
 /// Represents the location of a character within a source code string.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Location {
+    /// The file which the source code was read from, or [None] if it was not read from a file, such as a line entered at the prompt.
+    file: Option<FileId>,
     /// The zero-indexed position of the character.
     index: usize,
     /// The line (`>= 1`) which the character appears on.
     line: usize,
-    /// The column (`>= 1`) which the character appears in.
+    /// The column (`>= 1`) which the character appears in, where a tab moves to the next tab stop and a carriage return takes up no columns.
     column: usize,
+    /// The zero-indexed position of the character within its line, counting every character as one.
+    character: usize,
 }
 
 impl Display for Location {
@@ -23,68 +114,206 @@ impl Location {
     /// Returns a location representing the first character in a source code string, with index `0`, line `1`, column `1`.
     pub fn start() -> Self {
         Self {
+            file: None,
             index: 0,
             line: 1,
             column: 1,
+            character: 0,
         }
     }
+
+    /// Returns the file which the source code was read from, if it was read from one.
+    pub fn file(&self) -> Option<FileId> {
+        self.file
+    }
+
+    /// Returns the zero-indexed position of the character.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the line (`>= 1`) which the character appears on.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column (`>= 1`) which the character appears in, where a tab moves to the next tab stop and a carriage return takes up no columns.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Returns the zero-indexed position of the character within its line, counting every character (including tabs and carriage returns) as one.
+    pub fn character(&self) -> usize {
+        self.character
+    }
+
+    /// Returns the position of the character as `line:column`. The file is named by [SourceMap::position].
+    pub fn position(&self) -> String {
+        format!("{}:{}", self.line, self.column)
+    }
 }
 
-/// Can represent either a specific location, or the end of a source code string.
-pub enum GeneralLocation {
-    /// A specific location in the source code.
-    Location(Location),
-    /// The end of the source code string.
-    EndOfFile,
+/// Represents a range of characters within a source code string, such as those making up a token.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    /// The location of the first character.
+    start: Location,
+    /// The location just after the last character.
+    end: Location,
 }
 
-impl Display for GeneralLocation {
+impl Display for Span {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            GeneralLocation::Location(location) => write!(f, "{}", location),
-            GeneralLocation::EndOfFile => write!(f, "[end of file]"),
-        }
+        write!(f, "{}", self.start)
     }
 }
 
-/// A wrapper around the source code string.
-pub struct Source {
-    /// The source code string.
-    text: Vec<char>,
+impl Span {
+    /// Creates a span from the location of its first character up to (but not including) another location.
+    pub fn new(start: Location, end: Location) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns the location of the first character.
+    pub fn start(&self) -> Location {
+        self.start
+    }
+
+    /// Returns the location just after the last character.
+    pub fn end(&self) -> Location {
+        self.end
+    }
+
+    /// Returns the number of characters in the span.
+    pub fn length(&self) -> usize {
+        self.end.index - self.start.index
+    }
+
+    /// Returns the line of source code which the span starts on, with the span underlined by carets beneath it. A span which continues onto later lines is underlined to the end of its first line, and an empty span (such as the end of the source code) is shown by a single caret.
+    pub fn underline(&self, source: &str) -> String {
+        let line = source.lines().nth(self.start.line - 1).unwrap_or_default();
+
+        // Tabs are kept in the indentation, so that the carets line up however wide tabs are displayed.
+        let indentation = line
+            .chars()
+            .chain(std::iter::repeat(' '))
+            .take(self.start.character)
+            .map(|character| if character == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        let width = if self.end.line == self.start.line {
+            self.length()
+        } else {
+            line.chars().count().saturating_sub(self.start.character)
+        };
+
+        format!(
+            "    {}\n    {}{}",
+            line,
+            indentation,
+            "^".repeat(width.max(1))
+        )
+    }
+}
+
+/// A wrapper around the source code, which is read one character at a time, so that it can be streamed from a reader rather than held in memory all at once.
+pub struct Source<'a> {
+    /// The characters which have not been read yet.
+    characters: Box<dyn Iterator<Item = io::Result<char>> + 'a>,
+    /// The characters which have been read to look ahead, but not consumed yet.
+    lookahead: VecDeque<char>,
+    /// The error which stopped the source code from being read, if there was one.
+    error: Option<io::Error>,
+    /// The characters consumed since the last mark.
+    text: String,
     /// The location of the next character.
     location: Location,
+    /// The number of columns between tab stops.
+    tab_width: usize,
 }
 
-impl Source {
+impl<'a> Source<'a> {
     /// Creates a new source from a string input.
-    pub fn new(text: &str) -> Self {
+    pub fn new(text: &'a str) -> Self {
+        Self::from_characters(text.chars().map(Ok))
+    }
+
+    /// Creates a new source which reads UTF-8 text from a reader as it is needed.
+    pub fn from_reader(reader: impl Read + 'a) -> Self {
+        Self::from_characters(Utf8Characters {
+            bytes: BufReader::new(reader).bytes(),
+        })
+    }
+
+    /// Names the file which the source code was read from, so that every location within it refers to the file.
+    pub fn with_file(mut self, file: FileId) -> Self {
+        self.location.file = Some(file);
+        self
+    }
+
+    /// Sets the number of columns between tab stops, which is [DEFAULT_TAB_WIDTH] unless it is given. A width of `0` is treated as `1`, so that a tab takes up a single column.
+    pub fn with_tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width.max(1);
+        self
+    }
+
+    fn from_characters(characters: impl Iterator<Item = io::Result<char>> + 'a) -> Self {
         Self {
-            text: text.chars().collect(),
+            characters: Box::new(characters),
+            lookahead: VecDeque::new(),
+            error: None,
+            text: String::new(),
             location: Location::start(),
+            tab_width: DEFAULT_TAB_WIDTH,
+        }
+    }
+
+    /// Reads characters until there are a number to look ahead at, or the source code has been read fully.
+    fn look_ahead(&mut self, count: usize) {
+        while self.lookahead.len() < count && self.error.is_none() {
+            match self.characters.next() {
+                Some(Ok(character)) => self.lookahead.push_back(character),
+                Some(Err(error)) => self.error = Some(error),
+                None => break,
+            }
         }
     }
 
     /// Returns the next character in the string, without advancing the position.
-    pub fn peek(&self) -> Option<char> {
-        self.text.get(self.location.index).copied()
+    pub fn peek(&mut self) -> Option<char> {
+        self.look_ahead(1);
+        self.lookahead.front().copied()
     }
 
     /// Returns the (next + 1)th character in the string, without advancing the position.
-    pub fn peek_after(&self) -> Option<char> {
-        self.text.get(self.location.index + 1).copied()
+    pub fn peek_after(&mut self) -> Option<char> {
+        self.look_ahead(2);
+        self.lookahead.get(1).copied()
     }
 
     /// Returns the next character in the string, and advances the position.
     pub fn advance(&mut self) -> Option<char> {
-        let next = self.peek();
+        self.look_ahead(1);
+        let next = self.lookahead.pop_front();
 
         if let Some(character) = next {
+            self.text.push(character);
             self.location.index += 1;
-            self.location.column += 1;
+            self.location.character += 1;
 
-            if character == '\n' {
-                self.location.line += 1;
-                self.location.column = 1;
+            match character {
+                '\n' => {
+                    self.location.line += 1;
+                    self.location.column = 1;
+                    self.location.character = 0;
+                }
+                // A carriage return takes up no columns, so that the columns of a line ending in `\r\n` are the same as one ending in `\n`.
+                '\r' => {}
+                '\t' => {
+                    self.location.column +=
+                        self.tab_width - (self.location.column - 1) % self.tab_width;
+                }
+                _ => self.location.column += 1,
             }
         }
 
@@ -106,13 +335,76 @@ impl Source {
         false
     }
 
-    /// Returns whether the source code string has been consumed fully.
-    pub fn at_end(&self) -> bool {
-        self.location.index >= self.text.len()
+    /// Returns whether the source code has been consumed fully, or could not be read any further.
+    pub fn at_end(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    /// Marks the position of the next character, from which [Source::text] starts.
+    pub fn mark(&mut self) {
+        self.text.clear();
+    }
+
+    /// Returns the text from the last mark up to (but not including) the next character.
+    pub fn text(&self) -> &str {
+        &self.text
     }
 
     /// Returns the location of the next character.
     pub fn location(&self) -> Location {
         self.location
     }
+
+    /// Returns the error which stopped the source code from being read, if there was one, which is only returned once.
+    pub fn error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+/// The characters of UTF-8 text, decoded from a stream of bytes.
+struct Utf8Characters<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+}
+
+impl<R: Read> Iterator for Utf8Characters<R> {
+    type Item = io::Result<char>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the source code is not valid UTF-8",
+            )
+        };
+
+        let first = match self.bytes.next()? {
+            Ok(byte) => byte,
+            Err(error) => return Some(Err(error)),
+        };
+
+        let width = match first {
+            0x00..=0x7F => 1,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => return Some(Err(invalid())),
+        };
+
+        let mut buffer = [first, 0, 0, 0];
+
+        for byte in &mut buffer[1..width] {
+            *byte = match self.bytes.next() {
+                Some(Ok(byte)) => byte,
+                Some(Err(error)) => return Some(Err(error)),
+                None => return Some(Err(invalid())),
+            };
+        }
+
+        Some(
+            std::str::from_utf8(&buffer[..width])
+                .ok()
+                .and_then(|text| text.chars().next())
+                .ok_or_else(invalid),
+        )
+    }
 }
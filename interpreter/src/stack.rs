@@ -1,28 +1,89 @@
-use std::{cell::RefCell, rc::Rc};
+//! The call stack, which holds the environment of each function call.
 
 use crate::{
+    capabilities::Capabilities,
+    channel::Channels,
+    console::Console,
     environment::{Environment, MutEnvironment},
+    expression::EvaluationError,
     heap::{ManagedHeap, Pointer},
+    interrupt::Interrupt,
+    post_mortem::{self, Frame, PostMortem},
+    shared::{Mutable, Shared},
+    source::SourceMap,
+    sync::Primitives,
+    thread::Thread,
+    value::Value,
+    watch::Watches,
 };
 
+/// The frames of the functions being executed, along with the rest of the state every statement is executed with.
 pub struct Stack {
     stack: Vec<MutEnvironment>,
+    /// The most frames which have been on the stack at once.
+    peak_frames_count: usize,
+    /// Objects which are still being evaluated, such as the arguments to a call, which are not yet reachable from any variable.
+    temporaries: Vec<Pointer>,
+    /// Where the program reads from and writes to.
+    pub console: Console,
+    /// Stops the program when it is interrupted.
+    pub interrupt: Interrupt,
+    /// The statements executed and expressions evaluated since the steps were last reset.
+    steps: usize,
+    /// The most steps which may be taken before the program is stopped, if there is a limit.
+    max_steps: Option<usize>,
+    /// The native functions which reach outside of the interpreter that the program may call.
+    pub capabilities: Capabilities,
+    /// The threads which have been spawned, indexed by their handles, until they are joined.
+    pub threads: Vec<Option<Thread>>,
+    /// The channels shared with the program which spawned this one, if any, and the threads either has spawned.
+    pub channels: Channels,
+    /// The mutexes and atomic integers shared in the same way as the channels.
+    pub primitives: Primitives,
+    /// The strings being built by builders, indexed by their handles, until they are built.
+    pub builders: Vec<Option<String>>,
+    /// The variables and fields whose assignments are logged.
+    pub watches: Watches,
+    /// The files of the source code which has been run, so that locations within them can be reported.
+    pub sources: SourceMap,
+    /// What is recorded as an error escapes the program, if dumps on error are enabled.
+    pub post_mortem: Option<PostMortem>,
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Stack {
     pub fn new() -> Self {
         Stack {
-            stack: vec![Rc::new(RefCell::new(Environment::new(None)))],
+            stack: vec![Shared::new(Mutable::new(Environment::new(None)))],
+            peak_frames_count: 1,
+            temporaries: Vec::new(),
+            console: Console::default(),
+            interrupt: Interrupt::default(),
+            steps: 0,
+            max_steps: None,
+            capabilities: Capabilities::default(),
+            threads: Vec::new(),
+            channels: Channels::default(),
+            primitives: Primitives::default(),
+            builders: Vec::new(),
+            watches: Watches::default(),
+            sources: SourceMap::default(),
+            post_mortem: None,
         }
     }
 
     pub fn top(&mut self) -> MutEnvironment {
         if let Some(top) = self.stack.last() {
-            Rc::clone(top)
+            Shared::clone(top)
         } else {
-            let top = Rc::new(RefCell::new(Environment::new(None)));
+            let top = Shared::new(Mutable::new(Environment::new(None)));
 
-            self.stack.push(Rc::clone(&top));
+            self.stack.push(Shared::clone(&top));
 
             top
         }
@@ -30,7 +91,7 @@ impl Stack {
 
     pub fn enter_scope(&mut self) {
         if let Some(top) = self.stack.last_mut() {
-            *top = Rc::new(RefCell::new(Environment::new(Some(Rc::clone(top)))))
+            *top = Shared::new(Mutable::new(Environment::new(Some(Shared::clone(top)))))
         }
     }
 
@@ -45,7 +106,7 @@ impl Stack {
             // When exiting a scope, ensure that any object references given to use by functions are decremented.
             if let ManagedHeap::ReferenceCounted(heap) = heap {
                 for pointer in top.borrow().returned_object_references() {
-                    heap.decrement(Pointer::clone(pointer));
+                    heap.decrement(*pointer);
                 }
             }
 
@@ -61,13 +122,19 @@ impl Stack {
 
     pub fn push(&mut self) -> MutEnvironment {
         let global = match self.stack.first() {
-            Some(first) => Some(first.borrow().global(Rc::clone(first))),
+            Some(first) => Some(first.borrow().global(Shared::clone(first))),
             None => None,
         };
 
-        let environment = Rc::new(RefCell::new(Environment::new(global)));
+        let depth = match self.stack.last() {
+            Some(top) => top.borrow().depth() + 1,
+            None => 0,
+        };
+
+        let environment = Shared::new(Mutable::new(Environment::with_depth(global, depth)));
 
-        self.stack.push(Rc::clone(&environment));
+        self.stack.push(Shared::clone(&environment));
+        self.peak_frames_count = self.peak_frames_count.max(self.stack.len());
 
         environment
     }
@@ -76,8 +143,70 @@ impl Stack {
         self.stack.pop();
     }
 
+    /// Records the innermost frame, which is the call to a function, in the post-mortem dump as an error passes through it, if dumps on error are enabled.
+    pub fn record_frame(&mut self, function: &str) {
+        if self.post_mortem.is_none() {
+            return;
+        }
+
+        let bindings = post_mortem::state(self.top().borrow().locals());
+
+        if let Some(post_mortem) = &mut self.post_mortem {
+            post_mortem.frames.push(Frame {
+                function: function.to_string(),
+                bindings,
+            });
+        }
+    }
+
+    /// Counts a statement executed or an expression evaluated, failing if that exceeds the most steps which may be taken.
+    pub fn step(&mut self) -> Result<(), EvaluationError> {
+        self.steps += 1;
+
+        match self.max_steps {
+            Some(max_steps) if self.steps > max_steps => {
+                Err(EvaluationError::ResourceLimitExceeded { steps: max_steps })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the most steps which may be taken before the program is stopped, or removes the limit.
+    pub fn set_max_steps(&mut self, max_steps: Option<usize>) {
+        self.max_steps = max_steps;
+    }
+
+    /// Returns the steps taken since they were last reset.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Resets the steps taken, so that the program may take the most steps again.
+    pub fn reset_steps(&mut self) {
+        self.steps = 0;
+    }
+
+    /// Keeps the objects within a value which is still being evaluated reachable, until the temporaries are released.
+    pub fn hold_temporary(&mut self, value: &Value) {
+        match value {
+            Value::ObjectReference(pointer) => self.temporaries.push(*pointer),
+            Value::Object(data) => data.values().for_each(|value| self.hold_temporary(value)),
+            _ => {}
+        }
+    }
+
+    /// Returns the number of temporaries being held, so that those held afterwards can be released.
+    pub fn temporaries_count(&self) -> usize {
+        self.temporaries.len()
+    }
+
+    /// Releases every temporary held since there were `count` of them.
+    pub fn release_temporaries(&mut self, count: usize) {
+        self.temporaries.truncate(count);
+    }
+
     pub fn roots(&self) -> Vec<Pointer> {
-        let mut roots = Vec::new();
+        let mut roots = self.temporaries.clone();
 
         for environment in &self.stack {
             roots.append(&mut environment.borrow().roots());
@@ -86,7 +215,32 @@ impl Stack {
         roots
     }
 
+    /// Returns every target in the global scope, in order of identifier, with its value if it has been initialised.
+    pub fn globals(&self) -> Vec<(String, Option<Value>)> {
+        match self.stack.first() {
+            Some(first) => first
+                .borrow()
+                .global(Shared::clone(first))
+                .borrow()
+                .bindings(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Defines a target in the global scope, replacing any already defined with the same identifier.
+    pub fn define_global(&mut self, identifier: String, value: Option<Value>) {
+        if let Some(first) = self.stack.first() {
+            let global = first.borrow().global(Shared::clone(first));
+            global.borrow_mut().define(identifier, value);
+        }
+    }
+
     pub fn frames_count(&self) -> usize {
         self.stack.len()
     }
+
+    /// Returns the most frames which have been on the stack at once.
+    pub fn peak_frames_count(&self) -> usize {
+        self.peak_frames_count
+    }
 }
@@ -2,9 +2,10 @@
 
 use crate::{
     expression::{EvaluationError, Expression},
-    heap::{ManagedHeap, Pointer},
+    heap::{ManagedHeap, Object},
+    source::Span,
     stack::Stack,
-    stats::Logger,
+    stats, trace,
     value::{Function, Value},
 };
 
@@ -14,84 +15,348 @@ pub enum ControlFlow {
     Continue,
     /// Signals that execution of the block should terminate, with an optional value returned.
     Break(Option<Value>),
+    /// Signals that the innermost loop should be exited, by a break statement.
+    ExitLoop,
 }
 
 /// Represents a statement.
+///
+/// Like an [Expression], each statement which can fail (or which the linter reports, as a function definition or a loop) has a `span`, the source code it was parsed from, or [None] if it was built by the interpreter.
 #[derive(Clone, PartialEq)]
 pub enum Statement {
     /// A variable declaration.
     VariableDeclaration {
         identifier: String,
         initialiser: Option<Expression>,
+        /// Whether the object literal it is initialised with never escapes its scope, so it is stored inline in the environment rather than on the heap. Set by [crate::escape::analyse].
+        inline: bool,
+        span: Option<Span>,
     },
     /// An if-statement.
     IfStatement {
         condition: Expression,
         execute_if_true: Box<Statement>,
         execute_if_false: Option<Box<Statement>>,
+        span: Option<Span>,
     },
     /// A function definition.
     FunctionDefinition {
         identifier: String,
         parameters: Vec<String>,
         block: Box<Statement>,
+        span: Option<Span>,
     },
     /// A return statement.
     Return(Option<Expression>),
     WhileLoop {
         condition: Expression,
         block: Box<Statement>,
+        span: Option<Span>,
     },
+    /// A loop which repeats its block until a break statement (or a return statement) leaves it.
+    Loop {
+        block: Box<Statement>,
+        span: Option<Span>,
+    },
+    /// A break statement, which exits the innermost loop.
+    Break,
     /// A block.
     Block(Vec<Statement>),
     /// An expression statement.
     Expression(Expression),
+    /// An expression with no `;` after it, ending a block which yields a value (a block expression, or a branch of an if-expression), so the block yields its value.
+    Yield(Expression),
+    /// A test, which is skipped when the program is run, and run on its own by `slang test`.
+    Test { name: String, block: Box<Statement> },
+    /// A comment. Only produced when parsing trivia for tools such as the formatter.
+    Comment { text: String, trailing: bool },
+    /// One or more blank lines. Only produced when parsing trivia for tools such as the formatter.
+    BlankLine,
+}
+
+/// Prepares an object which does not escape its scope to be stored inline in the environment, rather than being allocated on the heap.
+///
+/// Any objects nested within it are allocated, so that only its own fields are stored inline, and (under reference counting) the objects it references are counted as referenced by it.
+fn store_inline(
+    data: Object,
+    stack: &mut Stack,
+    heap: &mut ManagedHeap,
+) -> Result<Value, EvaluationError> {
+    let temporaries = stack.temporaries_count();
+    stack.hold_temporary(&Value::Object(data.clone()));
+
+    let fields = inline_fields(data, stack, heap);
+    stack.release_temporaries(temporaries);
+
+    heap.elide();
+
+    Ok(Value::Object(fields?))
+}
+
+/// Allocates the objects nested within an object which will be stored inline, holding each as a temporary until they are all allocated.
+fn inline_fields(
+    data: Object,
+    stack: &mut Stack,
+    heap: &mut ManagedHeap,
+) -> Result<Object, EvaluationError> {
+    let mut fields = Object::new();
+
+    for (key, value) in data {
+        let value = match value {
+            Value::Object(nested) => {
+                let value = Value::ObjectReference(heap.allocate(nested, || stack.roots())?);
+                stack.hold_temporary(&value);
+
+                value
+            }
+            Value::ObjectReference(pointer) => {
+                if let ManagedHeap::ReferenceCounted(heap) = heap {
+                    heap.increment(pointer);
+                }
+
+                value
+            }
+            value => value,
+        };
+
+        fields.insert(key, value);
+    }
+
+    Ok(fields)
+}
+
+/// Executes a block which yields a value (a block expression, or a branch of an if-expression), returning the value of its final statement, if it is an expression with no `;` after it.
+///
+/// The block has exactly the scope of a [Statement::Block], so that variables declared within it are dropped (and objects reclaimed) as it is left.
+pub fn evaluate_block(
+    statement: Statement,
+    stack: &mut Stack,
+    heap: &mut ManagedHeap,
+) -> Result<Option<Value>, EvaluationError> {
+    let value = match yielding(statement).execute(stack, heap)? {
+        ControlFlow::Break(value) => value,
+        // The parser does not allow a break statement to leave a block which yields a value.
+        ControlFlow::Continue | ControlFlow::ExitLoop => None,
+    };
+
+    // Like a value returned from a function, the yielded reference is counted until the enclosing scope is exited.
+    if let Some(Value::ObjectReference(pointer)) = &value {
+        stack.add_returned_object_reference(*pointer);
+    }
+
+    Ok(value)
+}
+
+/// Turns the expression a block (or each branch of an if-statement) yields into a return statement, so that the block breaks out with its value, which is then kept alive as the block's scope is exited.
+///
+/// The parser does not allow `return` statements within a block which yields a value, so a break can only come from the value being yielded.
+fn yielding(statement: Statement) -> Statement {
+    match statement {
+        Statement::Yield(expression) => Statement::Return(Some(expression)),
+        Statement::Block(mut statements) => {
+            // Any comments after the final statement are skipped over.
+            if let Some(last) = statements.iter_mut().rev().find(|statement| {
+                !matches!(statement, Statement::Comment { .. } | Statement::BlankLine)
+            }) {
+                *last = yielding(std::mem::replace(last, Statement::BlankLine));
+            }
+
+            Statement::Block(statements)
+        }
+        Statement::IfStatement {
+            condition,
+            execute_if_true,
+            execute_if_false,
+            span,
+        } => Statement::IfStatement {
+            condition,
+            execute_if_true: Box::new(yielding(*execute_if_true)),
+            execute_if_false: execute_if_false.map(|statement| Box::new(yielding(*statement))),
+            span,
+        },
+        statement => statement,
+    }
+}
+
+/// Executes the statements of a block, within its scope, defining its functions before anything else so that they can be called before they are declared.
+fn execute_block(
+    statements: Vec<Statement>,
+    stack: &mut Stack,
+    heap: &mut ManagedHeap,
+) -> Result<ControlFlow, EvaluationError> {
+    let mut non_definitions = Vec::new();
+
+    for statement in statements {
+        match statement {
+            Statement::FunctionDefinition { .. } => {
+                statement.execute(stack, heap)?;
+            }
+            _ => non_definitions.push(statement),
+        }
+    }
+
+    for statement in non_definitions {
+        match statement.execute(stack, heap)? {
+            ControlFlow::Continue => continue,
+            control_flow => return Ok(control_flow),
+        }
+    }
+
+    Ok(ControlFlow::Continue)
+}
+
+/// Exits the scope of a block, and its region under the region-based heap, whether it finished or failed, after promoting any value escaping it to the enclosing scope's region.
+fn exit_block(stack: &mut Stack, heap: &mut ManagedHeap, escaping: Option<&Value>) {
+    if let ManagedHeap::ReferenceCounted(heap) = heap {
+        for value in stack.top().borrow().values() {
+            heap.conditionally_decrement(value);
+        }
+    }
+
+    if let ManagedHeap::Region(heap) = heap {
+        heap.exit(stack.top().borrow().depth(), escaping);
+    }
+
+    stack.exit_scope(heap);
+}
+
+/// Runs the finalizers of any objects which have been reclaimed, in the order they were reclaimed.
+///
+/// Each finalizer is called with a copy of its object's fields, excluding references to other objects, or with no arguments if it is a function without parameters. Finalizers are never nested: the finalizers of any objects reclaimed while one is running are queued, and run once it returns.
+pub fn run_finalizers(stack: &mut Stack, heap: &mut ManagedHeap) -> Result<(), EvaluationError> {
+    if heap.finalizing() {
+        return Ok(());
+    }
+
+    heap.set_finalizing(true);
+
+    while let Some(finalizer) = heap.next_finalizer() {
+        let arguments = match &finalizer.function {
+            Function::UserDefined { parameters, .. } if parameters.is_empty() => Vec::new(),
+            _ => vec![Box::new(Expression::Literal {
+                value: Value::Object(finalizer.fields),
+            })],
+        };
+
+        let call = Expression::Call {
+            function: Box::new(Expression::Literal {
+                value: Value::Function(finalizer.function),
+            }),
+            arguments,
+            span: None,
+        };
+
+        if let Err(error) = call.evaluate(stack, heap) {
+            heap.set_finalizing(false);
+
+            return Err(error);
+        }
+    }
+
+    heap.set_finalizing(false);
+
+    Ok(())
 }
 
 impl Statement {
-    /// Executes a statement and inserts a log entry.
+    /// Returns the name of the kind of statement this is, as it is counted in stats.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::VariableDeclaration { .. } => "variable declaration",
+            Self::IfStatement { .. } => "if statement",
+            Self::FunctionDefinition { .. } => "function definition",
+            Self::Return(_) => "return",
+            Self::WhileLoop { .. } => "while loop",
+            Self::Loop { .. } => "loop",
+            Self::Break => "break",
+            Self::Block(_) => "block",
+            Self::Expression(_) => "expression statement",
+            Self::Yield(_) => "yielded expression",
+            Self::Test { .. } => "test",
+            Self::Comment { .. } => "comment",
+            Self::BlankLine => "blank line",
+        }
+    }
+
+    /// Returns the source code which the statement was parsed from, if it is one with a span, and was parsed rather than built by the interpreter.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::VariableDeclaration { span, .. }
+            | Self::FunctionDefinition { span, .. }
+            | Self::IfStatement { span, .. }
+            | Self::WhileLoop { span, .. }
+            | Self::Loop { span, .. } => *span,
+            _ => None,
+        }
+    }
+
+    /// Executes a statement and inserts a log entry, locating any error which occurs within it at its span.
     pub fn execute(
         self,
         stack: &mut Stack,
         heap: &mut ManagedHeap,
-        logger: &mut Logger,
     ) -> Result<ControlFlow, EvaluationError> {
-        stack.top().borrow_mut().define(
-            String::from("STACK_FRAMES_COUNT"),
-            Some(Value::Integer(stack.frames_count() as i32)),
-        );
+        let span = self.span();
 
-        stack.top().borrow_mut().define(
-            String::from("HEAP_OBJECTS_COUNT"),
-            Some(Value::Integer(heap.objects_count() as i32)),
-        );
+        self.execute_unlocated(stack, heap)
+            .map_err(|error| error.at(span))
+    }
 
-        stack.top().borrow_mut().define(
-            String::from("MEMORY_MANAGEMENT"),
-            Some(Value::String(heap.get_technique_code())),
-        );
+    fn execute_unlocated(
+        self,
+        stack: &mut Stack,
+        heap: &mut ManagedHeap,
+    ) -> Result<ControlFlow, EvaluationError> {
+        if stack.interrupt.take() {
+            return Err(EvaluationError::Interrupted);
+        }
+
+        stack.step()?;
+
+        // Collections may be started, and incremental marking does a limited amount of work, before each statement.
+        if let ManagedHeap::GarbageCollected(heap) = heap {
+            if heap.statement_due() {
+                heap.start(&stack.roots());
+            }
+
+            if heap.step() {
+                heap.finish(&stack.roots());
+            }
+        }
+
+        run_finalizers(stack, heap)?;
 
-        logger.new_entry(heap.objects_count(), stack.frames_count());
+        stats::statement(self.kind(), heap, stack);
+
+        tracing::trace!(
+            target: trace::EXECUTE,
+            frames = stack.frames_count(),
+            objects = heap.objects_count(),
+            "Executing a statement."
+        );
 
         match self {
             Self::VariableDeclaration {
                 identifier,
                 initialiser,
+                inline,
+                ..
             } => {
                 let initialiser = match initialiser {
-                    Some(initialiser) => {
-                        Some(initialiser.evaluate_not_nothing(stack, heap, logger)?)
-                    }
+                    Some(initialiser) => Some(initialiser.evaluate_not_nothing(stack, heap)?),
                     None => None,
                 };
 
-                let previous = stack.top().borrow().get(&identifier);
+                let previous = stack.top().borrow().lookup(&identifier).flatten();
 
                 let initialiser = match initialiser {
-                    Some(Value::Object(data)) => Some(Value::ObjectReference(heap.allocate(data))),
+                    Some(Value::Object(data)) if inline => Some(store_inline(data, stack, heap)?),
+                    Some(Value::Object(data)) => Some(Value::ObjectReference(
+                        heap.allocate(data, || stack.roots())?,
+                    )),
                     Some(Value::ObjectReference(ref pointer)) => {
                         if let ManagedHeap::ReferenceCounted(heap) = heap {
-                            heap.increment(Pointer::clone(pointer));
+                            heap.increment(*pointer);
                         }
 
                         initialiser
@@ -99,7 +364,7 @@ impl Statement {
                     _ => initialiser,
                 };
 
-                if let (Ok(previous), ManagedHeap::ReferenceCounted(heap)) = (previous, heap) {
+                if let (Some(previous), ManagedHeap::ReferenceCounted(heap)) = (previous, heap) {
                     heap.conditionally_decrement(previous);
                 }
 
@@ -110,26 +375,34 @@ impl Statement {
                 identifier,
                 parameters,
                 block,
+                ..
             } => {
-                stack.top().borrow_mut().define(
-                    identifier,
-                    Some(Value::Function(Function::UserDefined { parameters, block })),
-                );
+                let function = Function::UserDefined {
+                    identifier: identifier.clone(),
+                    parameters,
+                    block,
+                };
+
+                stack
+                    .top()
+                    .borrow_mut()
+                    .define(identifier, Some(Value::Function(function)));
                 Ok(ControlFlow::Continue)
             }
             Self::IfStatement {
                 condition,
                 execute_if_true,
                 execute_if_false,
+                ..
             } => {
-                let condition = condition.evaluate_not_nothing(stack, heap, logger)?;
+                let condition = condition.evaluate_not_nothing(stack, heap)?;
 
                 if let Value::Boolean(condition) = condition {
                     if condition {
-                        execute_if_true.execute(stack, heap, logger)
+                        execute_if_true.execute(stack, heap)
                     } else {
                         match execute_if_false {
-                            Some(if_false) => if_false.execute(stack, heap, logger),
+                            Some(if_false) => if_false.execute(stack, heap),
                             None => Ok(ControlFlow::Continue),
                         }
                     }
@@ -140,86 +413,120 @@ impl Statement {
                     })
                 }
             }
-            Self::WhileLoop { condition, block } => {
-                while match condition
-                    .clone()
-                    .evaluate_not_nothing(stack, heap, logger)?
-                {
+            Self::WhileLoop {
+                condition, block, ..
+            } => {
+                while match condition.clone().evaluate_not_nothing(stack, heap)? {
                     Value::Boolean(condition) => condition,
                     condition => Err(EvaluationError::NonBooleanControlFlowCondition {
                         condition: condition.slang_type(),
                         control_flow: "while-loop".to_string(),
                     })?,
                 } {
-                    match block.clone().execute(stack, heap, logger)? {
+                    match block.clone().execute(stack, heap)? {
                         ControlFlow::Break(value) => return Ok(ControlFlow::Break(value)),
+                        ControlFlow::ExitLoop => break,
                         ControlFlow::Continue => continue,
                     }
                 }
 
                 Ok(ControlFlow::Continue)
             }
+            Self::Loop { block, .. } => loop {
+                match block.clone().execute(stack, heap)? {
+                    ControlFlow::Break(value) => return Ok(ControlFlow::Break(value)),
+                    ControlFlow::ExitLoop => return Ok(ControlFlow::Continue),
+                    ControlFlow::Continue => continue,
+                }
+            },
+            Self::Break => Ok(ControlFlow::ExitLoop),
             Self::Block(statements) => {
                 stack.enter_scope();
 
-                let mut non_definitions = Vec::new();
-
-                for statement in statements {
-                    match statement {
-                        Statement::FunctionDefinition { .. } => {
-                            statement.execute(stack, heap, logger)?;
-                        }
-                        _ => non_definitions.push(statement),
-                    }
+                if let ManagedHeap::Region(heap) = heap {
+                    heap.enter();
                 }
 
-                let mut return_value = ControlFlow::Continue;
+                // An error leaves the block too, so its scope and region are exited before it is returned.
+                let mut return_value = match execute_block(statements, stack, heap) {
+                    Ok(return_value) => return_value,
+                    Err(error) => {
+                        exit_block(stack, heap, None);
+                        return Err(error);
+                    }
+                };
 
-                for statement in non_definitions {
-                    match statement.execute(stack, heap, logger)? {
-                        ControlFlow::Break(value) => {
-                            return_value = ControlFlow::Break(value);
-                            break;
+                // Under reference counting, a returned object literal is allocated before the scope's values are decremented, as a declared object would have been, so that the objects it references (such as those held by parameters) are counted as referenced by it before they can be freed. Its count starts at one, for being returned.
+                if let ManagedHeap::ReferenceCounted(_) = heap {
+                    match return_value {
+                        ControlFlow::Break(Some(Value::Object(data))) => {
+                            match heap.allocate(data, || stack.roots()) {
+                                Ok(pointer) => {
+                                    return_value =
+                                        ControlFlow::Break(Some(Value::ObjectReference(pointer)));
+                                }
+                                Err(error) => {
+                                    exit_block(stack, heap, None);
+                                    return Err(error.into());
+                                }
+                            }
                         }
-                        ControlFlow::Continue => continue,
+                        ControlFlow::Break(Some(Value::ObjectReference(pointer))) => {
+                            if let ManagedHeap::ReferenceCounted(heap) = heap {
+                                heap.increment(pointer);
+                            }
+                        }
+                        _ => {}
                     }
                 }
 
-                if let ManagedHeap::ReferenceCounted(heap) = heap {
-                    if let ControlFlow::Break(Some(Value::ObjectReference(value))) = &return_value {
-                        heap.increment(Pointer::clone(value));
-                    }
+                let escaping = match &return_value {
+                    ControlFlow::Break(Some(value)) => Some(value),
+                    _ => None,
+                };
 
-                    for value in stack.top().borrow().values() {
-                        heap.conditionally_decrement(value);
-                    }
-                }
+                exit_block(stack, heap, escaping);
 
-                stack.exit_scope(heap);
+                // Under deferred reference counting, the block's changes to counts are buffered until enough have built up.
+                heap.safe_point();
 
-                if let ManagedHeap::GarbageCollected(heap) = heap {
-                    let mut roots = stack.roots();
+                if let ManagedHeap::GarbageCollected(_)
+                | ManagedHeap::Generational(_)
+                | ManagedHeap::Compacting(_)
+                | ManagedHeap::Copying(_) = heap
+                {
+                    // A returned object literal is not on the heap yet, but the objects it references must survive until it is.
+                    let temporaries = stack.temporaries_count();
 
-                    if let ControlFlow::Break(Some(Value::ObjectReference(pointer))) = &return_value
-                    {
-                        roots.push(Pointer::clone(pointer));
+                    if let ControlFlow::Break(Some(value)) = &return_value {
+                        stack.hold_temporary(value);
                     }
 
-                    heap.manage(&roots);
+                    let roots = stack.roots();
+                    stack.release_temporaries(temporaries);
+
+                    match heap {
+                        ManagedHeap::GarbageCollected(heap) => heap.manage(&roots),
+                        ManagedHeap::Generational(heap) => heap.manage(&roots),
+                        ManagedHeap::Compacting(heap) => heap.manage(&roots),
+                        ManagedHeap::Copying(heap) => heap.manage(&roots),
+                        _ => {}
+                    }
                 }
 
                 Ok(return_value)
             }
-            Self::Expression(expression) => match expression.evaluate(stack, heap, logger) {
-                Ok(_) => Ok(ControlFlow::Continue),
-                Err(error) => Err(error),
-            },
+            Self::Expression(expression) | Self::Yield(expression) => {
+                match expression.evaluate(stack, heap) {
+                    Ok(_) => Ok(ControlFlow::Continue),
+                    Err(error) => Err(error),
+                }
+            }
             Self::Return(expression) => match expression {
-                Some(expression) => Ok(ControlFlow::Break(
-                    expression.evaluate(stack, heap, logger)?,
-                )),
+                Some(expression) => Ok(ControlFlow::Break(expression.evaluate(stack, heap)?)),
                 None => Ok(ControlFlow::Break(None)),
             },
+            Self::Test { .. } | Self::Comment { .. } | Self::BlankLine => Ok(ControlFlow::Continue),
         }
     }
 }
@@ -1,93 +1,789 @@
+//! Recording stats about the interpreter and its heap while a program runs, and writing them to a file.
+
 use std::{
-    fs::{self, File},
-    io::{self, BufRead},
-    path::Path,
-    time::{Duration, Instant},
+    collections::HashMap,
+    fmt::{Debug, Display},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use tracing::{
+    Event, Subscriber,
+    field::{self, Visit},
 };
+use tracing_subscriber::{Layer, filter::filter_fn, layer::Context, registry::LookupSpan};
+
+use crate::{
+    clock::Instant,
+    heap::{Allocations, CountOperations, ManagedHeap, Usage},
+    memory,
+    profiler::Profiler,
+    stack::Stack,
+    trace,
+};
+
+/// Records stats from the events on [trace::STATS], as a [Layer] of a tracing subscriber, and writes them to a file.
+///
+/// Clones share the same records, so one can be added to a subscriber and another kept to read them once the program has finished. Only the events emitted while the subscriber is in use are recorded, so installing it as the default for a single thread (with [tracing::subscriber::set_default]) records one interpreter, and not those on the threads it spawns.
+#[derive(Clone)]
+pub struct Stats(Arc<Mutex<Records>>);
 
-pub struct Logger {
+struct Records {
+    /// Whether entries are recorded. When disabled, only per-function timings are recorded, if profiling is enabled.
+    enabled: bool,
     start: Instant,
     entries: Vec<Entry>,
+    collections: Vec<CollectionRecord>,
+    totals: Totals,
+    /// How often entries are recorded.
+    sampling: Sampling,
+    /// The number of statements seen so far, for sampling every N statements.
+    statements: usize,
+    /// When the previous entry was recorded, for sampling every T milliseconds.
+    last_sample: Option<Instant>,
+    /// Records per-function timings, when profiling is enabled.
+    profiler: Option<Profiler>,
+    /// Whether the stats which cost the most to measure as each statement runs are recorded: the interpreter's memory usage in every entry (rather than only the final one), and the kinds of statements and expressions run.
+    instrumented: bool,
+    /// The number of times each kind of statement was executed.
+    statement_kinds: HashMap<String, usize>,
+    /// The number of times each kind of expression was evaluated.
+    expression_kinds: HashMap<String, usize>,
 }
 
-fn get_memory_usage() -> Option<usize> {
-    let path = Path::new("/proc/self/status");
+impl Stats {
+    pub fn new(sampling: Sampling) -> Self {
+        Self(Arc::new(Mutex::new(Records {
+            enabled: true,
+            start: Instant::now(),
+            entries: Vec::new(),
+            collections: Vec::new(),
+            totals: Totals::default(),
+            sampling,
+            statements: 0,
+            last_sample: None,
+            profiler: None,
+            instrumented: false,
+            statement_kinds: HashMap::new(),
+            expression_kinds: HashMap::new(),
+        })))
+    }
 
-    if let Ok(file) = File::open(path) {
-        let reader = io::BufReader::new(file);
+    /// Creates stats which do not record any entries, for profiling alone.
+    pub fn disabled() -> Self {
+        let stats = Self::new(Sampling::default());
+        stats.records().enabled = false;
 
-        for line in reader.lines() {
-            let line = line;
+        stats
+    }
 
-            if let Ok(line) = line {
-                if !line.starts_with("VmRSS:") {
-                    continue;
-                }
+    fn records(&self) -> MutexGuard<'_, Records> {
+        self.0.lock().expect("the stats were poisoned")
+    }
+
+    /// Starts recording per-function timings, independently of whether entries are recorded.
+    pub fn enable_profiling(&self) {
+        self.records().profiler = Some(Profiler::new());
+    }
+
+    /// Starts recording the stats which cost the most to measure, if entries are recorded.
+    pub fn enable_instrumentation(&self) {
+        let mut records = self.records();
+        records.instrumented = records.enabled;
+    }
+
+    /// Returns the layer to add to a subscriber, which only sees the events on [trace::STATS].
+    pub fn layer<S: Subscriber + for<'a> LookupSpan<'a>>(&self) -> impl Layer<S> {
+        self.clone()
+            .with_filter(filter_fn(|metadata| metadata.target() == trace::STATS))
+    }
+
+    /// Returns the profiling report, if profiling is enabled.
+    pub fn profile_report(&self) -> Option<String> {
+        self.records().profiler.as_ref().map(Profiler::report)
+    }
+
+    /// Summarises the peak usage of the heap and stack, as of the most recent entry, along with how many changes to reference counts were avoided by deferring them, under reference counting, and how many of each kind of statement and expression were run.
+    pub fn summary(&self) -> Option<String> {
+        let records = self.records();
+        let last = records.entries.last()?;
+
+        let mut summary = format!(
+            "Peak usage: {} heap objects, {} heap bytes (estimated), {} stack frames.",
+            last.usage.peak_objects, last.usage.peak_bytes, last.peak_stack_frames_count
+        );
+
+        let operations = last.count_operations;
 
-                let parts: Vec<&str> = line.split_whitespace().collect();
+        if operations.requested > 0 {
+            summary.push_str(&format!(
+                "\nReference count operations: {} requested, {} applied ({:.1}% fewer).",
+                operations.requested,
+                operations.applied,
+                (1.0 - operations.applied as f64 / operations.requested as f64) * 100.0
+            ));
+        }
+
+        summary.push_str(&format!(
+            "\nAllocations: {} objects ({} elided).",
+            last.allocations.count, last.allocations.elided
+        ));
+        if records.instrumented {
+            summary.push_str(&histogram("Statements executed", &records.statement_kinds));
+            summary.push_str(&histogram(
+                "Expressions evaluated",
+                &records.expression_kinds,
+            ));
+        }
+
+        Some(summary)
+    }
+
+    /// Returns the entries recorded so far.
+    pub fn entries(&self) -> Vec<Entry> {
+        self.records().entries.clone()
+    }
+
+    /// Renders all of the entries and collections as a JSON object, with the keys `entries` and `collections`.
+    pub fn json(&self) -> String {
+        let records = self.records();
+
+        format!(
+            "{{\n  \"entries\": {},\n  \"collections\": {}\n}}\n",
+            json_array(&records.entries),
+            json_array(&records.collections)
+        )
+    }
+
+    /// Writes all of the entries to a file in a specific format.
+    ///
+    /// In CSV, each collection is written to a second file alongside, named `<name>.collections.csv`.
+    pub fn write(&self, output: &Output) {
+        let records = self.records();
 
-                // Get the VmRSS value, which is in kilobytes.
-                if let Some(rss_kb) = parts.get(1) {
-                    return Some(rss_kb.parse::<usize>().unwrap_or(0) * 1000);
+        if !records.enabled {
+            return;
+        }
+
+        match output.format {
+            Format::Csv => {
+                let _ = fs::write(&output.path, csv(&records.entries));
+
+                let _ = fs::write(collections_path(&output.path), csv(&records.collections));
+            }
+            Format::Json => {
+                drop(records);
+
+                let _ = fs::write(&output.path, self.json());
+            }
+            Format::JsonLines => {
+                let mut contents = String::new();
+
+                for entry in &records.entries {
+                    contents.push_str(&json_object(entry, Some("entry")));
+                    contents.push('\n');
                 }
+
+                for collection in &records.collections {
+                    contents.push_str(&json_object(collection, Some("collection")));
+                    contents.push('\n');
+                }
+
+                let _ = fs::write(&output.path, contents);
             }
         }
     }
-
-    None
 }
 
-impl Logger {
-    pub fn new() -> Self {
-        Self {
-            start: Instant::now(),
-            entries: Vec::new(),
+impl<S: Subscriber> Layer<S> for Stats {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+
+        let mut records = self.records();
+
+        match event.metadata().name() {
+            "entry" => records.entry(&fields),
+            "collection" => records.collection(&fields),
+            "expression" if records.instrumented => {
+                *records
+                    .expression_kinds
+                    .entry(fields.string("kind"))
+                    .or_default() += 1;
+            }
+            "call" => {
+                let allocations = fields.allocations();
+
+                if let Some(profiler) = &mut records.profiler {
+                    profiler.enter(&fields.string("function"), allocations);
+                }
+            }
+            "return" => {
+                let allocations = fields.allocations();
+
+                if let Some(profiler) = &mut records.profiler {
+                    profiler.exit(allocations);
+                }
+            }
+            _ => {}
         }
     }
+}
 
-    pub fn new_entry(&mut self, heap_objects_count: usize, stack_frames_count: usize) {
-        let memory_usage = get_memory_usage();
+impl Records {
+    /// Records an entry from an event if one is due, or always for the final entry, which has no kind of statement.
+    fn entry(&mut self, fields: &Fields) {
+        let kind = fields.strings.get("kind");
+
+        if let (Some(kind), true) = (kind, self.instrumented) {
+            *self.statement_kinds.entry(kind.clone()).or_default() += 1;
+        }
+
+        if !self.enabled || (kind.is_some() && !self.due()) {
+            return;
+        }
+
+        let memory_usage = if self.instrumented || kind.is_none() {
+            memory::resident_set_size()
+        } else {
+            None
+        };
 
         self.entries.push(Entry {
             elapsed: self.start.elapsed(),
-            heap_objects_count,
-            stack_frames_count,
+            heap_objects_count: fields.integer("heap_objects_count"),
+            stack_frames_count: fields.integer("stack_frames_count"),
             memory_usage,
+            allocations: fields.allocations(),
+            count_operations: CountOperations {
+                requested: fields.integer("count_operations"),
+                applied: fields.integer("count_operations_applied"),
+            },
+            usage: Usage {
+                bytes: fields.integer("heap_bytes"),
+                peak_objects: fields.integer("peak_heap_objects_count"),
+                peak_bytes: fields.integer("peak_heap_bytes"),
+                deduplicated_bytes: fields.integer("deduplicated_bytes"),
+            },
+            fragmentation: fields
+                .floats
+                .get("heap_fragmentation")
+                .copied()
+                .unwrap_or_default(),
+            peak_stack_frames_count: fields.integer("peak_stack_frames_count"),
+            totals: Totals {
+                collections: fields.integer("collections"),
+                objects_freed: fields.integer("objects_freed"),
+                ..self.totals
+            },
+        });
+    }
+
+    /// Records a collection from an event, adding it to the totals.
+    fn collection(&mut self, fields: &Fields) {
+        if !self.enabled {
+            return;
+        }
+
+        let duration = fields.duration("duration");
+        let longest_slice = fields.duration("longest_slice");
+        let freed = fields.integer("freed");
+        let cycles = fields.booleans.get("cycles").copied().unwrap_or_default();
+
+        self.totals.collection_time += duration;
+        self.totals.longest_pause = self.totals.longest_pause.max(longest_slice);
+
+        if cycles {
+            self.totals.cycle_objects_freed += freed;
+        }
+
+        self.collections.push(CollectionRecord {
+            elapsed: self
+                .start
+                .elapsed()
+                .saturating_sub(fields.duration("started_ago")),
+            duration,
+            slices: fields.integer("slices"),
+            longest_slice,
+            freed,
+            surviving: fields.integer("surviving"),
+            cycles,
+            moved: fields.integer("moved"),
+            threads: fields.integer("threads"),
         });
     }
 
-    pub fn write_to_csv(self, source_code_filename: &str) {
-        let mut contents =
-            String::from("elapsed,heap_objects_count,stack_frames_count,interpreter_memory_usage");
-
-        for entry in self.entries {
-            let memory_usage = if let Some(memory_usage) = entry.memory_usage {
-                format!("{}", memory_usage)
-            } else {
-                String::from("unable to calculate")
-            };
-
-            contents.push_str(
-                format!(
-                    "\n{},{},{},{}",
-                    entry.elapsed.as_secs_f64(),
-                    entry.heap_objects_count,
-                    entry.stack_frames_count,
-                    memory_usage,
-                )
-                .as_str(),
-            );
+    /// Returns whether an entry should be recorded for the current statement.
+    fn due(&mut self) -> bool {
+        match self.sampling {
+            Sampling::Statements(count) => {
+                let due = self.statements.is_multiple_of(count);
+                self.statements += 1;
+
+                due
+            }
+            Sampling::Interval(interval) => {
+                let now = Instant::now();
+
+                match self.last_sample {
+                    Some(last_sample) if now.duration_since(last_sample) < interval => false,
+                    _ => {
+                        self.last_sample = Some(now);
+
+                        true
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The values of the fields of an event on [trace::STATS], by name.
+#[derive(Default)]
+struct Fields {
+    integers: HashMap<&'static str, usize>,
+    floats: HashMap<&'static str, f64>,
+    booleans: HashMap<&'static str, bool>,
+    strings: HashMap<&'static str, String>,
+}
+
+impl Fields {
+    fn integer(&self, name: &str) -> usize {
+        self.integers.get(name).copied().unwrap_or_default()
+    }
+
+    /// Returns a duration, which is recorded as a number of seconds.
+    fn duration(&self, name: &str) -> Duration {
+        Duration::from_secs_f64(self.floats.get(name).copied().unwrap_or_default())
+    }
+
+    fn string(&self, name: &str) -> String {
+        self.strings.get(name).cloned().unwrap_or_default()
+    }
+
+    fn allocations(&self) -> Allocations {
+        Allocations {
+            count: self.integer("allocations"),
+            bytes: self.integer("bytes_allocated"),
+            elided: self.integer("allocations_elided"),
+        }
+    }
+}
+
+impl Visit for Fields {
+    fn record_u64(&mut self, field: &field::Field, value: u64) {
+        self.integers
+            .insert(field.name(), usize::try_from(value).unwrap_or(usize::MAX));
+    }
+
+    fn record_f64(&mut self, field: &field::Field, value: f64) {
+        self.floats.insert(field.name(), value);
+    }
+
+    fn record_bool(&mut self, field: &field::Field, value: bool) {
+        self.booleans.insert(field.name(), value);
+    }
+
+    fn record_str(&mut self, field: &field::Field, value: &str) {
+        self.strings.insert(field.name(), value.to_string());
+    }
+
+    fn record_debug(&mut self, _field: &field::Field, _value: &dyn Debug) {}
+}
+
+/// Emits an event for the state of the interpreter before a statement of some kind (see [crate::statement::Statement::kind]) is executed, after one for each collection the heap has performed since the previous statement.
+///
+/// Collections are always taken from the heap, even if nothing records them, so that they do not build up within it.
+pub fn statement(kind: &'static str, heap: &mut ManagedHeap, stack: &Stack) {
+    collections(heap);
+    entry(Some(kind), heap, stack);
+}
+
+/// Emits an event for the final state of the interpreter, which is always recorded as an entry, regardless of the sampling interval.
+pub fn finish(heap: &mut ManagedHeap, stack: &Stack) {
+    collections(heap);
+    entry(None, heap, stack);
+}
+
+/// Emits an event for the evaluation of an expression of some kind (see [crate::expression::Expression::kind]).
+pub fn expression(kind: &'static str) {
+    tracing::trace!(name: "expression", target: trace::STATS, kind);
+}
+
+/// Emits an event for the start of a call to a user-defined function.
+pub fn enter_function(identifier: &str, heap: &ManagedHeap) {
+    tracing::trace!(
+        name: "call",
+        target: trace::STATS,
+        function = identifier,
+        allocations = heap.allocations().count,
+        bytes_allocated = heap.allocations().bytes,
+        allocations_elided = heap.allocations().elided,
+    );
+}
+
+/// Emits an event for the end of the most recent call to a user-defined function.
+pub fn exit_function(heap: &ManagedHeap) {
+    tracing::trace!(
+        name: "return",
+        target: trace::STATS,
+        allocations = heap.allocations().count,
+        bytes_allocated = heap.allocations().bytes,
+        allocations_elided = heap.allocations().elided,
+    );
+}
+
+fn entry(kind: Option<&'static str>, heap: &ManagedHeap, stack: &Stack) {
+    tracing::trace!(
+        name: "entry",
+        target: trace::STATS,
+        kind,
+        heap_objects_count = heap.objects_count(),
+        stack_frames_count = stack.frames_count(),
+        collections = heap.stats().collections,
+        objects_freed = heap.stats().objects_freed,
+        allocations = heap.allocations().count,
+        bytes_allocated = heap.allocations().bytes,
+        allocations_elided = heap.allocations().elided,
+        count_operations = heap.count_operations().requested,
+        count_operations_applied = heap.count_operations().applied,
+        heap_bytes = heap.usage().bytes,
+        peak_heap_objects_count = heap.usage().peak_objects,
+        peak_heap_bytes = heap.usage().peak_bytes,
+        heap_fragmentation = heap.fragmentation(),
+        deduplicated_bytes = heap.usage().deduplicated_bytes,
+        peak_stack_frames_count = stack.peak_frames_count(),
+    );
+}
+
+fn collections(heap: &mut ManagedHeap) {
+    for collection in heap.take_collections() {
+        tracing::trace!(
+            name: "collection",
+            target: trace::STATS,
+            started_ago = collection.start.elapsed().as_secs_f64(),
+            duration = collection.duration.as_secs_f64(),
+            slices = collection.slices,
+            longest_slice = collection.longest_slice.as_secs_f64(),
+            freed = collection.freed,
+            surviving = collection.surviving,
+            cycles = collection.cycles,
+            moved = collection.moved,
+            threads = collection.threads,
+        );
+    }
+}
+
+/// Returns the path of the file which collections are written to, alongside a CSV stats file.
+fn collections_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    path.with_file_name(format!("{}.collections.csv", stem))
+}
+
+/// Renders records as CSV, with a header row.
+pub fn csv<R: Record>(records: &[R]) -> String {
+    let mut contents = R::NAMES.join(",");
+
+    for record in records {
+        let row = record
+            .fields()
+            .into_iter()
+            .map(|field| match field {
+                Field::Missing => String::from("unable to calculate"),
+                field => field.to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+
+        contents.push('\n');
+        contents.push_str(&row);
+    }
+
+    contents
+}
+
+/// Renders records as a JSON array of objects, with one object per line.
+pub fn json_array<R: Record>(records: &[R]) -> String {
+    if records.is_empty() {
+        return String::from("[]");
+    }
+
+    let objects = records
+        .iter()
+        .map(|record| format!("    {}", json_object(record, None)))
+        .collect::<Vec<String>>();
+
+    format!("[\n{}\n  ]", objects.join(",\n"))
+}
+
+/// Renders a record as a single line JSON object, optionally tagged with the kind of record it is.
+pub fn json_object<R: Record>(record: &R, kind: Option<&str>) -> String {
+    let mut fields = Vec::new();
+
+    if let Some(kind) = kind {
+        fields.push(format!("\"kind\": \"{}\"", kind));
+    }
+
+    for (name, field) in R::NAMES.iter().zip(record.fields()) {
+        fields.push(format!("\"{}\": {}", name, field));
+    }
+
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// Where, in what format, and how often stats are written.
+pub struct Output {
+    pub path: PathBuf,
+    pub format: Format,
+    pub sampling: Sampling,
+}
+
+/// How often an entry is recorded.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Sampling {
+    /// Before every N statements.
+    Statements(usize),
+    /// Before the first statement at least T has passed since the previous entry.
+    Interval(Duration),
+}
+
+impl Default for Sampling {
+    /// Records an entry before every statement.
+    fn default() -> Self {
+        Self::Statements(1)
+    }
+}
+
+impl Sampling {
+    /// Parses a number of statements (`100`) or a number of milliseconds (`10ms`), neither of which can be zero.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let sampling = match name.strip_suffix("ms") {
+            Some(milliseconds) => Self::Interval(Duration::from_millis(milliseconds.parse().ok()?)),
+            None => Self::Statements(name.parse().ok()?),
+        };
+
+        match sampling {
+            Self::Statements(0) => None,
+            Self::Interval(interval) if interval.is_zero() => None,
+            sampling => Some(sampling),
         }
+    }
+}
+
+/// The formats which stats can be written in.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+    JsonLines,
+}
+
+impl Format {
+    /// Returns the format with a specific name (`csv`, `json` or `jsonl`), if it is valid.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            "jsonl" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+
+    /// Infers the format from the extension of a path, if it is recognised.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_name)
+    }
+
+    /// Returns the file extension used for the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::JsonLines => "jsonl",
+        }
+    }
+}
+
+/// Renders the number of times each kind of statement or expression was run, under a heading with the total, from the most common kind to the least.
+fn histogram(heading: &str, counts: &HashMap<String, usize>) -> String {
+    let mut kinds = counts.iter().collect::<Vec<_>>();
 
-        let filename = format!("{}.csv", source_code_filename);
+    kinds.sort_by(|(a_kind, a), (b_kind, b)| b.cmp(a).then_with(|| a_kind.cmp(b_kind)));
 
-        let _ = fs::write(filename, contents);
+    let width = kinds.iter().map(|(kind, _)| kind.len()).max().unwrap_or(0);
+    let mut output = format!("\n{}: {}", heading, counts.values().sum::<usize>());
+
+    for (kind, count) in kinds {
+        output.push_str(&format!("\n  {:<width$}  {:>10}", kind, count));
     }
+
+    output
+}
+
+/// A row of stats, which can be written in any format.
+pub trait Record {
+    /// The name of each field, in the order they are written.
+    const NAMES: &'static [&'static str];
+
+    /// Returns the value of each field, in the same order as [Record::NAMES].
+    fn fields(&self) -> Vec<Field>;
+}
+
+/// Running totals, which are included in every entry.
+#[derive(Clone, Copy, Default)]
+pub struct Totals {
+    pub collections: usize,
+    pub collection_time: Duration,
+    /// The longest single pause of any collection.
+    pub longest_pause: Duration,
+    pub objects_freed: usize,
+    /// The objects freed by collecting reference cycles, under reference counting.
+    pub cycle_objects_freed: usize,
+}
+
+/// A snapshot of the interpreter's state, taken before a statement is executed.
+#[derive(Clone)]
+pub struct Entry {
+    pub elapsed: Duration,
+    pub heap_objects_count: usize,
+    pub stack_frames_count: usize,
+    pub memory_usage: Option<usize>,
+    pub allocations: Allocations,
+    /// The changes made to reference counts, under reference counting.
+    pub count_operations: CountOperations,
+    /// The current and peak usage of the heap, which catches peaks between entries.
+    pub usage: Usage,
+    /// The fraction of the heap's slots which are free.
+    pub fragmentation: f64,
+    /// The most frames which have been on the stack at once, which catches peaks between entries.
+    pub peak_stack_frames_count: usize,
+    pub totals: Totals,
 }
 
-struct Entry {
+impl Record for Entry {
+    const NAMES: &'static [&'static str] = &[
+        "elapsed",
+        "heap_objects_count",
+        "stack_frames_count",
+        "interpreter_memory_usage",
+        "collections",
+        "collection_time",
+        "longest_pause",
+        "objects_freed",
+        "cycle_objects_freed",
+        "allocations",
+        "bytes_allocated",
+        "allocations_elided",
+        "count_operations",
+        "count_operations_applied",
+        "heap_bytes",
+        "peak_heap_objects_count",
+        "peak_heap_bytes",
+        "heap_fragmentation",
+        "deduplicated_bytes",
+        "peak_stack_frames_count",
+    ];
+
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            Field::Float(self.elapsed.as_secs_f64()),
+            Field::Integer(self.heap_objects_count),
+            Field::Integer(self.stack_frames_count),
+            match self.memory_usage {
+                Some(memory_usage) => Field::Integer(memory_usage),
+                None => Field::Missing,
+            },
+            Field::Integer(self.totals.collections),
+            Field::Float(self.totals.collection_time.as_secs_f64()),
+            Field::Float(self.totals.longest_pause.as_secs_f64()),
+            Field::Integer(self.totals.objects_freed),
+            Field::Integer(self.totals.cycle_objects_freed),
+            Field::Integer(self.allocations.count),
+            Field::Integer(self.allocations.bytes),
+            Field::Integer(self.allocations.elided),
+            Field::Integer(self.count_operations.requested),
+            Field::Integer(self.count_operations.applied),
+            Field::Integer(self.usage.bytes),
+            Field::Integer(self.usage.peak_objects),
+            Field::Integer(self.usage.peak_bytes),
+            Field::Float(self.fragmentation),
+            Field::Integer(self.usage.deduplicated_bytes),
+            Field::Integer(self.peak_stack_frames_count),
+        ]
+    }
+}
+
+/// A single collection performed by the heap.
+struct CollectionRecord {
+    /// When the collection started, relative to the start of the program.
     elapsed: Duration,
-    heap_objects_count: usize,
-    stack_frames_count: usize,
-    memory_usage: Option<usize>,
+    /// The total time the collection paused the program for.
+    duration: Duration,
+    /// The number of pauses the collection was split into.
+    slices: usize,
+    longest_slice: Duration,
+    freed: usize,
+    surviving: usize,
+    /// Whether the collection was of reference cycles, under reference counting.
+    cycles: bool,
+    /// The number of surviving objects which were moved to compact the heap, or promoted to an outer region.
+    moved: usize,
+    /// The number of threads which marked the heap.
+    threads: usize,
+}
+
+impl Record for CollectionRecord {
+    const NAMES: &'static [&'static str] = &[
+        "elapsed",
+        "duration",
+        "slices",
+        "longest_slice",
+        "freed",
+        "surviving",
+        "cycles",
+        "moved",
+        "threads",
+    ];
+
+    fn fields(&self) -> Vec<Field> {
+        vec![
+            Field::Float(self.elapsed.as_secs_f64()),
+            Field::Float(self.duration.as_secs_f64()),
+            Field::Integer(self.slices),
+            Field::Float(self.longest_slice.as_secs_f64()),
+            Field::Integer(self.freed),
+            Field::Integer(self.surviving),
+            Field::Boolean(self.cycles),
+            Field::Integer(self.moved),
+            Field::Integer(self.threads),
+        ]
+    }
+}
+
+/// A single value within a record.
+pub enum Field {
+    Float(f64),
+    Integer(usize),
+    Boolean(bool),
+    /// A value which could not be calculated. This is written as `null` in JSON.
+    Missing,
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Float(value) => write!(f, "{}", value),
+            Self::Integer(value) => write!(f, "{}", value),
+            Self::Boolean(value) => write!(f, "{}", value),
+            Self::Missing => write!(f, "null"),
+        }
+    }
 }
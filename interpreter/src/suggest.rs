@@ -0,0 +1,48 @@
+//! Suggestions for names which may have been misspelled, such as `wile` for `while` or `prnit` for `print`, found by comparing a name with those which could have been meant.
+//!
+//! Names are compared by their edit distance: the number of characters which must be inserted, deleted or substituted (or adjacent characters which must be swapped) to turn one into the other.
+
+/// Returns the candidate closest to a name, if any is close enough to have been meant instead: within a third of the name's length (or one edit for shorter names), without changing every character. Ties go to the earliest candidate.
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let length = name.chars().count();
+    let limit = (length / 3).max(1);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit && *distance < length)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Returns the number of single characters which must be inserted, deleted or substituted, or pairs of adjacent characters which must be swapped, to turn one string into another.
+pub fn distance(left: &str, right: &str) -> usize {
+    let left = left.chars().collect::<Vec<_>>();
+    let right = right.chars().collect::<Vec<_>>();
+
+    // The distances between the prefixes of the left string and every prefix of the right, for the previous two rows as well as the current one, as swaps look two characters back.
+    let mut before = vec![0; right.len() + 1];
+    let mut previous = (0..=right.len()).collect::<Vec<_>>();
+    let mut current = vec![0; right.len() + 1];
+
+    for i in 1..=left.len() {
+        current[0] = i;
+
+        for j in 1..=right.len() {
+            let substitution = usize::from(left[i - 1] != right[j - 1]);
+
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + substitution);
+
+            if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[right.len()]
+}
@@ -0,0 +1,142 @@
+//! Mutexes and atomic integers, which hold state shared between threads (see [thread](crate::thread)), created by the native functions `mutex` and `atomic`.
+//!
+//! Like channels, they belong to the program which created them and the threads it spawns. A mutex holds a copy of its value outside of every heap, which `lock(mutex, function)` copies into the locking thread's heap for the function, and back again once it returns successfully.
+//!
+//! An atomic integer is updated by `atomic_add(atomic, amount)`, which adds to it and returns its new value, without any locking.
+
+use std::{
+    sync::{
+        Arc, Mutex, TryLockError,
+        atomic::{AtomicI32, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    expression::{EvaluationError, Expression},
+    heap::ManagedHeap,
+    session::SessionError,
+    stack::Stack,
+    thread::{Copied, export, import},
+    value::{Function, Value},
+};
+
+/// The mutexes and atomic integers which have been created by a program and the threads it spawns, indexed by their handles. Clones share the same ones.
+#[derive(Clone, Default)]
+pub struct Primitives {
+    /// Each mutex holds a copy of its value made outside of every heap.
+    mutexes: Arc<Mutex<Vec<Arc<Mutex<Copied>>>>>,
+    atomics: Arc<Mutex<Vec<Arc<AtomicI32>>>>,
+}
+
+/// How long a thread waits for a mutex to be unlocked before trying again, and checking whether its program has been interrupted.
+const INTERRUPT_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Creates a mutex holding a copy of a value, returning its handle.
+pub fn create_mutex(
+    value: &Value,
+    stack: &Stack,
+    heap: &ManagedHeap,
+) -> Result<Value, EvaluationError> {
+    let value = export(heap, value).map_err(|error| EvaluationError::MessageFailed {
+        message: error.to_string(),
+    })?;
+
+    let mut mutexes = stack
+        .primitives
+        .mutexes
+        .lock()
+        .expect("the mutexes were poisoned");
+    mutexes.push(Arc::new(Mutex::new(value)));
+
+    Value::try_from(mutexes.len() - 1).map_err(EvaluationError::from)
+}
+
+/// Locks the mutex with a handle, waiting for any other thread to unlock it, and calls a function with a copy of its value, returning the function's return value. Stops waiting if the program is interrupted.
+pub fn lock(
+    handle: i32,
+    function: Function,
+    stack: &mut Stack,
+    heap: &mut ManagedHeap,
+) -> Result<Option<Value>, EvaluationError> {
+    let mutex = stack
+        .primitives
+        .mutexes
+        .lock()
+        .expect("the mutexes were poisoned")
+        .get(usize::try_from(handle).unwrap_or(usize::MAX))
+        .cloned()
+        .ok_or(EvaluationError::UnknownMutex { handle })?;
+
+    let mut guard = loop {
+        match mutex.try_lock() {
+            Ok(guard) => break guard,
+            Err(TryLockError::WouldBlock) if stack.interrupt.take() => {
+                return Err(EvaluationError::Interrupted);
+            }
+            Err(TryLockError::WouldBlock) => thread::sleep(INTERRUPT_INTERVAL),
+            Err(TryLockError::Poisoned(_)) => panic!("the mutex was poisoned"),
+        }
+    };
+
+    let failed = |error: SessionError| EvaluationError::MessageFailed {
+        message: error.to_string(),
+    };
+
+    let value = import(&guard, stack, heap)
+        .map_err(failed)?
+        .expect("a copied value is never nothing");
+
+    // The value is held until it has been copied back, as the function may drop every other reference to it.
+    let temporaries = stack.temporaries_count();
+    stack.hold_temporary(&value);
+
+    let call = Expression::Call {
+        function: Box::new(Expression::Literal {
+            value: Value::Function(function),
+        }),
+        arguments: vec![Box::new(Expression::Literal {
+            value: value.clone(),
+        })],
+        span: None,
+    };
+
+    let result = call
+        .evaluate(stack, heap)
+        .and_then(|result| Ok((result, export(heap, &value).map_err(failed)?)));
+    stack.release_temporaries(temporaries);
+
+    let (result, value) = result?;
+    *guard = value;
+
+    Ok(result)
+}
+
+/// Creates an atomic integer with an initial value, returning its handle.
+pub fn create_atomic(value: i32, stack: &Stack) -> Result<Value, EvaluationError> {
+    let mut atomics = stack
+        .primitives
+        .atomics
+        .lock()
+        .expect("the atomics were poisoned");
+    atomics.push(Arc::new(AtomicI32::new(value)));
+
+    Value::try_from(atomics.len() - 1).map_err(EvaluationError::from)
+}
+
+/// Adds an amount to the atomic integer with a handle, wrapping on overflow, and returns its new value.
+pub fn add(handle: i32, amount: i32, stack: &Stack) -> Result<Value, EvaluationError> {
+    let atomic = stack
+        .primitives
+        .atomics
+        .lock()
+        .expect("the atomics were poisoned")
+        .get(usize::try_from(handle).unwrap_or(usize::MAX))
+        .cloned()
+        .ok_or(EvaluationError::UnknownAtomic { handle })?;
+
+    let previous = atomic.fetch_add(amount, Ordering::SeqCst);
+
+    Ok(Value::Integer(previous.wrapping_add(amount)))
+}
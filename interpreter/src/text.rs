@@ -0,0 +1,113 @@
+//! Native functions which format numbers and pad text, so that tables printed by a program line up, and which build strings up a piece at a time.
+//!
+//! Strings are immutable, so `s = s + piece` copies the whole of `s` each time, and building a string from n pieces that way takes O(n²) time. A builder created by `builder()` instead holds a growable string outside of the heap, which `append(builder, values...)` adds to in amortised O(1) time per character, until `build(builder)` returns the string and frees the builder. Like threads, builders belong to the interpreter which created them, and are referred to by integer handles.
+
+use crate::{
+    expression::EvaluationError,
+    stack::Stack,
+    value::{Type, Value},
+};
+
+/// Returns a number written with a number of digits after the decimal point, rounding it if needed.
+pub fn to_fixed(number: &Value, digits: usize) -> Result<String, EvaluationError> {
+    Ok(format!("{:.*}", digits, as_float(number)?))
+}
+
+/// Returns a number written with a comma between each group of three digits before the decimal point.
+pub fn thousands(number: &Value) -> Result<String, EvaluationError> {
+    as_float(number)?;
+
+    let written = number.to_string();
+    let (sign, unsigned) = match written.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", written.as_str()),
+    };
+    let (whole, fraction) = match unsigned.find('.') {
+        Some(point) => unsigned.split_at(point),
+        None => (unsigned, ""),
+    };
+
+    let mut grouped = String::new();
+
+    for (index, digit) in whole.chars().enumerate() {
+        if index > 0 && (whole.len() - index) % 3 == 0 {
+            grouped.push(',');
+        }
+
+        grouped.push(digit);
+    }
+
+    Ok(format!("{}{}{}", sign, grouped, fraction))
+}
+
+/// Which side of some text is padded.
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Returns some text padded on one side with a fill character until it is at least a number of characters wide.
+pub fn pad(text: &str, width: usize, fill: &str, side: Side) -> Result<String, EvaluationError> {
+    let mut characters = fill.chars();
+
+    let (Some(fill), None) = (characters.next(), characters.next()) else {
+        return Err(EvaluationError::InvalidFill {
+            fill: fill.to_string(),
+        });
+    };
+
+    let padding = fill
+        .to_string()
+        .repeat(width.saturating_sub(text.chars().count()));
+
+    Ok(match side {
+        Side::Left => padding + text,
+        Side::Right => text.to_string() + &padding,
+    })
+}
+
+/// Creates an empty builder, returning its handle.
+pub fn create_builder(stack: &mut Stack) -> Result<Value, EvaluationError> {
+    stack.builders.push(Some(String::new()));
+
+    Value::try_from(stack.builders.len() - 1).map_err(EvaluationError::from)
+}
+
+/// Adds some text to the end of the string being built by the builder with a handle.
+pub fn append(handle: i32, text: &str, stack: &mut Stack) -> Result<(), EvaluationError> {
+    builder(handle, stack)?.push_str(text);
+
+    Ok(())
+}
+
+/// Returns the string built by the builder with a handle, and frees the builder.
+pub fn build(handle: i32, stack: &mut Stack) -> Result<Value, EvaluationError> {
+    let built = usize::try_from(handle)
+        .ok()
+        .and_then(|index| stack.builders.get_mut(index))
+        .and_then(Option::take)
+        .ok_or(EvaluationError::UnknownBuilder { handle })?;
+
+    Ok(Value::String(built.into()))
+}
+
+/// Returns the string being built by the builder with a handle.
+fn builder(handle: i32, stack: &mut Stack) -> Result<&mut String, EvaluationError> {
+    usize::try_from(handle)
+        .ok()
+        .and_then(|index| stack.builders.get_mut(index))
+        .and_then(Option::as_mut)
+        .ok_or(EvaluationError::UnknownBuilder { handle })
+}
+
+/// Returns the value of a number, as a Float.
+fn as_float(number: &Value) -> Result<f64, EvaluationError> {
+    match number {
+        Value::Integer(integer) => Ok(f64::from(*integer)),
+        Value::Float(float) => Ok(*float),
+        other => Err(EvaluationError::InvalidArgumentType {
+            expected: Type::Float,
+            passed: other.slang_type(),
+        }),
+    }
+}
@@ -0,0 +1,421 @@
+//! Threads, which run a slang function on another OS thread, started by the native function `spawn` and waited for by `join`.
+//!
+//! Each thread runs its own interpreter, with the same heap strategy, starting with a copy of the globals. Values are copied between heaps (see [Copied]) rather than shared, so an object mutated by one thread is not changed for any other.
+
+use std::{
+    collections::HashMap,
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    environment::NATIVE_FUNCTIONS,
+    expression::{EvaluationError, Expression},
+    formatter::Formatter,
+    heap::{HeapError, ManagedHeap, Object, Pointer, Strategy},
+    interpreter::{Interpreter, InterpreterError, parse},
+    session::{self, Objects, SessionError},
+    shared::Shared,
+    stack::Stack,
+    statement::Statement,
+    value::{Function, NativeFunction, PluginFunction, Value},
+};
+
+/// A thread which has been spawned, and which finishes with a copy of its return value, if any, or the errors which stopped it.
+pub type Thread = JoinHandle<Result<Option<Copied>, String>>;
+
+/// A copy of some values made outside of every heap, which can be sent to another thread, and copied into its heap with [import].
+///
+/// Objects are copied once each, however many times they are referenced, so references between them are copied exactly, including cycles. User-defined functions are copied by their definitions.
+pub struct Copied {
+    globals: Vec<(String, Option<CopiedValue>)>,
+    values: Vec<CopiedValue>,
+    objects: Vec<CopiedObject>,
+    functions: Vec<String>,
+}
+
+/// A value copied outside of every heap.
+enum CopiedValue {
+    String(String),
+    Float(f64),
+    Integer(i32),
+    Boolean(bool),
+    /// The index of a function among the native functions.
+    Native(usize),
+    Plugin {
+        name: String,
+        function: fn(&[Value]) -> Result<Option<Value>, String>,
+    },
+    /// The index of a function among the definitions of the copy.
+    Function(usize),
+    /// The index of an object among the objects of the copy.
+    Object(usize),
+}
+
+/// An object copied outside of every heap.
+struct CopiedObject {
+    fields: Vec<(String, CopiedValue)>,
+    finalizer: Option<CopiedValue>,
+}
+
+impl Copied {
+    /// Copies some global variables and values out of the heap.
+    fn new(
+        heap: &ManagedHeap,
+        globals: &[(String, Option<Value>)],
+        values: &[Value],
+    ) -> Result<Self, HeapError> {
+        let mut exporter = Exporter {
+            heap,
+            objects: HashMap::new(),
+            copied: Self {
+                globals: Vec::new(),
+                values: Vec::new(),
+                objects: Vec::new(),
+                functions: Vec::new(),
+            },
+            functions: Vec::new(),
+        };
+
+        for (identifier, value) in globals {
+            let value = value
+                .as_ref()
+                .map(|value| exporter.value(value))
+                .transpose()?;
+
+            exporter.copied.globals.push((identifier.clone(), value));
+        }
+
+        for value in values {
+            let value = exporter.value(value)?;
+            exporter.copied.values.push(value);
+        }
+
+        let mut copied = exporter.copied;
+        copied.functions = exporter
+            .functions
+            .into_iter()
+            .map(|function| match function {
+                Function::UserDefined {
+                    identifier,
+                    parameters,
+                    block,
+                } => Formatter::new().format(&[Statement::FunctionDefinition {
+                    identifier,
+                    parameters,
+                    block,
+                    span: None,
+                }]),
+                Function::Native(_) => unreachable!("native functions are not defined"),
+            })
+            .collect();
+
+        Ok(copied)
+    }
+
+    /// Returns the global variables and values which were copied, whose objects are referred to by their indices in the objects returned alongside them.
+    fn values(&self) -> Result<Imported, SessionError> {
+        let mut functions = Vec::new();
+
+        for source in &self.functions {
+            match parse(source)?.pop() {
+                Some(Statement::FunctionDefinition {
+                    identifier,
+                    parameters,
+                    block,
+                    ..
+                }) => functions.push(Function::UserDefined {
+                    identifier,
+                    parameters,
+                    block,
+                }),
+                _ => unreachable!("a copied function is written as a definition"),
+            }
+        }
+
+        let value = |value: &CopiedValue| match value {
+            CopiedValue::String(string) => Value::from(string.as_str()),
+            CopiedValue::Float(float) => Value::Float(*float),
+            CopiedValue::Integer(integer) => Value::Integer(*integer),
+            CopiedValue::Boolean(boolean) => Value::Boolean(*boolean),
+            CopiedValue::Native(index) => {
+                Value::Function(Function::Native(NATIVE_FUNCTIONS[*index].1.clone()))
+            }
+            CopiedValue::Plugin { name, function } => {
+                Value::Function(Function::Native(NativeFunction::Plugin(PluginFunction {
+                    name: Shared::from(name.as_str()),
+                    function: *function,
+                })))
+            }
+            CopiedValue::Function(index) => Value::Function(functions[*index].clone()),
+            CopiedValue::Object(index) => Value::ObjectReference(Pointer {
+                index: *index,
+                generation: 0,
+            }),
+        };
+
+        Ok(Imported {
+            globals: self
+                .globals
+                .iter()
+                .map(|(identifier, global)| (identifier.clone(), global.as_ref().map(value)))
+                .collect(),
+            values: self.values.iter().map(value).collect(),
+            objects: ImportedObjects(
+                self.objects
+                    .iter()
+                    .map(|object| {
+                        let data = object
+                            .fields
+                            .iter()
+                            .map(|(field, field_value)| {
+                                (Shared::from(field.as_str()), value(field_value))
+                            })
+                            .collect();
+
+                        let finalizer =
+                            object
+                                .finalizer
+                                .as_ref()
+                                .map(|finalizer| match value(finalizer) {
+                                    Value::Function(function) => function,
+                                    _ => unreachable!("a finalizer is a function"),
+                                });
+
+                        (data, finalizer)
+                    })
+                    .collect(),
+            ),
+        })
+    }
+}
+
+/// Copies values out of a heap, as they are built into a [Copied].
+struct Exporter<'a> {
+    heap: &'a ManagedHeap,
+    /// The index of the copy of each object on the heap which has been copied so far.
+    objects: HashMap<Pointer, usize>,
+    copied: Copied,
+    /// The user-defined functions which have been copied so far, which are written out once every value has been copied.
+    functions: Vec<Function>,
+}
+
+impl Exporter<'_> {
+    /// Copies a value, along with every object it references.
+    fn value(&mut self, value: &Value) -> Result<CopiedValue, HeapError> {
+        Ok(match value {
+            Value::String(string) => CopiedValue::String(string.to_string()),
+            Value::Float(float) => CopiedValue::Float(*float),
+            Value::Integer(integer) => CopiedValue::Integer(*integer),
+            Value::Boolean(boolean) => CopiedValue::Boolean(*boolean),
+            Value::Function(function) => self.function(function),
+            Value::ObjectReference(pointer) => {
+                let heap = self.heap;
+                let object = heap.get(*pointer)?;
+
+                CopiedValue::Object(self.object(
+                    &object.data,
+                    object.finalizer.as_ref(),
+                    Some(*pointer),
+                )?)
+            }
+            Value::Object(data) => CopiedValue::Object(self.object(data, None, None)?),
+        })
+    }
+
+    /// Copies a function, defining it only once however many times it is referenced.
+    fn function(&mut self, function: &Function) -> CopiedValue {
+        match function {
+            Function::Native(NativeFunction::Plugin(plugin)) => CopiedValue::Plugin {
+                name: plugin.name.to_string(),
+                function: plugin.function,
+            },
+            Function::Native(native) => CopiedValue::Native(
+                NATIVE_FUNCTIONS
+                    .iter()
+                    .position(|(_, function)| function == native)
+                    .expect("every native function is defined"),
+            ),
+            Function::UserDefined { .. } => CopiedValue::Function(
+                match self.functions.iter().position(|copied| copied == function) {
+                    Some(index) => index,
+                    None => {
+                        self.functions.push(function.clone());
+                        self.functions.len() - 1
+                    }
+                },
+            ),
+        }
+    }
+
+    /// Copies an object (unless it has already been copied), along with every object it references, returning the index of its copy. Objects stored inline have no pointer, and are copied each time they are referenced.
+    fn object(
+        &mut self,
+        data: &Object,
+        finalizer: Option<&Function>,
+        pointer: Option<Pointer>,
+    ) -> Result<usize, HeapError> {
+        if let Some(index) = pointer.and_then(|pointer| self.objects.get(&pointer)) {
+            return Ok(*index);
+        }
+
+        // The object is added before its fields are copied, so that a cycle refers back to it.
+        let index = self.copied.objects.len();
+        self.copied.objects.push(CopiedObject {
+            fields: Vec::new(),
+            finalizer: None,
+        });
+
+        if let Some(pointer) = pointer {
+            self.objects.insert(pointer, index);
+        }
+
+        let mut fields = Vec::new();
+
+        for (field, value) in data {
+            fields.push((field.to_string(), self.value(value)?));
+        }
+
+        let finalizer = finalizer.map(|finalizer| self.function(finalizer));
+        self.copied.objects[index] = CopiedObject { fields, finalizer };
+
+        Ok(index)
+    }
+}
+
+/// The values of a [Copied], before they are copied into a heap, with each object referred to by its index among the objects.
+struct Imported {
+    globals: Vec<(String, Option<Value>)>,
+    values: Vec<Value>,
+    objects: ImportedObjects,
+}
+
+/// The objects of a [Copied], each with its finalizer, if any.
+struct ImportedObjects(Vec<(Object, Option<Function>)>);
+
+impl Objects for ImportedObjects {
+    fn object(&self, pointer: Pointer) -> Result<(&Object, Option<&Function>), HeapError> {
+        self.0
+            .get(pointer.index)
+            .map(|(data, finalizer)| (data, finalizer.as_ref()))
+            .ok_or(HeapError::UseAfterFree { pointer })
+    }
+}
+
+/// Starts a thread which calls a function with some arguments, returning the handle which joins it.
+pub fn spawn(
+    function: &Value,
+    arguments: &[Value],
+    stack: &mut Stack,
+    heap: &ManagedHeap,
+) -> Result<Value, EvaluationError> {
+    let mut values = vec![function.clone()];
+    values.extend_from_slice(arguments);
+
+    let copied = Copied::new(heap, &stack.globals(), &values)
+        .map_err(|error| failed(SessionError::from(error)))?;
+    let strategy = Strategy::from_code(&heap.get_technique_code())
+        .expect("every heap has the code of a strategy");
+    let interrupt = stack.interrupt.child();
+    let capabilities = stack.capabilities;
+    let watches = stack.watches.clone();
+    let channels = stack.channels.clone();
+    let primitives = stack.primitives.clone();
+
+    let thread = thread::Builder::new()
+        .spawn(move || {
+            let mut interpreter = Interpreter::new(strategy);
+            interpreter.set_interrupt(interrupt);
+            interpreter.set_capabilities(capabilities);
+            interpreter.set_watches(watches);
+            interpreter.set_channels(channels);
+            interpreter.set_primitives(primitives);
+
+            let imported = copied.values().map_err(|error| error.to_string())?;
+
+            // The function is called once the objects reachable from the globals and arguments are allocated, so that any they share are allocated once.
+            let (declarations, call) = session::restoration(
+                &imported.globals,
+                &imported.objects,
+                &imported.values,
+                |mut values| {
+                    let function = values.remove(0);
+
+                    Expression::Call {
+                        function: Box::new(function),
+                        arguments: values.into_iter().map(Box::new).collect(),
+                        span: None,
+                    }
+                },
+            )
+            .map_err(|error| error.to_string())?;
+
+            interpreter
+                .execute(declarations)
+                .and_then(|_| interpreter.evaluate_parsed(call))
+                .map_err(|error| error.to_string())?
+                .map(|value| export(interpreter.heap(), &value))
+                .transpose()
+                .map_err(|error| error.to_string())
+        })
+        .map_err(|error| EvaluationError::ThreadFailed {
+            message: error.to_string(),
+        })?;
+
+    stack.threads.push(Some(thread));
+
+    Value::try_from(stack.threads.len() - 1).map_err(EvaluationError::from)
+}
+
+/// Waits for the thread with a handle to finish, returning its return value, if any, copied into the heap.
+pub fn join(
+    handle: i32,
+    stack: &mut Stack,
+    heap: &mut ManagedHeap,
+) -> Result<Option<Value>, EvaluationError> {
+    let thread = usize::try_from(handle)
+        .ok()
+        .and_then(|index| stack.threads.get_mut(index))
+        .and_then(Option::take)
+        .ok_or(EvaluationError::UnknownThread { handle })?;
+
+    let result = thread
+        .join()
+        .map_err(|_| EvaluationError::ThreadFailed {
+            message: String::from("the thread panicked."),
+        })?
+        .map_err(|message| EvaluationError::ThreadFailed { message })?;
+
+    match result {
+        Some(copied) => import(&copied, stack, heap).map_err(failed),
+        None => Ok(None),
+    }
+}
+
+/// Copies a value out of the heap, so that it can be sent to another thread and copied into its heap with [import].
+pub fn export(heap: &ManagedHeap, value: &Value) -> Result<Copied, SessionError> {
+    Ok(Copied::new(heap, &[], std::slice::from_ref(value))?)
+}
+
+/// Copies a value copied by [export] into the heap, returning it.
+pub fn import(
+    copied: &Copied,
+    stack: &mut Stack,
+    heap: &mut ManagedHeap,
+) -> Result<Option<Value>, SessionError> {
+    let imported = copied.values()?;
+    let (_, expression) =
+        session::restoration(&[], &imported.objects, &imported.values, |mut values| {
+            values.remove(0)
+        })?;
+
+    Ok(expression
+        .evaluate(stack, heap)
+        .map_err(InterpreterError::from)?)
+}
+
+/// Wraps an error copying a value between threads.
+fn failed(error: SessionError) -> EvaluationError {
+    EvaluationError::ThreadFailed {
+        message: error.to_string(),
+    }
+}
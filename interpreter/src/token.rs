@@ -2,7 +2,7 @@
 
 use crate::{
     expression::{BinaryOperator, UnaryOperator},
-    source::Location,
+    source::{Location, Span},
 };
 
 /// The smallest meaningful unit of the language.
@@ -10,14 +10,14 @@ use crate::{
 pub struct Token {
     /// The contained data, including the token type, and any associated data.
     data: TokenData,
-    /// The location of its first character.
-    location: Location,
+    /// The characters it was lexed from.
+    span: Span,
 }
 
 impl Token {
     /// Creates a new Token.
-    pub fn new(data: TokenData, location: Location) -> Self {
-        Self { data, location }
+    pub fn new(data: TokenData, span: Span) -> Self {
+        Self { data, span }
     }
 
     /// Returns the kind of the token.
@@ -27,7 +27,12 @@ impl Token {
 
     /// Returns the location of the token's first character.
     pub fn location(&self) -> Location {
-        self.location
+        self.span.start()
+    }
+
+    /// Returns the characters the token was lexed from.
+    pub fn span(&self) -> Span {
+        self.span
     }
 
     /// Consumes the token and returns its data.
@@ -115,6 +120,10 @@ pub enum TokenData {
     Else,
     /// The `while` string.
     While,
+    /// The `loop` string.
+    Loop,
+    /// The `break` string.
+    Break,
     /// The `return` string.
     Return,
 
@@ -127,9 +136,45 @@ pub enum TokenData {
     ///
     /// Must start with either an alphabetic character or an underscore, with all subsequent characters being alphanumeric or underscores.
     Identifier(String),
+
+    // Trivia
+    /// A line or block comment, including its delimiters. Only emitted when trivia is retained.
+    ///
+    /// A trailing comment is one which follows another token on the same line. Doc comments (see [documentation]) are emitted as comments too.
+    Comment { text: String, trailing: bool },
+    /// One or more consecutive blank lines. Only emitted when trivia is retained.
+    BlankLine,
+
+    /// The end of the source code, which is always the last token.
+    EndOfFile,
 }
 
 impl TokenData {
+    /// Returns the documentation written in the token if it is a doc comment, or [None] otherwise. See [documentation].
+    pub fn documentation(&self) -> Option<String> {
+        match self {
+            TokenData::Comment { text, .. } => documentation(text),
+            _ => None,
+        }
+    }
+
+    /// Returns how the token is written if it is a keyword, which cannot be used as an identifier, or [None] otherwise.
+    pub fn keyword(&self) -> Option<&'static str> {
+        Some(match self {
+            TokenData::Boolean(true) => "true",
+            TokenData::Boolean(false) => "false",
+            TokenData::If => "if",
+            TokenData::Else => "else",
+            TokenData::While => "while",
+            TokenData::Loop => "loop",
+            TokenData::Break => "break",
+            TokenData::Return => "return",
+            TokenData::Let => "let",
+            TokenData::Fu => "fu",
+            _ => return None,
+        })
+    }
+
     /// Returns the [TokenKind] of some [TokenData].
     pub fn kind(&self) -> TokenKind {
         match self {
@@ -174,16 +219,73 @@ impl TokenData {
             TokenData::If => TokenKind::If,
             TokenData::Else => TokenKind::Else,
             TokenData::While => TokenKind::While,
+            TokenData::Loop => TokenKind::Loop,
+            TokenData::Break => TokenKind::Break,
             TokenData::Return => TokenKind::Return,
 
             // Identifier related
             TokenData::Let => TokenKind::Let,
             TokenData::Fu => TokenKind::Fu,
             TokenData::Identifier(_) => TokenKind::Identifier,
+
+            // Trivia
+            TokenData::Comment { .. } => TokenKind::Comment,
+            TokenData::BlankLine => TokenKind::BlankLine,
+
+            TokenData::EndOfFile => TokenKind::EndOfFile,
         }
     }
 }
 
+/// Returns the documentation written in a comment (including its delimiters) if it is a doc comment, or [None] otherwise.
+///
+/// Doc comments are line comments starting with `///`, or block comments starting with `/**`, which document whatever follows them, such as a function definition. The documentation is the text of the comment without its delimiters, or the leading `*` of each line of a block comment, and with any indentation removed.
+///
+/// ```
+/// use slang_core::token::documentation;
+///
+/// assert_eq!(documentation("/// Returns the larger of two numbers."), Some(String::from("Returns the larger of two numbers.")));
+/// assert_eq!(documentation("/**\n * Calls a function\n * a number of times.\n */"), Some(String::from("Calls a function\na number of times.")));
+/// assert_eq!(documentation("// An ordinary comment."), None);
+/// ```
+pub fn documentation(comment: &str) -> Option<String> {
+    let lines: Vec<&str> = if let Some(line) = comment.strip_prefix("///") {
+        if line.starts_with('/') {
+            return None;
+        }
+
+        vec![line]
+    } else if let Some(block) = comment
+        .strip_prefix("/**")
+        .and_then(|block| block.strip_suffix("*/"))
+    {
+        // Comments such as `/*****/` are decoration, rather than documentation.
+        if block.starts_with('*') {
+            return None;
+        }
+
+        block
+            .lines()
+            .map(|line| {
+                let line = line.trim_start();
+                line.strip_prefix('*').unwrap_or(line)
+            })
+            .collect()
+    } else {
+        return None;
+    };
+
+    Some(
+        lines
+            .iter()
+            .map(|line| line.trim())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string(),
+    )
+}
+
 /// A flag signalling the type of a token, without any additional data.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum TokenKind {
@@ -261,6 +363,10 @@ pub enum TokenKind {
     Else,
     /// The `while` string.
     While,
+    /// The `loop` string.
+    Loop,
+    /// The `break` string.
+    Break,
     /// The `return` string.
     Return,
 
@@ -273,9 +379,23 @@ pub enum TokenKind {
     ///
     /// Must start with either an alphabetic character or an underscore, with all subsequent characters being alphanumeric or underscores.
     Identifier,
+
+    // Trivia
+    /// A line or block comment, including its delimiters. Only emitted when trivia is retained.
+    Comment,
+    /// One or more consecutive blank lines. Only emitted when trivia is retained.
+    BlankLine,
+
+    /// The end of the source code, which is always the last token.
+    EndOfFile,
 }
 
 impl TokenKind {
+    /// Returns whether the token is trivia, which carries no meaning for the parser.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Self::Comment | Self::BlankLine)
+    }
+
     /// Attempts to cast itself to a [BinaryOperator], returning [None] if it does not represent a binary operator.
     pub fn binary_operator(&self) -> Option<BinaryOperator> {
         Some(match self {
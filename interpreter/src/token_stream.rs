@@ -5,40 +5,122 @@ use std::collections::VecDeque;
 use crate::{
     expression::{BinaryOperator, UnaryOperator},
     parser::ParserError,
-    source::{GeneralLocation, Location},
+    source::Span,
     token::{Token, TokenData, TokenKind},
 };
 
-/// A wrapper around a queue of tokens.
-pub struct TokenStream {
+/// A wrapper around a queue of tokens, which always ends with an end of file token.
+///
+/// Tokens are pulled from their source only as they are needed, so the source can be a [Lexer](crate::lexer::Lexer) which is still lexing, rather than every token being held at once.
+pub struct TokenStream<'a> {
+    /// The tokens which have not been pulled yet.
+    source: Box<dyn Iterator<Item = Token> + 'a>,
+    /// The tokens which have been pulled to look ahead, but not consumed yet.
     tokens: VecDeque<Token>,
+    /// The characters of the last token which was consumed, other than trivia.
+    previous: Option<Span>,
 }
 
-impl TokenStream {
-    /// Creates a new token stream from a list of tokens.
-    pub fn new(tokens: Vec<Token>) -> Self {
+impl<'a> TokenStream<'a> {
+    /// Creates a new token stream from some tokens, as returned by the lexer, so ending with an end of file token.
+    pub fn new(tokens: impl IntoIterator<Item = Token, IntoIter: 'a>) -> Self {
         Self {
-            tokens: tokens.into(),
+            source: Box::new(tokens.into_iter()),
+            tokens: VecDeque::new(),
+            previous: None,
         }
     }
 
-    /// Returns a reference to the next token in the stream.
-    pub fn peek(&self) -> Option<&Token> {
-        self.tokens.get(0)
+    /// Pulls the next token from the source, panicking if there are none left, as the end of file token is never consumed.
+    fn pull(&mut self) {
+        let token = self
+            .source
+            .next()
+            .expect("the token stream ends with an end of file token");
+
+        self.tokens.push_back(token);
     }
 
-    /// Consumes the next token and returns it.
-    pub fn advance(&mut self) -> Option<Token> {
-        self.tokens.pop_front()
+    /// Returns a reference to the next token in the stream, skipping over any trivia.
+    pub fn peek(&mut self) -> &Token {
+        self.peek_nth(0)
+    }
+
+    /// Returns a reference to the token a number of tokens after the next one (so `0` is the next token), skipping over any trivia, or to the end of file token if that comes first.
+    pub fn peek_nth(&mut self, count: usize) -> &Token {
+        let position = loop {
+            let mut remaining = count;
+
+            match self.tokens.iter().position(|token| {
+                if token.kind().is_trivia() {
+                    return false;
+                }
+
+                if remaining == 0 || token.kind() == TokenKind::EndOfFile {
+                    return true;
+                }
+
+                remaining -= 1;
+                false
+            }) {
+                Some(position) => break position,
+                None => self.pull(),
+            }
+        };
+
+        &self.tokens[position]
+    }
+
+    /// Consumes the next token and returns it, discarding any trivia before it. The end of file token is never consumed, so it is returned again by each call once it is reached.
+    pub fn advance(&mut self) -> Token {
+        self.peek();
+
+        while self
+            .tokens
+            .front()
+            .is_some_and(|token| token.kind().is_trivia())
+        {
+            self.tokens.pop_front();
+        }
+
+        let token = match self.tokens.front() {
+            Some(token) if token.kind() == TokenKind::EndOfFile => token.clone(),
+            _ => self
+                .tokens
+                .pop_front()
+                .expect("the token stream ends with an end of file token"),
+        };
+
+        self.previous = Some(token.span());
+
+        token
+    }
+
+    /// Returns the characters of the last token which was consumed, other than trivia, if any have been.
+    pub fn previous(&self) -> Option<Span> {
+        self.previous
+    }
+
+    /// Consumes and returns the next token only if it is trivia.
+    pub fn trivia(&mut self) -> Option<Token> {
+        if self.tokens.is_empty() {
+            self.pull();
+        }
+
+        if self.tokens.front()?.kind().is_trivia() {
+            self.tokens.pop_front()
+        } else {
+            None
+        }
     }
 
     /// Consumes and returns the next token only if it matches a target.
     pub fn only_take(&mut self, targets: &[TokenKind]) -> Option<Token> {
-        if let Some(next) = self.peek() {
-            for target in targets {
-                if &next.kind() == target {
-                    return self.advance();
-                }
+        let next = self.peek();
+
+        for target in targets {
+            if &next.kind() == target {
+                return Some(self.advance());
             }
         }
 
@@ -47,12 +129,12 @@ impl TokenStream {
 
     /// Consumes the next token only if it matches a target. The token is not returned.
     pub fn matches(&mut self, targets: &[TokenKind]) -> bool {
-        if let Some(next) = self.peek() {
-            for target in targets {
-                if &next.kind() == target {
-                    self.advance();
-                    return true;
-                }
+        let next = self.peek();
+
+        for target in targets {
+            if &next.kind() == target {
+                self.advance();
+                return true;
             }
         }
 
@@ -63,16 +145,15 @@ impl TokenStream {
     pub fn binary_operator(
         &mut self,
         targets: &[BinaryOperator],
-    ) -> Option<(BinaryOperator, Location)> {
-        if let Some(next) = self.peek() {
-            let location = next.location();
-
-            if let Some(operator) = next.kind().binary_operator() {
-                for target in targets {
-                    if target == &operator {
-                        self.advance();
-                        return Some((operator, location));
-                    }
+    ) -> Option<(BinaryOperator, Span)> {
+        let next = self.peek();
+        let span = next.span();
+
+        if let Some(operator) = next.kind().binary_operator() {
+            for target in targets {
+                if target == &operator {
+                    self.advance();
+                    return Some((operator, span));
                 }
             }
         }
@@ -81,19 +162,15 @@ impl TokenStream {
     }
 
     /// Consumes the next token only if it is a unary operator and matches a target.
-    pub fn unary_operator(
-        &mut self,
-        targets: &[UnaryOperator],
-    ) -> Option<(UnaryOperator, Location)> {
-        if let Some(next) = self.peek() {
-            let location = next.location();
-
-            if let Some(operator) = next.kind().unary_operator() {
-                for target in targets {
-                    if target == &operator {
-                        self.advance();
-                        return Some((operator, location));
-                    }
+    pub fn unary_operator(&mut self, targets: &[UnaryOperator]) -> Option<(UnaryOperator, Span)> {
+        let next = self.peek();
+        let span = next.span();
+
+        if let Some(operator) = next.kind().unary_operator() {
+            for target in targets {
+                if target == &operator {
+                    self.advance();
+                    return Some((operator, span));
                 }
             }
         }
@@ -101,45 +178,91 @@ impl TokenStream {
         None
     }
 
-    /// Consumes the next token only if it is an identifier. Will return an error if it is not an identifier.
+    /// Consumes the next token only if it is an identifier. Will return an error if it is not an identifier, which is more specific if it is a keyword.
     pub fn consume_identifier(&mut self) -> Result<String, ParserError> {
-        let token = self.peek().cloned();
+        let token = self.peek();
+        let span = token.span();
 
-        match token.map(|token| (token.location(), token.data())) {
-            Some((_, TokenData::Identifier(identifier))) => {
+        match token.clone().data() {
+            TokenData::Identifier(identifier) => {
                 self.advance();
                 Ok(identifier)
             }
-            Some((location, _)) => Err(ParserError::ExpectedToken {
-                expected: vec![TokenKind::Identifier],
-                location: GeneralLocation::Location(location),
-            }),
-            None => Err(ParserError::ExpectedToken {
-                expected: vec![TokenKind::Identifier],
-                location: GeneralLocation::EndOfFile,
-            }),
+            data => match data.keyword() {
+                Some(keyword) => Err(ParserError::KeywordAsIdentifier {
+                    keyword: keyword.to_string(),
+                    span,
+                }),
+                None => Err(ParserError::ExpectedToken {
+                    expected: vec![TokenKind::Identifier],
+                    span,
+                }),
+            },
         }
     }
 
     /// Consumes the next token only if it is of a certain kind. Will return an error if it is not of that kind.
     pub fn consume(&mut self, kind: TokenKind) -> Result<Token, ParserError> {
-        if let Some(token) = self.only_take(&[kind]) {
-            Ok(token)
-        } else if let Some(token) = self.peek() {
-            Err(ParserError::ExpectedToken {
+        match self.only_take(&[kind]) {
+            Some(token) => Ok(token),
+            None => Err(ParserError::ExpectedToken {
                 expected: vec![kind],
-                location: GeneralLocation::Location(token.location()),
-            })
+                span: self.peek().span(),
+            }),
+        }
+    }
+
+    /// Consumes the `;` which ends a statement. Will return an error if it is not there, unless the statement can end without one: when the next token is on a later line than the end of the statement, or is the `}` closing its block, or the end of the source code.
+    ///
+    /// The statement is only ended where it is already syntactically complete, so an operator at the end of a line, or at the start of the next, continues the statement onto the next line, as do an opening parenthesis (making a call) and a `.` (accessing a field) at the start of a line.
+    pub fn terminate(&mut self) -> Result<(), ParserError> {
+        if self.matches(&[TokenKind::Semicolon]) {
+            return Ok(());
+        }
+
+        let previous = self.previous;
+        let next = self.peek();
+
+        let on_later_line =
+            previous.is_some_and(|previous| next.location().line() > previous.end().line());
+
+        if on_later_line || matches!(next.kind(), TokenKind::RightBrace | TokenKind::EndOfFile) {
+            Ok(())
         } else {
             Err(ParserError::ExpectedToken {
-                expected: vec![kind],
-                location: GeneralLocation::EndOfFile,
+                expected: vec![TokenKind::Semicolon],
+                span: next.span(),
             })
         }
     }
 
-    /// Returns whether all of the tokens have been consumed.
-    pub fn at_end(&self) -> bool {
-        self.tokens.is_empty()
+    /// Skips tokens until the next is one of some targets, returning whether one was found before the end of the statement. Anything within parentheses or braces is skipped over as a whole, so a target nested within them is not found.
+    pub fn skip_to(&mut self, targets: &[TokenKind]) -> bool {
+        let mut depth = 0;
+
+        loop {
+            let kind = self.peek().kind();
+
+            if depth == 0 && targets.contains(&kind) {
+                return true;
+            }
+
+            match kind {
+                TokenKind::LeftParenthesis | TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightParenthesis | TokenKind::RightBrace if depth > 0 => depth -= 1,
+                TokenKind::RightParenthesis
+                | TokenKind::RightBrace
+                | TokenKind::Semicolon
+                | TokenKind::EndOfFile => return false,
+                _ => {}
+            }
+
+            self.advance();
+        }
+    }
+
+    /// Returns whether all of the tokens (other than trivia) have been consumed, so only the end of file token remains.
+    pub fn at_end(&mut self) -> bool {
+        self.peek().kind() == TokenKind::EndOfFile
     }
 }
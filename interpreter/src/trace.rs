@@ -0,0 +1,16 @@
+//! The targets of the diagnostic events about what the interpreter is doing, which are emitted with [tracing].
+//!
+//! Lexing, parsing and execution are spans, and each statement and collection is an event, with its figures as fields. Every target starts with `slang`, so `slang=debug` enables them all.
+//!
+//! Stats are events too, on [STATS], which a [Stats](crate::stats::Stats) layer records.
+
+/// The target of events about lexing.
+pub const LEXER: &str = "slang::lexer";
+/// The target of events about parsing.
+pub const PARSER: &str = "slang::parser";
+/// The target of events about executing statements.
+pub const EXECUTE: &str = "slang::execute";
+/// The target of events about garbage collection, and reclaiming objects in general.
+pub const GC: &str = "slang::gc";
+/// The target of the trace events which stats are recorded from: the state of the interpreter before each statement, each collection, each expression evaluated and each call to a user-defined function.
+pub const STATS: &str = "slang::stats";
@@ -0,0 +1,84 @@
+//! Tuning the garbage collector while a program runs, with `gc_config`, so that a script can adjust when collections start (and how they mark the heap) based on what `gc_stats` reports.
+//!
+//! Only the `gc` heap can be tuned. Settings are the fields of an object, named like those of [Tuning], and any which are left out keep their current values.
+
+use crate::{
+    expression::EvaluationError,
+    heap::{
+        ManagedHeap, Object,
+        garbage_collected::{Mode, Tuning},
+    },
+    shared::Shared,
+    value::Value,
+};
+
+/// Applies the settings to the heap's collector, returning the configuration now in effect as an object, or [None] if the heap cannot be tuned. Nothing is changed if any setting is not valid.
+pub fn configure(
+    settings: &Object,
+    heap: &mut ManagedHeap,
+) -> Result<Option<Value>, EvaluationError> {
+    let Some(mut tuning) = heap.tuning() else {
+        return Ok(None);
+    };
+
+    let mut settings = settings.iter().collect::<Vec<_>>();
+
+    // Settings are applied in order of name, so that the same error is reported for the same settings.
+    settings.sort_by_key(|(setting, _)| *setting);
+
+    for (setting, value) in settings {
+        let invalid = || EvaluationError::InvalidGcSetting {
+            setting: setting.to_string(),
+            value: value.clone(),
+        };
+
+        match setting.as_ref() {
+            "threshold" => tuning.threshold = value.clone().try_into()?,
+            "growth_factor" => {
+                tuning.growth_factor = match value {
+                    Value::Integer(factor) => *factor as f64,
+                    value => value.clone().try_into()?,
+                };
+
+                if tuning.growth_factor < 1.0 {
+                    return Err(invalid());
+                }
+            }
+            "every_n_statements" => {
+                let every_n_statements: usize = value.clone().try_into()?;
+
+                tuning.every_n_statements = (every_n_statements > 0).then_some(every_n_statements);
+            }
+            "mode" => {
+                let name: Shared<str> = value.clone().try_into()?;
+
+                tuning.mode = Mode::from_name(&name).ok_or_else(invalid)?;
+            }
+            _ => {
+                return Err(EvaluationError::UnknownGcSetting {
+                    setting: setting.to_string(),
+                });
+            }
+        }
+    }
+
+    heap.set_tuning(tuning);
+
+    Ok(Some(Value::Object(to_object(tuning))))
+}
+
+/// Converts a configuration to an object, with a field for each setting.
+pub fn to_object(tuning: Tuning) -> Object {
+    [
+        ("threshold", Value::Integer(tuning.threshold as i32)),
+        ("growth_factor", Value::Float(tuning.growth_factor)),
+        (
+            "every_n_statements",
+            Value::Integer(tuning.every_n_statements.unwrap_or(0) as i32),
+        ),
+        ("mode", Value::String(Shared::from(tuning.mode.name()))),
+    ]
+    .into_iter()
+    .map(|(field, value)| (Shared::from(field), value))
+    .collect()
+}
@@ -1,22 +1,85 @@
+//! The values which can be held by variables and object fields.
+
 use std::fmt::{Debug, Display};
 
 use crate::{
     heap::{Object, Pointer},
+    shared::Shared,
     statement::Statement,
 };
 
 #[derive(Clone, PartialEq)]
 pub enum NativeFunction {
     Print,
+    PrintWith,
+    Write,
+    ErrorPrint,
     Format,
     Input,
     Int,
     Float,
+    Gc,
+    HeapDump,
+    HeapStats,
+    HeapObjects,
+    StackFrames,
+    MemoryManagement,
+    GcConfig,
+    GcStats,
+    Finalize,
+    Spawn,
+    Join,
+    Channel,
+    Send,
+    Receive,
+    Mutex,
+    Lock,
+    Atomic,
+    AtomicAdd,
+    Exec,
+    ReadFile,
+    WriteFile,
+    Exists,
+    MakeDirectory,
+    Remove,
+    ListDirectory,
+    RegexMatch,
+    RegexFindAll,
+    RegexReplace,
+    Error,
+    ToFixed,
+    Thousands,
+    PadLeft,
+    PadRight,
+    Builder,
+    Append,
+    Build,
+    Inspect,
+    Assert,
+    AssertEqual,
+    /// A native function loaded from a plugin.
+    Plugin(PluginFunction),
+}
+
+/// A native function loaded from a plugin, which is called with the values of its arguments and returns a value, or a message explaining why it failed. Objects among the arguments are passed as they are, so a reference to an object on the heap cannot be followed.
+#[derive(Clone)]
+pub struct PluginFunction {
+    /// The name the function was registered with.
+    pub name: Shared<str>,
+    pub function: fn(&[Value]) -> Result<Option<Value>, String>,
+}
+
+impl PartialEq for PluginFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && std::ptr::fn_addr_eq(self.function, other.function)
+    }
 }
 
 #[derive(Clone, PartialEq)]
 pub enum Function {
     UserDefined {
+        /// The name the function was defined with.
+        identifier: String,
         parameters: Vec<String>,
         block: Box<Statement>,
     },
@@ -25,7 +88,7 @@ pub enum Function {
 
 #[derive(Clone, PartialEq)]
 pub enum Value {
-    String(String),
+    String(Shared<str>),
     Float(f64),
     Integer(i32),
     Boolean(bool),
@@ -44,9 +107,10 @@ impl Display for Value {
             Self::Function(function) => match function {
                 Function::Native(_) => write!(f, "<native function>"),
                 Function::UserDefined {
+                    identifier,
                     parameters,
                     block: _,
-                } => write!(f, "<function with {} named parameters>", parameters.len()),
+                } => write!(f, "<fu {}({})>", identifier, parameters.join(", ")),
             },
             Self::Object(fields) => {
                 write!(
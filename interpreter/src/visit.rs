@@ -0,0 +1,235 @@
+//! Traversal of the syntax tree, so that each pass over it (such as escape analysis) only needs to handle the statements and expressions it is interested in.
+//!
+//! A pass implements [Visitor] (or [VisitorMut], to change the tree as it goes), overriding the methods for what it is interested in. By default, each method walks into the children of what it visits with the matching `walk_` function, which an overriding method can also call to carry on into the children.
+
+use crate::{expression::Expression, statement::Statement};
+
+/// A pass over the syntax tree, which visits every statement and expression in the order they were written.
+pub trait Visitor<'a> {
+    fn visit_statement(&mut self, statement: &'a Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &'a Expression) {
+        walk_expression(self, expression);
+    }
+}
+
+/// Visits the statements and expressions directly within a statement.
+pub fn walk_statement<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, statement: &'a Statement) {
+    match statement {
+        Statement::VariableDeclaration { initialiser, .. } => {
+            if let Some(initialiser) = initialiser {
+                visitor.visit_expression(initialiser);
+            }
+        }
+        Statement::IfStatement {
+            condition,
+            execute_if_true,
+            execute_if_false,
+            ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(execute_if_true);
+
+            if let Some(execute_if_false) = execute_if_false {
+                visitor.visit_statement(execute_if_false);
+            }
+        }
+        Statement::FunctionDefinition { block, .. } | Statement::Test { block, .. } => {
+            visitor.visit_statement(block)
+        }
+        Statement::Return(expression) => {
+            if let Some(expression) = expression {
+                visitor.visit_expression(expression);
+            }
+        }
+        Statement::WhileLoop {
+            condition, block, ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(block);
+        }
+        Statement::Loop { block, .. } => visitor.visit_statement(block),
+        Statement::Block(statements) => {
+            for statement in statements {
+                visitor.visit_statement(statement);
+            }
+        }
+        Statement::Expression(expression) | Statement::Yield(expression) => {
+            visitor.visit_expression(expression)
+        }
+        Statement::Break | Statement::Comment { .. } | Statement::BlankLine => {}
+    }
+}
+
+/// Visits the expressions directly within an expression.
+pub fn walk_expression<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, expression: &'a Expression) {
+    match expression {
+        Expression::Ternary {
+            condition,
+            left,
+            right,
+            ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::Binary { left, right, .. } => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::Unary { operand, .. } => visitor.visit_expression(operand),
+        Expression::Call {
+            function,
+            arguments,
+            ..
+        } => {
+            visitor.visit_expression(function);
+
+            for argument in arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+        Expression::Assignment { value, .. } => visitor.visit_expression(value),
+        Expression::Grouping { contained } => visitor.visit_expression(contained),
+        Expression::Literal { .. } | Expression::Variable { .. } => {}
+        Expression::GetField { object, .. } => visitor.visit_expression(object),
+        Expression::SetField { object, value, .. } => {
+            visitor.visit_expression(object);
+            visitor.visit_expression(value);
+        }
+        Expression::Object { fields, .. } => {
+            for (_, value) in fields {
+                visitor.visit_expression(value);
+            }
+        }
+        Expression::Block { block } => visitor.visit_statement(block),
+        Expression::If {
+            condition,
+            execute_if_true,
+            execute_if_false,
+            ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(execute_if_true);
+            visitor.visit_statement(execute_if_false);
+        }
+    }
+}
+
+/// A pass over the syntax tree which can change it, visiting every statement and expression in the order they were written.
+pub trait VisitorMut {
+    fn visit_statement(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression);
+    }
+}
+
+/// Visits the statements and expressions directly within a statement, so that they can be changed.
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut Statement) {
+    match statement {
+        Statement::VariableDeclaration { initialiser, .. } => {
+            if let Some(initialiser) = initialiser {
+                visitor.visit_expression(initialiser);
+            }
+        }
+        Statement::IfStatement {
+            condition,
+            execute_if_true,
+            execute_if_false,
+            ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(execute_if_true);
+
+            if let Some(execute_if_false) = execute_if_false {
+                visitor.visit_statement(execute_if_false);
+            }
+        }
+        Statement::FunctionDefinition { block, .. } | Statement::Test { block, .. } => {
+            visitor.visit_statement(block)
+        }
+        Statement::Return(expression) => {
+            if let Some(expression) = expression {
+                visitor.visit_expression(expression);
+            }
+        }
+        Statement::WhileLoop {
+            condition, block, ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(block);
+        }
+        Statement::Loop { block, .. } => visitor.visit_statement(block),
+        Statement::Block(statements) => {
+            for statement in statements {
+                visitor.visit_statement(statement);
+            }
+        }
+        Statement::Expression(expression) | Statement::Yield(expression) => {
+            visitor.visit_expression(expression)
+        }
+        Statement::Break | Statement::Comment { .. } | Statement::BlankLine => {}
+    }
+}
+
+/// Visits the expressions directly within an expression, so that they can be changed.
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut Expression) {
+    match expression {
+        Expression::Ternary {
+            condition,
+            left,
+            right,
+            ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::Binary { left, right, .. } => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        Expression::Unary { operand, .. } => visitor.visit_expression(operand),
+        Expression::Call {
+            function,
+            arguments,
+            ..
+        } => {
+            visitor.visit_expression(function);
+
+            for argument in arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+        Expression::Assignment { value, .. } => visitor.visit_expression(value),
+        Expression::Grouping { contained } => visitor.visit_expression(contained),
+        Expression::Literal { .. } | Expression::Variable { .. } => {}
+        Expression::GetField { object, .. } => visitor.visit_expression(object),
+        Expression::SetField { object, value, .. } => {
+            visitor.visit_expression(object);
+            visitor.visit_expression(value);
+        }
+        Expression::Object { fields, .. } => {
+            for (_, value) in fields {
+                visitor.visit_expression(value);
+            }
+        }
+        Expression::Block { block } => visitor.visit_statement(block),
+        Expression::If {
+            condition,
+            execute_if_true,
+            execute_if_false,
+            ..
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_statement(execute_if_true);
+            visitor.visit_statement(execute_if_false);
+        }
+    }
+}
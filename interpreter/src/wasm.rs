@@ -0,0 +1,84 @@
+//! The API for running slang in a browser playground, enabled by the `wasm` feature and built for `wasm32-unknown-unknown` with wasm-bindgen.
+
+use tracing_subscriber::{Registry, layer::SubscriberExt};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    console::Capture,
+    heap::{ManagedHeap, Strategy},
+    interpreter::Interpreter,
+    stats::{self, Sampling, Stats},
+};
+
+#[cfg(target_family = "wasm")]
+#[wasm_bindgen]
+extern "C" {
+    /// The milliseconds since the page was loaded, from the browser's `performance.now()`.
+    #[wasm_bindgen(js_namespace = performance)]
+    fn now() -> f64;
+}
+
+/// The result of running a program in the playground.
+#[wasm_bindgen]
+pub struct Run {
+    output: String,
+    errors: Vec<String>,
+    stats: String,
+}
+
+#[wasm_bindgen]
+impl Run {
+    /// Everything the program printed.
+    #[wasm_bindgen(getter)]
+    pub fn output(&self) -> String {
+        self.output.clone()
+    }
+
+    /// Each error from lexing, parsing or evaluating the program. Evaluation stops at the first error, so there is at most one of those.
+    #[wasm_bindgen(getter)]
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.clone()
+    }
+
+    /// The stats recorded before every statement and for every collection, as a JSON object with the keys `entries` and `collections`, as written by `--stats-format json`.
+    #[wasm_bindgen(getter)]
+    pub fn stats(&self) -> String {
+        self.stats.clone()
+    }
+}
+
+/// Runs a program with a fresh interpreter whose heap is managed with a strategy, given by its code (`gc`, `gen`, `mc`, `cp`, `rc`, `rg` or `na`), recording stats before every statement.
+#[wasm_bindgen]
+pub fn run(source: &str, heap: &str) -> Result<Run, JsError> {
+    #[cfg(target_family = "wasm")]
+    crate::clock::set_clock(|| std::time::Duration::from_secs_f64(now() / 1000.0));
+
+    let strategy = Strategy::from_code(heap)
+        .ok_or_else(|| JsError::new(&format!("Unknown heap `{}`.", heap)))?;
+
+    let output = Capture::default();
+
+    let mut interpreter = Interpreter::with_heap(ManagedHeap::new(strategy));
+    interpreter.set_output(output.clone());
+    interpreter
+        .load_prelude()
+        .map_err(|error| JsError::new(&error.to_string()))?;
+
+    let recorded = Stats::new(Sampling::default());
+    let recording = tracing::subscriber::set_default(Registry::default().with(recorded.layer()));
+
+    let errors = match interpreter.run(source) {
+        Ok(()) => Vec::new(),
+        Err(error) => error.to_string().lines().map(String::from).collect(),
+    };
+
+    let (stack, mut heap) = interpreter.into_parts();
+    stats::finish(&mut heap, &stack);
+    drop(recording);
+
+    Ok(Run {
+        output: output.contents(),
+        errors,
+        stats: recorded.json(),
+    })
+}
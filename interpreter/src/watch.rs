@@ -0,0 +1,72 @@
+//! Watchpoints, which log to stderr whenever a watched variable or field is assigned, given by `--watch-var <name>`.
+//!
+//! A name without a dot (`total`) watches assignments to variables with that name, in any scope. A name starting with a dot (`.x`) watches a field of any object, and a name with a variable before the dot (`point.x`) watches a field of the objects set through that variable. Declaring a variable with `let` defines it rather than assigning it, so it is not logged.
+
+use crate::value::Value;
+
+/// The variables and fields which are watched.
+#[derive(Clone, Default)]
+pub struct Watches {
+    names: Vec<String>,
+}
+
+impl Watches {
+    /// Creates watchpoints for some names.
+    pub fn new(names: Vec<String>) -> Self {
+        Self { names }
+    }
+
+    /// Returns whether nothing is watched, which is the default.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Logs the assignment of a variable, if it is watched. The previous value is [None] if the variable had not been initialised.
+    pub fn variable(&self, identifier: &str, previous: Option<&Value>, next: Option<&Value>) {
+        if self.names.iter().any(|name| name == identifier) {
+            log(identifier, previous, next);
+        }
+    }
+
+    /// Logs the assignment of a field, if it is watched. The variable is the one the object was set through, if it was, and the previous value is [None] if the object did not have the field.
+    pub fn field(
+        &self,
+        variable: Option<&str>,
+        field: &str,
+        previous: Option<&Value>,
+        next: &Value,
+    ) {
+        if self.is_empty() {
+            return;
+        }
+
+        let any = format!(".{}", field);
+        let target = match variable {
+            Some(variable) => format!("{}.{}", variable, field),
+            None => any.clone(),
+        };
+
+        if self
+            .names
+            .iter()
+            .any(|name| *name == any || *name == target)
+        {
+            log(&target, previous, Some(next));
+        }
+    }
+}
+
+/// Writes an assignment to stderr.
+fn log(target: &str, previous: Option<&Value>, next: Option<&Value>) {
+    let describe = |value: Option<&Value>| match value {
+        Some(value) => format!("{:?}", value),
+        None => String::from("nothing"),
+    };
+
+    eprintln!(
+        "watchpoint: {} changed from {} to {}",
+        target,
+        describe(previous),
+        describe(next)
+    );
+}
@@ -0,0 +1,196 @@
+//! Checks that the command line reads its defaults from the subset of TOML in a `slang.toml` file found in the working directory, that command line arguments take precedence over them, and that a file which is not valid is reported along with the line it is invalid on.
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+/// A program which prints the heap's strategy, its tuning, and how many objects are on the heap while a function holds an object literal which never escapes it (along with the tuning, which is held by a global).
+const PROGRAM: &str = "fu held() { let point = {x: 1}; point.x = 2; return heap_objects(); }
+print(memory_management());
+let tuning = gc_config({});
+print(tuning.threshold);
+print(tuning.growth_factor);
+print(tuning.every_n_statements);
+print(held());";
+
+/// Creates an empty directory for a test, containing a configuration file and the program.
+fn project(name: &str, config: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("slang-config-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).expect("the directory can be created");
+
+    fs::write(directory.join("slang.toml"), config).expect("the configuration can be written");
+    fs::write(directory.join("program.slang"), PROGRAM).expect("the program can be written");
+
+    directory
+}
+
+/// Runs the program in a project's directory with some arguments, then removes the project.
+fn run(directory: &PathBuf, arguments: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_slang_interpreter"))
+        .args(arguments)
+        .arg("program.slang")
+        .current_dir(directory)
+        .output()
+        .expect("the interpreter can be run");
+
+    let _ = fs::remove_dir_all(directory);
+
+    output
+}
+
+/// Returns the lines the program printed, failing if it did not succeed.
+fn lines(output: &Output) -> Vec<String> {
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+/// Returns the error reported for the configuration file, failing unless the command exited because of it.
+fn error(output: &Output) -> String {
+    assert_eq!(output.status.code(), Some(2));
+
+    String::from_utf8_lossy(&output.stderr).trim().to_string()
+}
+
+#[test]
+fn defaults_are_used_without_a_configuration() {
+    let directory = project("defaults", "");
+
+    assert_eq!(lines(&run(&directory, &[])), ["gc", "0", "1", "0", "2"]);
+}
+
+#[test]
+fn settings_are_read() {
+    let config = "# The heap, with a comment after it.
+heap = \"gc\"  # Garbage collection.
+
+[ gc ]
+threshold = 50
+growth_factor = 2.5
+every_n_statements = 10
+threads = 2
+
+[optimizations]
+escape_analysis = true
+rc_deferred = false
+";
+    let directory = project("settings", config);
+
+    assert_eq!(lines(&run(&directory, &[])), ["gc", "50", "2.5", "10", "1"]);
+}
+
+#[test]
+fn command_line_arguments_take_precedence() {
+    let config = "heap = \"rc\"\n[gc]\nthreshold = 50\ngrowth_factor = 2\n";
+    let directory = project("precedence", config);
+
+    assert_eq!(
+        lines(&run(&directory, &["--gc-threshold", "7", "gc"])),
+        ["gc", "7", "2", "0", "2"]
+    );
+}
+
+#[test]
+fn strings_may_contain_a_hash() {
+    let config = "[stats]\nenabled = true\ndirectory = \"stats#1\" # Not \"stats\".\n";
+    let directory = project("hash", config);
+    let stats = directory.join("stats#1").join("program.slang.csv");
+    fs::create_dir(directory.join("stats#1")).expect("the directory can be created");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_slang_interpreter"))
+        .arg("program.slang")
+        .current_dir(&directory)
+        .output()
+        .expect("the interpreter can be run");
+    let written = stats.is_file();
+    let _ = fs::remove_dir_all(&directory);
+
+    lines(&output);
+    assert!(written);
+}
+
+#[test]
+fn invalid_syntax_is_reported_with_its_line() {
+    for (name, line) in [
+        ("no-value", "heap"),
+        ("unquoted", "heap = gc"),
+        ("unterminated", "heap = \"gc"),
+        ("no-fraction", "[gc]\ngrowth_factor = 2."),
+        ("exponent", "[gc]\ngrowth_factor = 1e3"),
+    ] {
+        let directory = project(name, &format!("# A comment.\n\n{}\n", line));
+        let line_number = line.lines().count() + 2;
+
+        assert!(
+            error(&run(&directory, &[]))
+                .ends_with(&format!("slang.toml:{} Invalid syntax.", line_number)),
+            "{}",
+            line
+        );
+    }
+}
+
+#[test]
+fn unknown_keys_are_reported() {
+    let directory = project("unknown", "[gc]\nthreshold = 1\nlimit = 2\n");
+
+    assert!(error(&run(&directory, &[])).ends_with("slang.toml:3 Unknown key `gc.limit`."));
+
+    let directory = project("unknown-rule", "[lint]\nunused = \"allow\"\n");
+
+    assert!(error(&run(&directory, &[])).ends_with("slang.toml:2 Unknown key `lint.unused`."));
+}
+
+#[test]
+fn invalid_values_are_reported() {
+    for (name, line, expected) in [
+        ("heap", "heap = \"gc2\"", "heap"),
+        ("threshold", "[gc]\nthreshold = -1", "gc.threshold"),
+        (
+            "growth-factor",
+            "[gc]\ngrowth_factor = 0.5",
+            "gc.growth_factor",
+        ),
+        (
+            "every",
+            "[gc]\nevery_n_statements = 0",
+            "gc.every_n_statements",
+        ),
+        ("threads", "[gc]\nthreads = \"4\"", "gc.threads"),
+        (
+            "escape-analysis",
+            "[optimizations]\nescape_analysis = 1",
+            "optimizations.escape_analysis",
+        ),
+        ("enabled", "[stats]\nenabled = \"yes\"", "stats.enabled"),
+        (
+            "level",
+            "[lint]\nunused-function = \"never\"",
+            "lint.unused-function",
+        ),
+    ] {
+        let directory = project(name, line);
+        let line_number = line.lines().count();
+        let error = error(&run(&directory, &[]));
+
+        assert!(
+            error.contains(&format!(
+                "slang.toml:{} Invalid value for `{}`",
+                line_number, expected
+            )),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Checks that each Rust type converts into a slang value and back unchanged, and that converting a value into a type it does not have fails with the reason why.
+
+use std::collections::HashMap;
+
+use slang_core::{
+    Value,
+    convert::{ConversionError, FromObject, IntoObject},
+    heap::{Object, Pointer},
+    object_conversions,
+    shared::Shared,
+    value::{Function, NativeFunction},
+};
+
+/// Converts a Rust value into a slang value and back again.
+fn round_trip<T>(value: T) -> Result<T, ConversionError>
+where
+    Value: From<T>,
+    T: TryFrom<Value, Error = ConversionError>,
+{
+    T::try_from(Value::from(value))
+}
+
+/// Returns the message of the error a conversion failed with.
+fn error<T>(result: Result<T, ConversionError>) -> String {
+    match result {
+        Ok(_) => panic!("the conversion should fail"),
+        Err(error) => error.to_string(),
+    }
+}
+
+#[test]
+fn integers() {
+    assert_eq!(round_trip(-7).unwrap(), -7);
+    assert_eq!(round_trip(i32::MAX).unwrap(), i32::MAX);
+
+    assert_eq!(i64::try_from(Value::try_from(-7i64).unwrap()).unwrap(), -7);
+    assert_eq!(
+        usize::try_from(Value::try_from(7usize).unwrap()).unwrap(),
+        7
+    );
+
+    assert_eq!(
+        error(Value::try_from(i64::from(i32::MAX) + 1)),
+        "The value 2147483648 is out of range for an Integer."
+    );
+    assert_eq!(
+        error(Value::try_from(usize::MAX)),
+        format!(
+            "The value {} is out of range for an Integer.",
+            i64::try_from(usize::MAX).unwrap_or(i64::MAX)
+        )
+    );
+    assert_eq!(
+        error(usize::try_from(Value::from(-1))),
+        "Expected an Integer which is not negative, found -1."
+    );
+    assert_eq!(
+        error(i32::try_from(Value::from(1.0))),
+        "Expected a value of type Integer, found Float."
+    );
+}
+
+#[test]
+fn floats() {
+    assert_eq!(round_trip(1.5).unwrap(), 1.5);
+    assert_eq!(round_trip(f64::INFINITY).unwrap(), f64::INFINITY);
+    assert!(round_trip(f64::NAN).unwrap().is_nan());
+
+    assert_eq!(
+        error(f64::try_from(Value::from(1))),
+        "Expected a value of type Float, found Integer."
+    );
+}
+
+#[test]
+fn booleans() {
+    assert!(round_trip(true).unwrap());
+    assert!(!round_trip(false).unwrap());
+
+    assert_eq!(
+        error(bool::try_from(Value::from("true"))),
+        "Expected a value of type Boolean, found String."
+    );
+}
+
+#[test]
+fn strings() {
+    assert_eq!(
+        round_trip(String::from("a \"quoted\"\nline")).unwrap(),
+        "a \"quoted\"\nline"
+    );
+    assert_eq!(String::try_from(Value::from("")).unwrap(), "");
+    assert_eq!(
+        &*round_trip(Shared::<str>::from("shared")).unwrap(),
+        "shared"
+    );
+
+    assert_eq!(
+        error(String::try_from(Value::from(false))),
+        "Expected a value of type String, found Boolean."
+    );
+}
+
+#[test]
+fn functions() {
+    let function = Function::Native(NativeFunction::Print);
+
+    assert!(round_trip(function.clone()).unwrap() == function);
+
+    assert_eq!(
+        error(Function::try_from(Value::from(1))),
+        "Expected a value of type Function, found Integer."
+    );
+}
+
+#[test]
+fn pointers() {
+    let pointer = Pointer {
+        index: 3,
+        generation: 1,
+    };
+
+    assert!(round_trip(pointer).unwrap() == pointer);
+
+    // An object which has not been allocated has no pointer.
+    assert!(matches!(
+        Pointer::try_from(Value::Object(Object::new())),
+        Err(ConversionError::WrongType { .. })
+    ));
+}
+
+#[test]
+fn objects() {
+    let object = Value::from(HashMap::from([("x", 1), ("y", 2)]));
+
+    assert!(Value::Object(Object::try_from(object.clone()).unwrap()) == object);
+
+    let pointer = Value::from(Pointer {
+        index: 0,
+        generation: 0,
+    });
+
+    // A reference to an object on the heap must be read from the heap first.
+    assert!(matches!(
+        Object::try_from(pointer),
+        Err(ConversionError::WrongType { .. })
+    ));
+    assert_eq!(
+        error(Object::try_from(Value::from("object"))),
+        "Expected a value of type Object, found String."
+    );
+}
+
+struct Point {
+    x: i32,
+    y: i32,
+    label: String,
+}
+
+object_conversions!(Point { x, y, label });
+
+#[test]
+fn structs() {
+    let object = Point {
+        x: 1,
+        y: -2,
+        label: String::from("origin"),
+    }
+    .into_object();
+
+    let point = Point::from_value(Value::Object(object.clone())).unwrap();
+    assert_eq!((point.x, point.y, point.label.as_str()), (1, -2, "origin"));
+
+    let mut missing = object.clone();
+    missing.remove("label");
+    assert_eq!(
+        error(Point::from_object(&missing)),
+        "The object has no field `label`."
+    );
+
+    let mut wrong = object;
+    wrong.insert(Shared::from("x"), Value::from("one"));
+    assert_eq!(
+        error(Point::from_object(&wrong)),
+        "Expected a value of type Integer, found String."
+    );
+
+    assert_eq!(
+        error(Point::from_value(Value::from(1))),
+        "Expected a value of type Object, found Integer."
+    );
+}
@@ -0,0 +1,201 @@
+//! Runs each program in `examples/` with every heap, and checks that what it writes to stdout and stderr, and its exit code, match those recorded in its `.expected` file. Also runs each numbered program in `tests/` with the heap it must be run in, and checks its output against its `.out` file.
+//!
+//! A program's output is expected to be the same whichever heap manages it, so `<name>.expected` is checked against every heap, unless `<name>.<heap>.expected` records a different outcome for one of them (such as `gc()` freeing a different number of objects). Running with `SLANG_BLESS=1` rewrites the expected files from what the programs did, recording the outcome with `gc` in `<name>.expected`, and the outcome with any other heap in its own file only if it differs.
+
+use std::{
+    env,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// The codes of the heaps each program is run with, the first of which records the outcome which is expected of every heap.
+const HEAPS: [&str; 7] = ["gc", "gen", "mc", "cp", "rc", "rg", "na"];
+
+/// The numbered programs in `tests/` which are meant to be run by hand, as they read input from the user, or are lines to enter into the REPL one by one.
+const INTERACTIVE: [&str; 2] = ["t29", "t33"];
+
+#[test]
+fn examples() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let bless = env::var_os("SLANG_BLESS").is_some();
+
+    let programs = programs(&root.join("examples"));
+    assert!(!programs.is_empty(), "there are no examples to run");
+
+    let mut failures = Vec::new();
+
+    for program in &programs {
+        let name = program
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .expect("example names are valid UTF-8");
+
+        let expected_path = |heap: Option<&str>| match heap {
+            Some(heap) => program.with_file_name(format!("{}.{}.expected", name, heap)),
+            None => program.with_file_name(format!("{}.expected", name)),
+        };
+
+        let mut outcomes = HEAPS.iter().map(|heap| (*heap, run(root, name, heap)));
+
+        let (_, default) = outcomes.next().expect("there is at least one heap");
+
+        if bless {
+            write(&expected_path(None), &default);
+
+            for (heap, outcome) in outcomes {
+                let path = expected_path(Some(heap));
+
+                if outcome == default {
+                    let _ = fs::remove_file(path);
+                } else {
+                    write(&path, &outcome);
+                }
+            }
+
+            continue;
+        }
+
+        let read_expected = |heap: Option<&str>| fs::read_to_string(expected_path(heap)).ok();
+
+        let Some(default_expected) = read_expected(None) else {
+            failures.push(format!(
+                "examples/{}.slang has no expected file; run with SLANG_BLESS=1 to record one",
+                name
+            ));
+            continue;
+        };
+
+        for (heap, outcome) in [(HEAPS[0], default)].into_iter().chain(outcomes) {
+            let expected = read_expected(Some(heap)).unwrap_or_else(|| default_expected.clone());
+
+            if outcome != expected {
+                failures.push(format!(
+                    "examples/{}.slang with {} did not match its expected file.\n--- expected\n{}\n--- actual\n{}",
+                    name, heap, expected, outcome
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+#[test]
+fn numbered_programs() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    let programs = programs(&root.join("tests"));
+    assert!(
+        !programs.is_empty(),
+        "there are no numbered programs to run"
+    );
+
+    let mut failures = Vec::new();
+
+    for program in &programs {
+        let name = program
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .expect("program names are valid UTF-8");
+
+        // Programs without a `.out` file have no output to check.
+        let Ok(expected) = fs::read_to_string(program.with_extension("out")) else {
+            continue;
+        };
+
+        if INTERACTIVE.contains(&name) {
+            continue;
+        }
+
+        let source = fs::read_to_string(program).expect("the program can be read");
+        let heap = heap(&source).unwrap_or(HEAPS[0]);
+        let output = run_numbered(root, name, heap);
+
+        // The `.out` files have no newline at the end.
+        if output.trim_end_matches('\n') != expected {
+            failures.push(format!(
+                "tests/{}.slang with {} did not match its .out file.\n--- expected\n{}\n--- actual\n{}",
+                name, heap, expected, output
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+/// Returns the `.slang` programs in a directory, in order of name.
+fn programs(directory: &Path) -> Vec<PathBuf> {
+    let mut programs = fs::read_dir(directory)
+        .expect("the directory can be read")
+        .map(|entry| entry.expect("the programs can be listed").path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "slang")
+        })
+        .collect::<Vec<PathBuf>>();
+
+    programs.sort();
+    programs
+}
+
+/// Returns the code of the heap a program must be run in, from a header such as `// Must be run in reference counting ("rc") mode.`, if it has one.
+fn heap(source: &str) -> Option<&str> {
+    let line = source
+        .lines()
+        .find(|line| line.starts_with("// Must be run in "))?;
+
+    let (_, rest) = line.split_once("(\"")?;
+    let (heap, _) = rest.split_once("\")")?;
+
+    HEAPS.contains(&heap).then_some(heap)
+}
+
+/// Runs a numbered program with a heap, returning what it wrote to stdout and stderr, interleaved as they were written.
+fn run_numbered(root: &Path, name: &str, heap: &str) -> String {
+    let path = env::temp_dir().join(format!("slang-{}-{}.out", std::process::id(), name));
+    let file = File::create(&path).expect("the output file can be created");
+
+    Command::new(env!("CARGO_BIN_EXE_slang_interpreter"))
+        .current_dir(root)
+        .arg(heap)
+        .arg(format!("tests/{}.slang", name))
+        .stdin(Stdio::null())
+        .stdout(file.try_clone().expect("the output file can be shared"))
+        .stderr(file)
+        .status()
+        .expect("the interpreter can be run");
+
+    let output = fs::read_to_string(&path).expect("the output file can be read");
+    let _ = fs::remove_file(&path);
+
+    output
+}
+
+/// Runs an example with a heap, returning what it wrote and its exit code, in the format of an expected file.
+fn run(root: &Path, name: &str, heap: &str) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_slang_interpreter"))
+        .current_dir(root)
+        .arg(heap)
+        .arg(format!("examples/{}.slang", name))
+        .output()
+        .expect("the interpreter can be run");
+
+    let code = match output.status.code() {
+        Some(code) => code.to_string(),
+        None => String::from("none"),
+    };
+
+    format!(
+        "exit code: {}\n--- stdout\n{}--- stderr\n{}",
+        code,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+}
+
+/// Writes an expected file, panicking if it cannot be written.
+fn write(path: &Path, outcome: &str) {
+    fs::write(path, outcome)
+        .unwrap_or_else(|error| panic!("unable to write {}: {}", path.display(), error));
+}
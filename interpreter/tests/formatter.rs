@@ -0,0 +1,117 @@
+//! Checks the formatter against source code laid out in ways it rewrites, that comments within expressions are kept, that its errors name the file they are in, and that formatting every program in `tests/` and `examples/` a second time leaves it unchanged.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use slang_core::{
+    formatter::{Formatter, FormatterError},
+    source::SourceMap,
+};
+
+#[test]
+fn moving_an_object_literal_keeps_its_meaning() {
+    let formatted = Formatter::format_source("let x   = {a: 1};\nprint( x.a );")
+        .expect("the source code can be formatted");
+
+    assert_eq!(formatted, "let x = { a: 1 };\nprint(x.a);\n");
+}
+
+#[test]
+fn only_the_final_expression_without_a_semicolon_is_yielded() {
+    let formatted =
+        Formatter::format_source("let x = if true { 2 } else { 3 };\nlet y = { x; 4; };")
+            .expect("the source code can be formatted");
+
+    assert_eq!(
+        formatted,
+        "let x = if true {\n    2\n} else {\n    3\n};\nlet y = {\n    x;\n    4;\n};\n"
+    );
+}
+
+#[test]
+fn comments_within_expressions_stay_beside_their_code() {
+    let source = "let x = f(\n    a, // first\n    b,\n);\nlet o = {a: 1, /* two */ b: 2};\nfu g(a /* x */, b) { return a; }";
+    let formatted = Formatter::format_source(source).expect("the source code can be formatted");
+
+    assert_eq!(
+        formatted,
+        "let x = f(a, // first\n    b);\nlet o = { a: 1, /* two */ b: 2 };\nfu g(a /* x */, b) {\n    return a;\n}\n"
+    );
+    assert_eq!(Formatter::format_source(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    let mut programs = ["tests", "examples"]
+        .iter()
+        .flat_map(|directory| {
+            fs::read_dir(root.join(directory))
+                .expect("the directory can be read")
+                .map(|entry| entry.expect("the programs can be listed").path())
+        })
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "slang")
+        })
+        .collect::<Vec<PathBuf>>();
+
+    programs.sort();
+
+    let mut failures = Vec::new();
+
+    for program in &programs {
+        let source = fs::read_to_string(program).expect("the program can be read");
+
+        let formatted = match Formatter::format_source(&source) {
+            Ok(formatted) => formatted,
+            // Some programs test the errors reported for source code which cannot be lexed or parsed, so they cannot be formatted either.
+            Err(errors)
+                if errors.iter().all(|error| {
+                    matches!(error, FormatterError::Lexer(_) | FormatterError::Parser(_))
+                }) =>
+            {
+                continue;
+            }
+            Err(errors) => {
+                failures.push(format!("{}: {:?}", program.display(), errors));
+                continue;
+            }
+        };
+
+        match Formatter::format_source(&formatted) {
+            Ok(again) if again == formatted => {}
+            Ok(again) => failures.push(format!(
+                "{} changed when formatted again.\n--- once\n{}\n--- twice\n{}",
+                program.display(),
+                formatted,
+                again
+            )),
+            Err(errors) => failures.push(format!(
+                "{} could not be formatted again: {:?}",
+                program.display(),
+                errors
+            )),
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+#[test]
+fn errors_name_the_file_and_underline_the_code() {
+    let source = "let x = 1;\nlet = 2;";
+    let mut sources = SourceMap::default();
+    let file = sources.add("g.slang", source);
+
+    let errors = Formatter::format_file(source, file).expect_err("the source code is invalid");
+    let span = errors[0].span().expect("a parser error has a span");
+
+    assert_eq!(
+        sources.render(&errors[0].to_string(), span, source),
+        "[g.slang:2:5] Expected one of the following tokens: [Identifier]\n    let = 2;\n        ^"
+    );
+}
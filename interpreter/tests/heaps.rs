@@ -0,0 +1,145 @@
+//! Checks that the heaps can manage object graphs too large to traverse recursively, such as a long linked list, without overflowing the stack, or taking time which grows with the square of their size.
+
+use slang_core::{
+    heap::{
+        ManagedHeap, Object, Pointer, Strategy, generational::GenerationalHeap,
+        reference_counted::ReferenceCountedHeap,
+    },
+    value::Value,
+};
+
+/// The number of objects in each linked list, far more than could be traversed recursively on a test thread's stack.
+const LENGTH: usize = 100_000;
+
+/// Returns an object whose `next` field is a value.
+fn node(next: Value) -> Object {
+    [("next".into(), next)].into_iter().collect()
+}
+
+/// Builds a linked list with an allocator, returning its head.
+fn linked_list(mut allocate: impl FnMut(Object) -> Pointer) -> Pointer {
+    let mut head = allocate(node(Value::Integer(0)));
+
+    for _ in 1..LENGTH {
+        head = allocate(node(Value::ObjectReference(head)));
+    }
+
+    head
+}
+
+/// Builds a linked list on a reference counted heap, as `head = { next: head }` would in a loop: each new head references the old one, which is then released by the variable which held it, buffering every node as a possible root of a garbage cycle.
+fn reference_counted_list(heap: &mut ReferenceCountedHeap) -> Pointer {
+    let mut previous = None;
+
+    linked_list(|data| {
+        let pointer = heap.allocate(data);
+
+        if let Some(previous) = previous.replace(pointer) {
+            heap.decrement(previous);
+        }
+
+        pointer
+    })
+}
+
+#[test]
+fn generational_heap_marks_a_long_list() {
+    let mut heap = GenerationalHeap::new();
+
+    let head = linked_list(|data| heap.allocate(data));
+
+    heap.collect(&[head]);
+    assert_eq!(heap.objects_count(), LENGTH);
+
+    heap.collect(&[]);
+    assert_eq!(heap.objects_count(), 0);
+}
+
+#[test]
+fn reference_counted_heap_builds_and_frees_a_long_list() {
+    let mut heap = ReferenceCountedHeap::new();
+    let head = reference_counted_list(&mut heap);
+
+    heap.collect_cycles();
+    assert_eq!(heap.objects_count(), LENGTH);
+
+    heap.decrement(head);
+    assert_eq!(heap.objects_count(), 0);
+}
+
+#[test]
+fn reference_counted_heap_collects_a_long_cycle() {
+    let mut heap = ReferenceCountedHeap::new();
+    let head = reference_counted_list(&mut heap);
+
+    // Closing the list into a ring, then releasing the only reference from outside it, leaves a cycle which only cycle collection can free.
+    let mut tail = head;
+
+    while let Some(Value::ObjectReference(next)) = heap
+        .objects
+        .get(tail)
+        .and_then(|object| object.data.get("next"))
+    {
+        tail = *next;
+    }
+
+    heap.increment(head);
+    heap.objects
+        .get_mut(tail)
+        .expect("the tail is on the heap")
+        .data
+        .insert("next".into(), Value::ObjectReference(head));
+
+    heap.decrement(head);
+    heap.collect_cycles();
+    assert_eq!(heap.objects_count(), 0);
+}
+
+#[test]
+fn parallel_marking_keeps_only_reachable_objects() {
+    for strategy in [
+        Strategy::GarbageCollected,
+        Strategy::Generational,
+        Strategy::Compacting,
+    ] {
+        let mut heap = ManagedHeap::new(strategy);
+        heap.set_gc_threads(4);
+
+        let mut allocate = |data| {
+            heap.allocate(data, Vec::new)
+                .expect("the heap has no limits")
+        };
+        let mut tail = None;
+
+        let head = linked_list(|data| {
+            let pointer = allocate(data);
+            tail.get_or_insert(pointer);
+
+            pointer
+        });
+
+        // Garbage, which the threads must not reach from the list.
+        linked_list(&mut allocate);
+
+        // Closing the list into a ring means that the threads reach the head again from the tail.
+        heap.set_field(
+            tail.expect("the list has a tail"),
+            "next",
+            Value::ObjectReference(head),
+        )
+        .expect("the tail is on the heap");
+
+        heap.collect(&[head]);
+        assert_eq!(heap.objects_count(), LENGTH, "{}", strategy.code());
+
+        let collections = heap.take_collections();
+        assert!(
+            !collections.is_empty() && collections.iter().all(|collection| collection.threads == 4),
+            "{}",
+            strategy.code()
+        );
+
+        heap.collect(&[]);
+        assert_eq!(heap.objects_count(), 0, "{}", strategy.code());
+    }
+}
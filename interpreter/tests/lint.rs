@@ -0,0 +1,134 @@
+//! Checks that each lint rule reports the code which breaks it, located at the statement or expression which broke it, and that `slang lint` fails only when a rule which is denied is broken.
+
+use std::{env, fs, path::PathBuf, process::Command};
+
+use slang_core::{
+    lint::{self, Level, Levels, Rule},
+    source::SourceMap,
+};
+
+/// Lints source code with the levels given, returning the name of each rule broken, along with the line and column it was located at.
+fn lint_with(source: &str, levels: &Levels) -> Vec<(&'static str, usize, usize)> {
+    let statements = slang_core::parse(source).expect("the source code can be parsed");
+
+    lint::lint(&statements, levels)
+        .into_iter()
+        .map(|diagnostic| {
+            let span = diagnostic.span.expect("parsed code is located");
+
+            (
+                diagnostic.rule.name(),
+                span.start().line(),
+                span.start().column(),
+            )
+        })
+        .collect()
+}
+
+/// Lints source code with every rule at its default level.
+fn lint(source: &str) -> Vec<(&'static str, usize, usize)> {
+    lint_with(source, &Levels::default())
+}
+
+#[test]
+fn constant_condition() {
+    let source = "let x = 1;\nif true { print(x); }\nwhile (1 < 2) && !false { }\nprint(1 == 1 ? 2 : 3);\nlet y = if x > 0 { 1 } else { 2 };";
+
+    assert_eq!(
+        lint(source),
+        [
+            ("constant-condition", 2, 1),
+            ("constant-condition", 3, 1),
+            ("constant-condition", 4, 7),
+        ]
+    );
+}
+
+#[test]
+fn assignment_in_condition() {
+    let source =
+        "let x = false;\nfu check() {\n    if (x = true) { return 1; }\n    return 0;\n}\ncheck();";
+
+    assert_eq!(lint(source), [("assignment-in-condition", 3, 5)]);
+}
+
+#[test]
+fn unused_function() {
+    let source = "fu used() { return 1; }\nfu unused() { return unused(); }\nprint(used());";
+
+    assert_eq!(lint(source), [("unused-function", 2, 1)]);
+}
+
+#[test]
+fn shadowed_builtin() {
+    let source = "let print = 1;\nfu apply(format) { return format; }\napply(2);";
+
+    assert_eq!(
+        lint(source),
+        [("shadowed-builtin", 1, 1), ("shadowed-builtin", 2, 1)]
+    );
+}
+
+#[test]
+fn infinite_loop() {
+    let source = "loop { print(1); }\nloop { break; }\nfu first() { loop { return 1; } }\nloop { loop { break; } }\nloop { fu leave() { return 2; } }\nprint(first());";
+
+    assert_eq!(
+        lint(source),
+        [
+            ("infinite-loop", 1, 1),
+            ("infinite-loop", 4, 1),
+            ("infinite-loop", 5, 1),
+            ("unused-function", 5, 8),
+        ]
+    );
+}
+
+#[test]
+fn allowed_rules_are_not_reported() {
+    let mut levels = Levels::default();
+    levels.set(Rule::ConstantCondition, Level::Allow);
+
+    assert_eq!(
+        lint_with("if true { }\nfu unused() { }", &levels),
+        [("unused-function", 2, 1)]
+    );
+}
+
+#[test]
+fn diagnostics_underline_the_code_which_broke_the_rule() {
+    let source = "let x = 1;\nwhile true { x = x + 1; }";
+    let statements = slang_core::parse(source).expect("the source code can be parsed");
+    let diagnostics = lint::lint(&statements, &Levels::default());
+
+    assert_eq!(
+        diagnostics[0].underlined(&SourceMap::default(), source),
+        "[line 2, column 1] warning[constant-condition]: The condition of a while loop is constant, so it always has the same value.\n    while true { x = x + 1; }\n    ^^^^^^^^^^^^^^^^^^^^^^^^^"
+    );
+}
+
+#[test]
+fn only_denied_rules_fail_the_command() {
+    let path = env::temp_dir().join(format!("slang-lint-{}.slang", std::process::id()));
+    fs::write(&path, "if true { print(1); }").expect("the program can be written");
+
+    let warned = run(&path, &[]);
+    let denied = run(&path, &["--deny", "constant-condition"]);
+    let _ = fs::remove_file(&path);
+
+    assert_eq!(warned.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&warned.stdout).contains("warning[constant-condition]"));
+
+    assert_eq!(denied.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&denied.stdout).contains("error[constant-condition]"));
+}
+
+/// Lints a file with `slang lint`, with some options.
+fn run(path: &PathBuf, options: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_slang_interpreter"))
+        .arg("lint")
+        .args(options)
+        .arg(path)
+        .output()
+        .expect("the interpreter can be run")
+}
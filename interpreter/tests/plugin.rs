@@ -0,0 +1,72 @@
+//! Checks that a plugin built from `examples/slang_math.rs` is loaded and its functions called, and that a library which is not a plugin is refused.
+
+use std::{path::PathBuf, process::Command};
+
+use slang_core::{HeapStrategy, Interpreter, value::Value};
+
+/// Builds the example plugin with the same features as these tests, and returns the path of its shared library.
+fn build() -> PathBuf {
+    let mut command = Command::new(env!("CARGO"));
+    command.args([
+        "build",
+        "--example",
+        "slang_math",
+        "--message-format=json",
+        "--manifest-path",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"),
+    ]);
+    if cfg!(feature = "send") {
+        command.args(["--features", "send"]);
+    }
+
+    let output = command.output().expect("cargo could be run");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout)
+        .expect("cargo writes UTF-8")
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|message| message["target"]["name"] == "slang_math")
+        .find_map(|message| message["filenames"][0].as_str().map(PathBuf::from))
+        .expect("the plugin was built")
+}
+
+#[test]
+fn a_plugin_is_loaded_and_its_functions_called() {
+    let mut interpreter = Interpreter::new(HeapStrategy::GarbageCollected);
+    unsafe { interpreter.load_plugin(build()) }.unwrap();
+
+    assert_eq!(
+        interpreter.evaluate("square(7)").unwrap(),
+        Some(Value::Integer(49))
+    );
+
+    let error = interpreter.run("square(\"7\");").unwrap_err().to_string();
+    assert!(error.contains("expected one Integer"), "{}", error);
+}
+
+#[test]
+fn a_library_which_is_not_a_plugin_is_refused() {
+    let plugin = build();
+    // The interpreter's own shared library is built alongside the plugin, but exports no descriptor.
+    let library = plugin
+        .parent()
+        .and_then(|examples| examples.parent())
+        .expect("the plugin is built in the examples directory")
+        .join(format!(
+            "{}slang_core{}",
+            std::env::consts::DLL_PREFIX,
+            std::env::consts::DLL_SUFFIX
+        ));
+
+    let mut interpreter = Interpreter::new(HeapStrategy::GarbageCollected);
+    let error = unsafe { interpreter.load_plugin(&library) }
+        .unwrap_err()
+        .to_string();
+
+    assert!(error.contains("slang_plugin"), "{}", error);
+}
@@ -0,0 +1,88 @@
+//! Checks that the REPL runs each line it reads, reports errors within them, and stops at the end of its input.
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Runs the REPL with some arguments, writing the input to it, and failing if it has not exited within a few seconds.
+fn repl(arguments: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_slang_interpreter"))
+        .args(arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the interpreter can be run");
+
+    child
+        .stdin
+        .take()
+        .expect("the input is piped")
+        .write_all(input.as_bytes())
+        .expect("the input can be written");
+
+    let start = Instant::now();
+
+    while child
+        .try_wait()
+        .expect("the REPL can be waited on")
+        .is_none()
+    {
+        if start.elapsed() > Duration::from_secs(5) {
+            let _ = child.kill();
+            panic!("the REPL did not exit at the end of its input");
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    child.wait_with_output().expect("the output can be read")
+}
+
+#[test]
+fn the_end_of_the_input_exits() {
+    let output = repl(&[], "let x = 1;\nprint(x + 1);\n");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "> > 2\n> ");
+}
+
+#[test]
+fn a_failed_block_exits_its_scope() {
+    // The block's object is freed as it fails under the heaps which free objects as their scope exits.
+    for (heap, objects) in [("gc", 1), ("rc", 0), ("rg", 0)] {
+        let output = repl(
+            &[heap],
+            "{ let leaked = {x: 1}; missing(); }\nprint(heap_objects());\nprint(leaked.x);\n",
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(
+            stdout.contains(&format!("> {}\n", objects)),
+            "{}: {}",
+            heap,
+            stdout
+        );
+        assert!(
+            String::from_utf8_lossy(&output.stderr)
+                .contains("The identifier `leaked` is not defined."),
+            "{}",
+            heap
+        );
+    }
+}
+
+#[test]
+fn errors_at_the_prompt_are_located_in_the_repl() {
+    let output = repl(&[], "let x = 1;\nprint(y);\n");
+
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("[<repl>:1:7] [evaluation error] The identifier `y` is not defined.\n    print(y);\n          ^"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
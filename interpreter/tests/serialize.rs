@@ -0,0 +1,178 @@
+//! Checks that values survive a round trip through serde (using JSON), that references are followed only with the heap and never around a cycle, and how values with no equivalent in the format are handled.
+
+use serde::{Deserialize, de::IntoDeserializer};
+use slang_core::{
+    HeapStrategy, Value,
+    heap::{ManagedHeap, Object, Pointer},
+    serialize::Resolved,
+    value::{Function, NativeFunction},
+};
+
+/// Builds an object from its fields.
+fn object<const N: usize>(fields: [(&str, Value); N]) -> Value {
+    Value::Object(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.into(), value))
+            .collect(),
+    )
+}
+
+/// Allocates an object on the heap, returning a reference to it.
+fn allocate(heap: &mut ManagedHeap, data: Object) -> Pointer {
+    heap.allocate(data, Vec::new)
+        .expect("the heap has no limits")
+}
+
+#[test]
+fn values_round_trip() {
+    let values = [
+        Value::from("a \"quoted\"\nline"),
+        Value::from(-1.5),
+        Value::from(i32::MIN),
+        Value::from(true),
+        object([
+            ("name", Value::from("point")),
+            ("x", Value::from(1)),
+            ("nested", object([("y", Value::from(2.5))])),
+            ("empty", object([])),
+        ]),
+    ];
+
+    for value in values {
+        let json = serde_json::to_string(&value).unwrap();
+
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), value);
+    }
+}
+
+#[test]
+fn fields_are_serialized_in_order_of_name() {
+    let value = object([
+        ("b", Value::from(2)),
+        ("a", Value::from(1)),
+        ("c", Value::from(false)),
+    ]);
+
+    assert_eq!(
+        serde_json::to_string(&value).unwrap(),
+        r#"{"a":1,"b":2,"c":false}"#
+    );
+}
+
+#[test]
+fn values_with_no_equivalent_fail() {
+    let function = Value::Function(Function::Native(NativeFunction::Print));
+
+    assert_eq!(
+        serde_json::to_string(&function).unwrap_err().to_string(),
+        "functions cannot be serialized"
+    );
+
+    for json in ["null", "[1, 2]", "2147483648", "18446744073709551615"] {
+        assert!(serde_json::from_str::<Value>(json).is_err(), "{}", json);
+    }
+}
+
+#[test]
+fn non_finite_floats() {
+    // They are deserialized as they are, from formats which can hold them.
+    let deserialize = |value: f64| -> Result<Value, serde::de::value::Error> {
+        Value::deserialize(value.into_deserializer())
+    };
+
+    assert_eq!(
+        deserialize(f64::INFINITY).unwrap(),
+        Value::from(f64::INFINITY)
+    );
+    assert!(matches!(deserialize(f64::NAN), Ok(Value::Float(value)) if value.is_nan()));
+
+    // JSON has no equivalent, so writes them as null, which cannot be deserialized.
+    for value in [f64::INFINITY, f64::NEG_INFINITY, f64::NAN] {
+        let json = serde_json::to_string(&Value::from(value)).unwrap();
+
+        assert_eq!(json, "null");
+        assert!(serde_json::from_str::<Value>(&json).is_err());
+    }
+}
+
+#[test]
+fn references_are_followed_with_the_heap() {
+    let mut heap = ManagedHeap::new(HeapStrategy::GarbageCollected);
+
+    let Value::Object(data) = object([
+        ("inner", object([("x", Value::from(1))])),
+        ("y", Value::from("two")),
+    ]) else {
+        unreachable!()
+    };
+    let reference = Value::ObjectReference(allocate(&mut heap, data));
+
+    assert_eq!(
+        serde_json::to_string(&reference).unwrap_err().to_string(),
+        "object references cannot be serialized without the heap"
+    );
+
+    let json = serde_json::to_string(&Resolved::new(&reference, &heap)).unwrap();
+    assert_eq!(json, r#"{"inner":{"x":1},"y":"two"}"#);
+
+    // Deserializing produces objects which have not been allocated.
+    assert_eq!(
+        serde_json::from_str::<Value>(&json).unwrap(),
+        object([
+            ("inner", object([("x", Value::from(1))])),
+            ("y", Value::from("two")),
+        ])
+    );
+}
+
+#[test]
+fn objects_shared_without_a_cycle_are_serialized_for_each_reference() {
+    let mut heap = ManagedHeap::new(HeapStrategy::GarbageCollected);
+
+    let shared = allocate(&mut heap, Object::new());
+    let data = [
+        ("left".into(), Value::ObjectReference(shared)),
+        ("right".into(), Value::ObjectReference(shared)),
+    ]
+    .into_iter()
+    .collect();
+    let reference = Value::ObjectReference(allocate(&mut heap, data));
+
+    assert_eq!(
+        serde_json::to_string(&Resolved::new(&reference, &heap)).unwrap(),
+        r#"{"left":{},"right":{}}"#
+    );
+}
+
+#[test]
+fn cyclic_objects_fail() {
+    let mut heap = ManagedHeap::new(HeapStrategy::GarbageCollected);
+
+    let first = allocate(&mut heap, Object::new());
+    let second = allocate(&mut heap, Object::new());
+
+    for (from, to) in [(first, second), (second, first)] {
+        heap.get_mut(from)
+            .unwrap()
+            .data
+            .insert("next".into(), Value::ObjectReference(to));
+    }
+
+    let own = allocate(&mut heap, Object::new());
+    heap.get_mut(own)
+        .unwrap()
+        .data
+        .insert("own".into(), Value::ObjectReference(own));
+
+    for pointer in [first, own] {
+        let reference = Value::ObjectReference(pointer);
+
+        assert_eq!(
+            serde_json::to_string(&Resolved::new(&reference, &heap))
+                .unwrap_err()
+                .to_string(),
+            "objects which reference each other in a cycle cannot be serialized"
+        );
+    }
+}
@@ -0,0 +1,36 @@
+//! Checks that a saved session restores values which cannot be written in source code exactly.
+
+use std::{env, fs, process};
+
+use slang_core::{HeapStrategy, Interpreter, value::Value};
+
+#[test]
+fn values_which_cannot_be_written_are_saved() {
+    let mut interpreter = Interpreter::new(HeapStrategy::GarbageCollected);
+    interpreter
+        .run("let __literal_0 = 5;\nlet quoted = 0;\nlet infinite = float(\"inf\");\nlet point = {x: 1};\nfu set(value) { quoted = value; point.label = value; }")
+        .unwrap();
+
+    // A string containing `"` cannot be written in source code.
+    let quoted = Value::from("say \"hi\"\n");
+    interpreter.call("set", &[quoted.clone()]).unwrap();
+
+    let path = env::temp_dir().join(format!("slang-session-{}.slang", process::id()));
+    interpreter.save(&path).unwrap();
+
+    let mut restored = Interpreter::new(HeapStrategy::GarbageCollected);
+    let result = restored.restore(&path);
+    let _ = fs::remove_file(&path);
+    result.unwrap();
+
+    assert_eq!(restored.evaluate("quoted").unwrap(), Some(quoted.clone()));
+    assert_eq!(restored.evaluate("point.label").unwrap(), Some(quoted));
+    assert_eq!(
+        restored.evaluate("infinite").unwrap(),
+        Some(Value::Float(f64::INFINITY))
+    );
+    assert_eq!(
+        restored.evaluate("__literal_0").unwrap(),
+        Some(Value::Integer(5))
+    );
+}
@@ -0,0 +1,119 @@
+//! Checks that interrupting a program stops the threads it has spawned as well as the program itself, that values are copied between threads exactly, and that channels, mutexes and atomic integers are only shared with those threads.
+
+use std::{sync::mpsc, thread, time::Duration};
+
+use slang_core::{HeapStrategy, Interpreter, interrupt::Interrupt, value::Value};
+
+/// Runs a program on a thread of its own, interrupts it once after it has had time to start, and returns the message of the error it stopped with, failing if it does not stop.
+fn interrupted(source: &'static str) -> String {
+    let interrupt = Interrupt::default();
+    let (sender, receiver) = mpsc::channel();
+
+    let running = interrupt.clone();
+    thread::spawn(move || {
+        let mut interpreter = Interpreter::new(HeapStrategy::GarbageCollected);
+        interpreter.set_interrupt(running);
+
+        let _ = sender.send(interpreter.run(source).map_err(|error| error.to_string()));
+    });
+
+    thread::sleep(Duration::from_millis(200));
+    interrupt.interrupt();
+
+    receiver
+        .recv_timeout(Duration::from_secs(5))
+        .expect("the program stops after one interrupt")
+        .expect_err("the program never finishes on its own")
+}
+
+#[test]
+fn one_interrupt_stops_a_program_and_its_threads() {
+    let error = interrupted("fu spin() { loop { } }\nlet worker = spawn(spin);\nloop { }");
+
+    assert!(error.contains("The program was interrupted."), "{}", error);
+}
+
+#[test]
+fn a_thread_cannot_use_up_the_interrupt_of_a_waiting_program() {
+    // The program only checks for an interrupt every so often while it waits for a message, whereas the thread checks before every statement, so it sees the interrupt first.
+    let error = interrupted("fu spin() { loop { } }\nlet worker = spawn(spin);\nrecv(channel());");
+
+    assert!(error.contains("The program was interrupted."), "{}", error);
+}
+
+#[test]
+fn a_thread_being_joined_is_interrupted() {
+    let error = interrupted("fu spin() { loop { } }\nlet worker = spawn(spin);\njoin(worker);");
+
+    assert!(error.contains("A thread failed"), "{}", error);
+    assert!(error.contains("The program was interrupted."), "{}", error);
+}
+
+#[test]
+fn values_which_cannot_be_written_are_copied_exactly() {
+    let mut interpreter = Interpreter::new(HeapStrategy::GarbageCollected);
+    interpreter
+        .run(
+            "let __literal_0 = 5;\nfu read(s) { return __literal_0; }\nfu echo(s) { return s; }\nfu spawned(function, s) { return join(spawn(function, s)); }",
+        )
+        .unwrap();
+
+    // A string containing `"` cannot be written in source code.
+    let quoted = Value::from("say \"hi\"");
+    let read = interpreter.evaluate("read").unwrap().unwrap();
+    let echo = interpreter.evaluate("echo").unwrap().unwrap();
+
+    assert_eq!(
+        interpreter
+            .call("spawned", &[read, quoted.clone()])
+            .unwrap(),
+        Some(Value::Integer(5))
+    );
+    assert_eq!(
+        interpreter
+            .call("spawned", &[echo, quoted.clone()])
+            .unwrap(),
+        Some(quoted)
+    );
+}
+
+#[test]
+fn channels_belong_to_the_program_which_created_them() {
+    let mut first = Interpreter::new(HeapStrategy::GarbageCollected);
+    first
+        .run("fu worker(channel) { send(channel, 42); }\nlet channel = channel();\nlet worker = spawn(worker, channel);\nlet received = recv(channel);")
+        .unwrap();
+
+    assert_eq!(
+        first.evaluate("received").unwrap(),
+        Some(Value::Integer(42))
+    );
+
+    let mut second = Interpreter::new(HeapStrategy::GarbageCollected);
+    let error = second.run("send(0, 1);").unwrap_err().to_string();
+
+    assert!(
+        error.contains("There is no channel with the handle 0."),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn mutexes_and_atomics_belong_to_the_program_which_created_them() {
+    let mut first = Interpreter::new(HeapStrategy::GarbageCollected);
+    first
+        .run("fu worker(counter) { atomic_add(counter, 2); }\nlet counter = atomic(1);\njoin(spawn(worker, counter));\nlet total = atomic_add(counter, 0);")
+        .unwrap();
+
+    assert_eq!(first.evaluate("total").unwrap(), Some(Value::Integer(3)));
+
+    let mut second = Interpreter::new(HeapStrategy::GarbageCollected);
+    let error = second.run("atomic_add(0, 1);").unwrap_err().to_string();
+
+    assert!(
+        error.contains("There is no atomic integer with the handle 0."),
+        "{}",
+        error
+    );
+}